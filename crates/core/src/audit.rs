@@ -0,0 +1,175 @@
+//! 审计记录
+//!
+//! 每次任务结束后在输出文件旁写出 `audit.json`，记录操作者、输入输出摘要、
+//! 规则包信息、OCR 模式与引擎、清理选项、命中与遮罩列表以及校验结果，用于事后追溯。
+//! 核心流水线与桌面端写出的审计记录使用同一结构。
+
+use crate::pipeline::{self, Region, TaskHit, VerifySummary};
+use crate::{CleanOptions, OcrMode, Result, RulePackRef, TaskConfig};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// 文件摘要
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileDigest {
+    pub path: String,
+    pub sha256: String,
+}
+
+/// OCR 引擎信息
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OcrEngineInfo {
+    /// 引擎名称
+    pub engine: String,
+    /// 引擎版本
+    pub version: Option<String>,
+    /// 引擎参数（JSON）
+    pub params: Option<String>,
+    /// 模型或语言数据的摘要
+    pub data_hash: Option<String>,
+}
+
+/// 任务审计记录
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskAudit {
+    /// 工具版本
+    pub tool_version: String,
+    /// 生成时间（Unix 时间戳，秒）
+    pub created_at: u64,
+    /// 操作者（可选）
+    #[serde(default)]
+    pub operator: Option<String>,
+    /// 输入文件
+    pub input: FileDigest,
+    /// 输出文件（Detect 模式下为空）
+    pub output: Option<FileDigest>,
+    /// 规则包信息（规则未来自规则包时为空）
+    pub rule_pack: Option<RulePackRef>,
+    /// 实际使用的规则集摘要
+    pub rules_hash: String,
+    /// OCR 模式
+    pub ocr_mode: OcrMode,
+    /// 检测阶段使用的 OCR 引擎（未使用 OCR 时为空）
+    #[serde(default)]
+    pub ocr_engine: Option<OcrEngineInfo>,
+    /// 处理器实际使用的脱敏方式（如 PDF 的 safe_render），未区分方式的处理器为空
+    #[serde(default)]
+    pub redact_mode: Option<String>,
+    /// 各页实际使用的脱敏方式（页码从 1 开始），可据此确认哪些页面被栅格化
+    #[serde(default)]
+    pub page_modes: BTreeMap<u32, String>,
+    /// 脱敏过程中未能完成、但不影响脱敏结果的步骤（如未能迁移书签）
+    #[serde(default)]
+    pub redact_warnings: Vec<String>,
+    /// 清理选项
+    pub clean: CleanOptions,
+    /// 实际执行的清理动作
+    pub clean_actions: Vec<String>,
+    /// 命中列表
+    pub hits: Vec<TaskHit>,
+    /// 实际应用的遮罩（按页，页码从 1 开始）
+    #[serde(default)]
+    pub masks: BTreeMap<u32, Vec<Region>>,
+    /// 校验结果
+    pub verify: VerifySummary,
+    /// 任务是否成功
+    pub success: bool,
+}
+
+impl TaskAudit {
+    /// 计算输入输出摘要并创建审计记录，其余字段取默认值，由调用方按需填写
+    pub fn new(input: &Path, output: Option<&Path>) -> Result<Self> {
+        Ok(Self {
+            tool_version: env!("CARGO_PKG_VERSION").to_string(),
+            created_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or_default(),
+            operator: None,
+            input: digest(input)?,
+            output: output.map(digest).transpose()?,
+            rule_pack: None,
+            rules_hash: String::new(),
+            ocr_mode: OcrMode::Clear,
+            ocr_engine: None,
+            redact_mode: None,
+            page_modes: BTreeMap::new(),
+            redact_warnings: Vec::new(),
+            clean: CleanOptions::default(),
+            clean_actions: Vec::new(),
+            hits: Vec::new(),
+            masks: BTreeMap::new(),
+            verify: VerifySummary::default(),
+            success: true,
+        })
+    }
+
+    /// 根据任务配置和执行结果构建审计记录
+    pub fn build(
+        config: &TaskConfig,
+        output: Option<&Path>,
+        hits: &[TaskHit],
+        clean_actions: &[String],
+        verify: &VerifySummary,
+    ) -> Result<Self> {
+        let rules_hash = match &config.rules_hash {
+            Some(hash) => hash.clone(),
+            None => sha256_bytes(&serde_json::to_vec(&config.rules)?),
+        };
+        let defaults = Self::new(Path::new(&config.input_path), output)?;
+        Ok(Self {
+            tool_version: config
+                .tool_version
+                .clone()
+                .unwrap_or(defaults.tool_version.clone()),
+            operator: config.operator.clone(),
+            rule_pack: config.rule_pack.clone(),
+            rules_hash,
+            ocr_mode: config.ocr_mode,
+            ocr_engine: config.ocr_engine.clone(),
+            clean: config.clean.clone(),
+            clean_actions: clean_actions.to_vec(),
+            hits: hits.to_vec(),
+            ..defaults
+        }
+        .with_verify(verify.clone()))
+    }
+
+    /// 记录校验结果：执行了校验且未通过时任务视为失败
+    pub fn with_verify(mut self, verify: VerifySummary) -> Self {
+        self.success = verify.ok || !verify.performed;
+        self.verify = verify;
+        self
+    }
+}
+
+fn digest(path: &Path) -> Result<FileDigest> {
+    Ok(FileDigest {
+        path: path.to_string_lossy().to_string(),
+        sha256: sha256_file(path)?,
+    })
+}
+
+/// 写出审计记录
+pub fn write_audit(audit: &TaskAudit, path: &Path) -> Result<()> {
+    pipeline::write_json(audit, path)
+}
+
+/// 读取审计记录
+pub fn read_audit(path: &Path) -> Result<TaskAudit> {
+    Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+}
+
+/// 计算文件的 SHA-256
+pub fn sha256_file(path: &Path) -> Result<String> {
+    Ok(sha256_bytes(&fs::read(path)?))
+}
+
+/// 计算数据的 SHA-256（十六进制小写）
+pub fn sha256_bytes(data: &[u8]) -> String {
+    hex::encode(Sha256::digest(data))
+}
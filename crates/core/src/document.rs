@@ -2,9 +2,13 @@
 //!
 //! 所有文件处理器都必须实现 `Document` trait，以确保统一的处理流程。
 
-use crate::rules::RuleSet;
+use crate::pipeline::{self, Region, TaskHit, VerifySummary};
+use crate::rules::CompiledRuleSet;
+use crate::{CleanOptions, VerifyOptions};
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
 
 /// 页面数据结构
 ///
@@ -18,6 +22,44 @@ pub struct Page {
     pub content: String,
 }
 
+/// 清理结果
+///
+/// 记录清理后的文档数据以及实际执行的清理动作，供审计使用。
+#[derive(Debug, Clone, Default)]
+pub struct CleanOutcome {
+    /// 清理后的文档二进制数据
+    pub data: Vec<u8>,
+    /// 已执行的清理动作描述
+    pub actions: Vec<String>,
+}
+
+/// 脱敏结果
+///
+/// 记录脱敏后的文档数据，以及处理器实际采用的脱敏方式和遮罩，供审计使用。
+#[derive(Debug, Clone, Default)]
+pub struct RedactOutcome {
+    /// 已脱敏文档的二进制数据
+    pub data: Vec<u8>,
+    /// 实际使用的脱敏方式（如 PDF 的 safe_render），未区分方式的处理器为空
+    pub mode: Option<String>,
+    /// 各页实际使用的脱敏方式（页码从 1 开始），整份文档使用同一方式的处理器为空
+    pub page_modes: BTreeMap<u32, String>,
+    /// 实际应用的遮罩（按页，页码从 1 开始），只按文本替换的处理器为空
+    pub masks: BTreeMap<u32, Vec<Region>>,
+    /// 未能完成、但不影响脱敏结果的步骤（如未能迁移书签），
+    /// 以及处理器在脱敏前必须执行的清理（如 Word 接受修订），写入审计记录
+    pub warnings: Vec<String>,
+}
+
+impl From<Vec<u8>> for RedactOutcome {
+    fn from(data: Vec<u8>) -> Self {
+        Self {
+            data,
+            ..Self::default()
+        }
+    }
+}
+
 /// 统一文档接口
 ///
 /// 这是所有文件处理器的"资格认证标准"。任何处理器都必须实现这些方法。
@@ -44,16 +86,65 @@ pub trait Document: Send + Sync {
     /// 页面列表，每个页面包含页码和文本内容
     fn get_pages(&self) -> Result<Vec<Page>>;
 
+    /// 检测敏感信息
+    ///
+    /// 默认对 `get_pages` 返回的每页文本执行规则匹配。命中来自其他途径
+    /// （如带位置的检测、用户确认的遮罩）的处理器可以覆盖此方法。
+    ///
+    /// # 返回
+    /// 命中列表，片段已脱敏
+    fn detect(&self, ruleset: &CompiledRuleSet) -> Result<Vec<TaskHit>> {
+        Ok(pipeline::collect_hits(&self.get_pages()?, ruleset))
+    }
+
     /// 执行脱敏
     ///
     /// 根据规则集对文档进行脱敏处理。
     ///
     /// # 参数
-    /// - `ruleset`: 包含所有匹配规则的集合
+    /// - `ruleset`: 已编译的规则集，由调用方编译一次并报告编译错误
     ///
     /// # 返回
-    /// 已脱敏文档的二进制数据
-    fn redact(&self, ruleset: &RuleSet) -> Result<Vec<u8>>;
+    /// 已脱敏文档的二进制数据，以及实际采用的脱敏方式和遮罩
+    fn redact(&self, ruleset: &CompiledRuleSet) -> Result<RedactOutcome>;
+
+    /// 清理隐藏数据
+    ///
+    /// 对已脱敏的文档数据执行元数据、注释、表单等清理。
+    /// 默认实现不做任何处理，适用于没有隐藏数据概念的格式（如 .txt）。
+    ///
+    /// # 参数
+    /// - `data`: 已脱敏文档的二进制数据
+    /// - `options`: 清理选项
+    ///
+    /// # 返回
+    /// 清理后的数据及执行的清理动作
+    fn clean(&self, data: Vec<u8>, _options: &CleanOptions) -> Result<CleanOutcome> {
+        Ok(CleanOutcome {
+            data,
+            actions: Vec::new(),
+        })
+    }
+
+    /// 校验输出
+    ///
+    /// 默认重新加载输出文件并再次检测，仍有命中即视为未通过；不支持 OCR 抽样。
+    ///
+    /// # 参数
+    /// - `output`: 已写出的脱敏文件
+    /// - `ruleset`: 脱敏时使用的规则集
+    /// - `options`: 校验选项
+    fn verify(
+        &self,
+        output: &Path,
+        ruleset: &CompiledRuleSet,
+        options: &VerifyOptions,
+    ) -> Result<VerifySummary>
+    where
+        Self: Sized,
+    {
+        pipeline::verify_reloaded::<Self>(output, ruleset, options)
+    }
 
     /// 声明支持的功能
     ///
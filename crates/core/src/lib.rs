@@ -1,10 +1,17 @@
 //! Core orchestration for redaction tasks.
 
+pub mod audit;
 pub mod document;
+pub mod pipeline;
 pub mod rules;
 
-pub use document::{Document, Page};
-pub use rules::{HeuristicType, Rule, RuleMatch, RuleSet, RuleType};
+pub use audit::{OcrEngineInfo, TaskAudit};
+pub use document::{CleanOutcome, Document, Page, RedactOutcome};
+pub use pipeline::{mask_snippet, run_task, run_task_with, Region, TaskHit, VerifySummary};
+pub use rules::{
+    CompiledRuleSet, HeuristicType, MatchReason, OverlapStrategy, Rule, RuleCompileError,
+    RuleContext, RuleMatch, RuleSet, RuleType,
+};
 
 use serde::{Deserialize, Serialize};
 
@@ -16,6 +23,22 @@ pub enum CoreError {
     Unsupported(&'static str),
     #[error("invalid configuration: {0}")]
     InvalidConfig(&'static str),
+    #[error("invalid rules: {}", describe_compile_errors(.0))]
+    RuleCompile(Vec<RuleCompileError>),
+    #[error("document error: {0}")]
+    Document(#[from] anyhow::Error),
+    #[error("io error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("json error: {0}")]
+    Json(#[from] serde_json::Error),
+}
+
+fn describe_compile_errors(errors: &[RuleCompileError]) -> String {
+    errors
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join("; ")
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -25,7 +48,25 @@ pub struct TaskConfig {
     pub ocr_mode: OcrMode,
     pub clean: CleanOptions,
     pub verify: VerifyOptions,
-    pub rule_pack: RulePackRef,
+    /// 规则来自规则包时的规则包引用
+    #[serde(default)]
+    pub rule_pack: Option<RulePackRef>,
+    /// 本次任务使用的规则集
+    #[serde(default)]
+    pub rules: RuleSet,
+    /// 规则摘要；规则中有 `RuleSet` 无法表示的部分（如区域规则）时由调用方提供，
+    /// 为空时按 `rules` 计算
+    #[serde(default)]
+    pub rules_hash: Option<String>,
+    /// 操作者（写入审计记录）
+    #[serde(default)]
+    pub operator: Option<String>,
+    /// 检测阶段使用的 OCR 引擎（写入审计记录）
+    #[serde(default)]
+    pub ocr_engine: Option<OcrEngineInfo>,
+    /// 调用方的版本（写入审计记录），为空时记录 linch-core 的版本
+    #[serde(default)]
+    pub tool_version: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -33,6 +74,9 @@ pub struct RulePackRef {
     pub name: String,
     pub version: String,
     pub hash: String,
+    /// 签名所用的受信公钥（十六进制），未签名时为空
+    #[serde(default)]
+    pub signer: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -42,19 +86,28 @@ pub struct TaskResult {
     pub audit_path: Option<String>,
     pub report_path: Option<String>,
     pub message: Option<String>,
+    /// 命中的敏感信息数量
+    #[serde(default)]
+    pub hits_count: usize,
+    /// 脱敏过程中的警告和校验阶段发现的问题
+    #[serde(default)]
+    pub warnings: Vec<String>,
 }
 
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum OcrMode {
     Detect,
     Clear,
     Rebuild,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct CleanOptions {
     pub metadata: bool,
     pub xmp: bool,
+    /// 隐藏数据（PieceInfo、LastModified 等）
+    #[serde(default)]
+    pub hidden: bool,
     pub annots: bool,
     pub forms: bool,
     pub attachments: bool,
@@ -65,8 +118,29 @@ pub struct CleanOptions {
 pub struct VerifyOptions {
     pub text_search: bool,
     pub ocr_sample: bool,
+    /// OCR 抽样页数
+    #[serde(default = "default_ocr_sample_pages")]
+    pub ocr_sample_pages: usize,
+    /// OCR 渲染分辨率
+    #[serde(default = "default_ocr_dpi")]
+    pub ocr_dpi: u32,
+}
+
+fn default_ocr_sample_pages() -> usize {
+    3
+}
+
+fn default_ocr_dpi() -> u32 {
+    150
 }
 
-pub fn run_task(_config: TaskConfig) -> Result<TaskResult> {
-    Err(CoreError::Unsupported("pipeline not wired yet"))
+impl Default for VerifyOptions {
+    fn default() -> Self {
+        Self {
+            text_search: true,
+            ocr_sample: false,
+            ocr_sample_pages: default_ocr_sample_pages(),
+            ocr_dpi: default_ocr_dpi(),
+        }
+    }
 }
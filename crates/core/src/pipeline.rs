@@ -0,0 +1,659 @@
+//! 脱敏任务编排
+//!
+//! 按 docs/solution.md 描述的流水线驱动一次完整的脱敏任务：
+//! 加载 → 分析 → 规则匹配 → 合并命中 → 应用脱敏 → 清理 → 写出 → 校验 → 审计与报告。
+//!
+//! 编排层只依赖 `Document` trait，具体格式由调用方通过泛型参数选择处理器。
+
+use crate::audit::{self, TaskAudit};
+use crate::document::{Document, Page};
+use crate::rules::CompiledRuleSet;
+use crate::{CoreError, OcrMode, Result, TaskConfig, TaskResult, VerifyOptions};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+/// 页面上的矩形区域（左上角原点的相对坐标 0-1）
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Region {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+/// 任务命中记录
+///
+/// 记录一次规则命中的页码、位置和脱敏后的片段，原文不会被保存。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskHit {
+    /// 页码，从 1 开始
+    pub page: u32,
+    /// 命中的规则 ID
+    pub rule_id: String,
+    /// 命中的规则名称
+    pub rule_name: String,
+    /// 脱敏显示的片段
+    pub snippet: String,
+    /// 页内字节范围，按位置检测的命中（区域、页面规则）为空
+    #[serde(default)]
+    pub range: Option<Range<usize>>,
+    /// 命中区域，只有文本偏移的命中为空
+    #[serde(default)]
+    pub bbox: Option<Region>,
+    /// 置信度（0-1），区域、页面规则为空
+    #[serde(default)]
+    pub confidence: Option<f32>,
+}
+
+/// 合并后的脱敏区间
+///
+/// 同一页内重叠或相邻的命中会被合并为一个区间。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MergedSpan {
+    /// 页码，从 1 开始
+    pub page: u32,
+    /// 起始位置（字节偏移）
+    pub start: usize,
+    /// 结束位置（字节偏移）
+    pub end: usize,
+    /// 参与合并的规则 ID
+    pub rule_ids: Vec<String>,
+}
+
+/// 输出校验结果
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct VerifySummary {
+    /// 是否执行了校验
+    pub performed: bool,
+    /// 是否通过（未发现残留敏感信息）
+    pub ok: bool,
+    /// 校验过程中的警告
+    pub warnings: Vec<String>,
+    /// 跳过的检查和提取错误等说明（不代表发现残留）
+    #[serde(default)]
+    pub notes: Vec<String>,
+    /// 实际执行 OCR 抽样的页码
+    #[serde(default)]
+    pub ocr_pages: Vec<u32>,
+}
+
+/// 任务报告
+///
+/// 汇总本次任务涉及的页面、命中、清理动作和校验结果。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskReport {
+    pub input_path: String,
+    pub output_path: Option<String>,
+    pub pages_touched: Vec<u32>,
+    pub hits: Vec<TaskHit>,
+    pub spans: Vec<MergedSpan>,
+    /// 脱敏过程中的警告
+    #[serde(default)]
+    pub redact_warnings: Vec<String>,
+    pub clean_actions: Vec<String>,
+    pub verify: VerifySummary,
+}
+
+/// 执行脱敏任务
+///
+/// 使用处理器 `D` 完成整条脱敏流水线，并在输出文件旁写出
+/// `<文件名>.audit.json` 与 `<文件名>.report.json`。
+///
+/// # 参数
+/// - `config`: 任务配置（输入输出路径、规则、OCR 模式、清理与校验选项）
+///
+/// # 返回
+/// 包含输出、审计和报告路径的任务结果
+///
+/// # 错误
+/// - 输入与输出路径相同（绝不覆盖源文件）
+/// - 规则无法编译（返回全部出错的规则）
+/// - OCR 模式为 `Rebuild`（尚未支持）
+/// - 文档加载、脱敏或写出失败
+pub fn run_task<D: Document>(config: TaskConfig) -> Result<TaskResult> {
+    let rules = prepare(&config)?;
+
+    // 1. 加载
+    let doc = D::load(Path::new(&config.input_path))?;
+
+    execute(&doc, &config, &rules)
+}
+
+/// 使用已加载的文档执行脱敏任务
+///
+/// 与 `run_task` 相同，但文档由调用方构造，适用于需要携带额外状态
+/// （如用户确认的遮罩、渲染参数）的处理器。
+pub fn run_task_with<D: Document>(doc: &D, config: TaskConfig) -> Result<TaskResult> {
+    let rules = prepare(&config)?;
+    execute(doc, &config, &rules)
+}
+
+/// 检查任务配置并编译规则
+fn prepare(config: &TaskConfig) -> Result<CompiledRuleSet> {
+    ensure_distinct_paths(
+        Path::new(&config.input_path),
+        Path::new(&config.output_path),
+    )?;
+    if config.ocr_mode == OcrMode::Rebuild {
+        return Err(CoreError::Unsupported("OCR rebuild mode"));
+    }
+    config.rules.compile().map_err(CoreError::RuleCompile)
+}
+
+fn execute<D: Document>(
+    doc: &D,
+    config: &TaskConfig,
+    rules: &CompiledRuleSet,
+) -> Result<TaskResult> {
+    let output = Path::new(&config.output_path);
+
+    // 2-3. 分析与规则匹配
+    let hits = doc.detect(rules)?;
+
+    // 4. 合并命中
+    let spans = merge_hits(&hits);
+
+    // Detect 模式只生成报告，不写出脱敏文件
+    let detect_only = config.ocr_mode == OcrMode::Detect;
+    let mut clean_actions = Vec::new();
+    let mut verify = VerifySummary::default();
+    let mut redact_mode = None;
+    let mut page_modes = BTreeMap::new();
+    let mut redact_warnings = Vec::new();
+    let mut masks = BTreeMap::new();
+
+    if !detect_only {
+        // 5. 应用脱敏
+        let redacted = doc.redact(rules)?;
+        redact_mode = redacted.mode;
+        page_modes = redacted.page_modes;
+        redact_warnings = redacted.warnings;
+        masks = redacted.masks;
+
+        // 6. 清理
+        let cleaned = doc.clean(redacted.data, &config.clean)?;
+        clean_actions = cleaned.actions;
+
+        // 7. 写出
+        write_output(output, &cleaned.data)?;
+
+        // 8. 校验
+        verify = doc.verify(output, rules, &config.verify)?;
+    }
+
+    // 9. 审计与报告
+    let written_output = (!detect_only).then_some(output);
+    let audit_path = sidecar_path(output, "audit.json");
+    let report_path = sidecar_path(output, "report.json");
+
+    let audit = TaskAudit {
+        redact_mode,
+        page_modes,
+        redact_warnings: redact_warnings.clone(),
+        masks,
+        ..TaskAudit::build(config, written_output, &hits, &clean_actions, &verify)?
+    };
+    audit::write_audit(&audit, &audit_path)?;
+
+    let report = TaskReport {
+        input_path: config.input_path.clone(),
+        output_path: written_output.map(|p| p.to_string_lossy().to_string()),
+        pages_touched: pages_touched(&hits),
+        hits,
+        spans,
+        redact_warnings: redact_warnings.clone(),
+        clean_actions,
+        verify: verify.clone(),
+    };
+    write_json(&report, &report_path)?;
+
+    let hits_count = report.hits.len();
+    let message = if detect_only {
+        format!("检测完成，共发现 {} 处敏感信息", hits_count)
+    } else if verify.ok {
+        format!("脱敏完成，共处理 {} 处敏感信息", hits_count)
+    } else {
+        format!("脱敏完成，但校验发现 {} 个问题", verify.warnings.len())
+    };
+
+    Ok(TaskResult {
+        success: detect_only || verify.ok,
+        output_path: report.output_path,
+        audit_path: Some(audit_path.to_string_lossy().to_string()),
+        report_path: Some(report_path.to_string_lossy().to_string()),
+        message: Some(message),
+        hits_count,
+        warnings: redact_warnings.into_iter().chain(verify.warnings).collect(),
+    })
+}
+
+/// 脱敏显示 snippet
+///
+/// 短文本全部替换为 `*`，长文本只保留首尾少量字符。
+pub fn mask_snippet(text: &str) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let len = chars.len();
+
+    if len <= 4 {
+        "*".repeat(len)
+    } else {
+        let visible = 4.min(len / 3);
+        let prefix: String = chars[..visible].iter().collect();
+        let suffix: String = chars[len - visible..].iter().collect();
+        format!("{}****{}", prefix, suffix)
+    }
+}
+
+/// 确保输出不会覆盖源文件
+fn ensure_distinct_paths(input: &Path, output: &Path) -> Result<()> {
+    let input = input.canonicalize()?;
+    let output = match output.canonicalize() {
+        Ok(path) => path,
+        // 输出文件尚不存在时，比较其所在目录
+        Err(_) => {
+            let parent = match output.parent() {
+                Some(p) if !p.as_os_str().is_empty() => p,
+                _ => Path::new("."),
+            };
+            match (parent.canonicalize(), output.file_name()) {
+                (Ok(parent), Some(name)) => parent.join(name),
+                _ => output.to_path_buf(),
+            }
+        }
+    };
+
+    if input == output {
+        return Err(CoreError::InvalidConfig(
+            "output path must differ from input path",
+        ));
+    }
+    Ok(())
+}
+
+/// 对每页文本进行规则匹配
+pub(crate) fn collect_hits(pages: &[Page], rules: &CompiledRuleSet) -> Vec<TaskHit> {
+    let mut hits = Vec::new();
+    for page in pages {
+        for m in rules.match_text(&page.content) {
+            hits.push(TaskHit {
+                page: page.page_number,
+                rule_id: m.rule_id,
+                rule_name: m.rule_name,
+                snippet: mask_snippet(&m.matched_text),
+                range: Some(m.start..m.end),
+                bbox: None,
+                confidence: Some(m.confidence),
+            });
+        }
+    }
+    hits
+}
+
+/// 按页合并重叠或相邻的命中区间，没有字节范围的命中不参与合并
+fn merge_hits(hits: &[TaskHit]) -> Vec<MergedSpan> {
+    let mut sorted: Vec<(&TaskHit, Range<usize>)> = hits
+        .iter()
+        .filter_map(|h| h.range.clone().map(|range| (h, range)))
+        .collect();
+    sorted.sort_by_key(|(h, range)| (h.page, range.start, range.end));
+
+    let mut spans: Vec<MergedSpan> = Vec::new();
+    for (hit, range) in sorted {
+        if let Some(last) = spans.last_mut() {
+            if last.page == hit.page && range.start <= last.end {
+                last.end = last.end.max(range.end);
+                if !last.rule_ids.contains(&hit.rule_id) {
+                    last.rule_ids.push(hit.rule_id.clone());
+                }
+                continue;
+            }
+        }
+        spans.push(MergedSpan {
+            page: hit.page,
+            start: range.start,
+            end: range.end,
+            rule_ids: vec![hit.rule_id.clone()],
+        });
+    }
+    spans
+}
+
+fn pages_touched(hits: &[TaskHit]) -> Vec<u32> {
+    let pages: BTreeSet<u32> = hits.iter().map(|h| h.page).collect();
+    pages.into_iter().collect()
+}
+
+/// 重新加载输出文件，检查是否仍能匹配到敏感信息
+pub(crate) fn verify_reloaded<D: Document>(
+    output: &Path,
+    rules: &CompiledRuleSet,
+    options: &VerifyOptions,
+) -> anyhow::Result<VerifySummary> {
+    let mut summary = VerifySummary {
+        performed: options.text_search || options.ocr_sample,
+        ok: true,
+        ..VerifySummary::default()
+    };
+
+    if options.text_search {
+        let redacted = D::load(output)?;
+        let residual = redacted.detect(rules)?;
+        for hit in &residual {
+            summary.warnings.push(format!(
+                "第 {} 页仍检测到「{}」: {}",
+                hit.page, hit.rule_name, hit.snippet
+            ));
+        }
+        summary.ok = residual.is_empty();
+    }
+
+    if options.ocr_sample {
+        summary
+            .notes
+            .push("当前处理器不支持 OCR 抽样校验，已跳过".to_string());
+    }
+
+    Ok(summary)
+}
+
+fn write_output(output: &Path, data: &[u8]) -> Result<()> {
+    if let Some(parent) = output.parent() {
+        if !parent.as_os_str().is_empty() {
+            fs::create_dir_all(parent)?;
+        }
+    }
+    fs::write(output, data)?;
+    Ok(())
+}
+
+/// 生成输出文件旁的附属文件路径，如 `a.pdf` → `a.audit.json`
+pub fn sidecar_path(output: &Path, suffix: &str) -> PathBuf {
+    let stem = output
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_else(|| "output".to_string());
+    output.with_file_name(format!("{}.{}", stem, suffix))
+}
+
+pub(crate) fn write_json<T: Serialize>(value: &T, path: &Path) -> Result<()> {
+    let raw = serde_json::to_string_pretty(value)?;
+    write_output(path, raw.as_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::document::RedactOutcome;
+    use crate::rules::{Rule, RuleSet, RuleType};
+    use crate::{CleanOptions, RulePackRef, VerifyOptions};
+
+    /// 测试用的纯文本处理器
+    struct PlainDocument {
+        content: String,
+    }
+
+    impl Document for PlainDocument {
+        fn load(path: &Path) -> anyhow::Result<Self> {
+            Ok(Self {
+                content: fs::read_to_string(path)?,
+            })
+        }
+
+        fn get_pages(&self) -> anyhow::Result<Vec<Page>> {
+            Ok(vec![Page {
+                page_number: 1,
+                content: self.content.clone(),
+            }])
+        }
+
+        fn redact(&self, ruleset: &CompiledRuleSet) -> anyhow::Result<RedactOutcome> {
+            Ok(ruleset.redact_text(&self.content, None).into_bytes().into())
+        }
+
+        fn get_supported_features(&self) -> Vec<String> {
+            vec!["text_redact".to_string()]
+        }
+    }
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("linch-core-{}-{}", name, std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn config(input: &Path, output: &Path, mode: OcrMode) -> TaskConfig {
+        let mut rules = RuleSet::new();
+        rules.add(Rule {
+            id: "phone".to_string(),
+            name: "手机号".to_string(),
+            enabled: true,
+            is_system: false,
+            rule_type: RuleType::Regex(r"1[3-9]\d{9}".to_string()),
+            context: None,
+        });
+        TaskConfig {
+            input_path: input.to_string_lossy().to_string(),
+            output_path: output.to_string_lossy().to_string(),
+            ocr_mode: mode,
+            clean: CleanOptions::default(),
+            verify: VerifyOptions::default(),
+            rule_pack: Some(RulePackRef {
+                name: "test".to_string(),
+                version: "1".to_string(),
+                hash: String::new(),
+                signer: None,
+            }),
+            rules,
+            rules_hash: None,
+            operator: None,
+            ocr_engine: None,
+            tool_version: None,
+        }
+    }
+
+    #[test]
+    fn test_run_task_writes_output_audit_and_report() {
+        let dir = temp_dir("run");
+        let input = dir.join("input.txt");
+        let output = dir.join("out").join("input__redacted.txt");
+        fs::write(&input, "联系电话 13812345678，备用 13987654321").unwrap();
+
+        let result = run_task::<PlainDocument>(config(&input, &output, OcrMode::Clear)).unwrap();
+
+        assert!(result.success);
+        assert_eq!(result.hits_count, 2);
+        let redacted = fs::read_to_string(&output).unwrap();
+        assert!(!redacted.contains("13812345678"));
+        assert!(Path::new(result.audit_path.as_deref().unwrap()).exists());
+        assert!(Path::new(result.report_path.as_deref().unwrap()).exists());
+
+        fs::remove_dir_all(dir).ok();
+    }
+
+    #[test]
+    fn test_run_task_detect_only_does_not_write_output() {
+        let dir = temp_dir("detect");
+        let input = dir.join("input.txt");
+        let output = dir.join("input__redacted.txt");
+        fs::write(&input, "13812345678").unwrap();
+
+        let result = run_task::<PlainDocument>(config(&input, &output, OcrMode::Detect)).unwrap();
+
+        assert!(result.output_path.is_none());
+        assert!(!output.exists());
+        assert_eq!(result.hits_count, 1);
+
+        fs::remove_dir_all(dir).ok();
+    }
+
+    #[test]
+    fn test_run_task_refuses_to_overwrite_input() {
+        let dir = temp_dir("overwrite");
+        let input = dir.join("input.txt");
+        fs::write(&input, "13812345678").unwrap();
+
+        let result = run_task::<PlainDocument>(config(&input, &input, OcrMode::Clear));
+
+        assert!(matches!(result, Err(CoreError::InvalidConfig(_))));
+        assert_eq!(fs::read_to_string(&input).unwrap(), "13812345678");
+
+        fs::remove_dir_all(dir).ok();
+    }
+
+    #[test]
+    fn test_run_task_reports_rule_compile_errors() {
+        let dir = temp_dir("compile");
+        let input = dir.join("input.txt");
+        let output = dir.join("input__redacted.txt");
+        fs::write(&input, "13812345678").unwrap();
+
+        let mut config = config(&input, &output, OcrMode::Clear);
+        config.rules.add(Rule {
+            id: "broken".to_string(),
+            name: "broken".to_string(),
+            enabled: true,
+            is_system: false,
+            rule_type: RuleType::Regex("(".to_string()),
+            context: None,
+        });
+        let result = run_task::<PlainDocument>(config);
+
+        match result {
+            Err(CoreError::RuleCompile(errors)) => assert_eq!(errors[0].rule_id, "broken"),
+            other => panic!("unexpected result: {:?}", other.map(|r| r.success)),
+        }
+        assert!(!output.exists());
+
+        fs::remove_dir_all(dir).ok();
+    }
+
+    /// 命中和遮罩由调用方提供的处理器
+    struct MaskedDocument {
+        hits: Vec<TaskHit>,
+    }
+
+    impl Document for MaskedDocument {
+        fn load(_path: &Path) -> anyhow::Result<Self> {
+            anyhow::bail!("MaskedDocument 只能通过 run_task_with 使用")
+        }
+
+        fn get_pages(&self) -> anyhow::Result<Vec<Page>> {
+            Ok(Vec::new())
+        }
+
+        fn detect(&self, _ruleset: &CompiledRuleSet) -> anyhow::Result<Vec<TaskHit>> {
+            Ok(self.hits.clone())
+        }
+
+        fn redact(&self, _ruleset: &CompiledRuleSet) -> anyhow::Result<RedactOutcome> {
+            let region = Region {
+                x: 0.1,
+                y: 0.1,
+                width: 0.2,
+                height: 0.2,
+            };
+            Ok(RedactOutcome {
+                data: b"masked".to_vec(),
+                mode: Some("safe_render".to_string()),
+                page_modes: BTreeMap::from([(2, "safe_render".to_string())]),
+                masks: BTreeMap::from([(2, vec![region])]),
+                warnings: vec!["未能迁移书签".to_string()],
+            })
+        }
+
+        fn verify(
+            &self,
+            _output: &Path,
+            _ruleset: &CompiledRuleSet,
+            _options: &VerifyOptions,
+        ) -> anyhow::Result<VerifySummary> {
+            Ok(VerifySummary {
+                performed: true,
+                ok: true,
+                ..VerifySummary::default()
+            })
+        }
+
+        fn get_supported_features(&self) -> Vec<String> {
+            Vec::new()
+        }
+    }
+
+    #[test]
+    fn test_run_task_with_records_redact_outcome() {
+        let dir = temp_dir("with");
+        let input = dir.join("input.pdf");
+        let output = dir.join("input__redacted.pdf");
+        fs::write(&input, "original").unwrap();
+
+        let doc = MaskedDocument {
+            hits: vec![TaskHit {
+                page: 2,
+                rule_id: "region".to_string(),
+                rule_name: "区域".to_string(),
+                snippet: String::new(),
+                range: None,
+                bbox: None,
+                confidence: None,
+            }],
+        };
+        let mut config = config(&input, &output, OcrMode::Clear);
+        config.rules_hash = Some("caller".to_string());
+        config.operator = Some("alice".to_string());
+        let result = run_task_with(&doc, config).unwrap();
+
+        assert!(result.success);
+        assert_eq!(result.warnings, vec!["未能迁移书签".to_string()]);
+        assert_eq!(fs::read(&output).unwrap(), b"masked");
+        let audit: TaskAudit =
+            serde_json::from_str(&fs::read_to_string(result.audit_path.unwrap()).unwrap()).unwrap();
+        assert_eq!(audit.redact_mode.as_deref(), Some("safe_render"));
+        assert_eq!(audit.page_modes[&2], "safe_render");
+        assert_eq!(audit.masks[&2].len(), 1);
+        assert_eq!(audit.redact_warnings, vec!["未能迁移书签".to_string()]);
+        assert_eq!(audit.rules_hash, "caller");
+        assert_eq!(audit.operator.as_deref(), Some("alice"));
+        let report: TaskReport =
+            serde_json::from_str(&fs::read_to_string(result.report_path.unwrap()).unwrap())
+                .unwrap();
+        assert_eq!(report.pages_touched, vec![2]);
+
+        fs::remove_dir_all(dir).ok();
+    }
+
+    #[test]
+    fn test_merge_hits_joins_overlapping_spans() {
+        let hit = |start, end, rule: &str| TaskHit {
+            page: 1,
+            rule_id: rule.to_string(),
+            rule_name: rule.to_string(),
+            snippet: String::new(),
+            range: Some(start..end),
+            bbox: None,
+            confidence: None,
+        };
+        let spans = merge_hits(&[
+            hit(0, 5, "a"),
+            hit(3, 8, "b"),
+            hit(8, 10, "a"),
+            hit(20, 22, "c"),
+            TaskHit {
+                range: None,
+                ..hit(0, 0, "region")
+            },
+        ]);
+
+        assert_eq!(spans.len(), 2);
+        assert_eq!((spans[0].start, spans[0].end), (0, 10));
+        assert_eq!(spans[0].rule_ids, vec!["a".to_string(), "b".to_string()]);
+    }
+
+    #[test]
+    fn test_mask_snippet() {
+        assert_eq!(mask_snippet("abc"), "***");
+        assert_eq!(mask_snippet("13812345678"), "138****678");
+    }
+}
@@ -0,0 +1,402 @@
+//! 预编译规则集
+//!
+//! `RuleSet` 只描述规则，`CompiledRuleSet` 把规则编译一次后反复匹配：
+//! - 正则规则各自编译，并合成一个 `RegexSet` 预筛，文本中不出现的正则不再逐个查找
+//! - 词典规则各建一个 Aho-Corasick 自动机，一次扫描找出所有词条，耗时与词条数量无关
+//! - 启发式规则使用内置的静态模式
+//! - 规则的上下文条件（含其中的正则）一并编译，在重叠处理之前过滤命中
+//!
+//! 编译失败的规则按规则 ID 报告。
+
+use aho_corasick::AhoCorasick;
+use regex::{Regex, RegexSet};
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+
+use super::context::CompiledContext;
+use super::heuristics::HeuristicMatcher;
+use super::{HeuristicType, MatchReason, OverlapStrategy, RuleMatch, RuleSet, RuleType};
+
+/// 规则编译错误
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+#[error("rule {rule_id}: {message}")]
+pub struct RuleCompileError {
+    pub rule_id: String,
+    pub message: String,
+}
+
+/// 预编译的规则集
+///
+/// 只包含启用且编译成功的规则，顺序与 `RuleSet` 中一致。
+#[derive(Debug, Clone)]
+pub struct CompiledRuleSet {
+    rules: Vec<CompiledRule>,
+    /// 所有正则规则的预筛集合，第 i 个模式对应 `regex_rules[i]`
+    regex_set: RegexSet,
+    regex_rules: Vec<usize>,
+    overlap: OverlapStrategy,
+}
+
+#[derive(Debug, Clone)]
+struct CompiledRule {
+    id: String,
+    name: String,
+    matcher: Matcher,
+    context: Option<CompiledContext>,
+}
+
+#[derive(Debug, Clone)]
+enum Matcher {
+    Regex(Regex),
+    Dictionary {
+        automaton: AhoCorasick,
+        words: Vec<String>,
+    },
+    Heuristic(HeuristicType),
+}
+
+impl CompiledRuleSet {
+    /// 编译规则集，任一启用的规则无法编译时返回所有出错的规则
+    pub fn new(ruleset: &RuleSet) -> Result<Self, Vec<RuleCompileError>> {
+        let (compiled, errors) = Self::build(ruleset);
+        if errors.is_empty() {
+            Ok(compiled)
+        } else {
+            Err(errors)
+        }
+    }
+
+    fn build(ruleset: &RuleSet) -> (Self, Vec<RuleCompileError>) {
+        let mut rules = Vec::new();
+        let mut errors = Vec::new();
+
+        for rule in ruleset.enabled_rules() {
+            let matcher = match &rule.rule_type {
+                RuleType::Regex(pattern) => Regex::new(pattern)
+                    .map(Matcher::Regex)
+                    .map_err(|e| e.to_string()),
+                RuleType::Dictionary(words) => {
+                    // 空词条会在每个位置命中，直接忽略
+                    let mut words: Vec<String> =
+                        words.iter().filter(|w| !w.is_empty()).cloned().collect();
+                    words.sort();
+                    words.dedup();
+                    AhoCorasick::new(&words)
+                        .map(|automaton| Matcher::Dictionary { automaton, words })
+                        .map_err(|e| e.to_string())
+                }
+                RuleType::Heuristic(kind) => Ok(Matcher::Heuristic(kind.clone())),
+            };
+            let context = rule.context.as_ref().map(CompiledContext::new).transpose();
+            match matcher.and_then(|matcher| Ok((matcher, context?))) {
+                Ok((matcher, context)) => rules.push(CompiledRule {
+                    id: rule.id.clone(),
+                    name: rule.name.clone(),
+                    matcher,
+                    context,
+                }),
+                Err(message) => errors.push(RuleCompileError {
+                    rule_id: rule.id.clone(),
+                    message,
+                }),
+            }
+        }
+
+        let regex_rules: Vec<usize> = rules
+            .iter()
+            .enumerate()
+            .filter(|(_, r)| matches!(r.matcher, Matcher::Regex(_)))
+            .map(|(i, _)| i)
+            .collect();
+        let patterns = regex_rules.iter().map(|&i| match &rules[i].matcher {
+            Matcher::Regex(re) => re.as_str(),
+            _ => unreachable!(),
+        });
+        // 各模式已单独编译成功，合成集合只可能因体积超限失败，此时不做预筛
+        let regex_set = RegexSet::new(patterns).unwrap_or_else(|_| RegexSet::empty());
+
+        let compiled = Self {
+            rules,
+            regex_set,
+            regex_rules,
+            overlap: ruleset.overlap,
+        };
+        (compiled, errors)
+    }
+
+    /// 已编译的规则数量
+    pub fn len(&self) -> usize {
+        self.rules.len()
+    }
+
+    /// 是否没有可用的规则
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// 对文本进行规则匹配
+    ///
+    /// 返回互不重叠、按起始位置排序的匹配结果。重叠的命中按 `overlap` 策略取舍，
+    /// 同一规则首尾相接的命中合并为一个。
+    pub fn match_text(&self, text: &str) -> Vec<RuleMatch> {
+        self.match_text_where(text, |_| true)
+    }
+
+    /// 只用 `filter` 接受的规则（按规则 ID）进行匹配
+    ///
+    /// 重叠处理只在这些规则的命中之间进行。
+    pub fn match_text_where<F>(&self, text: &str, filter: F) -> Vec<RuleMatch>
+    where
+        F: Fn(&str) -> bool,
+    {
+        // 预筛：集合为空（编译失败）时视为全部可能命中
+        let mut regex_hit = vec![self.regex_set.is_empty(); self.rules.len()];
+        if !self.regex_set.is_empty() {
+            for i in self.regex_set.matches(text).iter() {
+                regex_hit[self.regex_rules[i]] = true;
+            }
+        }
+
+        let mut matches = Vec::new();
+        for (order, rule) in self.rules.iter().enumerate() {
+            if !filter(&rule.id) {
+                continue;
+            }
+            let mut push = |start: usize,
+                            matched_text: String,
+                            confidence: f32,
+                            mut reasons: Vec<MatchReason>| {
+                let end = start + matched_text.len();
+                if let Some(context) = &rule.context {
+                    match context.check(text, start, end) {
+                        None => return,
+                        Some(words) if !words.is_empty() => {
+                            reasons.push(MatchReason::ContextWords { words })
+                        }
+                        Some(_) => {}
+                    }
+                }
+                matches.push((
+                    order,
+                    RuleMatch {
+                        rule_id: rule.id.clone(),
+                        rule_name: rule.name.clone(),
+                        start,
+                        end,
+                        matched_text,
+                        confidence,
+                        reasons,
+                    },
+                ));
+            };
+
+            match &rule.matcher {
+                Matcher::Regex(re) => {
+                    if !regex_hit[order] {
+                        continue;
+                    }
+                    for m in re.find_iter(text) {
+                        let reason = MatchReason::Pattern {
+                            pattern: re.as_str().to_string(),
+                        };
+                        push(m.start(), m.as_str().to_string(), 1.0, vec![reason]);
+                    }
+                }
+                Matcher::Dictionary { automaton, words } => {
+                    for m in automaton.find_overlapping_iter(text) {
+                        let word = &words[m.pattern().as_usize()];
+                        let reason = MatchReason::DictionaryWord { word: word.clone() };
+                        push(m.start(), word.clone(), 1.0, vec![reason]);
+                    }
+                }
+                Matcher::Heuristic(kind) => {
+                    for m in match_heuristic(kind, text) {
+                        push(m.start, m.text, m.confidence, m.reasons);
+                    }
+                }
+            }
+        }
+
+        resolve_overlaps(text, matches, self.overlap)
+    }
+
+    /// 对文本进行脱敏
+    ///
+    /// 将所有匹配的文本替换为脱敏标记，`replacement` 默认为 "█"。
+    pub fn redact_text(&self, text: &str, replacement: Option<&str>) -> String {
+        let matches = self.match_text(text);
+        if matches.is_empty() {
+            return text.to_string();
+        }
+
+        let rep = replacement.unwrap_or("█");
+        let mut result = String::with_capacity(text.len());
+        let mut last_end = 0;
+
+        for m in matches {
+            // 已被前一个命中覆盖的部分不再替换
+            let start = m.start.max(last_end);
+            if start >= m.end {
+                continue;
+            }
+            // 添加匹配之前的文本
+            result.push_str(&text[last_end..start]);
+            // 添加替换文本（根据原文长度生成）
+            let rep_len = text[start..m.end].chars().count();
+            for _ in 0..rep_len {
+                result.push_str(rep);
+            }
+            last_end = m.end;
+        }
+
+        // 添加最后一段未匹配的文本
+        if last_end < text.len() {
+            result.push_str(&text[last_end..]);
+        }
+
+        result
+    }
+}
+
+fn match_heuristic(kind: &HeuristicType, text: &str) -> Vec<super::heuristics::HeuristicMatch> {
+    match kind {
+        // 语言相关
+        HeuristicType::Address => HeuristicMatcher::match_address(text),
+        HeuristicType::PersonName => HeuristicMatcher::match_person_name(text),
+        HeuristicType::Organization => HeuristicMatcher::match_organization(text),
+        // 通用
+        HeuristicType::Date => HeuristicMatcher::match_date(text),
+        HeuristicType::Amount => HeuristicMatcher::match_amount(text),
+        HeuristicType::Phone => HeuristicMatcher::match_phone(text),
+        HeuristicType::Email => HeuristicMatcher::match_email(text),
+        HeuristicType::IdNumber => HeuristicMatcher::match_id_number(text),
+        HeuristicType::CreditCard => HeuristicMatcher::match_credit_card(text),
+    }
+}
+
+/// 按策略处理重叠的命中，并合并首尾相接的命中
+///
+/// `candidates` 中的序号为规则在启用规则中的顺序，越小越优先。
+fn resolve_overlaps(
+    text: &str,
+    mut candidates: Vec<(usize, RuleMatch)>,
+    strategy: OverlapStrategy,
+) -> Vec<RuleMatch> {
+    candidates.retain(|(_, m)| m.start < m.end);
+
+    let mut kept = match strategy {
+        OverlapStrategy::Union => candidates,
+        OverlapStrategy::LongestMatch | OverlapStrategy::RulePriority => {
+            if strategy == OverlapStrategy::LongestMatch {
+                candidates.sort_by_key(|(order, m)| (Reverse(m.end - m.start), *order, m.start));
+            } else {
+                candidates.sort_by_key(|(order, m)| (*order, Reverse(m.end - m.start), m.start));
+            }
+            // 已保留的区间互不重叠，只需检查起点在候选终点之前的最后一个区间
+            let mut taken: BTreeMap<usize, usize> = BTreeMap::new();
+            let mut kept = Vec::new();
+            for (order, m) in candidates {
+                let overlaps = taken
+                    .range(..m.end)
+                    .next_back()
+                    .is_some_and(|(_, &end)| end > m.start);
+                if !overlaps {
+                    taken.insert(m.start, m.end);
+                    kept.push((order, m));
+                }
+            }
+            kept
+        }
+    };
+    kept.sort_by_key(|(order, m)| (m.start, *order));
+
+    let mut resolved: Vec<(usize, RuleMatch)> = Vec::with_capacity(kept.len());
+    for (order, m) in kept {
+        if let Some((last_order, last)) = resolved.last_mut() {
+            let joins = match strategy {
+                OverlapStrategy::Union => m.start <= last.end,
+                _ => m.start == last.end && *last_order == order,
+            };
+            if joins {
+                if m.end > last.end {
+                    last.end = m.end;
+                    last.matched_text = text[last.start..last.end].to_string();
+                }
+                last.confidence = last.confidence.max(m.confidence);
+                for reason in m.reasons {
+                    if !last.reasons.contains(&reason) {
+                        last.reasons.push(reason);
+                    }
+                }
+                continue;
+            }
+        }
+        resolved.push((order, m));
+    }
+
+    resolved.into_iter().map(|(_, m)| m).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rules::Rule;
+
+    fn rule(id: &str, rule_type: RuleType) -> Rule {
+        Rule {
+            id: id.to_string(),
+            name: id.to_string(),
+            enabled: true,
+            is_system: false,
+            rule_type,
+            context: None,
+        }
+    }
+
+    #[test]
+    fn test_compile_errors_are_reported_per_rule() {
+        let mut rules = RuleSet::new();
+        rules.add(rule("ok", RuleType::Regex(r"\d+".to_string())));
+        rules.add(rule("bad1", RuleType::Regex("(".to_string())));
+        rules.add(rule("bad2", RuleType::Regex("[z-a]".to_string())));
+
+        let errors = CompiledRuleSet::new(&rules).unwrap_err();
+        let ids: Vec<&str> = errors.iter().map(|e| e.rule_id.as_str()).collect();
+        assert_eq!(ids, vec!["bad1", "bad2"]);
+
+        rules.rules.retain(|r| r.id == "ok");
+        assert_eq!(CompiledRuleSet::new(&rules).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_large_dictionary_and_prefilter() {
+        let mut words: Vec<String> = (0..50_000).map(|i| format!("员工{:05}", i)).collect();
+        words.push(String::new());
+        let mut rules = RuleSet::new();
+        rules.add(rule("names", RuleType::Dictionary(words)));
+        rules.add(rule("never", RuleType::Regex("zzz+".to_string())));
+        rules.add(rule(
+            "digits",
+            RuleType::Regex(r"[A-Z]{2}\d{2}".to_string()),
+        ));
+        let compiled = CompiledRuleSet::new(&rules).unwrap();
+
+        let text = "员工00042 与 员工49999 提交了 AB12";
+        let matches = compiled.match_text(text);
+        let found: Vec<(&str, &str)> = matches
+            .iter()
+            .map(|m| (m.rule_id.as_str(), &text[m.start..m.end]))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                ("names", "员工00042"),
+                ("names", "员工49999"),
+                ("digits", "AB12")
+            ]
+        );
+
+        let only_names = compiled.match_text_where(text, |id| id == "names");
+        assert_eq!(only_names.len(), 2);
+    }
+}
@@ -0,0 +1,236 @@
+//! 规则上下文条件
+//!
+//! 命中附近必须出现（或不得出现）某些词时命中才成立，用于排除模板、样例中的误报。
+//! 词语不区分大小写，正则按原样匹配：
+//!
+//! ```json
+//! {
+//!   "required": ["身份证号", { "regex": "Account\\s+No\\.?" }],
+//!   "forbidden": ["示例", "sample"],
+//!   "distance": 20,
+//!   "direction": "before",
+//!   "scope": "same_line"
+//! }
+//! ```
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+/// 上下文条件
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RuleContext {
+    /// 附近必须出现其中之一（为空时不要求）
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub required: Vec<ContextTerm>,
+    /// 附近出现其中之一时排除该命中
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub forbidden: Vec<ContextTerm>,
+    /// 查找的字符数，为空时不限（整行或整页）
+    #[serde(default)]
+    pub distance: Option<usize>,
+    /// 在命中的哪一侧查找
+    #[serde(default)]
+    pub direction: ContextDirection,
+    /// 查找范围是否跨行
+    #[serde(default)]
+    pub scope: ContextScope,
+}
+
+/// 上下文词：纯文本词语或正则表达式
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum ContextTerm {
+    Word(String),
+    Regex { regex: String },
+}
+
+/// 查找方向
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ContextDirection {
+    /// 只看命中之前
+    Before,
+    /// 只看命中之后
+    After,
+    /// 前后都看
+    #[default]
+    Around,
+}
+
+/// 查找范围
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ContextScope {
+    /// 不跨越换行
+    #[default]
+    SameLine,
+    /// 整页文本
+    SamePage,
+}
+
+/// 编译后的上下文条件
+#[derive(Debug, Clone)]
+pub(crate) struct CompiledContext {
+    required: Vec<CompiledTerm>,
+    forbidden: Vec<CompiledTerm>,
+    distance: Option<usize>,
+    direction: ContextDirection,
+    scope: ContextScope,
+}
+
+#[derive(Debug, Clone)]
+enum CompiledTerm {
+    Word { word: String, lowercase: String },
+    Regex(Regex),
+}
+
+impl CompiledTerm {
+    fn new(term: &ContextTerm) -> Result<Self, String> {
+        match term {
+            ContextTerm::Word(word) => Ok(Self::Word {
+                word: word.clone(),
+                lowercase: word.to_lowercase(),
+            }),
+            ContextTerm::Regex { regex } => Regex::new(regex)
+                .map(Self::Regex)
+                .map_err(|e| format!("context regex {}: {}", regex, e)),
+        }
+    }
+
+    /// 在片段中查找，返回找到的文本
+    fn find(&self, segment: &str) -> Option<String> {
+        match self {
+            Self::Word { word, .. } if word.is_empty() => None,
+            Self::Word { word, lowercase } => segment
+                .to_lowercase()
+                .contains(lowercase.as_str())
+                .then(|| word.clone()),
+            Self::Regex(re) => re.find(segment).map(|m| m.as_str().to_string()),
+        }
+    }
+}
+
+impl CompiledContext {
+    pub(crate) fn new(context: &RuleContext) -> Result<Self, String> {
+        let compile = |terms: &[ContextTerm]| -> Result<Vec<CompiledTerm>, String> {
+            terms.iter().map(CompiledTerm::new).collect()
+        };
+        Ok(Self {
+            required: compile(&context.required)?,
+            forbidden: compile(&context.forbidden)?,
+            distance: context.distance,
+            direction: context.direction,
+            scope: context.scope,
+        })
+    }
+
+    /// 检查 `text[start..end]` 处的命中是否满足条件
+    ///
+    /// 满足时返回附近找到的必需词（没有必需词时为空），不满足时返回 None。
+    pub(crate) fn check(&self, text: &str, start: usize, end: usize) -> Option<Vec<String>> {
+        let segments = [self.before(text, start), self.after(text, end)];
+        let find = |term: &CompiledTerm| segments.iter().find_map(|s| term.find(s));
+
+        if self.forbidden.iter().any(|term| find(term).is_some()) {
+            return None;
+        }
+        let found: Vec<String> = self.required.iter().filter_map(find).collect();
+        if !self.required.is_empty() && found.is_empty() {
+            return None;
+        }
+        Some(found)
+    }
+
+    fn before<'a>(&self, text: &'a str, start: usize) -> &'a str {
+        if self.direction == ContextDirection::After {
+            return "";
+        }
+        let mut from = start;
+        for (count, (i, c)) in text[..start].char_indices().rev().enumerate() {
+            if self.stops_at(count, c) {
+                break;
+            }
+            from = i;
+        }
+        &text[from..start]
+    }
+
+    fn after<'a>(&self, text: &'a str, end: usize) -> &'a str {
+        if self.direction == ContextDirection::Before {
+            return "";
+        }
+        let mut to = end;
+        for (count, (i, c)) in text[end..].char_indices().enumerate() {
+            if self.stops_at(count, c) {
+                break;
+            }
+            to = end + i + c.len_utf8();
+        }
+        &text[end..to]
+    }
+
+    /// 第 `count` 个字符（从命中向外数，从 0 开始）是否已超出查找范围
+    fn stops_at(&self, count: usize, c: char) -> bool {
+        self.distance.is_some_and(|d| count >= d)
+            || (self.scope == ContextScope::SameLine && matches!(c, '\n' | '\r'))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn context(json: &str) -> CompiledContext {
+        CompiledContext::new(&serde_json::from_str(json).unwrap()).unwrap()
+    }
+
+    fn check(context: &CompiledContext, text: &str, target: &str) -> Option<Vec<String>> {
+        let start = text.find(target).unwrap();
+        context.check(text, start, start + target.len())
+    }
+
+    #[test]
+    fn test_required_terms_within_distance_before() {
+        let ctx = context(
+            r#"{"required": ["身份证号", {"regex": "Account\\s+No\\.?"}], "distance": 12, "direction": "before"}"#,
+        );
+
+        assert_eq!(
+            check(&ctx, "身份证号：123456", "123456"),
+            Some(vec!["身份证号".to_string()])
+        );
+        assert_eq!(
+            check(&ctx, "account no. 123456", "123456"),
+            None,
+            "正则按原样匹配，区分大小写"
+        );
+        assert!(check(&ctx, "Account No. 123456", "123456").is_some());
+        // 超出距离、只在之后出现
+        assert_eq!(
+            check(&ctx, "身份证号码，请在下方空白处填写：123456", "123456"),
+            None
+        );
+        assert_eq!(check(&ctx, "123456 身份证号", "123456"), None);
+    }
+
+    #[test]
+    fn test_forbidden_words_and_line_scope() {
+        let ctx = context(r#"{"forbidden": ["示例", "Sample"]}"#);
+
+        assert_eq!(check(&ctx, "金额 ¥100", "¥100"), Some(Vec::new()));
+        assert_eq!(check(&ctx, "示例金额 ¥100", "¥100"), None);
+        assert_eq!(check(&ctx, "¥100 (SAMPLE)", "¥100"), None);
+        // 默认不跨行
+        assert!(check(&ctx, "示例\n¥100", "¥100").is_some());
+
+        let page = context(r#"{"forbidden": ["示例"], "scope": "same_page"}"#);
+        assert_eq!(check(&page, "示例\n¥100", "¥100"), None);
+    }
+
+    #[test]
+    fn test_invalid_regex_is_reported() {
+        let context: RuleContext =
+            serde_json::from_str(r#"{"forbidden": [{"regex": "("}]}"#).unwrap();
+        assert!(CompiledContext::new(&context).is_err());
+    }
+}
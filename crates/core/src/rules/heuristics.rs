@@ -16,6 +16,9 @@ use serde::Deserialize;
 use std::collections::{HashMap, HashSet};
 use whatlang::{detect, Lang};
 
+use super::validators::Validator;
+use super::MatchReason;
+
 // ============================================================================
 // 配置数据结构
 // ============================================================================
@@ -48,6 +51,7 @@ struct AddressConfig {
     patterns: Option<Vec<String>>,
     min_keywords: Option<usize>,
     min_length: Option<usize>,
+    context_words: Option<Vec<String>>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -57,6 +61,7 @@ struct PersonNameConfig {
     double_surnames: Option<Vec<String>>,
     name_length: Option<NameLengthConfig>,
     patterns: Option<Vec<String>>,
+    context_words: Option<Vec<String>>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -71,6 +76,7 @@ struct OrganizationConfig {
     patterns: Option<Vec<String>>,
     min_prefix_chars: usize,
     max_prefix_chars: usize,
+    context_words: Option<Vec<String>>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -85,7 +91,23 @@ struct CommonConfig {
 
 #[derive(Debug, Deserialize)]
 struct PatternConfig {
-    patterns: Vec<String>,
+    patterns: Vec<PatternEntry>,
+    /// 未通过校验器确认时的置信度
+    confidence: Option<f32>,
+    /// 命中前方出现时提高置信度的提示词
+    #[serde(default)]
+    context_words: Vec<String>,
+}
+
+/// 通用模式：纯正则，或正则加校验器
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum PatternEntry {
+    Plain(String),
+    Validated {
+        pattern: String,
+        validator: Option<Validator>,
+    },
 }
 
 // ============================================================================
@@ -113,14 +135,37 @@ static LANGUAGE_DATA: Lazy<HashMap<String, LanguageData>> = Lazy::new(|| {
 
 // 预编译正则表达式
 static COMMON_PATTERNS: Lazy<CommonPatterns> = Lazy::new(|| CommonPatterns {
-    date: compile_patterns(&CONFIG.common.date.patterns),
-    amount: compile_patterns(&CONFIG.common.amount.patterns),
-    phone: compile_patterns(&CONFIG.common.phone.patterns),
-    email: compile_patterns(&CONFIG.common.email.patterns),
-    id_number: compile_patterns(&CONFIG.common.id_number.patterns),
-    credit_card: compile_patterns(&CONFIG.common.credit_card.patterns),
+    date: compile_pattern_group(&CONFIG.common.date),
+    amount: compile_pattern_group(&CONFIG.common.amount),
+    phone: compile_pattern_group(&CONFIG.common.phone),
+    email: compile_pattern_group(&CONFIG.common.email),
+    id_number: compile_pattern_group(&CONFIG.common.id_number),
+    credit_card: compile_pattern_group(&CONFIG.common.credit_card),
 });
 
+// ============================================================================
+// 置信度
+// ============================================================================
+
+/// 通用模式未配置置信度时的默认值
+const DEFAULT_PATTERN_CONFIDENCE: f32 = 0.6;
+/// 通过校验器确认的证件号、卡号
+const CHECKSUM_CONFIDENCE: f32 = 0.95;
+/// 语言相关的正则（地址、人名、组织）
+const LANGUAGE_PATTERN_CONFIDENCE: f32 = 0.7;
+/// 地址关键词：每个关键词的增量与上限
+const ADDRESS_KEYWORD_CONFIDENCE: f32 = 0.2;
+const ADDRESS_KEYWORD_MAX_CONFIDENCE: f32 = 0.85;
+/// 以复姓、单姓开头的人名（单姓误报较多）
+const DOUBLE_SURNAME_CONFIDENCE: f32 = 0.6;
+const SINGLE_SURNAME_CONFIDENCE: f32 = 0.4;
+/// 以组织后缀结尾的名称
+const ORG_SUFFIX_CONFIDENCE: f32 = 0.6;
+/// 命中前方出现提示词时的增量
+const CONTEXT_CONFIDENCE: f32 = 0.3;
+/// 向前查找提示词的字符数
+const CONTEXT_WINDOW_CHARS: usize = 12;
+
 // ============================================================================
 // 语言数据结构
 // ============================================================================
@@ -145,15 +190,33 @@ struct LanguageData {
     org_patterns: Vec<Regex>,
     org_min_prefix: usize,
     org_max_prefix: usize,
+
+    // 提示词
+    address_context: Vec<String>,
+    name_context: Vec<String>,
+    org_context: Vec<String>,
 }
 
 struct CommonPatterns {
-    date: Vec<Regex>,
-    amount: Vec<Regex>,
-    phone: Vec<Regex>,
-    email: Vec<Regex>,
-    id_number: Vec<Regex>,
-    credit_card: Vec<Regex>,
+    date: PatternGroup,
+    amount: PatternGroup,
+    phone: PatternGroup,
+    email: PatternGroup,
+    id_number: PatternGroup,
+    credit_card: PatternGroup,
+}
+
+/// 同一类通用信息的模式
+struct PatternGroup {
+    patterns: Vec<CheckedPattern>,
+    confidence: f32,
+    context_words: Vec<String>,
+}
+
+/// 正则匹配后还需通过校验器的模式
+struct CheckedPattern {
+    regex: Regex,
+    validator: Option<Validator>,
 }
 
 // ============================================================================
@@ -251,6 +314,21 @@ fn load_language_data(lang_code: &str, config: &LanguageConfig) -> LanguageData
         org_patterns,
         org_min_prefix,
         org_max_prefix,
+        address_context: config
+            .address
+            .as_ref()
+            .and_then(|a| a.context_words.clone())
+            .unwrap_or_default(),
+        name_context: config
+            .person_name
+            .as_ref()
+            .and_then(|p| p.context_words.clone())
+            .unwrap_or_default(),
+        org_context: config
+            .organization
+            .as_ref()
+            .and_then(|o| o.context_words.clone())
+            .unwrap_or_default(),
     }
 }
 
@@ -403,6 +481,28 @@ fn compile_patterns(patterns: &[String]) -> Vec<Regex> {
     patterns.iter().filter_map(|p| Regex::new(p).ok()).collect()
 }
 
+fn compile_pattern_group(config: &PatternConfig) -> PatternGroup {
+    PatternGroup {
+        patterns: compile_checked_patterns(&config.patterns),
+        confidence: config.confidence.unwrap_or(DEFAULT_PATTERN_CONFIDENCE),
+        context_words: config.context_words.clone(),
+    }
+}
+
+fn compile_checked_patterns(patterns: &[PatternEntry]) -> Vec<CheckedPattern> {
+    patterns
+        .iter()
+        .filter_map(|entry| {
+            let (pattern, validator) = match entry {
+                PatternEntry::Plain(pattern) => (pattern, None),
+                PatternEntry::Validated { pattern, validator } => (pattern, *validator),
+            };
+            let regex = Regex::new(pattern).ok()?;
+            Some(CheckedPattern { regex, validator })
+        })
+        .collect()
+}
+
 // ============================================================================
 // 语言检测
 // ============================================================================
@@ -508,6 +608,53 @@ pub struct HeuristicMatch {
     pub text: String,
     pub start: usize,
     pub end: usize,
+    /// 置信度（0-1）
+    pub confidence: f32,
+    /// 命中依据
+    pub reasons: Vec<MatchReason>,
+}
+
+impl HeuristicMatch {
+    fn new(text: &str, start: usize, confidence: f32, reason: MatchReason) -> Self {
+        Self {
+            text: text.to_string(),
+            start,
+            end: start + text.len(),
+            confidence,
+            reasons: vec![reason],
+        }
+    }
+
+    /// 命中前方出现提示词时提高置信度
+    fn with_context(mut self, text: &str, context_words: &[String]) -> Self {
+        let words = context_words_before(text, self.start, context_words);
+        if !words.is_empty() {
+            self.confidence = (self.confidence + CONTEXT_CONFIDENCE).min(1.0);
+            self.reasons.push(MatchReason::ContextWords { words });
+        }
+        self
+    }
+}
+
+/// 查找 `start` 之前若干字符内出现的提示词（不区分大小写）
+fn context_words_before(text: &str, start: usize, context_words: &[String]) -> Vec<String> {
+    if context_words.is_empty() {
+        return Vec::new();
+    }
+    let window: String = text[..start]
+        .chars()
+        .rev()
+        .take(CONTEXT_WINDOW_CHARS)
+        .collect::<Vec<_>>()
+        .into_iter()
+        .rev()
+        .collect::<String>()
+        .to_lowercase();
+    context_words
+        .iter()
+        .filter(|word| window.contains(&word.to_lowercase()))
+        .cloned()
+        .collect()
 }
 
 // ============================================================================
@@ -555,11 +702,14 @@ impl HeuristicMatcher {
         // 1. 先尝试正则匹配
         for pattern in &lang_data.address_patterns {
             for m in pattern.find_iter(text) {
-                matches.push(HeuristicMatch {
-                    text: m.as_str().to_string(),
-                    start: m.start(),
-                    end: m.end(),
-                });
+                matches.push(HeuristicMatch::new(
+                    m.as_str(),
+                    m.start(),
+                    LANGUAGE_PATTERN_CONFIDENCE,
+                    MatchReason::Pattern {
+                        pattern: pattern.as_str().to_string(),
+                    },
+                ));
             }
         }
 
@@ -571,7 +721,7 @@ impl HeuristicMatcher {
             while i < chars.len() {
                 let segment_start = i;
                 let mut segment = String::new();
-                let mut keyword_count = 0;
+                let mut keywords = Vec::new();
 
                 while i < chars.len() && !is_separator(chars[i]) {
                     segment.push(chars[i]);
@@ -581,24 +731,30 @@ impl HeuristicMatcher {
                 if segment.chars().count() >= lang_data.address_min_length {
                     for keyword in &lang_data.address_keywords {
                         if segment.contains(keyword.as_str()) {
-                            keyword_count += 1;
+                            keywords.push(keyword.clone());
                         }
                     }
                 }
 
-                if keyword_count >= lang_data.address_min_keywords {
+                if keywords.len() >= lang_data.address_min_keywords {
                     let byte_start = text
                         .char_indices()
                         .nth(segment_start)
                         .map(|(idx, _)| idx)
                         .unwrap_or(0);
-                    let byte_end = byte_start + segment.len();
-
-                    matches.push(HeuristicMatch {
-                        text: segment,
-                        start: byte_start,
-                        end: byte_end,
-                    });
+                    let confidence = (keywords.len() as f32 * ADDRESS_KEYWORD_CONFIDENCE)
+                        .min(ADDRESS_KEYWORD_MAX_CONFIDENCE);
+                    keywords.sort();
+
+                    matches.push(HeuristicMatch::new(
+                        &segment,
+                        byte_start,
+                        confidence,
+                        MatchReason::Keywords {
+                            count: keywords.len(),
+                            keywords,
+                        },
+                    ));
                 }
 
                 while i < chars.len() && is_separator(chars[i]) {
@@ -610,6 +766,9 @@ impl HeuristicMatcher {
         matches.sort_by_key(|m| m.start);
         matches.dedup_by(|a, b| a.start == b.start);
         matches
+            .into_iter()
+            .map(|m| m.with_context(text, &lang_data.address_context))
+            .collect()
     }
 
     /// 识别人名
@@ -631,11 +790,14 @@ impl HeuristicMatcher {
         // 1. 先尝试正则匹配（主要用于英文）
         for pattern in &lang_data.name_patterns {
             for m in pattern.find_iter(text) {
-                matches.push(HeuristicMatch {
-                    text: m.as_str().to_string(),
-                    start: m.start(),
-                    end: m.end(),
-                });
+                matches.push(HeuristicMatch::new(
+                    m.as_str(),
+                    m.start(),
+                    LANGUAGE_PATTERN_CONFIDENCE,
+                    MatchReason::Pattern {
+                        pattern: pattern.as_str().to_string(),
+                    },
+                ));
             }
         }
 
@@ -659,13 +821,15 @@ impl HeuristicMatcher {
                                         chars[i..i + 2 + name_len].iter().collect();
                                     let byte_start =
                                         text.char_indices().nth(i).map(|(idx, _)| idx).unwrap_or(0);
-                                    let byte_end = byte_start + full_name.len();
 
-                                    matches.push(HeuristicMatch {
-                                        text: full_name,
-                                        start: byte_start,
-                                        end: byte_end,
-                                    });
+                                    matches.push(HeuristicMatch::new(
+                                        &full_name,
+                                        byte_start,
+                                        DOUBLE_SURNAME_CONFIDENCE,
+                                        MatchReason::Surname {
+                                            surname: surname.clone(),
+                                        },
+                                    ));
                                     i += 2 + name_len;
                                     found = true;
                                     break;
@@ -692,13 +856,15 @@ impl HeuristicMatcher {
                                             .nth(i)
                                             .map(|(idx, _)| idx)
                                             .unwrap_or(0);
-                                        let byte_end = byte_start + full_name.len();
 
-                                        matches.push(HeuristicMatch {
-                                            text: full_name,
-                                            start: byte_start,
-                                            end: byte_end,
-                                        });
+                                        matches.push(HeuristicMatch::new(
+                                            &full_name,
+                                            byte_start,
+                                            SINGLE_SURNAME_CONFIDENCE,
+                                            MatchReason::Surname {
+                                                surname: surname.clone(),
+                                            },
+                                        ));
                                         i += 1 + name_len;
                                         found = true;
                                         break;
@@ -718,6 +884,9 @@ impl HeuristicMatcher {
         matches.sort_by_key(|m| m.start);
         matches.dedup_by(|a, b| a.start == b.start);
         matches
+            .into_iter()
+            .map(|m| m.with_context(text, &lang_data.name_context))
+            .collect()
     }
 
     /// 识别组织机构名称
@@ -740,11 +909,14 @@ impl HeuristicMatcher {
         for pattern in &lang_data.org_patterns {
             for cap in pattern.captures_iter(text) {
                 if let Some(m) = cap.get(0) {
-                    matches.push(HeuristicMatch {
-                        text: m.as_str().to_string(),
-                        start: m.start(),
-                        end: m.end(),
-                    });
+                    matches.push(HeuristicMatch::new(
+                        m.as_str(),
+                        m.start(),
+                        LANGUAGE_PATTERN_CONFIDENCE,
+                        MatchReason::Pattern {
+                            pattern: pattern.as_str().to_string(),
+                        },
+                    ));
                 }
             }
         }
@@ -783,11 +955,14 @@ impl HeuristicMatcher {
                 let prefix_char_count = org_name.chars().count() - suffix.chars().count();
 
                 if prefix_char_count >= lang_data.org_min_prefix {
-                    matches.push(HeuristicMatch {
-                        text: org_name.to_string(),
-                        start: org_start,
-                        end: org_end,
-                    });
+                    matches.push(HeuristicMatch::new(
+                        org_name,
+                        org_start,
+                        ORG_SUFFIX_CONFIDENCE,
+                        MatchReason::OrganizationSuffix {
+                            suffix: suffix.clone(),
+                        },
+                    ));
                 }
 
                 start = org_end;
@@ -797,6 +972,9 @@ impl HeuristicMatcher {
         matches.sort_by_key(|m| m.start);
         matches.dedup_by_key(|m| m.start);
         matches
+            .into_iter()
+            .map(|m| m.with_context(text, &lang_data.org_context))
+            .collect()
     }
 
     // ========================================================================
@@ -834,17 +1012,31 @@ impl HeuristicMatcher {
     }
 }
 
-/// 使用正则表达式列表进行匹配
-fn match_with_patterns(text: &str, patterns: &[Regex]) -> Vec<HeuristicMatch> {
+/// 使用正则表达式列表进行匹配，配置了校验器的模式只保留通过校验的结果
+fn match_with_patterns(text: &str, group: &PatternGroup) -> Vec<HeuristicMatch> {
     let mut matches = Vec::new();
 
-    for pattern in patterns {
-        for m in pattern.find_iter(text) {
-            matches.push(HeuristicMatch {
-                text: m.as_str().to_string(),
-                start: m.start(),
-                end: m.end(),
-            });
+    for pattern in &group.patterns {
+        for m in pattern.regex.find_iter(text) {
+            let mut found = HeuristicMatch::new(
+                m.as_str(),
+                m.start(),
+                group.confidence,
+                MatchReason::Pattern {
+                    pattern: pattern.regex.as_str().to_string(),
+                },
+            );
+            if let Some(validator) = pattern.validator {
+                // 证件号、卡号不能是更长数字串的一部分
+                let in_digit_run = text[..m.start()].ends_with(|c: char| c.is_ascii_digit())
+                    || text[m.end()..].starts_with(|c: char| c.is_ascii_digit());
+                if in_digit_run || !validator.validate(m.as_str()) {
+                    continue;
+                }
+                found.confidence = found.confidence.max(CHECKSUM_CONFIDENCE);
+                found.reasons.push(MatchReason::Checksum { validator });
+            }
+            matches.push(found.with_context(text, &group.context_words));
         }
     }
 
@@ -945,14 +1137,14 @@ mod tests {
     fn test_match_amount() {
         let text = "总金额为¥12,345.67元，折合USD 1,700.00";
         let matches = HeuristicMatcher::match_amount(text);
-        assert!(matches.len() >= 1);
+        assert!(!matches.is_empty());
     }
 
     #[test]
     fn test_match_phone() {
         let text = "联系电话：13812345678，或拨打 (555) 123-4567";
         let matches = HeuristicMatcher::match_phone(text);
-        assert!(matches.len() >= 1);
+        assert!(!matches.is_empty());
     }
 
     #[test]
@@ -961,4 +1153,51 @@ mod tests {
         let matches = HeuristicMatcher::match_email(text);
         assert_eq!(matches.len(), 2);
     }
+
+    #[test]
+    fn test_match_id_number_requires_valid_checksum() {
+        let text = "身份证号 11010519491231002X，订单号 110105194912310021，SSN 123-45-6789";
+        let matches = HeuristicMatcher::match_id_number(text);
+        let texts: Vec<&str> = matches.iter().map(|m| m.text.as_str()).collect();
+        assert_eq!(texts, vec!["11010519491231002X", "123-45-6789"]);
+    }
+
+    #[test]
+    fn test_match_credit_card_requires_luhn() {
+        let text = "卡号 4111 1111 1111 1111，订单 1234567812345678，流水 941111111111111111";
+        let matches = HeuristicMatcher::match_credit_card(text);
+        let texts: Vec<&str> = matches.iter().map(|m| m.text.as_str()).collect();
+        assert_eq!(texts, vec!["4111 1111 1111 1111"]);
+    }
+
+    #[test]
+    fn test_context_words_raise_confidence() {
+        let plain = HeuristicMatcher::match_person_name("张伟和同事去吃饭");
+        let labeled = HeuristicMatcher::match_person_name("联系人：张伟");
+        let plain = plain.iter().find(|m| m.text == "张伟").unwrap();
+        let labeled = labeled.iter().find(|m| m.text == "张伟").unwrap();
+
+        assert!(matches!(
+            plain.reasons.as_slice(),
+            [MatchReason::Surname { surname }] if surname == "张"
+        ));
+        assert!(labeled.confidence > plain.confidence);
+        assert!(labeled.reasons.iter().any(|r| matches!(
+            r,
+            MatchReason::ContextWords { words } if words == &["联系人".to_string()]
+        )));
+    }
+
+    #[test]
+    fn test_checksum_reason() {
+        let matches = HeuristicMatcher::match_credit_card("4111 1111 1111 1111");
+        assert_eq!(matches.len(), 1);
+        assert!(matches[0].confidence >= CHECKSUM_CONFIDENCE);
+        assert!(matches[0].reasons.iter().any(|r| matches!(
+            r,
+            MatchReason::Checksum {
+                validator: Validator::Luhn
+            }
+        )));
+    }
 }
@@ -2,11 +2,16 @@
 //!
 //! 定义各种类型的脱敏规则，包括正则表达式、词典匹配和启发式算法。
 
-use regex::Regex;
 use serde::{Deserialize, Serialize};
 
+mod compiled;
+mod context;
 mod heuristics;
-use heuristics::HeuristicMatcher;
+mod validators;
+
+pub use compiled::{CompiledRuleSet, RuleCompileError};
+pub use context::{ContextDirection, ContextScope, ContextTerm, RuleContext};
+pub use validators::Validator;
 
 /// 启发式算法类型
 ///
@@ -67,6 +72,22 @@ pub struct Rule {
     pub is_system: bool,
     /// 规则类型和匹配数据
     pub rule_type: RuleType,
+    /// 上下文条件：附近必须或不得出现的词
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub context: Option<RuleContext>,
+}
+
+/// 重叠命中的处理策略
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OverlapStrategy {
+    /// 保留最长的命中，等长时靠前的规则优先
+    #[default]
+    LongestMatch,
+    /// 靠前的规则优先，同一规则内保留最长的命中
+    RulePriority,
+    /// 取并集：重叠或相邻的命中合并为一个，归属于起点最靠前的命中
+    Union,
 }
 
 /// 规则集合
@@ -75,6 +96,9 @@ pub struct Rule {
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct RuleSet {
     pub rules: Vec<Rule>,
+    /// 重叠命中的处理策略
+    #[serde(default)]
+    pub overlap: OverlapStrategy,
 }
 
 /// 规则匹配结果
@@ -92,12 +116,39 @@ pub struct RuleMatch {
     pub start: usize,
     /// 结束位置（字节偏移）
     pub end: usize,
+    /// 置信度（0-1）：正则、词典规则为 1，启发式规则按命中依据估计
+    pub confidence: f32,
+    /// 命中依据
+    #[serde(default)]
+    pub reasons: Vec<MatchReason>,
+}
+
+/// 命中依据
+///
+/// 说明一次匹配为什么成立，供人工复核时参考。
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum MatchReason {
+    /// 命中正则表达式（规则自身的正则或启发式模式）
+    Pattern { pattern: String },
+    /// 命中词典词条
+    DictionaryWord { word: String },
+    /// 以常见姓氏开头
+    Surname { surname: String },
+    /// 片段中包含的地址关键词
+    Keywords { count: usize, keywords: Vec<String> },
+    /// 以组织机构后缀结尾
+    OrganizationSuffix { suffix: String },
+    /// 通过校验（校验位、地区码、出生日期等）
+    Checksum { validator: Validator },
+    /// 命中附近出现的提示词（如「姓名」「地址」，或规则上下文条件要求的词）
+    ContextWords { words: Vec<String> },
 }
 
 impl RuleSet {
     /// 创建空规则集
     pub fn new() -> Self {
-        Self { rules: Vec::new() }
+        Self::default()
     }
 
     /// 添加规则
@@ -110,121 +161,128 @@ impl RuleSet {
         self.rules.iter().filter(|r| r.enabled).collect()
     }
 
-    /// 对文本进行规则匹配
-    ///
-    /// 返回所有匹配结果，包含匹配位置和规则信息。
-    ///
-    /// # 参数
-    /// - `text`: 要匹配的文本内容
+    /// 编译规则集，任一启用的规则无法编译时返回所有出错的规则
     ///
-    /// # 返回
-    /// 匹配结果列表
-    pub fn match_text(&self, text: &str) -> Vec<RuleMatch> {
-        let mut matches = Vec::new();
-
-        for rule in self.enabled_rules() {
-            match &rule.rule_type {
-                RuleType::Regex(pattern) => {
-                    if let Ok(re) = Regex::new(pattern) {
-                        for m in re.find_iter(text) {
-                            matches.push(RuleMatch {
-                                rule_id: rule.id.clone(),
-                                rule_name: rule.name.clone(),
-                                matched_text: m.as_str().to_string(),
-                                start: m.start(),
-                                end: m.end(),
-                            });
-                        }
-                    }
-                }
-                RuleType::Dictionary(words) => {
-                    for word in words {
-                        let mut start = 0;
-                        while let Some(pos) = text[start..].find(word) {
-                            let abs_start = start + pos;
-                            let abs_end = abs_start + word.len();
-                            matches.push(RuleMatch {
-                                rule_id: rule.id.clone(),
-                                rule_name: rule.name.clone(),
-                                matched_text: word.clone(),
-                                start: abs_start,
-                                end: abs_end,
-                            });
-                            start = abs_end;
-                        }
-                    }
-                }
-                RuleType::Heuristic(heuristic_type) => {
-                    let heuristic_matches = match heuristic_type {
-                        // 语言相关
-                        HeuristicType::Address => HeuristicMatcher::match_address(text),
-                        HeuristicType::PersonName => HeuristicMatcher::match_person_name(text),
-                        HeuristicType::Organization => HeuristicMatcher::match_organization(text),
-                        // 通用
-                        HeuristicType::Date => HeuristicMatcher::match_date(text),
-                        HeuristicType::Amount => HeuristicMatcher::match_amount(text),
-                        HeuristicType::Phone => HeuristicMatcher::match_phone(text),
-                        HeuristicType::Email => HeuristicMatcher::match_email(text),
-                        HeuristicType::IdNumber => HeuristicMatcher::match_id_number(text),
-                        HeuristicType::CreditCard => HeuristicMatcher::match_credit_card(text),
-                    };
-
-                    for m in heuristic_matches {
-                        matches.push(RuleMatch {
-                            rule_id: rule.id.clone(),
-                            rule_name: rule.name.clone(),
-                            matched_text: m.text,
-                            start: m.start,
-                            end: m.end,
-                        });
-                    }
-                }
-            }
+    /// 匹配和脱敏都在编译后的 `CompiledRuleSet` 上进行，同一规则集只需编译一次。
+    pub fn compile(&self) -> Result<CompiledRuleSet, Vec<RuleCompileError>> {
+        CompiledRuleSet::new(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(id: &str, rule_type: RuleType) -> Rule {
+        Rule {
+            id: id.to_string(),
+            name: id.to_string(),
+            enabled: true,
+            is_system: false,
+            rule_type,
+            context: None,
         }
+    }
+
+    fn words(words: &[&str]) -> RuleType {
+        RuleType::Dictionary(words.iter().map(|w| w.to_string()).collect())
+    }
 
-        // 按起始位置排序
-        matches.sort_by_key(|m| m.start);
+    fn spans(matches: &[RuleMatch]) -> Vec<(&str, &str)> {
         matches
+            .iter()
+            .map(|m| (m.rule_id.as_str(), m.matched_text.as_str()))
+            .collect()
     }
 
-    /// 对文本进行脱敏
-    ///
-    /// 将所有匹配的文本替换为脱敏标记。
-    ///
-    /// # 参数
-    /// - `text`: 要脱敏的文本内容
-    /// - `replacement`: 替换字符，默认为 "█"
-    ///
-    /// # 返回
-    /// 脱敏后的文本
-    pub fn redact_text(&self, text: &str, replacement: Option<&str>) -> String {
-        let matches = self.match_text(text);
-        if matches.is_empty() {
-            return text.to_string();
-        }
+    #[test]
+    fn test_longest_match_wins_and_redacts_once() {
+        let mut rules = RuleSet::new();
+        rules.add(rule("dict", words(&["zhang"])));
+        rules.add(rule(
+            "email",
+            RuleType::Regex(r"[a-z.]+@[a-z]+\.com".to_string()),
+        ));
+        let text = "mail zhang.san@corp.com now";
 
-        let rep = replacement.unwrap_or("█");
-        let mut result = String::with_capacity(text.len());
-        let mut last_end = 0;
-
-        for m in matches {
-            // 添加匹配之前的文本
-            if m.start > last_end {
-                result.push_str(&text[last_end..m.start]);
-            }
-            // 添加替换文本（根据原文长度生成）
-            let rep_len = m.matched_text.chars().count();
-            for _ in 0..rep_len {
-                result.push_str(rep);
-            }
-            last_end = m.end;
-        }
+        assert_eq!(
+            spans(&rules.compile().unwrap().match_text(text)),
+            vec![("email", "zhang.san@corp.com")]
+        );
+        assert_eq!(
+            rules.compile().unwrap().redact_text(text, Some("*")),
+            format!("mail {} now", "*".repeat(18))
+        );
+    }
 
-        // 添加最后一段未匹配的文本
-        if last_end < text.len() {
-            result.push_str(&text[last_end..]);
-        }
+    #[test]
+    fn test_rule_priority_prefers_earlier_rules() {
+        let mut rules = RuleSet::new();
+        rules.add(rule("short", words(&["张三"])));
+        rules.add(rule("long", words(&["张三丰"])));
+        rules.overlap = OverlapStrategy::RulePriority;
+
+        assert_eq!(
+            spans(&rules.compile().unwrap().match_text("张三丰来了")),
+            vec![("short", "张三")]
+        );
+        assert_eq!(
+            rules
+                .compile()
+                .unwrap()
+                .redact_text("张三丰来了", Some("*")),
+            "**丰来了"
+        );
+    }
+
+    #[test]
+    fn test_union_merges_overlapping_and_adjacent_spans() {
+        let mut rules = RuleSet::new();
+        rules.add(rule("a", words(&["abc", "ghi"])));
+        rules.add(rule("b", words(&["bcdef"])));
+
+        // 默认策略下只合并同一规则首尾相接的命中
+        let text = "abcdefghi ghighi";
+        assert_eq!(
+            spans(&rules.compile().unwrap().match_text(text)),
+            vec![("b", "bcdef"), ("a", "ghi"), ("a", "ghighi")]
+        );
+
+        rules.overlap = OverlapStrategy::Union;
+        let matches = rules.compile().unwrap().match_text(text);
+        assert_eq!(spans(&matches), vec![("a", "abcdefghi"), ("a", "ghighi")]);
+        assert_eq!(matches[0].reasons.len(), 3);
+    }
+
+    #[test]
+    fn test_context_filters_matches() {
+        let mut amount = rule("amount", RuleType::Regex(r"¥\d+".to_string()));
+        amount.context = Some(RuleContext {
+            forbidden: vec![ContextTerm::Word("示例".to_string())],
+            ..RuleContext::default()
+        });
+        let mut account = rule("account", RuleType::Regex(r"\d{6}".to_string()));
+        account.context = Some(RuleContext {
+            required: vec![ContextTerm::Word("Account No.".to_string())],
+            distance: Some(15),
+            direction: ContextDirection::Before,
+            ..RuleContext::default()
+        });
+        let mut rules = RuleSet::new();
+        rules.add(amount);
+        rules.add(account);
 
-        result
+        let text = "示例：¥100\n实付 ¥250\naccount no. 123456，订单 654321";
+        let matches = rules.compile().unwrap().match_text(text);
+        assert_eq!(
+            spans(&matches),
+            vec![("amount", "¥250"), ("account", "123456")]
+        );
+        assert_eq!(
+            matches[1].reasons.last(),
+            Some(&MatchReason::ContextWords {
+                words: vec!["Account No.".to_string()]
+            })
+        );
     }
 }
@@ -0,0 +1,203 @@
+//! 证件号、卡号校验
+//!
+//! 正则只能判断格式，校验器再按各自的编码规则检查校验位、地区码和出生日期，
+//! 排除格式相同的订单号、流水号等。在 heuristics.json 中按模式配置：
+//!
+//! ```json
+//! { "pattern": "\\d{16}", "validator": "luhn" }
+//! ```
+
+use serde::{Deserialize, Serialize};
+
+/// 校验器类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Validator {
+    /// 银行卡号（Luhn 校验）
+    Luhn,
+    /// 中国居民身份证号（GB 11643：校验位、地区码、出生日期）
+    CnResidentId,
+    /// 美国社会安全号（区号、组号、序号的保留值）
+    UsSsn,
+    /// 日本个人编号（My Number 校验位）
+    JpMyNumber,
+    /// 韩国居民登录号（出生日期与性别位）
+    KrRrn,
+}
+
+impl Validator {
+    /// 检查匹配到的文本是否有效（忽略空格、连字符等分隔符）
+    pub fn validate(self, text: &str) -> bool {
+        let chars: Vec<char> = text.chars().filter(|c| c.is_ascii_alphanumeric()).collect();
+        match self {
+            Validator::Luhn => luhn(&chars),
+            Validator::CnResidentId => cn_resident_id(&chars),
+            Validator::UsSsn => us_ssn(&chars),
+            Validator::JpMyNumber => jp_my_number(&chars),
+            Validator::KrRrn => kr_rrn(&chars),
+        }
+    }
+}
+
+fn digits(chars: &[char]) -> Option<Vec<u32>> {
+    chars.iter().map(|c| c.to_digit(10)).collect()
+}
+
+fn luhn(chars: &[char]) -> bool {
+    let Some(digits) = digits(chars) else {
+        return false;
+    };
+    if !(12..=19).contains(&digits.len()) || digits.iter().all(|&d| d == 0) {
+        return false;
+    }
+    let sum: u32 = digits
+        .iter()
+        .rev()
+        .enumerate()
+        .map(|(i, &d)| match (i % 2, d * 2) {
+            (0, _) => d,
+            (_, doubled) if doubled > 9 => doubled - 9,
+            (_, doubled) => doubled,
+        })
+        .sum();
+    sum % 10 == 0
+}
+
+/// 省级行政区划代码（GB/T 2260，81-83 为港澳台居民居住证）
+const CN_PROVINCES: &[u32] = &[
+    11, 12, 13, 14, 15, 21, 22, 23, 31, 32, 33, 34, 35, 36, 37, 41, 42, 43, 44, 45, 46, 50, 51, 52,
+    53, 54, 61, 62, 63, 64, 65, 71, 81, 82, 83,
+];
+
+fn cn_resident_id(chars: &[char]) -> bool {
+    const WEIGHTS: [u32; 17] = [7, 9, 10, 5, 8, 4, 2, 1, 6, 3, 7, 9, 10, 5, 8, 4, 2];
+    const CHECK: &[u8; 11] = b"10X98765432";
+
+    if chars.len() != 18 {
+        return false;
+    }
+    let Some(body) = digits(&chars[..17]) else {
+        return false;
+    };
+    if !CN_PROVINCES.contains(&(body[0] * 10 + body[1])) {
+        return false;
+    }
+    let year = number(&body[6..10]);
+    if !valid_date(year, number(&body[10..12]), number(&body[12..14])) {
+        return false;
+    }
+    let sum: u32 = body.iter().zip(WEIGHTS).map(|(d, w)| d * w).sum();
+    chars[17].to_ascii_uppercase() == CHECK[(sum % 11) as usize] as char
+}
+
+fn us_ssn(chars: &[char]) -> bool {
+    let Some(digits) = digits(chars) else {
+        return false;
+    };
+    if digits.len() != 9 {
+        return false;
+    }
+    let area = number(&digits[..3]);
+    let group = number(&digits[3..5]);
+    let serial = number(&digits[5..]);
+    area != 0 && area != 666 && area < 900 && group != 0 && serial != 0
+}
+
+fn jp_my_number(chars: &[char]) -> bool {
+    let Some(digits) = digits(chars) else {
+        return false;
+    };
+    if digits.len() != 12 {
+        return false;
+    }
+    // 从右数第 n 位（不含校验位）的权重：n ≤ 6 为 n + 1，否则为 n - 5
+    let sum: u32 = digits[..11]
+        .iter()
+        .rev()
+        .enumerate()
+        .map(|(i, &d)| {
+            let n = i as u32 + 1;
+            d * if n <= 6 { n + 1 } else { n - 5 }
+        })
+        .sum();
+    let check = match sum % 11 {
+        0 | 1 => 0,
+        remainder => 11 - remainder,
+    };
+    digits[11] == check
+}
+
+/// 2020 年 10 月后发放的号码后六位随机分配、不再有校验位，因此只检查出生日期和性别位
+fn kr_rrn(chars: &[char]) -> bool {
+    let Some(digits) = digits(chars) else {
+        return false;
+    };
+    if digits.len() != 13 {
+        return false;
+    }
+    let century = match digits[6] {
+        1 | 2 | 5 | 6 => 1900,
+        3 | 4 | 7 | 8 => 2000,
+        9 | 0 => 1800,
+        _ => return false,
+    };
+    valid_date(
+        century + number(&digits[..2]),
+        number(&digits[2..4]),
+        number(&digits[4..6]),
+    )
+}
+
+fn number(digits: &[u32]) -> u32 {
+    digits.iter().fold(0, |acc, d| acc * 10 + d)
+}
+
+fn valid_date(year: u32, month: u32, day: u32) -> bool {
+    let days = match month {
+        1 | 3 | 5 | 7 | 8 | 10 | 12 => 31,
+        4 | 6 | 9 | 11 => 30,
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        _ => return false,
+    };
+    (1..=days).contains(&day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_luhn() {
+        assert!(Validator::Luhn.validate("4111 1111 1111 1111"));
+        assert!(Validator::Luhn.validate("5500-0000-0000-0004"));
+        assert!(!Validator::Luhn.validate("4111 1111 1111 1112"));
+        assert!(!Validator::Luhn.validate("0000000000000000"));
+    }
+
+    #[test]
+    fn test_cn_resident_id() {
+        assert!(Validator::CnResidentId.validate("11010519491231002X"));
+        assert!(Validator::CnResidentId.validate("11010519491231002x"));
+        assert!(Validator::CnResidentId.validate("440304200002290014"));
+        // 校验位错误
+        assert!(!Validator::CnResidentId.validate("110105194912310021"));
+        // 地区码、日期无效
+        assert!(!Validator::CnResidentId.validate("99010519491231002X"));
+        assert!(!Validator::CnResidentId.validate("440304200102290011"));
+    }
+
+    #[test]
+    fn test_id_numbers_of_other_countries() {
+        assert!(Validator::UsSsn.validate("123-45-6789"));
+        assert!(!Validator::UsSsn.validate("666-45-6789"));
+        assert!(!Validator::UsSsn.validate("123-00-6789"));
+
+        assert!(Validator::JpMyNumber.validate("1234 5678 9018"));
+        assert!(!Validator::JpMyNumber.validate("1234 5678 9012"));
+
+        assert!(Validator::KrRrn.validate("900101-1234567"));
+        assert!(!Validator::KrRrn.validate("901301-1234567"));
+        assert!(!Validator::KrRrn.validate("010229-3234567"));
+    }
+}
@@ -0,0 +1,594 @@
+//! Word 文档处理器
+//!
+//! 实现 `Document` trait，支持 .docx 文件的加载、文本提取、脱敏和隐藏数据清理。
+
+mod package;
+mod wordml;
+
+use anyhow::{anyhow, Result};
+use linch_core::document::{CleanOutcome, Document, Page, RedactOutcome};
+use linch_core::rules::CompiledRuleSet;
+use linch_core::CleanOptions;
+use package::Package;
+use std::fs;
+use std::path::{Path, PathBuf};
+use wordml::MarkupScrub;
+
+/// 正文部件
+const MAIN_PART: &str = "word/document.xml";
+
+/// 核心属性部件
+const CORE_PROPS_PART: &str = "docProps/core.xml";
+
+/// 清理后的核心属性（保留部件以维持包结构有效）
+const EMPTY_CORE_PROPS: &str = concat!(
+    r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>"#,
+    "\r\n",
+    r#"<cp:coreProperties xmlns:cp="http://schemas.openxmlformats.org/package/2006/metadata/core-properties" "#,
+    r#"xmlns:dc="http://purl.org/dc/elements/1.1/" xmlns:dcterms="http://purl.org/dc/terms/" "#,
+    r#"xmlns:dcmitype="http://purl.org/dc/dcmitype/" xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance">"#,
+    r#"</cp:coreProperties>"#,
+);
+
+/// 扩展属性部件（公司、经理、模板等）
+const APP_PROPS_PART: &str = "docProps/app.xml";
+
+/// 清理后的扩展属性
+const EMPTY_APP_PROPS: &str = concat!(
+    r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>"#,
+    "\r\n",
+    r#"<Properties xmlns="http://schemas.openxmlformats.org/officeDocument/2006/extended-properties" "#,
+    r#"xmlns:vt="http://schemas.openxmlformats.org/officeDocument/2006/docPropsVTypes">"#,
+    r#"</Properties>"#,
+);
+
+/// 批注相关部件
+const COMMENT_PARTS: [&str; 5] = [
+    "word/comments.xml",
+    "word/commentsExtended.xml",
+    "word/commentsIds.xml",
+    "word/commentsExtensible.xml",
+    "word/people.xml",
+];
+
+/// 文档级清理选项
+#[derive(Debug, Clone, Copy)]
+struct ScrubOptions {
+    /// 核心属性、扩展属性与自定义属性
+    properties: bool,
+    /// 批注
+    comments: bool,
+    /// 修订记录
+    revisions: bool,
+    /// 自定义 XML
+    custom_xml: bool,
+}
+
+impl ScrubOptions {
+    /// 可能保留未脱敏正文的内容：批注、修订和自定义 XML，不含文档属性
+    fn content() -> Self {
+        Self {
+            properties: false,
+            comments: true,
+            revisions: true,
+            custom_xml: true,
+        }
+    }
+
+    fn markup(&self) -> MarkupScrub {
+        MarkupScrub {
+            comments: self.comments,
+            revisions: self.revisions,
+            custom_xml: self.custom_xml,
+        }
+    }
+}
+
+/// Word 文档处理器
+///
+/// 加载时将整个 .docx 包读入内存，脱敏和清理都在副本上进行。
+pub struct DocxDocument {
+    path: PathBuf,
+    package: Package,
+}
+
+impl DocxDocument {
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// 包含正文文本的部件：正文、页眉、页脚、脚注、尾注
+    fn text_parts(package: &Package) -> Vec<String> {
+        let mut extra: Vec<String> = package
+            .names()
+            .into_iter()
+            .filter(|name| {
+                let Some(file) = name.strip_prefix("word/") else {
+                    return false;
+                };
+                !file.contains('/')
+                    && file.ends_with(".xml")
+                    && (file.starts_with("header")
+                        || file.starts_with("footer")
+                        || file == "footnotes.xml"
+                        || file == "endnotes.xml")
+            })
+            .collect();
+        extra.sort();
+
+        let mut parts = vec![MAIN_PART.to_string()];
+        parts.extend(extra);
+        parts
+    }
+
+    /// 清理隐藏数据，返回执行的动作
+    fn scrub(package: &mut Package, options: ScrubOptions) -> Result<Vec<String>> {
+        let mut actions = Vec::new();
+
+        let markup = options.markup();
+        if markup.comments || markup.revisions || markup.custom_xml {
+            for part in Self::text_parts(package) {
+                let Some(xml) = package.get(&part) else {
+                    continue;
+                };
+                let (data, counts) = wordml::scrub_markup(xml, markup)?;
+                if counts.is_empty() {
+                    continue;
+                }
+                package.set(&part, data);
+                for (element, count) in counts {
+                    actions.push(format!("已移除 {} 中的 {} 个 {}", part, count, element));
+                }
+            }
+        }
+
+        let mut doomed: Vec<String> = Vec::new();
+        if options.comments {
+            doomed.extend(COMMENT_PARTS.iter().map(|s| s.to_string()));
+        }
+        if options.custom_xml {
+            doomed.extend(
+                package
+                    .names()
+                    .into_iter()
+                    .filter(|name| name.starts_with("customXml/")),
+            );
+        }
+        if options.properties {
+            doomed.push("docProps/custom.xml".to_string());
+            for (part, empty) in [
+                (CORE_PROPS_PART, EMPTY_CORE_PROPS),
+                (APP_PROPS_PART, EMPTY_APP_PROPS),
+            ] {
+                // 已清空的部件不再重复记录
+                if package
+                    .get(part)
+                    .is_some_and(|data| data != empty.as_bytes())
+                {
+                    package.set(part, empty.as_bytes().to_vec());
+                    actions.push(format!("已清空 {}", part));
+                }
+            }
+        }
+        for part in package.remove_parts(&doomed)? {
+            actions.push(format!("已移除部件 {}", part));
+        }
+
+        Ok(actions)
+    }
+}
+
+impl Document for DocxDocument {
+    fn load(path: &Path) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        if !path.exists() {
+            return Err(anyhow!("文件不存在: {}", path.display()));
+        }
+
+        let data = fs::read(path).map_err(|e| anyhow!("无法读取文件: {}", e))?;
+        let package = Package::read(&data)?;
+        if package.get(MAIN_PART).is_none() {
+            return Err(anyhow!("不是有效的 Word 文档: 缺少 {}", MAIN_PART));
+        }
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            package,
+        })
+    }
+
+    /// 正文按节划分为页面，随后依次追加页眉、页脚、脚注、尾注各一页
+    fn get_pages(&self) -> Result<Vec<Page>> {
+        let mut contents = Vec::new();
+        for part in Self::text_parts(&self.package) {
+            let Some(xml) = self.package.get(&part) else {
+                continue;
+            };
+            let sections = wordml::extract_sections(xml)?;
+            if part == MAIN_PART {
+                contents.extend(sections);
+            } else {
+                let text = sections.join("\n");
+                if !text.trim().is_empty() {
+                    contents.push(text);
+                }
+            }
+        }
+
+        Ok(contents
+            .into_iter()
+            .enumerate()
+            .map(|(index, content)| Page {
+                page_number: (index + 1) as u32,
+                content,
+            })
+            .collect())
+    }
+
+    /// 先接受修订、移除批注和自定义 XML，再替换命中的文字和域代码，
+    /// 并脱敏外部关系（超链接等）的目标地址
+    ///
+    /// 修订和批注中可能保留未脱敏的原文，因此无论清理选项如何都会先处理。
+    /// 这些清理动作随 `warnings` 返回、写入审计记录，之后的 `clean` 不会再重复报告。
+    /// 文档属性不在此处清理，由 `clean` 按 `metadata` 选项处理。
+    fn redact(&self, ruleset: &CompiledRuleSet) -> Result<RedactOutcome> {
+        let mut package = self.package.clone();
+        let scrubbed = Self::scrub(&mut package, ScrubOptions::content())?;
+
+        for part in Self::text_parts(&package) {
+            let Some(xml) = package.get(&part) else {
+                continue;
+            };
+            let (data, hits) = wordml::redact_part(xml, ruleset)?;
+            if hits > 0 {
+                package.set(&part, data);
+            }
+        }
+
+        for part in package.names() {
+            if !part.ends_with(".rels") {
+                continue;
+            }
+            let Some(xml) = package.get(&part) else {
+                continue;
+            };
+            let (data, hits) = wordml::redact_relationships(xml, ruleset)?;
+            if hits > 0 {
+                package.set(&part, data);
+            }
+        }
+
+        Ok(RedactOutcome {
+            data: package.write()?,
+            warnings: scrubbed
+                .into_iter()
+                .map(|action| format!("脱敏前清理: {}", action))
+                .collect(),
+            ..RedactOutcome::default()
+        })
+    }
+
+    /// 映射通用清理选项：metadata -> 文档属性，annots -> 批注与修订，xmp -> 自定义 XML
+    fn clean(&self, data: Vec<u8>, options: &CleanOptions) -> Result<CleanOutcome> {
+        let mut package = Package::read(&data)?;
+        let actions = Self::scrub(
+            &mut package,
+            ScrubOptions {
+                properties: options.metadata,
+                comments: options.annots,
+                revisions: options.annots,
+                custom_xml: options.xmp,
+            },
+        )?;
+
+        if actions.is_empty() {
+            return Ok(CleanOutcome { data, actions });
+        }
+
+        Ok(CleanOutcome {
+            data: package.write()?,
+            actions,
+        })
+    }
+
+    fn get_supported_features(&self) -> Vec<String> {
+        vec!["text_redact".to_string(), "metadata_clean".to_string()]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use linch_core::rules::{Rule, RuleSet, RuleType};
+    use std::io::{Cursor, Write};
+    use zip::write::SimpleFileOptions;
+    use zip::ZipWriter;
+
+    const DOCUMENT_XML: &str = concat!(
+        r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>"#,
+        r#"<w:document xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main"><w:body>"#,
+        r#"<w:p><w:r><w:t>电话 138</w:t></w:r><w:r><w:rPr><w:b/></w:rPr><w:t>0013</w:t></w:r>"#,
+        r#"<w:r><w:t xml:space="preserve">8000 结束</w:t></w:r>"#,
+        r#"<w:commentRangeStart w:id="0"/><w:r><w:commentReference w:id="0"/></w:r><w:commentRangeEnd w:id="0"/></w:p>"#,
+        r#"<w:p><w:del w:id="1" w:author="A"><w:r><w:delText>旧号码 13900139000</w:delText></w:r></w:del>"#,
+        r#"<w:ins w:id="2" w:author="A"><w:r><w:t>新内容</w:t></w:r></w:ins>"#,
+        r#"<w:pPr><w:sectPr/></w:pPr></w:p>"#,
+        r#"<w:p><w:r><w:t>第二节</w:t></w:r></w:p>"#,
+        r#"<w:sectPr/></w:body></w:document>"#,
+    );
+
+    const RELS_XML: &str = concat!(
+        r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>"#,
+        r#"<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships">"#,
+        r#"<Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/comments" Target="comments.xml"/>"#,
+        r#"<Relationship Id="rId2" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/customXml" Target="../customXml/item1.xml"/>"#,
+        r#"<Relationship Id="rId3" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/styles" Target="styles.xml"/>"#,
+        r#"</Relationships>"#,
+    );
+
+    fn build_docx() -> Vec<u8> {
+        build_docx_with(
+            DOCUMENT_XML,
+            &[
+                ("word/_rels/document.xml.rels", RELS_XML),
+                ("word/comments.xml", "<w:comments/>"),
+                ("word/styles.xml", "<w:styles/>"),
+                ("customXml/item1.xml", "<secret>机密</secret>"),
+                (
+                    CORE_PROPS_PART,
+                    "<cp:coreProperties><dc:creator>张三</dc:creator></cp:coreProperties>",
+                ),
+            ],
+        )
+    }
+
+    fn build_docx_with(document: &str, extra: &[(&str, &str)]) -> Vec<u8> {
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        let options = SimpleFileOptions::default();
+        let mut parts = vec![
+            (
+                "[Content_Types].xml",
+                r#"<?xml version="1.0"?><Types xmlns="http://schemas.openxmlformats.org/package/2006/content-types"><Override PartName="/word/comments.xml" ContentType="x"/><Override PartName="/word/document.xml" ContentType="y"/></Types>"#,
+            ),
+            (MAIN_PART, document),
+        ];
+        parts.extend_from_slice(extra);
+        for (name, content) in parts {
+            writer.start_file(name, options).unwrap();
+            writer.write_all(content.as_bytes()).unwrap();
+        }
+        writer.finish().unwrap().into_inner()
+    }
+
+    fn part_text(package: &Package, name: &str) -> String {
+        String::from_utf8(package.get(name).unwrap().to_vec()).unwrap()
+    }
+
+    fn phone_rules() -> CompiledRuleSet {
+        let mut ruleset = RuleSet::new();
+        ruleset.add(Rule {
+            id: "phone".to_string(),
+            name: "手机号".to_string(),
+            enabled: true,
+            is_system: false,
+            rule_type: RuleType::Regex(r"1[3-9]\d{9}".to_string()),
+            context: None,
+        });
+        ruleset.compile().unwrap()
+    }
+
+    fn load(data: Vec<u8>) -> DocxDocument {
+        DocxDocument {
+            path: PathBuf::from("test.docx"),
+            package: Package::read(&data).unwrap(),
+        }
+    }
+
+    #[test]
+    fn test_pages_split_by_section() {
+        let doc = load(build_docx());
+        let pages = doc.get_pages().unwrap();
+
+        assert_eq!(pages.len(), 2);
+        assert_eq!(pages[0].content, "电话 13800138000 结束\n新内容");
+        assert_eq!(pages[1].content, "第二节");
+    }
+
+    #[test]
+    fn test_redact_across_runs_and_scrub() {
+        let doc = load(build_docx());
+        let output = doc.redact(&phone_rules()).unwrap();
+        let redacted = Package::read(&output.data).unwrap();
+
+        let xml = String::from_utf8(redacted.get(MAIN_PART).unwrap().to_vec()).unwrap();
+        assert!(!xml.contains("138"));
+        assert!(!xml.contains("8000"));
+        assert!(xml.contains("<w:t>电话 ███</w:t>"));
+        assert!(xml.contains("<w:rPr><w:b/></w:rPr><w:t>████</w:t>"));
+        assert!(xml.contains("████ 结束"));
+        // 修订已接受，批注锚点已移除
+        assert!(!xml.contains("13900139000"));
+        assert!(!xml.contains("w:ins"));
+        assert!(xml.contains("新内容"));
+        assert!(!xml.contains("comment"));
+
+        assert!(redacted.get("word/comments.xml").is_none());
+        assert!(redacted.get("customXml/item1.xml").is_none());
+        assert!(redacted.get("word/styles.xml").is_some());
+
+        let rels = String::from_utf8(
+            redacted
+                .get("word/_rels/document.xml.rels")
+                .unwrap()
+                .to_vec(),
+        )
+        .unwrap();
+        assert!(!rels.contains("comments.xml"));
+        assert!(!rels.contains("customXml"));
+        assert!(rels.contains("styles.xml"));
+
+        let types =
+            String::from_utf8(redacted.get("[Content_Types].xml").unwrap().to_vec()).unwrap();
+        assert!(!types.contains("comments.xml"));
+        assert!(types.contains("document.xml"));
+
+        // 文档属性留给 clean 按 metadata 选项处理
+        assert!(part_text(&redacted, CORE_PROPS_PART).contains("张三"));
+        // 脱敏前的清理随警告返回，之后的 clean 不再重复
+        assert!(output
+            .warnings
+            .contains(&"脱敏前清理: 已移除部件 word/comments.xml".to_string()));
+        assert!(output
+            .warnings
+            .iter()
+            .any(|w| w.contains("w:del") && w.contains(MAIN_PART)));
+        assert!(!output.warnings.iter().any(|w| w.contains(CORE_PROPS_PART)));
+        let cleaned = doc
+            .clean(
+                output.data,
+                &CleanOptions {
+                    metadata: true,
+                    annots: true,
+                    xmp: true,
+                    ..CleanOptions::default()
+                },
+            )
+            .unwrap();
+        assert_eq!(cleaned.actions, vec![format!("已清空 {}", CORE_PROPS_PART)]);
+        let cleaned = Package::read(&cleaned.data).unwrap();
+        assert!(!part_text(&cleaned, CORE_PROPS_PART).contains("张三"));
+    }
+
+    #[test]
+    fn test_redacts_field_codes_hyperlinks_and_app_props() {
+        let document = concat!(
+            r#"<w:document xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main"><w:body><w:p>"#,
+            r#"<w:r><w:fldChar w:fldCharType="begin"/></w:r>"#,
+            r#"<w:r><w:instrText xml:space="preserve"> HYPERLINK "tel:1380</w:instrText></w:r>"#,
+            r#"<w:r><w:instrText>0138000" </w:instrText></w:r>"#,
+            r#"<w:r><w:fldChar w:fldCharType="separate"/></w:r><w:r><w:t>致电</w:t></w:r>"#,
+            r#"<w:r><w:fldChar w:fldCharType="end"/></w:r>"#,
+            r#"<w:fldSimple w:instr=" MERGEFIELD 13900139000 " w:dirty="true"><w:r><w:t>合并</w:t></w:r></w:fldSimple>"#,
+            r#"</w:p></w:body></w:document>"#,
+        );
+        let rels = concat!(
+            r#"<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships">"#,
+            r#"<Relationship Id="rId1" Type="hyperlink" Target="tel:13700137000" TargetMode="External"/>"#,
+            r#"<Relationship Id="rId2" Type="styles" Target="styles13700137000.xml"/>"#,
+            r#"</Relationships>"#,
+        );
+        let doc = load(build_docx_with(
+            document,
+            &[
+                ("word/_rels/document.xml.rels", rels),
+                (
+                    APP_PROPS_PART,
+                    "<Properties><Company>示例公司</Company><Manager>李四</Manager></Properties>",
+                ),
+            ],
+        ));
+        let output = doc.redact(&phone_rules()).unwrap();
+        let redacted = Package::read(&output.data).unwrap();
+
+        let xml = part_text(&redacted, MAIN_PART);
+        assert!(xml.contains("HYPERLINK &quot;tel:████</w:instrText>"));
+        assert!(xml.contains("<w:instrText>███████&quot; </w:instrText>"));
+        assert!(xml.contains(r#"w:instr=" MERGEFIELD ███████████ " w:dirty="true""#));
+        assert!(xml.contains("致电") && xml.contains("合并"));
+
+        // 只脱敏外部关系的目标，包内部件引用保持不变
+        let rels = part_text(&redacted, "word/_rels/document.xml.rels");
+        assert!(rels.contains(r#"Target="tel:███████████""#));
+        assert!(rels.contains("styles13700137000.xml"));
+
+        // 扩展属性不含命中，保持不变，由 clean 按 metadata 选项清空
+        assert!(part_text(&redacted, APP_PROPS_PART).contains("李四"));
+        let cleaned = doc
+            .clean(
+                output.data,
+                &CleanOptions {
+                    metadata: true,
+                    ..CleanOptions::default()
+                },
+            )
+            .unwrap();
+        let app = part_text(&Package::read(&cleaned.data).unwrap(), APP_PROPS_PART);
+        assert!(!app.contains("示例公司"));
+        assert!(!app.contains("李四"));
+    }
+
+    #[test]
+    fn test_redacts_runs_split_across_revisions() {
+        // 号码被拆成普通 run、带格式的插入修订和制表符后的 run；删除修订中的旧号码被移除
+        let document = concat!(
+            r#"<w:document xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main"><w:body>"#,
+            r#"<w:p><w:r><w:t>号码 1380</w:t></w:r>"#,
+            r#"<w:ins w:id="1" w:author="A"><w:r><w:rPr><w:i/></w:rPr><w:t>013</w:t></w:r></w:ins>"#,
+            r#"<w:del w:id="2" w:author="A"><w:r><w:delText>13900139000</w:delText></w:r></w:del>"#,
+            r#"<w:r><w:rPr><w:rPrChange w:id="3" w:author="A"><w:rPr><w:b/></w:rPr></w:rPrChange></w:rPr><w:t>8000</w:t></w:r>"#,
+            r#"<w:moveFrom w:id="4" w:author="A"><w:r><w:t>原位置</w:t></w:r></w:moveFrom>"#,
+            r#"<w:moveTo w:id="5" w:author="A"><w:r><w:t>新位置</w:t></w:r></w:moveTo>"#,
+            r#"</w:p></w:body></w:document>"#,
+        );
+        let doc = load(build_docx_with(document, &[]));
+        assert_eq!(
+            doc.get_pages().unwrap()[0].content,
+            "号码 13800138000原位置新位置"
+        );
+
+        let output = doc.redact(&phone_rules()).unwrap();
+        let redacted = Package::read(&output.data).unwrap();
+        let xml = part_text(&redacted, MAIN_PART);
+
+        assert!(xml.contains("<w:t>号码 ████</w:t>"));
+        assert!(xml.contains("<w:rPr><w:i/></w:rPr><w:t>███</w:t>"));
+        assert!(xml.contains("<w:t>████</w:t>"));
+        assert!(!xml.contains("13900139000"));
+        assert!(!xml.contains("原位置"));
+        assert!(xml.contains("新位置"));
+        for element in ["w:ins", "w:del", "w:rPrChange", "w:moveFrom", "w:moveTo"] {
+            assert!(!xml.contains(element), "{} 未被处理", element);
+        }
+    }
+
+    #[test]
+    fn test_redacts_headers_and_footers() {
+        let header = concat!(
+            r#"<w:hdr xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main">"#,
+            r#"<w:p><w:r><w:t>联系 138</w:t></w:r><w:r><w:t>00138000</w:t></w:r></w:p></w:hdr>"#,
+        );
+        let footer = concat!(
+            r#"<w:ftr xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main">"#,
+            r#"<w:p><w:r><w:t>13900139000</w:t></w:r><w:r><w:t> 第 1 页</w:t></w:r></w:p></w:ftr>"#,
+        );
+        let document = concat!(
+            r#"<w:document xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main">"#,
+            r#"<w:body><w:p><w:r><w:t>正文</w:t></w:r></w:p></w:body></w:document>"#,
+        );
+        let doc = load(build_docx_with(
+            document,
+            &[("word/header1.xml", header), ("word/footer1.xml", footer)],
+        ));
+
+        let pages = doc.get_pages().unwrap();
+        let contents: Vec<&str> = pages.iter().map(|p| p.content.as_str()).collect();
+        assert_eq!(
+            contents,
+            ["正文", "13900139000 第 1 页", "联系 13800138000"]
+        );
+
+        let output = doc.redact(&phone_rules()).unwrap();
+        let redacted = Package::read(&output.data).unwrap();
+        assert_eq!(
+            part_text(&redacted, "word/header1.xml"),
+            header
+                .replace("138</w:t>", "███</w:t>")
+                .replace("00138000", "████████")
+        );
+        let footer_xml = part_text(&redacted, "word/footer1.xml");
+        assert!(footer_xml.contains("<w:t>███████████</w:t>"));
+        assert!(footer_xml.contains("第 1 页"));
+    }
+}
@@ -0,0 +1,211 @@
+//! OPC 包读写
+//!
+//! .docx 是一个 ZIP 容器，这里把所有部件读入内存，支持替换、删除部件，
+//! 并在删除部件时同步清理 `.rels` 关系与 `[Content_Types].xml` 中的引用。
+
+use anyhow::{anyhow, Result};
+use quick_xml::events::Event;
+use quick_xml::{Reader, Writer};
+use std::collections::HashSet;
+use std::io::{Cursor, Read, Write};
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+
+const CONTENT_TYPES: &str = "[Content_Types].xml";
+
+/// 包内部件
+#[derive(Debug, Clone)]
+struct Part {
+    name: String,
+    data: Vec<u8>,
+}
+
+/// 内存中的 OPC 包
+#[derive(Debug, Clone)]
+pub struct Package {
+    parts: Vec<Part>,
+}
+
+impl Package {
+    /// 从 ZIP 数据读取
+    pub fn read(data: &[u8]) -> Result<Self> {
+        let mut archive =
+            ZipArchive::new(Cursor::new(data)).map_err(|e| anyhow!("无法解析 docx 容器: {}", e))?;
+
+        let mut parts = Vec::with_capacity(archive.len());
+        for index in 0..archive.len() {
+            let mut file = archive
+                .by_index(index)
+                .map_err(|e| anyhow!("无法读取 docx 部件: {}", e))?;
+            if file.is_dir() {
+                continue;
+            }
+            let mut data = Vec::with_capacity(file.size() as usize);
+            file.read_to_end(&mut data)?;
+            parts.push(Part {
+                name: file.name().to_string(),
+                data,
+            });
+        }
+
+        Ok(Self { parts })
+    }
+
+    /// 写出为 ZIP 数据（保持原有部件顺序）
+    pub fn write(&self) -> Result<Vec<u8>> {
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+
+        for part in &self.parts {
+            writer
+                .start_file(part.name.as_str(), options)
+                .map_err(|e| anyhow!("写入 docx 部件失败: {}", e))?;
+            writer.write_all(&part.data)?;
+        }
+
+        let cursor = writer
+            .finish()
+            .map_err(|e| anyhow!("写入 docx 容器失败: {}", e))?;
+        Ok(cursor.into_inner())
+    }
+
+    /// 所有部件名称
+    pub fn names(&self) -> Vec<String> {
+        self.parts.iter().map(|p| p.name.clone()).collect()
+    }
+
+    /// 读取部件
+    pub fn get(&self, name: &str) -> Option<&[u8]> {
+        self.parts
+            .iter()
+            .find(|p| p.name == name)
+            .map(|p| p.data.as_slice())
+    }
+
+    /// 替换部件内容（部件不存在时忽略）
+    pub fn set(&mut self, name: &str, data: Vec<u8>) {
+        if let Some(part) = self.parts.iter_mut().find(|p| p.name == name) {
+            part.data = data;
+        }
+    }
+
+    /// 删除部件，并清理指向这些部件的关系和内容类型声明
+    ///
+    /// 返回实际删除的部件名称。
+    pub fn remove_parts(&mut self, names: &[String]) -> Result<Vec<String>> {
+        let targets: HashSet<&str> = names.iter().map(String::as_str).collect();
+        let removed: Vec<String> = self
+            .parts
+            .iter()
+            .filter(|p| targets.contains(p.name.as_str()))
+            .map(|p| p.name.clone())
+            .collect();
+        if removed.is_empty() {
+            return Ok(removed);
+        }
+
+        let removed_set: HashSet<&str> = removed.iter().map(String::as_str).collect();
+        self.parts
+            .retain(|p| !removed_set.contains(p.name.as_str()));
+
+        for index in 0..self.parts.len() {
+            let name = self.parts[index].name.clone();
+            if name.ends_with(".rels") {
+                let base = rels_base_dir(&name);
+                let data = filter_elements(&self.parts[index].data, b"Relationship", |attr| {
+                    attr(b"TargetMode").as_deref() != Some("External")
+                        && attr(b"Target")
+                            .map(|target| {
+                                removed_set.contains(resolve_target(&base, &target).as_str())
+                            })
+                            .unwrap_or(false)
+                })?;
+                self.parts[index].data = data;
+            } else if name == CONTENT_TYPES {
+                let data = filter_elements(&self.parts[index].data, b"Override", |attr| {
+                    attr(b"PartName")
+                        .map(|part| removed_set.contains(part.trim_start_matches('/')))
+                        .unwrap_or(false)
+                })?;
+                self.parts[index].data = data;
+            }
+        }
+
+        Ok(removed)
+    }
+}
+
+/// `.rels` 文件所描述部件所在的目录
+///
+/// `word/_rels/document.xml.rels` -> `word/`，`_rels/.rels` -> 空。
+fn rels_base_dir(rels_name: &str) -> String {
+    match rels_name.find("_rels/") {
+        Some(pos) => rels_name[..pos].to_string(),
+        None => String::new(),
+    }
+}
+
+/// 将关系目标解析为包内绝对部件名（不含前导 `/`）
+fn resolve_target(base: &str, target: &str) -> String {
+    let joined = match target.strip_prefix('/') {
+        Some(absolute) => absolute.to_string(),
+        None => format!("{}{}", base, target),
+    };
+
+    let mut segments: Vec<&str> = Vec::new();
+    for segment in joined.split('/') {
+        match segment {
+            "" | "." => {}
+            ".." => {
+                segments.pop();
+            }
+            s => segments.push(s),
+        }
+    }
+    segments.join("/")
+}
+
+/// 删除满足条件的指定元素（仅处理空元素或无子元素的元素）
+///
+/// `should_remove` 接收一个按属性名读取属性值的函数。
+fn filter_elements(
+    xml: &[u8],
+    element: &[u8],
+    should_remove: impl Fn(&dyn Fn(&[u8]) -> Option<String>) -> bool,
+) -> Result<Vec<u8>> {
+    let mut reader = Reader::from_reader(xml);
+    let mut writer = Writer::new(Vec::with_capacity(xml.len()));
+    let mut skipping = false;
+
+    loop {
+        let event = reader
+            .read_event()
+            .map_err(|e| anyhow!("解析 XML 失败: {}", e))?;
+        match &event {
+            Event::Eof => break,
+            Event::Start(e) | Event::Empty(e) if e.local_name().as_ref() == element => {
+                let attr = |key: &[u8]| -> Option<String> {
+                    e.attributes()
+                        .flatten()
+                        .find(|a| a.key.local_name().as_ref() == key)
+                        .and_then(|a| a.unescape_value().ok().map(|v| v.to_string()))
+                };
+                if should_remove(&attr) {
+                    skipping = matches!(event, Event::Start(_));
+                    continue;
+                }
+            }
+            Event::End(e) if skipping && e.local_name().as_ref() == element => {
+                skipping = false;
+                continue;
+            }
+            _ if skipping => continue,
+            _ => {}
+        }
+        writer
+            .write_event(event)
+            .map_err(|e| anyhow!("写入 XML 失败: {}", e))?;
+    }
+
+    Ok(writer.into_inner())
+}
@@ -0,0 +1,488 @@
+//! WordprocessingML 文本处理
+//!
+//! Word 会把同一段文字拆成多个 `w:r`/`w:t`（拼写检查、格式变化、修订等），
+//! 因此按段落（`w:p`）拼接全部 `w:t` 文本后再匹配规则，并把命中范围映射回
+//! 各个 `w:t` 节点逐字符替换，样式标签保持不变。
+//!
+//! 域代码（`w:instrText`，如 `HYPERLINK "mailto:…"`、MERGEFIELD）同样可能跨多个 run，
+//! 按段落单独拼接后以同样方式替换；`w:fldSimple` 的 `w:instr` 属性直接替换。
+
+use anyhow::{anyhow, Result};
+use linch_core::rules::{CompiledRuleSet, RuleMatch};
+use quick_xml::events::attributes::Attribute;
+use quick_xml::events::{BytesStart, BytesText, Event};
+use quick_xml::{Reader, Writer};
+use std::collections::{BTreeMap, HashMap};
+
+/// 替换字符（与 `CompiledRuleSet::redact_text` 默认值一致）
+const REPLACEMENT: char = '█';
+
+/// 修订记录中需要整体删除的元素（接受修订后不再存在的内容）
+const REVISION_REMOVE: [&[u8]; 16] = [
+    b"w:del",
+    b"w:moveFrom",
+    b"w:moveFromRangeStart",
+    b"w:moveFromRangeEnd",
+    b"w:moveToRangeStart",
+    b"w:moveToRangeEnd",
+    b"w:rPrChange",
+    b"w:pPrChange",
+    b"w:sectPrChange",
+    b"w:tblPrChange",
+    b"w:tblPrExChange",
+    b"w:tblGridChange",
+    b"w:trPrChange",
+    b"w:tcPrChange",
+    b"w:numberingChange",
+    b"w:cellDel",
+];
+
+/// 修订记录中需要去掉外壳、保留子内容的元素
+const REVISION_UNWRAP: [&[u8]; 3] = [b"w:ins", b"w:moveTo", b"w:cellIns"];
+
+/// 批注锚点
+const COMMENT_REMOVE: [&[u8]; 3] = [
+    b"w:commentRangeStart",
+    b"w:commentRangeEnd",
+    b"w:commentReference",
+];
+
+/// 自定义 XML 标记
+const CUSTOM_XML_REMOVE: [&[u8]; 6] = [
+    b"w:customXmlPr",
+    b"w:customXmlInsRangeStart",
+    b"w:customXmlInsRangeEnd",
+    b"w:customXmlDelRangeStart",
+    b"w:customXmlDelRangeEnd",
+    b"w:dataBinding",
+];
+const CUSTOM_XML_UNWRAP: [&[u8]; 1] = [b"w:customXml"];
+
+/// 部件级清理选项
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MarkupScrub {
+    pub comments: bool,
+    pub revisions: bool,
+    pub custom_xml: bool,
+}
+
+/// 元素处理方式
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Action {
+    Keep,
+    Remove,
+    Unwrap,
+}
+
+/// 段落中的一个 `w:t` 文本片段
+struct Segment {
+    /// 事件序号
+    event: usize,
+    /// 在段落文本中的字节范围
+    start: usize,
+    end: usize,
+}
+
+/// 正在读取的文本节点
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum TextNode {
+    /// `w:t` 正文
+    Text,
+    /// `w:instrText` 域代码
+    Instruction,
+}
+
+/// 一个 `w:p` 段落
+#[derive(Default)]
+struct Paragraph {
+    text: String,
+    segments: Vec<Segment>,
+    /// 域代码文本（不属于可见正文，单独匹配）
+    instructions: String,
+    instruction_segments: Vec<Segment>,
+    /// 段落属性中包含 `w:sectPr`，即本段是一个节的最后一段
+    section_end: bool,
+}
+
+fn read_events(xml: &[u8]) -> Result<Vec<Event<'_>>> {
+    let mut reader = Reader::from_reader(xml);
+    let mut events = Vec::new();
+    loop {
+        match reader
+            .read_event()
+            .map_err(|e| anyhow!("解析 XML 失败: {}", e))?
+        {
+            Event::Eof => break,
+            event => events.push(event),
+        }
+    }
+    Ok(events)
+}
+
+fn write_events<'a>(events: impl IntoIterator<Item = Event<'a>>) -> Result<Vec<u8>> {
+    let mut writer = Writer::new(Vec::new());
+    for event in events {
+        writer
+            .write_event(event)
+            .map_err(|e| anyhow!("写入 XML 失败: {}", e))?;
+    }
+    Ok(writer.into_inner())
+}
+
+/// 按完成顺序收集所有段落
+fn collect_paragraphs(events: &[Event]) -> Result<Vec<Paragraph>> {
+    let mut done = Vec::new();
+    let mut stack: Vec<Paragraph> = Vec::new();
+    let mut run_depth = 0usize;
+    let mut in_text: Option<TextNode> = None;
+
+    for (index, event) in events.iter().enumerate() {
+        match event {
+            Event::Start(e) => match e.name().as_ref() {
+                b"w:p" => stack.push(Paragraph::default()),
+                b"w:r" => run_depth += 1,
+                b"w:t" => in_text = Some(TextNode::Text),
+                b"w:instrText" => in_text = Some(TextNode::Instruction),
+                b"w:sectPr" => {
+                    if let Some(p) = stack.last_mut() {
+                        p.section_end = true;
+                    }
+                }
+                _ => {}
+            },
+            Event::Empty(e) => match e.name().as_ref() {
+                b"w:p" => done.push(Paragraph::default()),
+                b"w:tab" if run_depth > 0 => push_char(&mut stack, '\t'),
+                b"w:br" | b"w:cr" if run_depth > 0 => push_char(&mut stack, '\n'),
+                b"w:sectPr" => {
+                    if let Some(p) = stack.last_mut() {
+                        p.section_end = true;
+                    }
+                }
+                _ => {}
+            },
+            Event::End(e) => match e.name().as_ref() {
+                b"w:p" => {
+                    if let Some(p) = stack.pop() {
+                        done.push(p);
+                    }
+                }
+                b"w:r" => run_depth = run_depth.saturating_sub(1),
+                b"w:t" | b"w:instrText" => in_text = None,
+                _ => {}
+            },
+            Event::Text(t) => {
+                let (Some(node), Some(p)) = (in_text, stack.last_mut()) else {
+                    continue;
+                };
+                let text = t.unescape().map_err(|e| anyhow!("解析文本失败: {}", e))?;
+                let (content, segments) = match node {
+                    TextNode::Text => (&mut p.text, &mut p.segments),
+                    TextNode::Instruction => (&mut p.instructions, &mut p.instruction_segments),
+                };
+                let start = content.len();
+                content.push_str(&text);
+                segments.push(Segment {
+                    event: index,
+                    start,
+                    end: content.len(),
+                });
+            }
+            _ => {}
+        }
+    }
+
+    Ok(done)
+}
+
+fn push_char(stack: &mut [Paragraph], ch: char) {
+    if let Some(p) = stack.last_mut() {
+        p.text.push(ch);
+    }
+}
+
+/// 提取部件文本，按节（`w:sectPr`）分组
+///
+/// 每个节内的段落以换行连接。页眉、页脚等没有节划分的部件返回单个元素。
+pub fn extract_sections(xml: &[u8]) -> Result<Vec<String>> {
+    let events = read_events(xml)?;
+    let mut sections = Vec::new();
+    let mut lines: Vec<String> = Vec::new();
+
+    for paragraph in collect_paragraphs(&events)? {
+        lines.push(paragraph.text);
+        if paragraph.section_end {
+            sections.push(std::mem::take(&mut lines).join("\n"));
+        }
+    }
+    if !lines.is_empty() || sections.is_empty() {
+        sections.push(lines.join("\n"));
+    }
+
+    Ok(sections)
+}
+
+/// 对部件中的文本执行脱敏，返回 (新 XML, 命中数量)
+pub fn redact_part(xml: &[u8], ruleset: &CompiledRuleSet) -> Result<(Vec<u8>, usize)> {
+    let events = read_events(xml)?;
+    let mut replacements: HashMap<usize, Event<'static>> = HashMap::new();
+    let mut hits = 0;
+
+    for paragraph in collect_paragraphs(&events)? {
+        hits += redact_segments(
+            &paragraph.text,
+            &paragraph.segments,
+            ruleset,
+            &mut replacements,
+        );
+        hits += redact_segments(
+            &paragraph.instructions,
+            &paragraph.instruction_segments,
+            ruleset,
+            &mut replacements,
+        );
+    }
+
+    for (index, event) in events.iter().enumerate() {
+        if let Event::Start(e) | Event::Empty(e) = event {
+            if e.name().as_ref() != b"w:fldSimple" {
+                continue;
+            }
+            if let Some((element, count)) = redact_attribute(e, b"w:instr", ruleset)? {
+                hits += count;
+                replacements.insert(
+                    index,
+                    match event {
+                        Event::Start(_) => Event::Start(element),
+                        _ => Event::Empty(element),
+                    },
+                );
+            }
+        }
+    }
+
+    Ok((replace_events(xml, events, replacements)?, hits))
+}
+
+/// 脱敏 `.rels` 中外部关系（`TargetMode="External"`）的目标，如超链接地址
+///
+/// 返回 (新 XML, 命中数量)。
+pub fn redact_relationships(xml: &[u8], ruleset: &CompiledRuleSet) -> Result<(Vec<u8>, usize)> {
+    let events = read_events(xml)?;
+    let mut replacements: HashMap<usize, Event<'static>> = HashMap::new();
+    let mut hits = 0;
+
+    for (index, event) in events.iter().enumerate() {
+        let (Event::Start(e) | Event::Empty(e)) = event else {
+            continue;
+        };
+        let external = e
+            .try_get_attribute("TargetMode")
+            .ok()
+            .flatten()
+            .is_some_and(|mode| mode.value.as_ref() == b"External");
+        if e.local_name().as_ref() != b"Relationship" || !external {
+            continue;
+        }
+        if let Some((element, count)) = redact_attribute(e, b"Target", ruleset)? {
+            hits += count;
+            replacements.insert(
+                index,
+                match event {
+                    Event::Start(_) => Event::Start(element),
+                    _ => Event::Empty(element),
+                },
+            );
+        }
+    }
+
+    Ok((replace_events(xml, events, replacements)?, hits))
+}
+
+/// 匹配拼接后的文本，把命中范围映射回各个文本节点，返回命中数量
+fn redact_segments(
+    text: &str,
+    segments: &[Segment],
+    ruleset: &CompiledRuleSet,
+    replacements: &mut HashMap<usize, Event<'static>>,
+) -> usize {
+    let matches = ruleset.match_text(text);
+    if matches.is_empty() {
+        return 0;
+    }
+
+    for segment in segments {
+        let original = &text[segment.start..segment.end];
+        if let Some(redacted) = mask(original, segment.start, &matches) {
+            replacements.insert(
+                segment.event,
+                Event::Text(BytesText::new(&redacted).into_owned()),
+            );
+        }
+    }
+    matches.len()
+}
+
+/// 对元素的一个属性值执行脱敏，有命中时返回改写后的元素与命中数量
+fn redact_attribute(
+    element: &BytesStart,
+    key: &[u8],
+    ruleset: &CompiledRuleSet,
+) -> Result<Option<(BytesStart<'static>, usize)>> {
+    let Some(value) = element
+        .try_get_attribute(key)
+        .map_err(|e| anyhow!("解析属性失败: {}", e))?
+    else {
+        return Ok(None);
+    };
+    let value = value
+        .unescape_value()
+        .map_err(|e| anyhow!("解析属性失败: {}", e))?;
+    let matches = ruleset.match_text(&value);
+    let Some(redacted) = mask(&value, 0, &matches) else {
+        return Ok(None);
+    };
+
+    let name = String::from_utf8_lossy(element.name().as_ref()).to_string();
+    let mut rewritten = BytesStart::new(name);
+    for attribute in element.attributes() {
+        let attribute = attribute.map_err(|e| anyhow!("解析属性失败: {}", e))?;
+        if attribute.key.as_ref() == key {
+            let key = String::from_utf8_lossy(key);
+            rewritten.push_attribute((key.as_ref(), redacted.as_str()));
+        } else {
+            rewritten.push_attribute(Attribute {
+                key: attribute.key,
+                value: attribute.value.into_owned().into(),
+            });
+        }
+    }
+    Ok(Some((rewritten, matches.len())))
+}
+
+/// 把 `text`（位于匹配文本的 `offset` 处）中落在命中范围内的字符替换为 `REPLACEMENT`
+///
+/// 没有字符被替换时返回 None。
+fn mask(text: &str, offset: usize, matches: &[RuleMatch]) -> Option<String> {
+    let mut changed = false;
+    let masked: String = text
+        .char_indices()
+        .map(|(index, ch)| {
+            let pos = offset + index;
+            if matches.iter().any(|m| m.start <= pos && pos < m.end) {
+                changed = true;
+                REPLACEMENT
+            } else {
+                ch
+            }
+        })
+        .collect();
+    changed.then_some(masked)
+}
+
+/// 用替换事件重写 XML，没有替换时原样返回
+fn replace_events(
+    xml: &[u8],
+    events: Vec<Event>,
+    mut replacements: HashMap<usize, Event<'static>>,
+) -> Result<Vec<u8>> {
+    if replacements.is_empty() {
+        return Ok(xml.to_vec());
+    }
+    write_events(
+        events
+            .into_iter()
+            .enumerate()
+            .map(|(index, event)| replacements.remove(&index).unwrap_or(event)),
+    )
+}
+
+/// 清理部件中的批注锚点、修订标记和自定义 XML 标记
+///
+/// 修订按"全部接受"处理：删除 `w:del`/`w:moveFrom` 等内容，保留 `w:ins`/`w:moveTo` 的内容。
+/// 返回 (新 XML, 每类元素的处理次数)。
+pub fn scrub_markup(
+    xml: &[u8],
+    options: MarkupScrub,
+) -> Result<(Vec<u8>, BTreeMap<String, usize>)> {
+    let decide = |name: &[u8]| -> Action {
+        if options.revisions {
+            if REVISION_REMOVE.contains(&name) {
+                return Action::Remove;
+            }
+            if REVISION_UNWRAP.contains(&name) {
+                return Action::Unwrap;
+            }
+        }
+        if options.comments && COMMENT_REMOVE.contains(&name) {
+            return Action::Remove;
+        }
+        if options.custom_xml {
+            if CUSTOM_XML_REMOVE.contains(&name) {
+                return Action::Remove;
+            }
+            if CUSTOM_XML_UNWRAP.contains(&name) {
+                return Action::Unwrap;
+            }
+        }
+        Action::Keep
+    };
+
+    let events = read_events(xml)?;
+    let mut output = Vec::with_capacity(events.len());
+    let mut counts: BTreeMap<String, usize> = BTreeMap::new();
+    // 每个已打开元素是否被去壳
+    let mut open: Vec<bool> = Vec::new();
+    // 正在跳过的子树深度
+    let mut skip_depth = 0usize;
+
+    for event in events {
+        if skip_depth > 0 {
+            match event {
+                Event::Start(_) => skip_depth += 1,
+                Event::End(_) => skip_depth -= 1,
+                _ => {}
+            }
+            continue;
+        }
+
+        match &event {
+            Event::Start(e) => {
+                let action = decide(e.name().as_ref());
+                if action != Action::Keep {
+                    *counts
+                        .entry(String::from_utf8_lossy(e.name().as_ref()).to_string())
+                        .or_default() += 1;
+                }
+                match action {
+                    Action::Remove => {
+                        skip_depth = 1;
+                        continue;
+                    }
+                    Action::Unwrap => {
+                        open.push(true);
+                        continue;
+                    }
+                    Action::Keep => open.push(false),
+                }
+            }
+            Event::Empty(e) => {
+                let action = decide(e.name().as_ref());
+                if action != Action::Keep {
+                    *counts
+                        .entry(String::from_utf8_lossy(e.name().as_ref()).to_string())
+                        .or_default() += 1;
+                    continue;
+                }
+            }
+            Event::End(_) if open.pop().unwrap_or(false) => continue,
+            _ => {}
+        }
+        output.push(event);
+    }
+
+    if counts.is_empty() {
+        return Ok((xml.to_vec(), counts));
+    }
+    Ok((write_events(output)?, counts))
+}
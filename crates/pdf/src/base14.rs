@@ -0,0 +1,202 @@
+//! 标准 14 字体的字形宽度
+//!
+//! 旧 PDF 常直接引用未嵌入的 Helvetica、Times、Courier 而不给出 /Widths，
+//! 阅读器按 Adobe AFM 中的宽度排版。这里内置 ASCII 可打印字符（含 WinAnsi 的
+//! quotesingle 与 grave）的 AFM 宽度，按 /BaseFont 选择字体，按编码与 /Differences
+//! 把字符码映射到字形名称。Symbol、ZapfDingbats 及其余字形仍使用默认宽度。
+
+/// 内置宽度的字形：StandardEncoding 中 32..=126 的字形，加上 quotesingle 与 grave
+#[rustfmt::skip]
+const GLYPH_NAMES: [&str; 97] = [
+    "space", "exclam", "quotedbl", "numbersign", "dollar", "percent", "ampersand", "quoteright",
+    "parenleft", "parenright", "asterisk", "plus", "comma", "hyphen", "period", "slash", "zero",
+    "one", "two", "three", "four", "five", "six", "seven", "eight", "nine", "colon", "semicolon",
+    "less", "equal", "greater", "question", "at", "A", "B", "C", "D", "E", "F", "G", "H", "I", "J",
+    "K", "L", "M", "N", "O", "P", "Q", "R", "S", "T", "U", "V", "W", "X", "Y", "Z", "bracketleft",
+    "backslash", "bracketright", "asciicircum", "underscore", "quoteleft", "a", "b", "c", "d", "e",
+    "f", "g", "h", "i", "j", "k", "l", "m", "n", "o", "p", "q", "r", "s", "t", "u", "v", "w", "x",
+    "y", "z", "braceleft", "bar", "braceright", "asciitilde", "quotesingle", "grave",
+];
+
+type Widths = [u16; 97];
+
+const COURIER: Widths = [600; 97];
+
+#[rustfmt::skip]
+const HELVETICA: Widths = [
+    278, 278, 355, 556, 556, 889, 667, 222, 333, 333, 389, 584, 278, 333, 278, 278,
+    556, 556, 556, 556, 556, 556, 556, 556, 556, 556,
+    278, 278, 584, 584, 584, 556, 1015,
+    667, 667, 722, 722, 667, 611, 778, 722, 278, 500, 667, 556, 833,
+    722, 778, 667, 778, 722, 667, 611, 722, 667, 944, 667, 667, 611,
+    278, 278, 278, 469, 556, 222,
+    556, 556, 500, 556, 556, 278, 556, 556, 222, 222, 500, 222, 833,
+    556, 556, 556, 556, 333, 500, 278, 556, 500, 722, 500, 500, 500,
+    334, 260, 334, 584,
+    191, 333,
+];
+
+#[rustfmt::skip]
+const HELVETICA_BOLD: Widths = [
+    278, 333, 474, 556, 556, 889, 722, 278, 333, 333, 389, 584, 278, 333, 278, 278,
+    556, 556, 556, 556, 556, 556, 556, 556, 556, 556,
+    333, 333, 584, 584, 584, 611, 975,
+    722, 722, 722, 722, 667, 611, 778, 722, 278, 556, 722, 611, 833,
+    722, 778, 667, 778, 722, 667, 611, 722, 667, 944, 667, 667, 611,
+    333, 278, 333, 584, 556, 278,
+    556, 611, 556, 611, 556, 333, 611, 611, 278, 278, 556, 278, 889,
+    611, 611, 611, 611, 389, 556, 333, 611, 556, 778, 556, 556, 500,
+    389, 280, 389, 584,
+    238, 333,
+];
+
+#[rustfmt::skip]
+const TIMES_ROMAN: Widths = [
+    250, 333, 408, 500, 500, 833, 778, 333, 333, 333, 500, 564, 250, 333, 250, 278,
+    500, 500, 500, 500, 500, 500, 500, 500, 500, 500,
+    278, 278, 564, 564, 564, 444, 921,
+    722, 667, 667, 722, 611, 556, 722, 722, 333, 389, 722, 611, 889,
+    722, 722, 556, 722, 667, 556, 611, 722, 722, 944, 722, 722, 611,
+    333, 278, 333, 469, 500, 333,
+    444, 500, 444, 500, 444, 333, 500, 500, 278, 278, 500, 278, 778,
+    500, 500, 500, 500, 333, 389, 278, 500, 500, 722, 500, 500, 444,
+    480, 200, 480, 541,
+    180, 333,
+];
+
+#[rustfmt::skip]
+const TIMES_BOLD: Widths = [
+    250, 333, 555, 500, 500, 1000, 833, 333, 333, 333, 500, 570, 250, 333, 250, 278,
+    500, 500, 500, 500, 500, 500, 500, 500, 500, 500,
+    333, 333, 570, 570, 570, 500, 930,
+    722, 667, 722, 722, 667, 611, 778, 778, 389, 500, 778, 667, 944,
+    722, 778, 611, 778, 722, 556, 667, 722, 722, 1000, 722, 722, 667,
+    333, 278, 333, 581, 500, 333,
+    500, 556, 444, 556, 444, 333, 500, 556, 278, 333, 556, 278, 833,
+    556, 500, 556, 556, 444, 389, 333, 556, 500, 722, 500, 500, 444,
+    394, 220, 394, 520,
+    278, 333,
+];
+
+#[rustfmt::skip]
+const TIMES_ITALIC: Widths = [
+    250, 333, 420, 500, 500, 833, 778, 333, 333, 333, 500, 675, 250, 333, 250, 278,
+    500, 500, 500, 500, 500, 500, 500, 500, 500, 500,
+    333, 333, 675, 675, 675, 500, 920,
+    611, 611, 667, 722, 611, 611, 722, 722, 333, 444, 667, 556, 833,
+    667, 722, 611, 722, 611, 500, 556, 722, 611, 833, 611, 556, 556,
+    389, 278, 389, 422, 500, 333,
+    500, 500, 444, 500, 444, 278, 500, 500, 278, 278, 444, 278, 722,
+    500, 500, 500, 500, 389, 389, 278, 500, 444, 667, 444, 444, 389,
+    400, 275, 400, 541,
+    214, 333,
+];
+
+#[rustfmt::skip]
+const TIMES_BOLD_ITALIC: Widths = [
+    250, 389, 555, 500, 500, 833, 778, 333, 333, 333, 500, 570, 250, 333, 250, 278,
+    500, 500, 500, 500, 500, 500, 500, 500, 500, 500,
+    333, 333, 570, 570, 570, 500, 832,
+    667, 667, 667, 722, 667, 667, 722, 778, 389, 500, 667, 611, 889,
+    722, 722, 611, 722, 667, 556, 611, 722, 667, 889, 667, 611, 611,
+    333, 278, 333, 570, 500, 333,
+    500, 500, 444, 500, 444, 333, 500, 556, 278, 278, 500, 278, 778,
+    556, 500, 500, 500, 389, 389, 278, 556, 444, 667, 500, 444, 389,
+    348, 220, 348, 570,
+    278, 333,
+];
+
+/// 字体的内置编码
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum BaseEncoding {
+    Standard,
+    /// WinAnsiEncoding 与 MacRomanEncoding 在 ASCII 范围内相同
+    WinAnsi,
+}
+
+/// 标准字体的宽度表
+pub(crate) struct Base14 {
+    widths: &'static Widths,
+}
+
+impl Base14 {
+    /// 按 /BaseFont 查找标准字体
+    ///
+    /// 忽略子集前缀（`ABCDEF+`），并识别 Arial、TimesNewRoman、CourierNew 等常见别名。
+    pub fn find(base_font: &[u8]) -> Option<Self> {
+        let name = String::from_utf8_lossy(base_font);
+        let name = match name.split_once('+') {
+            Some((prefix, rest)) if prefix.len() == 6 => rest,
+            _ => &name,
+        };
+        let bold = name.contains("Bold");
+        let italic = name.contains("Italic") || name.contains("Oblique");
+
+        let widths = if name.starts_with("Courier") {
+            &COURIER
+        } else if name.starts_with("Helvetica") || name.starts_with("Arial") {
+            if bold {
+                &HELVETICA_BOLD
+            } else {
+                &HELVETICA
+            }
+        } else if name.starts_with("Times") {
+            match (bold, italic) {
+                (false, false) => &TIMES_ROMAN,
+                (true, false) => &TIMES_BOLD,
+                (false, true) => &TIMES_ITALIC,
+                (true, true) => &TIMES_BOLD_ITALIC,
+            }
+        } else {
+            return None;
+        };
+        Some(Self { widths })
+    }
+
+    /// 字形名称的宽度（千分之一 em）
+    pub fn width_of(&self, glyph: &[u8]) -> Option<f32> {
+        GLYPH_NAMES
+            .iter()
+            .position(|name| name.as_bytes() == glyph)
+            .map(|i| self.widths[i] as f32)
+    }
+
+    /// 字符码在内置编码下的宽度（仅 ASCII 可打印范围）
+    pub fn width(&self, code: u32, encoding: BaseEncoding) -> Option<f32> {
+        let name: &[u8] = match (code, encoding) {
+            (39, BaseEncoding::WinAnsi) => b"quotesingle",
+            (96, BaseEncoding::WinAnsi) => b"grave",
+            (32..=126, _) => GLYPH_NAMES[(code - 32) as usize].as_bytes(),
+            _ => return None,
+        };
+        self.width_of(name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_finds_fonts_by_base_name() {
+        let helvetica = Base14::find(b"Helvetica").unwrap();
+        assert_eq!(
+            helvetica.width(b'i' as u32, BaseEncoding::Standard),
+            Some(222.0)
+        );
+        assert_eq!(helvetica.width(39, BaseEncoding::Standard), Some(222.0));
+        assert_eq!(helvetica.width(39, BaseEncoding::WinAnsi), Some(191.0));
+
+        let bold = Base14::find(b"ABCDEF+Arial,BoldItalic").unwrap();
+        assert_eq!(bold.width(b'm' as u32, BaseEncoding::Standard), Some(889.0));
+        let times = Base14::find(b"Times-BoldItalic").unwrap();
+        assert_eq!(
+            times.width(b'A' as u32, BaseEncoding::Standard),
+            Some(667.0)
+        );
+        let courier = Base14::find(b"Courier-Oblique").unwrap();
+        assert_eq!(courier.width_of(b"W"), Some(600.0));
+
+        assert!(Base14::find(b"Symbol").is_none());
+    }
+}
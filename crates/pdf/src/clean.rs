@@ -0,0 +1,240 @@
+//! PDF 隐藏数据清理
+//!
+//! 基于 lopdf 对脱敏后的 PDF 执行元数据、XMP、注释、表单、附件和 JavaScript 清理。
+
+use anyhow::{anyhow, Result};
+use linch_core::{CleanOptions, CleanOutcome};
+use lopdf::{Document, Object, ObjectId};
+
+/// Info 字典中需要移除的字段
+const INFO_FIELDS: [&[u8]; 8] = [
+    b"Title",
+    b"Author",
+    b"Subject",
+    b"Keywords",
+    b"Creator",
+    b"Producer",
+    b"CreationDate",
+    b"ModDate",
+];
+
+/// 对 PDF 数据执行清理
+pub fn clean_pdf(data: Vec<u8>, options: &CleanOptions) -> Result<CleanOutcome> {
+    let mut doc = Document::load_mem(&data).map_err(|e| anyhow!("无法解析 PDF: {}", e))?;
+    let mut actions = Vec::new();
+
+    if options.metadata {
+        clean_info_dict(&mut doc, &mut actions);
+    }
+    if options.xmp {
+        remove_catalog_entry(&mut doc, b"Metadata", &mut actions);
+    }
+    if options.javascript {
+        remove_javascript(&mut doc, &mut actions);
+    }
+    if options.attachments {
+        remove_name_tree_entry(&mut doc, b"EmbeddedFiles", &mut actions);
+        remove_annotations(&mut doc, Some(b"FileAttachment"), &mut actions);
+    }
+    if options.forms {
+        remove_catalog_entry(&mut doc, b"AcroForm", &mut actions);
+        remove_annotations(&mut doc, Some(b"Widget"), &mut actions);
+    }
+    if options.annots {
+        remove_annotations(&mut doc, None, &mut actions);
+    }
+
+    if actions.is_empty() {
+        return Ok(CleanOutcome { data, actions });
+    }
+
+    doc.prune_objects();
+    doc.compress();
+    let mut output = Vec::new();
+    doc.save_to(&mut output)
+        .map_err(|e| anyhow!("保存 PDF 失败: {}", e))?;
+
+    Ok(CleanOutcome {
+        data: output,
+        actions,
+    })
+}
+
+/// 获取文档 Catalog 的 ObjectId
+fn catalog_id(doc: &Document) -> Option<ObjectId> {
+    match doc.trailer.get(b"Root") {
+        Ok(Object::Reference(id)) => Some(*id),
+        _ => None,
+    }
+}
+
+/// 清理 Info 字典
+fn clean_info_dict(doc: &mut Document, actions: &mut Vec<String>) {
+    let info_id = match doc.trailer.get(b"Info") {
+        Ok(Object::Reference(id)) => *id,
+        _ => return,
+    };
+
+    if let Ok(Object::Dictionary(ref mut info)) = doc.get_object_mut(info_id) {
+        for field in INFO_FIELDS {
+            if info.has(field) {
+                info.remove(field);
+                actions.push(format!("已移除 Info/{}", String::from_utf8_lossy(field)));
+            }
+        }
+    }
+
+    doc.trailer.remove(b"Info");
+    actions.push("已移除 Info 字典".to_string());
+}
+
+/// 从 Catalog 中移除指定条目
+fn remove_catalog_entry(doc: &mut Document, key: &[u8], actions: &mut Vec<String>) {
+    let Some(catalog_id) = catalog_id(doc) else {
+        return;
+    };
+    if let Ok(Object::Dictionary(ref mut catalog)) = doc.get_object_mut(catalog_id) {
+        if catalog.remove(key).is_some() {
+            actions.push(format!("已移除 Catalog/{}", String::from_utf8_lossy(key)));
+        }
+    }
+}
+
+/// 从 Names 字典中移除指定名称树
+fn remove_name_tree_entry(doc: &mut Document, key: &[u8], actions: &mut Vec<String>) {
+    let Some(catalog_id) = catalog_id(doc) else {
+        return;
+    };
+    let names_obj = match doc.get_object(catalog_id) {
+        Ok(Object::Dictionary(catalog)) => catalog.get(b"Names").ok().cloned(),
+        _ => None,
+    };
+
+    let removed = match names_obj {
+        Some(Object::Reference(names_id)) => match doc.get_object_mut(names_id) {
+            Ok(Object::Dictionary(ref mut names)) => names.remove(key).is_some(),
+            _ => false,
+        },
+        Some(Object::Dictionary(_)) => match doc.get_object_mut(catalog_id) {
+            Ok(Object::Dictionary(ref mut catalog)) => match catalog.get_mut(b"Names") {
+                Ok(Object::Dictionary(ref mut names)) => names.remove(key).is_some(),
+                _ => false,
+            },
+            _ => false,
+        },
+        _ => false,
+    };
+
+    if removed {
+        actions.push(format!("已移除 Names/{}", String::from_utf8_lossy(key)));
+    }
+}
+
+/// 移除文档级与页面级 JavaScript 动作
+fn remove_javascript(doc: &mut Document, actions: &mut Vec<String>) {
+    remove_name_tree_entry(doc, b"JavaScript", actions);
+    remove_catalog_entry(doc, b"AA", actions);
+
+    // OpenAction 可能是 JavaScript 动作
+    if let Some(catalog_id) = catalog_id(doc) {
+        let open_action = match doc.get_object(catalog_id) {
+            Ok(Object::Dictionary(catalog)) => catalog.get(b"OpenAction").ok().cloned(),
+            _ => None,
+        };
+        if let Some(action) = open_action {
+            if is_javascript_action(doc, &action) {
+                remove_catalog_entry(doc, b"OpenAction", actions);
+            }
+        }
+    }
+
+    let page_ids: Vec<ObjectId> = doc.page_iter().collect();
+    for (index, page_id) in page_ids.into_iter().enumerate() {
+        if let Ok(Object::Dictionary(ref mut page)) = doc.get_object_mut(page_id) {
+            if page.remove(b"AA").is_some() {
+                actions.push(format!("已移除第 {} 页的 AA 动作", index + 1));
+            }
+        }
+    }
+}
+
+/// 检查 Action 是否是 JavaScript Action
+fn is_javascript_action(doc: &Document, action: &Object) -> bool {
+    let dict = match action {
+        Object::Reference(id) => match doc.get_object(*id) {
+            Ok(Object::Dictionary(dict)) => dict,
+            _ => return false,
+        },
+        Object::Dictionary(dict) => dict,
+        _ => return false,
+    };
+    matches!(dict.get(b"S"), Ok(Object::Name(s)) if s == b"JavaScript")
+}
+
+/// 移除页面注释
+///
+/// `subtype` 为 `None` 时移除所有注释，否则只移除指定子类型的注释。
+fn remove_annotations(doc: &mut Document, subtype: Option<&[u8]>, actions: &mut Vec<String>) {
+    let page_ids: Vec<ObjectId> = doc.page_iter().collect();
+
+    for (index, page_id) in page_ids.into_iter().enumerate() {
+        let annots = match doc.get_object(page_id) {
+            Ok(Object::Dictionary(page)) => match page.get(b"Annots") {
+                Ok(Object::Reference(id)) => match doc.get_object(*id) {
+                    Ok(Object::Array(arr)) => arr.clone(),
+                    _ => continue,
+                },
+                Ok(Object::Array(arr)) => arr.clone(),
+                _ => continue,
+            },
+            _ => continue,
+        };
+
+        let kept: Vec<Object> = annots
+            .iter()
+            .filter(|annot| match subtype {
+                None => false,
+                Some(wanted) => annotation_subtype(doc, annot).as_deref() != Some(wanted),
+            })
+            .cloned()
+            .collect();
+
+        let removed = annots.len() - kept.len();
+        if removed == 0 {
+            continue;
+        }
+
+        if let Ok(Object::Dictionary(ref mut page)) = doc.get_object_mut(page_id) {
+            if kept.is_empty() {
+                page.remove(b"Annots");
+            } else {
+                page.set("Annots", Object::Array(kept));
+            }
+        }
+
+        let label = subtype
+            .map(|s| String::from_utf8_lossy(s).to_string())
+            .unwrap_or_else(|| "全部".to_string());
+        actions.push(format!(
+            "已移除第 {} 页的 {} 个注释（{}）",
+            index + 1,
+            removed,
+            label
+        ));
+    }
+}
+
+fn annotation_subtype(doc: &Document, annot: &Object) -> Option<Vec<u8>> {
+    let dict = match annot {
+        Object::Reference(id) => match doc.get_object(*id) {
+            Ok(Object::Dictionary(dict)) => dict,
+            _ => return None,
+        },
+        Object::Dictionary(dict) => dict,
+        _ => return None,
+    };
+    match dict.get(b"Subtype") {
+        Ok(Object::Name(name)) => Some(name.clone()),
+        _ => None,
+    }
+}
@@ -0,0 +1,440 @@
+//! CMap 解析
+//!
+//! 只读取拆分字符码和映射所需的部分：
+//! - `codespacerange`：字符码的字节长度
+//! - `cidrange` / `cidchar`：字符码到 CID（编码 CMap）
+//! - `bfrange` / `bfchar`：字符码到 Unicode（ToUnicode CMap）
+//!
+//! 预定义 CMap 只内置编码空间（Identity 另含恒等 CID 映射），不含完整的 CID 表。
+
+use std::collections::HashMap;
+
+/// 编码空间范围，字符码逐字节落在 `low..=high` 内
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct CodeRange {
+    low: Vec<u8>,
+    high: Vec<u8>,
+}
+
+impl CodeRange {
+    fn new(low: &[u8], high: &[u8]) -> Self {
+        Self {
+            low: low.to_vec(),
+            high: high.to_vec(),
+        }
+    }
+
+    fn matches(&self, bytes: &[u8]) -> bool {
+        bytes.len() >= self.low.len()
+            && self
+                .low
+                .iter()
+                .zip(&self.high)
+                .zip(bytes)
+                .all(|((lo, hi), b)| lo <= b && b <= hi)
+    }
+}
+
+/// Unicode 映射区间：`low..=high` 映射到 `start` 起递增的 UTF-16 序列
+#[derive(Debug, Clone)]
+struct UnicodeRange {
+    low: u32,
+    high: u32,
+    start: Vec<u16>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct CMap {
+    codespace: Vec<CodeRange>,
+    /// (起始码, 结束码, 起始 CID)
+    cid_ranges: Vec<(u32, u32, u32)>,
+    cid_chars: HashMap<u32, u32>,
+    unicode_ranges: Vec<UnicodeRange>,
+    unicode_chars: HashMap<u32, String>,
+    /// `usecmap` 引用的基础 CMap 名称
+    parent: Option<String>,
+}
+
+impl CMap {
+    /// 解析 CMap 流内容
+    pub fn parse(data: &[u8]) -> Self {
+        let tokens = tokenize(data);
+        let mut cmap = Self::default();
+        let mut i = 0;
+
+        while i < tokens.len() {
+            let Token::Word(word) = &tokens[i] else {
+                i += 1;
+                continue;
+            };
+            match word.as_str() {
+                "usecmap" => {
+                    if let Some(Token::Name(name)) = i.checked_sub(1).map(|p| &tokens[p]) {
+                        cmap.parent = Some(name.clone());
+                    }
+                }
+                "begincodespacerange" => {
+                    i = cmap.section(&tokens, i + 1, "endcodespacerange", |cmap, args| {
+                        if let [Token::Hex(low), Token::Hex(high), ..] = args {
+                            cmap.codespace.push(CodeRange::new(low, high));
+                        }
+                        2
+                    });
+                    continue;
+                }
+                "begincidrange" => {
+                    i = cmap.section(&tokens, i + 1, "endcidrange", |cmap, args| {
+                        if let [Token::Hex(low), Token::Hex(high), Token::Int(cid), ..] = args {
+                            cmap.cid_ranges.push((code(low), code(high), *cid));
+                        }
+                        3
+                    });
+                    continue;
+                }
+                "begincidchar" => {
+                    i = cmap.section(&tokens, i + 1, "endcidchar", |cmap, args| {
+                        if let [Token::Hex(src), Token::Int(cid), ..] = args {
+                            cmap.cid_chars.insert(code(src), *cid);
+                        }
+                        2
+                    });
+                    continue;
+                }
+                "beginbfchar" => {
+                    i = cmap.section(&tokens, i + 1, "endbfchar", |cmap, args| {
+                        if let [Token::Hex(src), Token::Hex(dst), ..] = args {
+                            cmap.unicode_chars.insert(code(src), utf16_string(dst));
+                        }
+                        2
+                    });
+                    continue;
+                }
+                "beginbfrange" => {
+                    i = cmap.section(&tokens, i + 1, "endbfrange", |cmap, args| match args {
+                        [Token::Hex(low), Token::Hex(high), Token::Hex(dst), ..] => {
+                            cmap.unicode_ranges.push(UnicodeRange {
+                                low: code(low),
+                                high: code(high),
+                                start: utf16_units(dst),
+                            });
+                            3
+                        }
+                        [Token::Hex(low), Token::Hex(_), Token::ArrayStart, rest @ ..] => {
+                            let mut next = code(low);
+                            let mut used = 3;
+                            for token in rest {
+                                used += 1;
+                                match token {
+                                    Token::Hex(dst) => {
+                                        cmap.unicode_chars.insert(next, utf16_string(dst));
+                                        next += 1;
+                                    }
+                                    Token::ArrayEnd => break,
+                                    _ => {}
+                                }
+                            }
+                            used
+                        }
+                        _ => 3,
+                    });
+                    continue;
+                }
+                _ => {}
+            }
+            i += 1;
+        }
+
+        cmap
+    }
+
+    /// 预定义 CMap
+    ///
+    /// Identity 编码为两字节且 CID 等于字符码；基于 Unicode 的 CMap 为两字节；
+    /// 其余 CJK 编码按单双字节混合的编码空间拆分，CID 未知。
+    pub fn predefined(name: &str) -> Option<Self> {
+        let mut cmap = Self::default();
+        if name.starts_with("Identity-") {
+            cmap.codespace
+                .push(CodeRange::new(&[0x00, 0x00], &[0xFF, 0xFF]));
+            cmap.cid_ranges.push((0, 0xFFFF, 0));
+        } else if name.contains("UCS2") || name.contains("UTF16") {
+            cmap.codespace
+                .push(CodeRange::new(&[0x00, 0x00], &[0xFF, 0xFF]));
+        } else if name.contains("RKSJ") {
+            cmap.codespace.push(CodeRange::new(&[0x00], &[0x80]));
+            cmap.codespace.push(CodeRange::new(&[0xA0], &[0xDF]));
+            cmap.codespace
+                .push(CodeRange::new(&[0x81, 0x40], &[0x9F, 0xFC]));
+            cmap.codespace
+                .push(CodeRange::new(&[0xE0, 0x40], &[0xFC, 0xFC]));
+        } else if name.ends_with("-H") || name.ends_with("-V") {
+            cmap.codespace.push(CodeRange::new(&[0x00], &[0x80]));
+            cmap.codespace
+                .push(CodeRange::new(&[0x81, 0x40], &[0xFE, 0xFE]));
+        } else {
+            return None;
+        }
+        Some(cmap)
+    }
+
+    /// 指定基础 CMap（内容中已有 `usecmap` 时以内容为准）
+    pub fn with_parent(mut self, name: &str) -> Self {
+        self.parent.get_or_insert_with(|| name.to_string());
+        self
+    }
+
+    /// 合并 `usecmap` 引用的预定义 CMap（本 CMap 中的定义优先）
+    pub fn resolve_parent(mut self) -> Self {
+        if let Some(parent) = self.parent.take().as_deref().and_then(Self::predefined) {
+            if self.codespace.is_empty() {
+                self.codespace = parent.codespace;
+            }
+            self.cid_ranges.extend(parent.cid_ranges);
+        }
+        self
+    }
+
+    pub fn has_codespace(&self) -> bool {
+        !self.codespace.is_empty()
+    }
+
+    /// 字符串开头的字符码字节数
+    ///
+    /// 按编码空间从短到长匹配；都不匹配时取最短的编码长度。
+    pub fn code_len(&self, bytes: &[u8]) -> Option<usize> {
+        let mut lens: Vec<usize> = self.codespace.iter().map(|r| r.low.len()).collect();
+        lens.sort_unstable();
+        lens.dedup();
+        lens.iter()
+            .copied()
+            .find(|&len| {
+                self.codespace
+                    .iter()
+                    .any(|r| r.low.len() == len && r.matches(bytes))
+            })
+            .or_else(|| lens.first().copied())
+    }
+
+    /// 字符码对应的 CID
+    pub fn cid(&self, code: u32) -> Option<u32> {
+        self.cid_chars.get(&code).copied().or_else(|| {
+            self.cid_ranges
+                .iter()
+                .find(|(low, high, _)| (*low..=*high).contains(&code))
+                .map(|(low, _, start)| start + (code - low))
+        })
+    }
+
+    /// 字符码对应的 Unicode 文本
+    pub fn unicode(&self, code: u32) -> Option<String> {
+        if let Some(text) = self.unicode_chars.get(&code) {
+            return Some(text.clone());
+        }
+        let range = self
+            .unicode_ranges
+            .iter()
+            .find(|r| (r.low..=r.high).contains(&code))?;
+        let mut units = range.start.clone();
+        if let Some(last) = units.last_mut() {
+            *last = last.wrapping_add((code - range.low) as u16);
+        }
+        Some(String::from_utf16_lossy(&units))
+    }
+
+    /// 解析 begin/end 区段，`entry` 返回每条记录消耗的记号数，返回区段结束后的位置
+    fn section<F>(&mut self, tokens: &[Token], start: usize, end: &str, mut entry: F) -> usize
+    where
+        F: FnMut(&mut Self, &[Token]) -> usize,
+    {
+        let mut i = start;
+        while i < tokens.len() {
+            if matches!(&tokens[i], Token::Word(w) if w == end) {
+                return i + 1;
+            }
+            i += entry(self, &tokens[i..]).max(1);
+        }
+        i
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Hex(Vec<u8>),
+    Int(u32),
+    Name(String),
+    Word(String),
+    ArrayStart,
+    ArrayEnd,
+}
+
+/// 将 CMap 内容切分为记号（忽略注释、字面字符串与字典括号）
+fn tokenize(data: &[u8]) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < data.len() {
+        match data[i] {
+            b'%' => {
+                while i < data.len() && data[i] != b'\n' && data[i] != b'\r' {
+                    i += 1;
+                }
+            }
+            b'<' if data.get(i + 1) == Some(&b'<') => i += 2,
+            b'>' if data.get(i + 1) == Some(&b'>') => i += 2,
+            b'<' => {
+                let end = data[i..]
+                    .iter()
+                    .position(|&b| b == b'>')
+                    .map_or(data.len(), |p| i + p);
+                tokens.push(Token::Hex(decode_hex(&data[i + 1..end])));
+                i = end + 1;
+            }
+            b'(' => {
+                // 跳过字面字符串（含嵌套括号与转义）
+                let mut depth = 0;
+                while i < data.len() {
+                    match data[i] {
+                        b'\\' => i += 1,
+                        b'(' => depth += 1,
+                        b')' => {
+                            depth -= 1;
+                            if depth == 0 {
+                                break;
+                            }
+                        }
+                        _ => {}
+                    }
+                    i += 1;
+                }
+                i += 1;
+            }
+            b'[' => {
+                tokens.push(Token::ArrayStart);
+                i += 1;
+            }
+            b']' => {
+                tokens.push(Token::ArrayEnd);
+                i += 1;
+            }
+            b if b.is_ascii_whitespace() || b == b'{' || b == b'}' => i += 1,
+            _ => {
+                let start = i;
+                // 名称以 `/` 开头，其后直到下一个分隔符
+                if data[i] == b'/' {
+                    i += 1;
+                }
+                while i < data.len() && !is_delimiter(data[i]) {
+                    i += 1;
+                }
+                if i == start {
+                    i += 1;
+                    continue;
+                }
+                let word = String::from_utf8_lossy(&data[start..i]).to_string();
+                tokens.push(if let Some(name) = word.strip_prefix('/') {
+                    Token::Name(name.to_string())
+                } else if let Ok(n) = word.parse::<u32>() {
+                    Token::Int(n)
+                } else {
+                    Token::Word(word)
+                });
+            }
+        }
+    }
+
+    tokens
+}
+
+fn is_delimiter(b: u8) -> bool {
+    b.is_ascii_whitespace() || b"<>[](){}%/".contains(&b)
+}
+
+fn decode_hex(raw: &[u8]) -> Vec<u8> {
+    let digits: Vec<u8> = raw
+        .iter()
+        .filter_map(|&b| (b as char).to_digit(16).map(|d| d as u8))
+        .collect();
+    digits
+        .chunks(2)
+        .map(|pair| (pair[0] << 4) | pair.get(1).copied().unwrap_or(0))
+        .collect()
+}
+
+/// 大端字节序列转为字符码
+pub(crate) fn code(bytes: &[u8]) -> u32 {
+    bytes.iter().fold(0u32, |acc, &b| (acc << 8) | b as u32)
+}
+
+fn utf16_units(bytes: &[u8]) -> Vec<u16> {
+    bytes
+        .chunks(2)
+        .map(|pair| ((pair[0] as u16) << 8) | pair.get(1).copied().unwrap_or(0) as u16)
+        .collect()
+}
+
+fn utf16_string(bytes: &[u8]) -> String {
+    String::from_utf16_lossy(&utf16_units(bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EMBEDDED: &[u8] = b"/CIDInit /ProcSet findresource begin
+12 dict begin
+begincmap
+/CIDSystemInfo << /Registry (Adobe) /Ordering (UCS) /Supplement 0 >> def
+/CMapName /Custom def
+2 begincodespacerange
+<00> <7F>
+<8000> <FFFF>
+endcodespacerange
+1 begincidrange
+<8100> <81FF> 100
+endcidrange
+1 begincidchar
+<41> 7
+endcidchar
+2 beginbfchar
+<41> <0041>
+<8102> <4E2D>
+endbfchar
+2 beginbfrange
+<8110> <8112> <0061>
+<8120> <8121> [<6587> <5B57>]
+endbfrange
+endcmap";
+
+    #[test]
+    fn test_parses_codespace_and_mappings() {
+        let cmap = CMap::parse(EMBEDDED);
+
+        assert_eq!(cmap.code_len(b"A\x81\x02"), Some(1));
+        assert_eq!(cmap.code_len(b"\x81\x02"), Some(2));
+
+        assert_eq!(cmap.cid(0x41), Some(7));
+        assert_eq!(cmap.cid(0x8105), Some(105));
+        assert_eq!(cmap.cid(0x9000), None);
+
+        assert_eq!(cmap.unicode(0x8102).as_deref(), Some("中"));
+        assert_eq!(cmap.unicode(0x8111).as_deref(), Some("b"));
+        assert_eq!(cmap.unicode(0x8121).as_deref(), Some("字"));
+    }
+
+    #[test]
+    fn test_predefined_and_usecmap() {
+        let identity = CMap::predefined("Identity-H").unwrap();
+        assert_eq!(identity.code_len(b"\x00\x41"), Some(2));
+        assert_eq!(identity.cid(0x1234), Some(0x1234));
+
+        let gbk = CMap::predefined("GBK-EUC-H").unwrap();
+        assert_eq!(gbk.code_len(b"A"), Some(1));
+        assert_eq!(gbk.code_len(b"\xD6\xD0"), Some(2));
+
+        let derived =
+            CMap::parse(b"/Identity-H usecmap 1 begincidchar <0003> 9 endcidchar").resolve_parent();
+        assert_eq!(derived.code_len(b"\x00\x03"), Some(2));
+        assert_eq!(derived.cid(3), Some(9));
+        assert_eq!(derived.cid(4), Some(4));
+    }
+}
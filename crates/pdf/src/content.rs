@@ -0,0 +1,630 @@
+//! 内容流文本删除
+//!
+//! 跟踪 PDF 文本状态与图形状态，逐字形计算在用户空间中的位置，
+//! 将落入遮罩区域的字形从 Tj/TJ/'/" 操作中真正删除，并用等宽的
+//! TJ 位移补偿，保证其余文字位置不变。`Do` 调用连同调用处的状态一并记录，
+//! 由调用方按同样的状态处理 Form XObject 的内容。
+
+use crate::fonts::{number, FontMetrics, Glyph};
+use lopdf::content::Operation;
+use lopdf::{Object, StringFormat};
+use std::collections::HashMap;
+
+/// 仿射矩阵 [a b c d e f]
+pub(crate) type Matrix = [f32; 6];
+
+pub(crate) const IDENTITY: Matrix = [1.0, 0.0, 0.0, 1.0, 0.0, 0.0];
+
+/// 字形框相对字号的垂直中心（基线之上）
+const GLYPH_CENTER_RISE: f32 = 0.3;
+
+/// 遮罩矩形（PDF 用户空间，原点在左下）
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MaskRect {
+    pub left: f32,
+    pub bottom: f32,
+    pub right: f32,
+    pub top: f32,
+}
+
+impl MaskRect {
+    pub fn contains(&self, x: f32, y: f32) -> bool {
+        x >= self.left && x <= self.right && y >= self.bottom && y <= self.top
+    }
+
+    pub fn width(&self) -> f32 {
+        self.right - self.left
+    }
+
+    pub fn height(&self) -> f32 {
+        self.top - self.bottom
+    }
+}
+
+/// 文本状态（随 q/Q 保存与恢复）
+#[derive(Debug, Clone)]
+struct TextState {
+    char_spacing: f32,
+    word_spacing: f32,
+    horizontal_scale: f32,
+    leading: f32,
+    rise: f32,
+    font_size: f32,
+    font: Option<Vec<u8>>,
+}
+
+impl Default for TextState {
+    fn default() -> Self {
+        Self {
+            char_spacing: 0.0,
+            word_spacing: 0.0,
+            horizontal_scale: 1.0,
+            leading: 0.0,
+            rise: 0.0,
+            font_size: 0.0,
+            font: None,
+        }
+    }
+}
+
+/// 内容流中的 `Do` 调用
+///
+/// Form XObject 继承调用处的图形状态与文本状态（文本矩阵除外）。
+#[derive(Debug, Clone)]
+pub struct FormCall {
+    name: Vec<u8>,
+    ctm: Matrix,
+    state: TextState,
+}
+
+impl FormCall {
+    /// 以默认文本状态在 `ctm` 下绘制的表单（如注释外观流）
+    pub(crate) fn detached(ctm: Matrix) -> Self {
+        Self {
+            name: Vec::new(),
+            ctm,
+            state: TextState::default(),
+        }
+    }
+
+    /// XObject 资源名称
+    pub fn name(&self) -> &[u8] {
+        &self.name
+    }
+
+    /// 调用处的 CTM
+    pub(crate) fn ctm(&self) -> Matrix {
+        self.ctm
+    }
+}
+
+/// 内容流文本删除器
+pub struct TextRemover<'a> {
+    fonts: &'a HashMap<Vec<u8>, FontMetrics>,
+    masks: &'a [MaskRect],
+    fallback: FontMetrics,
+    ctm: Matrix,
+    text_matrix: Matrix,
+    line_matrix: Matrix,
+    state: TextState,
+    stack: Vec<(Matrix, TextState)>,
+    removed: usize,
+    form_calls: Vec<FormCall>,
+}
+
+impl<'a> TextRemover<'a> {
+    pub fn new(fonts: &'a HashMap<Vec<u8>, FontMetrics>, masks: &'a [MaskRect]) -> Self {
+        Self {
+            fonts,
+            masks,
+            fallback: FontMetrics::default(),
+            ctm: IDENTITY,
+            text_matrix: IDENTITY,
+            line_matrix: IDENTITY,
+            state: TextState::default(),
+            stack: Vec::new(),
+            removed: 0,
+            form_calls: Vec::new(),
+        }
+    }
+
+    /// 处理 Form XObject 内容：CTM 为 `matrix` × 调用处的 CTM，文本状态继承调用处
+    pub fn in_form(
+        fonts: &'a HashMap<Vec<u8>, FontMetrics>,
+        masks: &'a [MaskRect],
+        call: &FormCall,
+        matrix: Matrix,
+    ) -> Self {
+        Self {
+            ctm: multiply(&matrix, &call.ctm),
+            state: call.state.clone(),
+            ..Self::new(fonts, masks)
+        }
+    }
+
+    /// 取出处理过程中记录的 `Do` 调用
+    pub fn take_form_calls(&mut self) -> Vec<FormCall> {
+        std::mem::take(&mut self.form_calls)
+    }
+
+    /// 已删除的字形数
+    pub fn removed_glyphs(&self) -> usize {
+        self.removed
+    }
+
+    /// 处理操作序列，返回改写后的操作
+    pub fn process(&mut self, operations: Vec<Operation>) -> Vec<Operation> {
+        let mut output = Vec::with_capacity(operations.len());
+
+        for op in operations {
+            match op.operator.as_str() {
+                "q" => {
+                    self.stack.push((self.ctm, self.state.clone()));
+                    output.push(op);
+                }
+                "Q" => {
+                    if let Some((ctm, state)) = self.stack.pop() {
+                        self.ctm = ctm;
+                        self.state = state;
+                    }
+                    output.push(op);
+                }
+                "cm" => {
+                    if let Some(m) = matrix_operand(&op.operands) {
+                        self.ctm = multiply(&m, &self.ctm);
+                    }
+                    output.push(op);
+                }
+                "Do" => {
+                    if let Some(Object::Name(name)) = op.operands.first() {
+                        self.form_calls.push(FormCall {
+                            name: name.clone(),
+                            ctm: self.ctm,
+                            state: self.state.clone(),
+                        });
+                    }
+                    output.push(op);
+                }
+                "BT" => {
+                    self.text_matrix = IDENTITY;
+                    self.line_matrix = IDENTITY;
+                    output.push(op);
+                }
+                "Tc" | "Tw" | "Tz" | "TL" | "Ts" => {
+                    if let Some(value) = op.operands.first().and_then(number) {
+                        self.set_state(op.operator.as_str(), value);
+                    }
+                    output.push(op);
+                }
+                "Tf" => {
+                    if let Some(Object::Name(name)) = op.operands.first() {
+                        self.state.font = Some(name.clone());
+                    }
+                    if let Some(size) = op.operands.get(1).and_then(number) {
+                        self.state.font_size = size;
+                    }
+                    output.push(op);
+                }
+                "Tm" => {
+                    if let Some(m) = matrix_operand(&op.operands) {
+                        self.text_matrix = m;
+                        self.line_matrix = m;
+                    }
+                    output.push(op);
+                }
+                "Td" | "TD" => {
+                    let tx = op.operands.first().and_then(number);
+                    let ty = op.operands.get(1).and_then(number);
+                    if let (Some(tx), Some(ty)) = (tx, ty) {
+                        if op.operator == "TD" {
+                            self.state.leading = -ty;
+                        }
+                        self.move_line(tx, ty);
+                    }
+                    output.push(op);
+                }
+                "T*" => {
+                    self.next_line();
+                    output.push(op);
+                }
+                "Tj" => match op.operands.first() {
+                    Some(Object::String(bytes, format)) => {
+                        let (parts, removed) = self.show_string(bytes, *format);
+                        if removed > 0 {
+                            output.push(Operation::new("TJ", vec![Object::Array(parts)]));
+                        } else {
+                            output.push(op);
+                        }
+                    }
+                    _ => output.push(op),
+                },
+                "'" => {
+                    self.next_line();
+                    match op.operands.first() {
+                        Some(Object::String(bytes, format)) => {
+                            let (parts, removed) = self.show_string(bytes, *format);
+                            if removed > 0 {
+                                output.push(Operation::new("T*", vec![]));
+                                output.push(Operation::new("TJ", vec![Object::Array(parts)]));
+                            } else {
+                                output.push(op);
+                            }
+                        }
+                        _ => output.push(op),
+                    }
+                }
+                "\"" => {
+                    let aw = op.operands.first().and_then(number);
+                    let ac = op.operands.get(1).and_then(number);
+                    if let (Some(aw), Some(ac)) = (aw, ac) {
+                        self.state.word_spacing = aw;
+                        self.state.char_spacing = ac;
+                    }
+                    self.next_line();
+                    match op.operands.get(2) {
+                        Some(Object::String(bytes, format)) => {
+                            let (parts, removed) = self.show_string(bytes, *format);
+                            if removed > 0 {
+                                output.push(Operation::new("Tw", vec![op.operands[0].clone()]));
+                                output.push(Operation::new("Tc", vec![op.operands[1].clone()]));
+                                output.push(Operation::new("T*", vec![]));
+                                output.push(Operation::new("TJ", vec![Object::Array(parts)]));
+                            } else {
+                                output.push(op);
+                            }
+                        }
+                        _ => output.push(op),
+                    }
+                }
+                "TJ" => match op.operands.first() {
+                    Some(Object::Array(items)) => {
+                        let mut parts = Vec::with_capacity(items.len());
+                        let mut removed = 0;
+                        for item in items {
+                            match item {
+                                Object::String(bytes, format) => {
+                                    let (sub, count) = self.show_string(bytes, *format);
+                                    removed += count;
+                                    parts.extend(sub);
+                                }
+                                other => {
+                                    if let Some(adjust) = number(other) {
+                                        self.advance(
+                                            -adjust / 1000.0
+                                                * self.state.font_size
+                                                * self.state.horizontal_scale,
+                                        );
+                                    }
+                                    parts.push(other.clone());
+                                }
+                            }
+                        }
+                        if removed > 0 {
+                            output.push(Operation::new(
+                                "TJ",
+                                vec![Object::Array(merge_adjustments(parts))],
+                            ));
+                        } else {
+                            output.push(op);
+                        }
+                    }
+                    _ => output.push(op),
+                },
+                _ => output.push(op),
+            }
+        }
+
+        output
+    }
+
+    fn set_state(&mut self, operator: &str, value: f32) {
+        match operator {
+            "Tc" => self.state.char_spacing = value,
+            "Tw" => self.state.word_spacing = value,
+            "Tz" => self.state.horizontal_scale = value / 100.0,
+            "TL" => self.state.leading = value,
+            "Ts" => self.state.rise = value,
+            _ => {}
+        }
+    }
+
+    fn move_line(&mut self, tx: f32, ty: f32) {
+        self.line_matrix = multiply(&[1.0, 0.0, 0.0, 1.0, tx, ty], &self.line_matrix);
+        self.text_matrix = self.line_matrix;
+    }
+
+    fn next_line(&mut self) {
+        self.move_line(0.0, -self.state.leading);
+    }
+
+    fn advance(&mut self, tx: f32) {
+        self.text_matrix = multiply(&[1.0, 0.0, 0.0, 1.0, tx, 0.0], &self.text_matrix);
+    }
+
+    /// 逐字形显示字符串，返回 TJ 数组片段与删除的字形数
+    ///
+    /// 被删除的字形以数值位移代替，位移量等于该字形原本的推进距离。
+    fn show_string(&mut self, bytes: &[u8], format: StringFormat) -> (Vec<Object>, usize) {
+        let fonts = self.fonts;
+        let metrics = self
+            .state
+            .font
+            .as_ref()
+            .and_then(|name| fonts.get(name))
+            .unwrap_or(&self.fallback);
+        let glyphs = metrics.glyphs(bytes);
+        let word_spaces: Vec<bool> = glyphs.iter().map(|g| metrics.is_word_space(g)).collect();
+
+        let mut parts = Vec::new();
+        let mut kept: Vec<u8> = Vec::new();
+        let mut removed = 0;
+
+        for (glyph, word_space) in glyphs.iter().zip(word_spaces) {
+            let spacing = self.state.char_spacing
+                + if word_space {
+                    self.state.word_spacing
+                } else {
+                    0.0
+                };
+            let hit = self.glyph_in_mask(glyph);
+            let advance_unscaled = glyph.width * self.state.font_size + spacing;
+
+            if hit && self.state.font_size != 0.0 {
+                if !kept.is_empty() {
+                    parts.push(Object::String(std::mem::take(&mut kept), format));
+                }
+                parts.push(Object::Real(
+                    -advance_unscaled / self.state.font_size * 1000.0,
+                ));
+                removed += 1;
+                self.removed += 1;
+            } else {
+                kept.extend_from_slice(&bytes[glyph.start..glyph.end]);
+            }
+
+            self.advance(advance_unscaled * self.state.horizontal_scale);
+        }
+
+        if !kept.is_empty() {
+            parts.push(Object::String(kept, format));
+        }
+
+        (merge_adjustments(parts), removed)
+    }
+
+    /// 字形中心点是否落在任一遮罩内
+    fn glyph_in_mask(&self, glyph: &Glyph) -> bool {
+        let size = self.state.font_size;
+        let x = glyph.width * size * self.state.horizontal_scale / 2.0;
+        let y = GLYPH_CENTER_RISE * size + self.state.rise;
+        let m = multiply(&self.text_matrix, &self.ctm);
+        let (ux, uy) = transform(&m, x, y);
+        self.masks.iter().any(|mask| mask.contains(ux, uy))
+    }
+}
+
+/// 合并 TJ 数组中相邻的数值位移
+fn merge_adjustments(parts: Vec<Object>) -> Vec<Object> {
+    let mut merged: Vec<Object> = Vec::with_capacity(parts.len());
+    for part in parts {
+        if let (Some(value), Some(last)) = (number(&part), merged.last_mut()) {
+            if let Some(prev) = number(last) {
+                *last = Object::Real(prev + value);
+                continue;
+            }
+        }
+        merged.push(part);
+    }
+    merged
+}
+
+pub(crate) fn matrix_operand(operands: &[Object]) -> Option<Matrix> {
+    if operands.len() < 6 {
+        return None;
+    }
+    let mut m = [0.0; 6];
+    for (slot, operand) in m.iter_mut().zip(operands) {
+        *slot = number(operand)?;
+    }
+    Some(m)
+}
+
+/// 矩阵乘法 a × b
+pub(crate) fn multiply(a: &Matrix, b: &Matrix) -> Matrix {
+    [
+        a[0] * b[0] + a[1] * b[2],
+        a[0] * b[1] + a[1] * b[3],
+        a[2] * b[0] + a[3] * b[2],
+        a[2] * b[1] + a[3] * b[3],
+        a[4] * b[0] + a[5] * b[2] + b[4],
+        a[4] * b[1] + a[5] * b[3] + b[5],
+    ]
+}
+
+pub(crate) fn transform(m: &Matrix, x: f32, y: f32) -> (f32, f32) {
+    (x * m[0] + y * m[2] + m[4], x * m[1] + y * m[3] + m[5])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lopdf::content::Content;
+    use lopdf::{dictionary, Document};
+
+    fn helvetica_like() -> HashMap<Vec<u8>, FontMetrics> {
+        let doc = Document::with_version("1.5");
+        let font = dictionary! {
+            "Type" => "Font",
+            "Subtype" => "Type1",
+            "FirstChar" => 32,
+            "Widths" => vec![Object::Integer(500); 96],
+        };
+        HashMap::from([(b"F1".to_vec(), FontMetrics::from_dict(&doc, &font))])
+    }
+
+    fn shown_text(ops: &[Operation]) -> Vec<u8> {
+        let mut text = Vec::new();
+        for op in ops {
+            for operand in &op.operands {
+                match operand {
+                    Object::String(s, _) => text.extend_from_slice(s),
+                    Object::Array(items) => {
+                        for item in items {
+                            if let Object::String(s, _) = item {
+                                text.extend_from_slice(s);
+                            }
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+        text
+    }
+
+    #[test]
+    fn test_removes_masked_glyphs_and_keeps_advance() {
+        let fonts = helvetica_like();
+        // 字号 10，每个字形宽 5pt，"ID:1234" 从 x=100 开始，"1234" 位于 x=[115, 135]
+        let content = Content::decode(b"BT /F1 10 Tf 100 700 Td (ID:1234 end) Tj ET").unwrap();
+        let masks = [MaskRect {
+            left: 115.0,
+            bottom: 698.0,
+            right: 135.0,
+            top: 710.0,
+        }];
+
+        let mut remover = TextRemover::new(&fonts, &masks);
+        let ops = remover.process(content.operations);
+
+        assert_eq!(shown_text(&ops), b"ID: end");
+
+        let tj = ops.iter().find(|op| op.operator == "TJ").unwrap();
+        let Object::Array(items) = &tj.operands[0] else {
+            panic!("TJ 操作数应为数组");
+        };
+        assert_eq!(items.len(), 3);
+        assert_eq!(number(&items[1]), Some(-2000.0));
+    }
+
+    #[test]
+    fn test_positions_unembedded_standard_font_without_widths() {
+        let doc = Document::with_version("1.5");
+        let font = dictionary! {
+            "Type" => "Font",
+            "Subtype" => "Type1",
+            "BaseFont" => "Helvetica",
+            "Encoding" => "WinAnsiEncoding",
+        };
+        let fonts = HashMap::from([(b"F1".to_vec(), FontMetrics::from_dict(&doc, &font))]);
+        // Helvetica AFM：l、i 宽 222，空格 278，数字 556；"1234" 位于 x=[107.22, 129.46]
+        // 按统一的 500 估算时 "1234" 会落在 x=[115, 135]，遮罩只能删掉 "i 123"
+        let content = Content::decode(b"BT /F1 10 Tf 100 700 Td (li 1234 W) Tj ET").unwrap();
+        let masks = [MaskRect {
+            left: 107.0,
+            bottom: 698.0,
+            right: 129.5,
+            top: 710.0,
+        }];
+
+        let mut remover = TextRemover::new(&fonts, &masks);
+        let ops = remover.process(content.operations);
+
+        assert_eq!(shown_text(&ops), b"li  W");
+        let tj = ops.iter().find(|op| op.operator == "TJ").unwrap();
+        let Object::Array(items) = &tj.operands[0] else {
+            panic!("TJ 操作数应为数组");
+        };
+        assert!((number(&items[1]).unwrap() + 2224.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_tracks_scaling_word_spacing_and_leading() {
+        let fonts = helvetica_like();
+        // Tz 50 使字形推进减半（2.5pt），Tw 4 只作用于空格；' 按 TL 换行
+        let content =
+            Content::decode(b"BT /F1 10 Tf 50 Tz 4 Tw 14 TL 0 100 Td (A B) Tj (XYZ) ' ET").unwrap();
+        // 第一行 B 的中心：A 2.5 + 空格 (5 + 4) * 0.5 + 1.25 = 8.25
+        // 第二行 Y 的中心：x = 2.5 + 1.25 = 3.75，y = 86 + 3
+        let masks = [
+            MaskRect {
+                left: 7.5,
+                bottom: 99.0,
+                right: 9.0,
+                top: 105.0,
+            },
+            MaskRect {
+                left: 3.0,
+                bottom: 85.0,
+                right: 4.5,
+                top: 91.0,
+            },
+        ];
+
+        let mut remover = TextRemover::new(&fonts, &masks);
+        let ops = remover.process(content.operations);
+
+        assert_eq!(shown_text(&ops), b"A XZ");
+        assert_eq!(remover.removed_glyphs(), 2);
+    }
+
+    #[test]
+    fn test_removes_two_byte_cid_glyphs() {
+        let mut doc = Document::with_version("1.5");
+        let descendant = doc.add_object(dictionary! {
+            "Type" => "Font",
+            "Subtype" => "CIDFontType2",
+            "DW" => 1000,
+            // CID 3 为半宽字形
+            "W" => vec![3.into(), vec![Object::Integer(500)].into()],
+        });
+        let font = dictionary! {
+            "Type" => "Font",
+            "Subtype" => "Type0",
+            "Encoding" => "Identity-H",
+            "DescendantFonts" => vec![descendant.into()],
+        };
+        let fonts = HashMap::from([(b"F1".to_vec(), FontMetrics::from_dict(&doc, &font))]);
+
+        // 字号 10：CID 3 宽 5pt，其余 10pt；第二个字形位于 x=[5, 15]
+        let content = Content::decode(b"BT /F1 10 Tf 0 0 Td <0003 4E2D 6587 0003> Tj ET").unwrap();
+        let masks = [MaskRect {
+            left: 6.0,
+            bottom: -2.0,
+            right: 14.0,
+            top: 8.0,
+        }];
+
+        let mut remover = TextRemover::new(&fonts, &masks);
+        let ops = remover.process(content.operations);
+
+        assert_eq!(remover.removed_glyphs(), 1);
+        assert_eq!(shown_text(&ops), vec![0x00, 0x03, 0x65, 0x87, 0x00, 0x03]);
+        let tj = ops.iter().find(|op| op.operator == "TJ").unwrap();
+        let Object::Array(items) = &tj.operands[0] else {
+            panic!("TJ 操作数应为数组");
+        };
+        assert_eq!(number(&items[1]), Some(-1000.0));
+    }
+
+    #[test]
+    fn test_tracks_tj_offsets_and_spacing() {
+        let fonts = helvetica_like();
+        // Tc=1 使每个字形推进 6pt；TJ 中 -1000 额外右移 10pt
+        let content =
+            Content::decode(b"BT /F1 10 Tf 1 Tc 0 0 Td [(AB) -1000 (CD)] TJ 0 -20 Td (EF) Tj ET")
+                .unwrap();
+        // C 的中心位于 x = 12 + 10 + 2.5 = 24.5
+        let masks = [MaskRect {
+            left: 23.0,
+            bottom: -2.0,
+            right: 26.0,
+            top: 8.0,
+        }];
+
+        let mut remover = TextRemover::new(&fonts, &masks);
+        let ops = remover.process(content.operations);
+
+        assert_eq!(shown_text(&ops), b"ABDEF");
+    }
+}
@@ -0,0 +1,400 @@
+//! 字体度量
+//!
+//! 从字体字典中读取字形宽度，用于在内容流中精确计算每个字形的位置：
+//! - 简单字体：单字节编码，/FirstChar + /Widths；缺少 /Widths 的标准 14 字体
+//!   按 /BaseFont 使用内置的 AFM 宽度
+//! - Type0 字体：按 /Encoding CMap 的编码空间拆分字符码并映射到 CID，
+//!   再从 DescendantFonts 的 /W 与 /DW 读取宽度；编码 CMap 缺少编码空间时
+//!   使用 ToUnicode CMap 的编码空间
+
+use crate::base14::{Base14, BaseEncoding};
+use crate::cmap::{code, CMap};
+use lopdf::{Dictionary, Document, Object};
+use std::collections::HashMap;
+
+/// 缺少宽度信息时使用的默认字形宽度（千分之一 em）
+const FALLBACK_WIDTH: f32 = 500.0;
+
+/// 单个字形
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Glyph {
+    /// 字符码
+    pub code: u32,
+    /// 在字符串中的起始字节
+    pub start: usize,
+    /// 在字符串中的结束字节（不含）
+    pub end: usize,
+    /// 水平宽度（文本空间，已除以 1000 或按 FontMatrix 缩放）
+    pub width: f32,
+}
+
+/// 字体度量信息
+#[derive(Debug, Clone)]
+pub struct FontMetrics {
+    /// 字符码（简单字体）或 CID（Type0 字体）-> 宽度（字形空间单位）
+    widths: HashMap<u32, f32>,
+    /// 未列出字符的默认宽度
+    default_width: f32,
+    /// 字形空间到文本空间的缩放（通常为 0.001）
+    scale: f32,
+    /// Type0 字体的编码 CMap，简单字体为空
+    encoding: Option<CMap>,
+    /// ToUnicode CMap
+    to_unicode: Option<CMap>,
+}
+
+impl Default for FontMetrics {
+    fn default() -> Self {
+        Self {
+            widths: HashMap::new(),
+            default_width: FALLBACK_WIDTH,
+            scale: 0.001,
+            encoding: None,
+            to_unicode: None,
+        }
+    }
+}
+
+impl FontMetrics {
+    /// 从字体字典构建度量信息
+    pub fn from_dict(doc: &Document, font: &Dictionary) -> Self {
+        let mut metrics = Self::measure(doc, font);
+        metrics.to_unicode = font
+            .get(b"ToUnicode")
+            .ok()
+            .and_then(|v| resolve(doc, v).as_stream().ok())
+            .map(|stream| CMap::parse(&stream_data(stream)));
+        metrics
+    }
+
+    fn measure(doc: &Document, font: &Dictionary) -> Self {
+        match name_of(font.get(b"Subtype").ok()) {
+            Some(b"Type0") => Self::type0(doc, font),
+            Some(b"Type3") => {
+                let mut metrics = Self::simple(doc, font);
+                if let Some(scale) = font
+                    .get(b"FontMatrix")
+                    .ok()
+                    .and_then(|m| resolve(doc, m).as_array().ok())
+                    .and_then(|m| m.first())
+                    .and_then(number)
+                {
+                    metrics.scale = scale.abs();
+                }
+                metrics
+            }
+            _ => Self::simple(doc, font),
+        }
+    }
+
+    /// 简单字体：/FirstChar + /Widths，缺省使用 FontDescriptor 的 /MissingWidth
+    ///
+    /// 没有 /Widths 时（未嵌入的标准 14 字体）按 /BaseFont 使用 AFM 宽度。
+    fn simple(doc: &Document, font: &Dictionary) -> Self {
+        let mut metrics = Self::default();
+
+        if let Some(missing) = font
+            .get(b"FontDescriptor")
+            .ok()
+            .and_then(|d| resolve(doc, d).as_dict().ok())
+            .and_then(|d| d.get(b"MissingWidth").ok())
+            .and_then(|w| number(resolve(doc, w)))
+        {
+            if missing > 0.0 {
+                metrics.default_width = missing;
+            }
+        }
+
+        let first_char = font
+            .get(b"FirstChar")
+            .ok()
+            .and_then(|v| number(resolve(doc, v)))
+            .unwrap_or(0.0) as u32;
+        match font
+            .get(b"Widths")
+            .ok()
+            .and_then(|w| resolve(doc, w).as_array().ok())
+        {
+            Some(widths) => {
+                for (offset, width) in widths.iter().enumerate() {
+                    if let Some(width) = number(resolve(doc, width)) {
+                        metrics.widths.insert(first_char + offset as u32, width);
+                    }
+                }
+            }
+            None => metrics.widths = standard_widths(doc, font),
+        }
+
+        metrics
+    }
+
+    /// Type0 字体：按编码 CMap 映射 CID，从 DescendantFonts 的 /W 与 /DW 读取宽度
+    fn type0(doc: &Document, font: &Dictionary) -> Self {
+        let mut metrics = Self {
+            default_width: 1000.0,
+            encoding: Some(type0_encoding(doc, font)),
+            ..Self::default()
+        };
+
+        let Some(descendant) = font
+            .get(b"DescendantFonts")
+            .ok()
+            .and_then(|d| resolve(doc, d).as_array().ok())
+            .and_then(|arr| arr.first())
+            .and_then(|d| resolve(doc, d).as_dict().ok())
+        else {
+            return metrics;
+        };
+
+        if let Some(dw) = descendant
+            .get(b"DW")
+            .ok()
+            .and_then(|v| number(resolve(doc, v)))
+        {
+            metrics.default_width = dw;
+        }
+
+        if let Some(w) = descendant
+            .get(b"W")
+            .ok()
+            .and_then(|w| resolve(doc, w).as_array().ok())
+        {
+            parse_cid_widths(doc, w, &mut metrics.widths);
+        }
+
+        metrics
+    }
+
+    /// 将字符串拆分为字形
+    pub fn glyphs(&self, bytes: &[u8]) -> Vec<Glyph> {
+        let mut glyphs = Vec::with_capacity(bytes.len());
+        let mut start = 0;
+
+        while start < bytes.len() {
+            let len = self.code_len(&bytes[start..]);
+            let end = (start + len).min(bytes.len());
+            let code = code(&bytes[start..end]);
+            glyphs.push(Glyph {
+                code,
+                start,
+                end,
+                width: self.width(code),
+            });
+            start = end;
+        }
+
+        glyphs
+    }
+
+    /// 字符串开头的字符码字节数
+    fn code_len(&self, bytes: &[u8]) -> usize {
+        let Some(encoding) = &self.encoding else {
+            return 1;
+        };
+        let cmap = match &self.to_unicode {
+            Some(to_unicode) if !encoding.has_codespace() => to_unicode,
+            _ => encoding,
+        };
+        cmap.code_len(bytes).unwrap_or(2).max(1)
+    }
+
+    /// 字形宽度（文本空间）
+    ///
+    /// Type0 字体的 CID 未知时（预定义的非 Identity CMap）只能估算：
+    /// 基于 Unicode 的 CMap 中 ASCII 字符按半宽，其余按默认宽度。
+    pub fn width(&self, code: u32) -> f32 {
+        let width = match &self.encoding {
+            None => self.widths.get(&code).copied(),
+            Some(encoding) => match encoding.cid(code) {
+                Some(cid) => self.widths.get(&cid).copied(),
+                None if (0x20..0x7F).contains(&code) => Some(self.default_width / 2.0),
+                None => None,
+            },
+        };
+        width.unwrap_or(self.default_width) * self.scale
+    }
+
+    /// 字符码对应的 Unicode 文本（优先使用 ToUnicode，简单字体回退到 Latin-1）
+    pub fn unicode(&self, code: u32) -> Option<String> {
+        if let Some(text) = self.to_unicode.as_ref().and_then(|cmap| cmap.unicode(code)) {
+            return Some(text);
+        }
+        match self.encoding {
+            None => char::from_u32(code).map(String::from),
+            Some(_) => None,
+        }
+    }
+
+    /// 该字形是否应用字间距 Tw（仅单字节编码的 32 号字符）
+    pub fn is_word_space(&self, glyph: &Glyph) -> bool {
+        glyph.code == 32 && glyph.end - glyph.start == 1
+    }
+}
+
+/// 解析 CIDFont 的 /W 数组
+///
+/// 支持两种格式：`c [w1 w2 ...]` 与 `c_first c_last w`。
+fn parse_cid_widths(doc: &Document, w: &[Object], widths: &mut HashMap<u32, f32>) {
+    let mut i = 0;
+    while i < w.len() {
+        let Some(first) = number(resolve(doc, &w[i])) else {
+            break;
+        };
+        let first = first as u32;
+
+        match w.get(i + 1).map(|o| resolve(doc, o)) {
+            Some(Object::Array(list)) => {
+                for (offset, width) in list.iter().enumerate() {
+                    if let Some(width) = number(resolve(doc, width)) {
+                        widths.insert(first + offset as u32, width);
+                    }
+                }
+                i += 2;
+            }
+            Some(last) => {
+                let (Some(last), Some(width)) = (
+                    number(last),
+                    w.get(i + 2).and_then(|o| number(resolve(doc, o))),
+                ) else {
+                    break;
+                };
+                for cid in first..=(last as u32) {
+                    widths.insert(cid, width);
+                }
+                i += 3;
+            }
+            None => break,
+        }
+    }
+}
+
+/// 标准 14 字体按 /Encoding（含 /Differences）映射到字形后的 AFM 宽度
+///
+/// 不是标准字体时返回空表；无法确定宽度的字符码使用默认宽度。
+fn standard_widths(doc: &Document, font: &Dictionary) -> HashMap<u32, f32> {
+    let Some(base14) =
+        name_of(font.get(b"BaseFont").ok().map(|n| resolve(doc, n))).and_then(Base14::find)
+    else {
+        return HashMap::new();
+    };
+
+    let mut base = BaseEncoding::Standard;
+    let mut differences = Vec::new();
+    match font.get(b"Encoding").ok().map(|e| resolve(doc, e)) {
+        Some(Object::Name(name)) => base = base_encoding(name),
+        Some(Object::Dictionary(dict)) => {
+            if let Some(name) = name_of(dict.get(b"BaseEncoding").ok()) {
+                base = base_encoding(name);
+            }
+            if let Ok(Object::Array(items)) = dict.get(b"Differences").map(|d| resolve(doc, d)) {
+                let mut code = 0;
+                for item in items {
+                    match resolve(doc, item) {
+                        Object::Integer(i) => code = *i as u32,
+                        Object::Name(glyph) => {
+                            differences.push((code, glyph.as_slice()));
+                            code += 1;
+                        }
+                        _ => {}
+                    }
+                }
+            }
+        }
+        _ => {}
+    }
+
+    let mut widths: HashMap<u32, f32> = (32..=126)
+        .filter_map(|code| Some((code, base14.width(code, base)?)))
+        .collect();
+    for (code, glyph) in differences {
+        match base14.width_of(glyph) {
+            Some(width) => widths.insert(code, width),
+            None => widths.remove(&code),
+        };
+    }
+    widths
+}
+
+fn base_encoding(name: &[u8]) -> BaseEncoding {
+    match name {
+        b"WinAnsiEncoding" | b"MacRomanEncoding" => BaseEncoding::WinAnsi,
+        _ => BaseEncoding::Standard,
+    }
+}
+
+/// 页面资源中全部字体的度量信息（按资源名称）
+pub fn page_fonts(doc: &Document, page_id: lopdf::ObjectId) -> HashMap<Vec<u8>, FontMetrics> {
+    doc.get_page_fonts(page_id)
+        .map(|fonts| {
+            fonts
+                .into_iter()
+                .map(|(name, font)| (name, FontMetrics::from_dict(doc, font)))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// 资源字典中全部字体的度量信息（按资源名称）
+pub(crate) fn fonts_in(doc: &Document, resources: &Dictionary) -> HashMap<Vec<u8>, FontMetrics> {
+    let Some(fonts) = resources
+        .get(b"Font")
+        .ok()
+        .and_then(|f| resolve(doc, f).as_dict().ok())
+    else {
+        return HashMap::new();
+    };
+    fonts
+        .iter()
+        .filter_map(|(name, font)| {
+            let font = resolve(doc, font).as_dict().ok()?;
+            Some((name.clone(), FontMetrics::from_dict(doc, font)))
+        })
+        .collect()
+}
+
+/// 读取 Type0 字体的编码 CMap（预定义名称或嵌入的 CMap 流）
+fn type0_encoding(doc: &Document, font: &Dictionary) -> CMap {
+    match font.get(b"Encoding").ok().map(|e| resolve(doc, e)) {
+        Some(Object::Name(name)) => CMap::predefined(&String::from_utf8_lossy(name))
+            .unwrap_or_else(|| CMap::predefined("Identity-H").unwrap_or_default()),
+        Some(Object::Stream(stream)) => {
+            let mut cmap = CMap::parse(&stream_data(stream));
+            // 流字典中的 /UseCMap 与内容中的 usecmap 作用相同
+            if let Ok(Object::Name(parent)) = stream.dict.get(b"UseCMap") {
+                cmap = cmap.with_parent(&String::from_utf8_lossy(parent));
+            }
+            cmap.resolve_parent()
+        }
+        _ => CMap::predefined("Identity-H").unwrap_or_default(),
+    }
+}
+
+fn stream_data(stream: &lopdf::Stream) -> Vec<u8> {
+    stream
+        .decompressed_content()
+        .unwrap_or_else(|_| stream.content.clone())
+}
+
+/// 解引用间接对象
+pub(crate) fn resolve<'a>(doc: &'a Document, obj: &'a Object) -> &'a Object {
+    match obj {
+        Object::Reference(id) => doc.get_object(*id).unwrap_or(obj),
+        _ => obj,
+    }
+}
+
+/// 读取数值
+pub(crate) fn number(obj: &Object) -> Option<f32> {
+    match obj {
+        Object::Integer(i) => Some(*i as f32),
+        Object::Real(r) => Some(*r),
+        _ => None,
+    }
+}
+
+fn name_of(obj: Option<&Object>) -> Option<&[u8]> {
+    match obj {
+        Some(Object::Name(name)) => Some(name.as_slice()),
+        _ => None,
+    }
+}
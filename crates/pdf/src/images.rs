@@ -0,0 +1,496 @@
+//! 页面图片的定位
+//!
+//! 跟踪内容流中的图形状态，记录每个图片的绘制位置：`Do` 调用的图片 XObject
+//! 和内嵌图片（BI/ID/EI）都绘制在当前 CTM 变换后的单位正方形中。
+//! lopdf 无法解析内嵌图片的二进制数据，因此先在字节层面把内嵌图片切分出来，
+//! 其余部分照常解析为操作序列。
+
+use crate::content::{matrix_operand, multiply, transform, MaskRect};
+use crate::fonts::number;
+use anyhow::{anyhow, Result};
+use lopdf::content::{Content, Operation};
+use lopdf::{Dictionary, Object};
+
+/// 内容流片段：普通操作或内嵌图片
+#[derive(Debug, Clone)]
+pub enum ContentPiece {
+    Operations(Vec<Operation>),
+    InlineImage(InlineImage),
+}
+
+/// 内嵌图片（BI ... ID 数据 EI）
+#[derive(Debug, Clone)]
+pub struct InlineImage {
+    /// 图片参数（保留缩写键名）
+    pub dict: Dictionary,
+    /// 图片数据（仍为编码后的形式）
+    pub data: Vec<u8>,
+    /// 原始字节（BI 到 EI），未修改时原样写回
+    raw: Vec<u8>,
+}
+
+/// 图片在页面上的绘制位置
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImagePlacement {
+    pub source: ImageSource,
+    /// 单位正方形到页面用户空间的变换
+    pub ctm: [f32; 6],
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ImageSource {
+    /// `Do` 调用的 XObject 资源名称（可能是表单，由调用方判断）
+    XObject(Vec<u8>),
+    /// 内嵌图片在片段列表中的下标
+    Inline(usize),
+}
+
+/// 图片像素坐标中的矩形（左上角原点，右下边界不含）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PixelRect {
+    pub left: u32,
+    pub top: u32,
+    pub right: u32,
+    pub bottom: u32,
+}
+
+impl InlineImage {
+    /// 转换为等价的图片 XObject 字典（展开缩写键名与取值）
+    pub fn to_xobject_dict(&self) -> Dictionary {
+        let mut dict = Dictionary::new();
+        dict.set("Type", "XObject");
+        dict.set("Subtype", "Image");
+        for (key, value) in self.dict.iter() {
+            let key = expand_key(key);
+            let value = match (key, value) {
+                (b"ColorSpace" | b"Filter", Object::Name(name)) => {
+                    Object::Name(expand_value(name).to_vec())
+                }
+                (b"Filter", Object::Array(names)) => Object::Array(
+                    names
+                        .iter()
+                        .map(|n| match n {
+                            Object::Name(name) => Object::Name(expand_value(name).to_vec()),
+                            other => other.clone(),
+                        })
+                        .collect(),
+                ),
+                _ => value.clone(),
+            };
+            dict.set(key.to_vec(), value);
+        }
+        dict
+    }
+}
+
+/// 把内容流切分为操作片段和内嵌图片
+pub fn split_inline_images(data: &[u8]) -> Result<Vec<ContentPiece>> {
+    let mut pieces = Vec::new();
+    let mut segment_start = 0;
+    let mut scanner = Scanner { data, pos: 0 };
+
+    while let Some((start, end)) = scanner.next_token() {
+        if &data[start..end] != b"BI" {
+            continue;
+        }
+        let image = scanner
+            .inline_image(start, end)
+            .ok_or_else(|| anyhow!("内嵌图片不完整"))?;
+        push_operations(&mut pieces, &data[segment_start..start])?;
+        segment_start = scanner.pos;
+        pieces.push(ContentPiece::InlineImage(image));
+    }
+    push_operations(&mut pieces, &data[segment_start..])?;
+    Ok(pieces)
+}
+
+/// 把片段重新编码为内容流
+pub fn encode_pieces(pieces: &[ContentPiece]) -> Result<Vec<u8>> {
+    let mut data = Vec::new();
+    for piece in pieces {
+        match piece {
+            ContentPiece::Operations(operations) => {
+                let encoded = Content {
+                    operations: operations.clone(),
+                }
+                .encode()
+                .map_err(|e| anyhow!("无法编码内容流: {}", e))?;
+                data.extend(encoded);
+                data.push(b'\n');
+            }
+            ContentPiece::InlineImage(image) => {
+                data.extend(&image.raw);
+                data.push(b'\n');
+            }
+        }
+    }
+    Ok(data)
+}
+
+/// 按绘制顺序列出内容流中的图片位置（不进入表单内部）
+///
+/// `base` 为内容流开始时的 CTM：页面内容为单位矩阵，表单内容为调用处 CTM 与表单 /Matrix 的乘积。
+pub fn image_placements(pieces: &[ContentPiece], base: [f32; 6]) -> Vec<ImagePlacement> {
+    let mut ctm = base;
+    let mut stack = Vec::new();
+    let mut placements = Vec::new();
+
+    for (index, piece) in pieces.iter().enumerate() {
+        match piece {
+            ContentPiece::Operations(operations) => {
+                for op in operations {
+                    match op.operator.as_str() {
+                        "q" => stack.push(ctm),
+                        "Q" => ctm = stack.pop().unwrap_or(base),
+                        "cm" => {
+                            if let Some(m) = matrix_operand(&op.operands) {
+                                ctm = multiply(&m, &ctm);
+                            }
+                        }
+                        "Do" => {
+                            if let Some(Object::Name(name)) = op.operands.first() {
+                                placements.push(ImagePlacement {
+                                    source: ImageSource::XObject(name.clone()),
+                                    ctm,
+                                });
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            ContentPiece::InlineImage(_) => placements.push(ImagePlacement {
+                source: ImageSource::Inline(index),
+                ctm,
+            }),
+        }
+    }
+    placements
+}
+
+/// 遮罩在图片像素坐标中覆盖的范围
+///
+/// 把遮罩四角逆变换到图片的单位正方形，取外接矩形并向外取整；
+/// 图片旋转或倾斜时结果会略大于遮罩本身。与图片不相交时返回 `None`。
+pub fn mask_pixels(ctm: &[f32; 6], width: u32, height: u32, mask: &MaskRect) -> Option<PixelRect> {
+    let [a, b, c, d, e, f] = *ctm;
+    let det = a * d - b * c;
+    if det.abs() < f32::EPSILON {
+        return None;
+    }
+    let inverse = [
+        d / det,
+        -b / det,
+        -c / det,
+        a / det,
+        (c * f - d * e) / det,
+        (b * e - a * f) / det,
+    ];
+
+    let corners = [
+        (mask.left, mask.bottom),
+        (mask.right, mask.bottom),
+        (mask.left, mask.top),
+        (mask.right, mask.top),
+    ]
+    .map(|(x, y)| transform(&inverse, x, y));
+    let (mut u0, mut u1, mut v0, mut v1) = (f32::MAX, f32::MIN, f32::MAX, f32::MIN);
+    for (u, v) in corners {
+        u0 = u0.min(u);
+        u1 = u1.max(u);
+        v0 = v0.min(v);
+        v1 = v1.max(v);
+    }
+
+    // 图片第一行位于单位正方形顶部（v = 1）
+    let (w, h) = (width as f32, height as f32);
+    let left = (u0 * w).floor().clamp(0.0, w) as u32;
+    let right = (u1 * w).ceil().clamp(0.0, w) as u32;
+    let top = ((1.0 - v1) * h).floor().clamp(0.0, h) as u32;
+    let bottom = ((1.0 - v0) * h).ceil().clamp(0.0, h) as u32;
+
+    (left < right && top < bottom).then_some(PixelRect {
+        left,
+        top,
+        right,
+        bottom,
+    })
+}
+
+fn push_operations(pieces: &mut Vec<ContentPiece>, data: &[u8]) -> Result<()> {
+    if data.iter().all(u8::is_ascii_whitespace) {
+        return Ok(());
+    }
+    let content = Content::decode(data).map_err(|e| anyhow!("无法解析内容流: {}", e))?;
+    pieces.push(ContentPiece::Operations(content.operations));
+    Ok(())
+}
+
+fn is_whitespace(byte: u8) -> bool {
+    matches!(byte, b' ' | b'\t' | b'\r' | b'\n' | b'\x0c' | b'\0')
+}
+
+fn is_delimiter(byte: u8) -> bool {
+    matches!(
+        byte,
+        b'(' | b')' | b'<' | b'>' | b'[' | b']' | b'{' | b'}' | b'/' | b'%'
+    )
+}
+
+/// 内容流的简单词法扫描，只用于定位 BI/ID/EI
+struct Scanner<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl Scanner<'_> {
+    /// 下一个普通记号（操作符、数字、名称主体）的范围，跳过字符串、注释和分隔符
+    fn next_token(&mut self) -> Option<(usize, usize)> {
+        let data = self.data;
+        while self.pos < data.len() {
+            match data[self.pos] {
+                b if is_whitespace(b) => self.pos += 1,
+                b'%' => {
+                    while self.pos < data.len() && !matches!(data[self.pos], b'\r' | b'\n') {
+                        self.pos += 1;
+                    }
+                }
+                b'(' => self.skip_string(),
+                b'<' if data.get(self.pos + 1) == Some(&b'<') => self.pos += 2,
+                b'<' => {
+                    while self.pos < data.len() && data[self.pos] != b'>' {
+                        self.pos += 1;
+                    }
+                    self.pos += 1;
+                }
+                b'/' => {
+                    // 名称作为整体跳过，避免 /BI 之类的名称被当作操作符
+                    self.pos += 1;
+                    self.skip_regular();
+                }
+                b if is_delimiter(b) => self.pos += 1,
+                _ => {
+                    let start = self.pos;
+                    self.skip_regular();
+                    return Some((start, self.pos));
+                }
+            }
+        }
+        None
+    }
+
+    fn skip_regular(&mut self) {
+        while self.pos < self.data.len()
+            && !is_whitespace(self.data[self.pos])
+            && !is_delimiter(self.data[self.pos])
+        {
+            self.pos += 1;
+        }
+    }
+
+    fn skip_string(&mut self) {
+        let mut depth = 0;
+        while self.pos < self.data.len() {
+            match self.data[self.pos] {
+                b'\\' => self.pos += 1,
+                b'(' => depth += 1,
+                b')' => {
+                    depth -= 1;
+                    if depth == 0 {
+                        self.pos += 1;
+                        return;
+                    }
+                }
+                _ => {}
+            }
+            self.pos += 1;
+        }
+    }
+
+    /// 从 BI 记号开始读取完整的内嵌图片，结束后位置在 EI 之后
+    fn inline_image(&mut self, start: usize, dict_start: usize) -> Option<InlineImage> {
+        let id_start = loop {
+            let (token_start, token_end) = self.next_token()?;
+            if &self.data[token_start..token_end] == b"ID" {
+                break token_start;
+            }
+        };
+
+        let mut dict_source = self.data[dict_start..id_start].to_vec();
+        dict_source.extend_from_slice(b" ID");
+        let operands = Content::decode(&dict_source)
+            .ok()?
+            .operations
+            .pop()?
+            .operands;
+        let mut dict = Dictionary::new();
+        for pair in operands.chunks(2) {
+            if let [Object::Name(key), value] = pair {
+                dict.set(key.clone(), value.clone());
+            }
+        }
+
+        // ID 之后恰好一个空白字符
+        let data_start = self.pos + 1;
+        let data_end = match unfiltered_length(&dict) {
+            Some(len) if data_start + len <= self.data.len() => data_start + len,
+            _ => find_end(self.data, data_start)?,
+        };
+        let ei = self.data[data_end..]
+            .windows(2)
+            .position(|w| w == b"EI")
+            .map(|offset| data_end + offset)?;
+
+        self.pos = ei + 2;
+        Some(InlineImage {
+            dict,
+            data: self.data[data_start..data_end].to_vec(),
+            raw: self.data[start..self.pos].to_vec(),
+        })
+    }
+}
+
+/// 未压缩内嵌图片的数据长度
+fn unfiltered_length(dict: &Dictionary) -> Option<usize> {
+    if dict.get(b"F").is_ok() || dict.get(b"Filter").is_ok() {
+        return None;
+    }
+    let get = |short: &[u8], long: &[u8]| dict.get(short).or_else(|_| dict.get(long)).ok();
+    let width = number(get(b"W", b"Width")?)? as usize;
+    let height = number(get(b"H", b"Height")?)? as usize;
+    let image_mask = matches!(get(b"IM", b"ImageMask"), Some(Object::Boolean(true)));
+    let (components, bits) = if image_mask {
+        (1, 1)
+    } else {
+        let components = match get(b"CS", b"ColorSpace")? {
+            Object::Name(name) => match expand_value(name) {
+                b"DeviceGray" => 1,
+                b"DeviceRGB" => 3,
+                b"DeviceCMYK" => 4,
+                _ => return None,
+            },
+            Object::Array(items) if matches!(items.first(), Some(Object::Name(n)) if expand_value(n) == b"Indexed") => {
+                1
+            }
+            _ => return None,
+        };
+        let bits = get(b"BPC", b"BitsPerComponent")
+            .and_then(number)
+            .unwrap_or(8.0) as usize;
+        (components, bits)
+    };
+    Some((width * components * bits).div_ceil(8) * height)
+}
+
+/// 压缩数据长度未知时，查找前后都是空白的 EI
+fn find_end(data: &[u8], from: usize) -> Option<usize> {
+    (from..data.len().saturating_sub(1)).find_map(|i| {
+        let before = i > from && is_whitespace(data[i - 1]);
+        let after = match data.get(i + 2) {
+            Some(&b) => is_whitespace(b),
+            None => true,
+        };
+        (before && after && &data[i..i + 2] == b"EI").then_some(i - 1)
+    })
+}
+
+fn expand_key(key: &[u8]) -> &[u8] {
+    match key {
+        b"BPC" => b"BitsPerComponent",
+        b"CS" => b"ColorSpace",
+        b"D" => b"Decode",
+        b"DP" => b"DecodeParms",
+        b"F" => b"Filter",
+        b"H" => b"Height",
+        b"IM" => b"ImageMask",
+        b"I" => b"Interpolate",
+        b"W" => b"Width",
+        other => other,
+    }
+}
+
+fn expand_value(value: &[u8]) -> &[u8] {
+    match value {
+        b"G" => b"DeviceGray",
+        b"RGB" => b"DeviceRGB",
+        b"CMYK" => b"DeviceCMYK",
+        b"I" => b"Indexed",
+        b"AHx" => b"ASCIIHexDecode",
+        b"A85" => b"ASCII85Decode",
+        b"LZW" => b"LZWDecode",
+        b"Fl" => b"FlateDecode",
+        b"RL" => b"RunLengthDecode",
+        b"CCF" => b"CCITTFaxDecode",
+        b"DCT" => b"DCTDecode",
+        other => other,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::content::IDENTITY;
+
+    #[test]
+    fn test_splits_inline_images_and_tracks_ctm() {
+        let mut data = b"q 200 0 0 100 50 600 cm /Im1 Do Q (BI ID EI) Tj\n".to_vec();
+        data.extend_from_slice(b"q 20 0 0 10 300 300 cm BI /W 2 /H 2 /CS /G /BPC 8 ID ");
+        // 数据中包含 "EI" 字节，按长度截取而不是搜索
+        data.extend_from_slice(b"EI\x00\x01");
+        data.extend_from_slice(b"\nEI Q");
+
+        let pieces = split_inline_images(&data).unwrap();
+        assert_eq!(pieces.len(), 3);
+        let ContentPiece::InlineImage(image) = &pieces[1] else {
+            panic!("第二个片段应为内嵌图片");
+        };
+        assert_eq!(image.data, b"EI\x00\x01");
+        let dict = image.to_xobject_dict();
+        assert_eq!(
+            dict.get(b"ColorSpace").unwrap().as_name().unwrap(),
+            b"DeviceGray"
+        );
+        assert_eq!(dict.get(b"Width").unwrap().as_i64().unwrap(), 2);
+
+        let placements = image_placements(&pieces, IDENTITY);
+        assert_eq!(placements.len(), 2);
+        assert_eq!(placements[0].source, ImageSource::XObject(b"Im1".to_vec()));
+        assert_eq!(placements[0].ctm, [200.0, 0.0, 0.0, 100.0, 50.0, 600.0]);
+        assert_eq!(placements[1].source, ImageSource::Inline(1));
+
+        // 未修改时原样写回
+        let reparsed = split_inline_images(&encode_pieces(&pieces).unwrap()).unwrap();
+        assert_eq!(reparsed.len(), 3);
+        assert_eq!(image_placements(&reparsed, IDENTITY), placements);
+    }
+
+    #[test]
+    fn test_maps_mask_into_image_pixels() {
+        // 400x200 像素的图片绘制在 (50, 600) 起宽 200、高 100 的区域
+        let ctm = [200.0, 0.0, 0.0, 100.0, 50.0, 600.0];
+        let mask = MaskRect {
+            left: 100.0,
+            bottom: 650.0,
+            right: 150.0,
+            top: 700.0,
+        };
+        assert_eq!(
+            mask_pixels(&ctm, 400, 200, &mask),
+            Some(PixelRect {
+                left: 100,
+                top: 0,
+                right: 200,
+                bottom: 100,
+            })
+        );
+
+        // 与图片不相交
+        let outside = MaskRect {
+            left: 0.0,
+            bottom: 0.0,
+            right: 10.0,
+            top: 10.0,
+        };
+        assert_eq!(mask_pixels(&ctm, 400, 200, &outside), None);
+    }
+}
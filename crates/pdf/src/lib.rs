@@ -2,10 +2,29 @@
 //!
 //! 实现 `Document` trait，提供 PDF 文件的加载、文本提取和脱敏功能。
 
+mod base14;
+mod clean;
+mod cmap;
+mod content;
+mod fonts;
+mod images;
+mod redact;
+mod structure;
+mod xobject;
+
+pub use content::{FormCall, MaskRect, TextRemover};
+pub use fonts::{page_fonts, FontMetrics, Glyph};
+pub use images::{
+    encode_pieces, image_placements, mask_pixels, split_inline_images, ContentPiece,
+    ImagePlacement, ImageSource, InlineImage, PixelRect,
+};
+pub use structure::copy_document_structure;
+pub use xobject::{page_resources, remove_annotation_text, remove_page_text};
+
 use anyhow::{anyhow, Result};
-use linch_core::document::{Document, Page};
-use linch_core::rules::RuleSet;
-use pdfium_render::prelude::*;
+use linch_core::document::{CleanOutcome, Document, Page, RedactOutcome};
+use linch_core::rules::CompiledRuleSet;
+use linch_render::bind_pdfium;
 use serde::{Deserialize, Serialize};
 use std::path::{Path, PathBuf};
 
@@ -58,18 +77,6 @@ pub struct PdfDocument {
     path: PathBuf,
 }
 
-/// 初始化 Pdfium 库
-///
-/// 尝试从当前目录加载，失败则回退到系统库。
-fn init_pdfium() -> Result<Pdfium> {
-    let bindings = Pdfium::bind_to_library(Pdfium::pdfium_platform_library_name_at_path("./"))
-        .or_else(|_| Pdfium::bind_to_system_library())
-        .map_err(|e| anyhow!("无法加载 Pdfium 库: {}", e))?;
-
-    // Pdfium::new() 直接返回 Pdfium 实例，不是 Result
-    Ok(Pdfium::new(bindings))
-}
-
 impl Document for PdfDocument {
     fn load(path: &Path) -> Result<Self>
     where
@@ -80,7 +87,7 @@ impl Document for PdfDocument {
         }
 
         // 验证文件可以被 Pdfium 打开
-        let pdfium = init_pdfium()?;
+        let pdfium = bind_pdfium()?;
         pdfium
             .load_pdf_from_file(path, None)
             .map_err(|e| anyhow!("无法打开 PDF 文件: {}", e))?;
@@ -91,28 +98,32 @@ impl Document for PdfDocument {
     }
 
     fn get_pages(&self) -> Result<Vec<Page>> {
-        let pdfium = init_pdfium()?;
+        let pdfium = bind_pdfium()?;
         let doc = pdfium
             .load_pdf_from_file(&self.path, None)
             .map_err(|e| anyhow!("无法加载 PDF: {}", e))?;
 
+        // 与脱敏使用同一套逐字符提取逻辑，保证命中偏移一致
         let mut pages = Vec::new();
         for (index, page) in doc.pages().iter().enumerate() {
-            let page_number = (index + 1) as u32;
-            let content = page.text().map(|t| t.all()).unwrap_or_default();
-
             pages.push(Page {
-                page_number,
-                content,
+                page_number: (index + 1) as u32,
+                content: redact::PageText::extract(&page)
+                    .map_err(|e| anyhow!("第 {} 页{}", index + 1, e))?
+                    .content,
             });
         }
 
         Ok(pages)
     }
 
-    fn redact(&self, _ruleset: &RuleSet) -> Result<Vec<u8>> {
-        // TODO: 实现 PDF 脱敏逻辑
-        Err(anyhow!("PDF 脱敏功能尚未在新架构中实现"))
+    fn redact(&self, ruleset: &CompiledRuleSet) -> Result<RedactOutcome> {
+        let pdfium = bind_pdfium()?;
+        redact::redact_pdf(&pdfium, &self.path, ruleset)
+    }
+
+    fn clean(&self, data: Vec<u8>, options: &linch_core::CleanOptions) -> Result<CleanOutcome> {
+        clean::clean_pdf(data, options)
     }
 
     fn get_supported_features(&self) -> Vec<String> {
@@ -0,0 +1,304 @@
+//! PDF 文本脱敏
+//!
+//! 流程：
+//! 1. 使用 Pdfium 逐字符提取页面文本与字符框，记录字节偏移 -> 字符框
+//! 2. 对页面文本执行 `CompiledRuleSet::match_text`，将命中字节范围映射回字符框，合并为遮罩
+//! 3. 使用 lopdf 改写页面内容流，删除遮罩内的字形，并在遮罩位置绘制黑框
+
+use crate::content::MaskRect;
+use crate::xobject::{remove_annotation_text, remove_page_text};
+use anyhow::{anyhow, Result};
+use linch_core::document::RedactOutcome;
+use linch_core::rules::CompiledRuleSet;
+use linch_core::Region;
+use lopdf::content::{Content, Operation};
+use lopdf::{dictionary, Object, ObjectId, Stream};
+use pdfium_render::prelude::*;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// 单个字符在页面文本中的位置
+struct CharBox {
+    start: usize,
+    end: usize,
+    rect: Option<MaskRect>,
+}
+
+/// 页面文本及字符框
+pub(crate) struct PageText {
+    pub content: String,
+    chars: Vec<CharBox>,
+}
+
+impl PageText {
+    /// 从 Pdfium 页面提取文本与字符框
+    ///
+    /// 提取失败时返回错误，避免把无法读取的页面当作无命中而跳过。
+    pub fn extract(page: &PdfPage) -> Result<Self> {
+        let mut content = String::new();
+        let mut chars = Vec::new();
+
+        for ch in linch_render::page_chars(page)? {
+            let start = content.len();
+            content.push_str(&ch.text);
+            chars.push(CharBox {
+                start,
+                end: content.len(),
+                rect: ch.bounds.map(|b| MaskRect {
+                    left: b.left,
+                    bottom: b.bottom,
+                    right: b.right,
+                    top: b.top,
+                }),
+            });
+        }
+
+        Ok(Self { content, chars })
+    }
+
+    /// 将命中的字节范围映射为遮罩矩形（同一行的字符合并为一个矩形）
+    fn masks_for(&self, start: usize, end: usize) -> Vec<MaskRect> {
+        let mut masks: Vec<MaskRect> = Vec::new();
+
+        for rect in self
+            .chars
+            .iter()
+            .filter(|c| c.start < end && c.end > start)
+            .filter_map(|c| c.rect)
+        {
+            match masks.last_mut() {
+                Some(last) if same_line(last, &rect) => {
+                    last.left = last.left.min(rect.left);
+                    last.bottom = last.bottom.min(rect.bottom);
+                    last.right = last.right.max(rect.right);
+                    last.top = last.top.max(rect.top);
+                }
+                _ => masks.push(rect),
+            }
+        }
+
+        masks
+    }
+}
+
+/// 单页遮罩及页面尺寸（点）
+struct PageMasks {
+    rects: Vec<MaskRect>,
+    width: f32,
+    height: f32,
+}
+
+impl PageMasks {
+    /// 换算为左上角原点的相对坐标，写入审计记录
+    fn regions(&self) -> Vec<Region> {
+        self.rects
+            .iter()
+            .map(|rect| Region {
+                x: f64::from(rect.left / self.width),
+                y: f64::from(1.0 - rect.top / self.height),
+                width: f64::from(rect.width() / self.width),
+                height: f64::from(rect.height() / self.height),
+            })
+            .collect()
+    }
+}
+
+/// 两个矩形在垂直方向重叠超过较小高度的一半时视为同一行
+fn same_line(a: &MaskRect, b: &MaskRect) -> bool {
+    let overlap = a.top.min(b.top) - a.bottom.max(b.bottom);
+    overlap > a.height().min(b.height()) / 2.0
+}
+
+/// 对 PDF 执行文本脱敏，返回脱敏后的文件内容及实际应用的遮罩
+pub fn redact_pdf(
+    pdfium: &Pdfium,
+    path: &Path,
+    ruleset: &CompiledRuleSet,
+) -> Result<RedactOutcome> {
+    let masks = collect_masks(pdfium, path, ruleset)?;
+
+    let mut doc = lopdf::Document::load(path).map_err(|e| anyhow!("无法解析 PDF: {}", e))?;
+    let page_ids = doc.get_pages();
+
+    for (page_number, page) in &masks {
+        let page_id = *page_ids
+            .get(page_number)
+            .ok_or_else(|| anyhow!("找不到第 {} 页", page_number))?;
+        redact_page(&mut doc, page_id, &page.rects)
+            .map_err(|e| anyhow!("第 {} 页脱敏失败: {}", page_number, e))?;
+    }
+
+    let mut data = Vec::new();
+    doc.save_to(&mut data)
+        .map_err(|e| anyhow!("保存 PDF 失败: {}", e))?;
+    Ok(RedactOutcome {
+        data,
+        mode: Some("text_replace".to_string()),
+        masks: masks
+            .iter()
+            .map(|(page_number, page)| (*page_number, page.regions()))
+            .collect(),
+        ..RedactOutcome::default()
+    })
+}
+
+/// 收集各页需要遮罩的区域（页码从 1 开始）
+fn collect_masks(
+    pdfium: &Pdfium,
+    path: &Path,
+    ruleset: &CompiledRuleSet,
+) -> Result<BTreeMap<u32, PageMasks>> {
+    let document = pdfium
+        .load_pdf_from_file(path, None)
+        .map_err(|e| anyhow!("无法加载 PDF: {}", e))?;
+
+    let mut masks = BTreeMap::new();
+    for (index, page) in document.pages().iter().enumerate() {
+        let text = PageText::extract(&page).map_err(|e| anyhow!("第 {} 页{}", index + 1, e))?;
+        let rects: Vec<MaskRect> = ruleset
+            .match_text(&text.content)
+            .iter()
+            .flat_map(|m| text.masks_for(m.start, m.end))
+            .collect();
+        if !rects.is_empty() {
+            masks.insert(
+                (index + 1) as u32,
+                PageMasks {
+                    rects,
+                    width: page.width().value,
+                    height: page.height().value,
+                },
+            );
+        }
+    }
+
+    Ok(masks)
+}
+
+/// 改写单页内容流：删除遮罩内字形并绘制黑框
+fn redact_page(doc: &mut lopdf::Document, page_id: ObjectId, masks: &[MaskRect]) -> Result<()> {
+    let data = page_content(doc, page_id)?;
+    let content = Content::decode(&data).map_err(|e| anyhow!("无法解析内容流: {}", e))?;
+
+    let (page_operations, _) = remove_page_text(doc, page_id, content.operations, masks)?;
+    remove_annotation_text(doc, page_id, masks)?;
+    let mut operations = vec![Operation::new("q", vec![])];
+    operations.extend(page_operations);
+    operations.push(Operation::new("Q", vec![]));
+    operations.extend(black_boxes(masks));
+
+    let encoded = Content { operations }
+        .encode()
+        .map_err(|e| anyhow!("无法编码内容流: {}", e))?;
+    let mut stream = Stream::new(dictionary! {}, encoded);
+    // 压缩失败时保留未压缩内容
+    let _ = stream.compress();
+    let stream_id = doc.add_object(stream);
+
+    match doc.get_object_mut(page_id) {
+        Ok(Object::Dictionary(page)) => {
+            page.set("Contents", stream_id);
+            Ok(())
+        }
+        _ => Err(anyhow!("页面对象无效")),
+    }
+}
+
+/// 读取并拼接页面的全部内容流
+///
+/// 多个内容流之间插入换行，避免相邻流的操作符被拼接在一起。
+/// 带 /Filter 的流解压失败时返回错误：把压缩字节当作内容解析会漏删文字。
+fn page_content(doc: &lopdf::Document, page_id: ObjectId) -> Result<Vec<u8>> {
+    let mut content = Vec::new();
+    for stream_id in doc.get_page_contents(page_id) {
+        if let Ok(stream) = doc.get_object(stream_id).and_then(Object::as_stream) {
+            match stream.decompressed_content() {
+                Ok(data) => content.extend_from_slice(&data),
+                Err(_) if stream.dict.get(b"Filter").is_err() => {
+                    content.extend_from_slice(&stream.content)
+                }
+                Err(e) => {
+                    return Err(anyhow!(
+                        "无法解压内容流 {} {} R: {}",
+                        stream_id.0,
+                        stream_id.1,
+                        e
+                    ))
+                }
+            }
+            content.push(b'\n');
+        }
+    }
+    Ok(content)
+}
+
+/// 生成覆盖遮罩区域的黑框绘制操作
+fn black_boxes(masks: &[MaskRect]) -> Vec<Operation> {
+    let mut operations = vec![
+        Operation::new("q", vec![]),
+        Operation::new("rg", vec![0.into(), 0.into(), 0.into()]),
+    ];
+    for mask in masks {
+        operations.push(Operation::new(
+            "re",
+            vec![
+                mask.left.into(),
+                mask.bottom.into(),
+                mask.width().into(),
+                mask.height().into(),
+            ],
+        ));
+    }
+    operations.push(Operation::new("f", vec![]));
+    operations.push(Operation::new("Q", vec![]));
+    operations
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lopdf::Document;
+
+    fn page_with_stream(stream: Stream) -> (Document, ObjectId) {
+        let mut doc = Document::with_version("1.5");
+        let content_id = doc.add_object(stream);
+        let page_id = doc.add_object(dictionary! {
+            "Type" => "Page",
+            "Contents" => content_id,
+        });
+        (doc, page_id)
+    }
+
+    #[test]
+    fn test_page_content_rejects_undecodable_filtered_stream() {
+        let broken = Stream::new(
+            dictionary! { "Filter" => "ASCIIHexDecode" },
+            b"42 54 20 45 54>".to_vec(),
+        );
+        let (doc, page_id) = page_with_stream(broken);
+        assert!(page_content(&doc, page_id).is_err());
+
+        let plain = Stream::new(dictionary! {}, b"BT (a) Tj ET".to_vec());
+        let (doc, page_id) = page_with_stream(plain);
+        assert_eq!(page_content(&doc, page_id).unwrap(), b"BT (a) Tj ET\n");
+    }
+
+    #[test]
+    fn test_page_masks_regions_use_top_left_origin() {
+        let page = PageMasks {
+            rects: vec![MaskRect {
+                left: 50.0,
+                bottom: 150.0,
+                right: 100.0,
+                top: 180.0,
+            }],
+            width: 200.0,
+            height: 200.0,
+        };
+        let region = page.regions()[0];
+        assert!((region.x - 0.25).abs() < 1e-6);
+        assert!((region.y - 0.1).abs() < 1e-6);
+        assert!((region.width - 0.25).abs() < 1e-6);
+        assert!((region.height - 0.15).abs() < 1e-6);
+    }
+}
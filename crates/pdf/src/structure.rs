@@ -0,0 +1,531 @@
+//! 文档结构迁移
+//!
+//! 用 pdfium 新建文档逐页重建时，书签、页面标签、命名目标、链接注释和文档级设置
+//! 不会随页面复制。这里按页面对应关系把它们从源文档复制到新文档：指向保留页面的引用
+//! 改为新页面对象，指向已删除页面的目标被丢弃。
+
+use crate::fonts::resolve;
+use anyhow::{anyhow, Result};
+use lopdf::{Dictionary, Document, Object, ObjectId};
+use std::collections::{HashMap, HashSet};
+
+/// 原样复制的文档级设置
+const CATALOG_SETTINGS: [&[u8]; 4] = [b"ViewerPreferences", b"PageMode", b"PageLayout", b"Lang"];
+
+/// 名称树、数字树的最大深度（防止循环引用）
+const MAX_DEPTH: usize = 32;
+
+/// 把源文档的书签、页面标签、命名目标、链接注释和文档级设置复制到新文档
+///
+/// `pages` 按源文档页序给出每页在新文档中的页面对象，已删除的页面为 `None`。
+pub fn copy_document_structure(
+    source: &Document,
+    target: &mut Document,
+    pages: &[Option<ObjectId>],
+) -> Result<()> {
+    let source_catalog = source
+        .catalog()
+        .map_err(|e| anyhow!("无法读取源文档目录: {}", e))?;
+    let source_pages: Vec<ObjectId> = source.page_iter().collect();
+    let mut copier = Copier {
+        source,
+        pages: source_pages
+            .iter()
+            .zip(pages)
+            .filter_map(|(&from, to)| Some((from, (*to)?)))
+            .collect(),
+        source_pages: source_pages.iter().copied().collect(),
+        copied: HashMap::new(),
+    };
+
+    let mut catalog = Dictionary::new();
+    for key in CATALOG_SETTINGS {
+        if let Ok(value) = source_catalog.get(key) {
+            catalog.set(key.to_vec(), copier.copy(target, value));
+        }
+    }
+
+    if let Ok(outlines) = source_catalog.get(b"Outlines") {
+        let outlines = copier.copy(target, outlines);
+        prune_outlines(target, &outlines);
+        catalog.set("Outlines", outlines);
+    }
+
+    if let Ok(open_action) = source_catalog.get(b"OpenAction") {
+        let open_action = copier.copy(target, open_action);
+        if action_valid(target, &open_action) && dest_valid(target, &open_action) {
+            catalog.set("OpenAction", open_action);
+        }
+    }
+
+    // 旧式命名目标字典
+    if let Ok(dests) = source_catalog.get(b"Dests") {
+        if let Object::Dictionary(dests) = copier.copy(target, resolve(source, dests)) {
+            let dests: Dictionary = dests
+                .into_iter()
+                .filter(|(_, dest)| dest_valid(target, dest))
+                .collect();
+            catalog.set("Dests", dests);
+        }
+    }
+
+    // 名称树中的命名目标（其余名称树如脚本、附件不复制）
+    if let Ok(Object::Dictionary(names)) = source_catalog.get(b"Names").map(|n| resolve(source, n))
+    {
+        if let Ok(dests) = names.get(b"Dests") {
+            let mut dests = copier.copy(target, dests);
+            prune_name_tree(target, &mut dests, 0);
+            catalog.set("Names", Dictionary::from_iter([("Dests", dests)]));
+        }
+    }
+
+    if let Some(labels) = page_labels(source, source_catalog, pages) {
+        catalog.set("PageLabels", labels);
+    }
+
+    copy_links(&mut copier, target, &source_pages, pages);
+
+    let target_catalog = target
+        .catalog_mut()
+        .map_err(|e| anyhow!("无法读取新文档目录: {}", e))?;
+    for (key, value) in catalog {
+        target_catalog.set(key, value);
+    }
+    Ok(())
+}
+
+struct Copier<'a> {
+    source: &'a Document,
+    /// 源页面 -> 新页面
+    pages: HashMap<ObjectId, ObjectId>,
+    source_pages: HashSet<ObjectId>,
+    /// 已复制的源对象 -> 新对象
+    copied: HashMap<ObjectId, ObjectId>,
+}
+
+impl Copier<'_> {
+    /// 深度复制对象，页面引用改为新页面，已删除页面与页面树节点替换为 null
+    fn copy(&mut self, target: &mut Document, obj: &Object) -> Object {
+        match obj {
+            Object::Reference(id) => self.copy_reference(target, *id),
+            Object::Array(items) => {
+                Object::Array(items.iter().map(|item| self.copy(target, item)).collect())
+            }
+            Object::Dictionary(dict) => Object::Dictionary(self.copy_dict(target, dict)),
+            Object::Stream(stream) => {
+                let mut stream = stream.clone();
+                stream.dict = self.copy_dict(target, &stream.dict);
+                Object::Stream(stream)
+            }
+            other => other.clone(),
+        }
+    }
+
+    fn copy_dict(&mut self, target: &mut Document, dict: &Dictionary) -> Dictionary {
+        dict.iter()
+            .map(|(key, value)| (key.clone(), self.copy(target, value)))
+            .collect()
+    }
+
+    fn copy_reference(&mut self, target: &mut Document, id: ObjectId) -> Object {
+        if let Some(&page) = self.pages.get(&id) {
+            return Object::Reference(page);
+        }
+        if self.source_pages.contains(&id) {
+            return Object::Null;
+        }
+        if let Some(&copied) = self.copied.get(&id) {
+            return Object::Reference(copied);
+        }
+        let Ok(obj) = self.source.get_object(id) else {
+            return Object::Null;
+        };
+        if matches!(obj, Object::Dictionary(dict) if dict.type_is(b"Pages")) {
+            return Object::Null;
+        }
+
+        // 先登记再复制，书签的 /Parent、/Prev 等循环引用指向同一个新对象
+        let new_id = target.new_object_id();
+        self.copied.insert(id, new_id);
+        let copied = self.copy(target, obj);
+        target.objects.insert(new_id, copied);
+        Object::Reference(new_id)
+    }
+}
+
+/// 目标是否仍指向存在的页面（命名目标按名称解析，视为有效）
+fn dest_valid(doc: &Document, dest: &Object) -> bool {
+    match resolve(doc, dest) {
+        Object::Array(items) => !matches!(items.first(), None | Some(Object::Null)),
+        Object::Dictionary(dict) => match dict.get(b"D") {
+            Ok(d) => dest_valid(doc, d),
+            Err(_) => true,
+        },
+        Object::Null => false,
+        _ => true,
+    }
+}
+
+/// GoTo 动作的目标是否有效，其他动作保留
+fn action_valid(doc: &Document, action: &Object) -> bool {
+    match resolve(doc, action) {
+        Object::Dictionary(action) => match action.get(b"S") {
+            Ok(Object::Name(name)) if name == b"GoTo" => {
+                action.get(b"D").is_ok_and(|d| dest_valid(doc, d))
+            }
+            _ => true,
+        },
+        _ => true,
+    }
+}
+
+/// 书签项的目标指向已删除页面时移除目标，保留标题和子项
+fn prune_outlines(doc: &mut Document, outlines: &Object) {
+    let Object::Reference(root) = outlines else {
+        return;
+    };
+    let mut stack = vec![*root];
+    let mut visited = HashSet::new();
+    while let Some(id) = stack.pop() {
+        if !visited.insert(id) {
+            continue;
+        }
+        let Ok(item) = doc.get_dictionary(id) else {
+            continue;
+        };
+        let dest_invalid = item.get(b"Dest").is_ok_and(|d| !dest_valid(doc, d));
+        let action_invalid = item.get(b"A").is_ok_and(|a| !action_valid(doc, a));
+        for key in [b"First".as_slice(), b"Next"] {
+            if let Ok(Object::Reference(next)) = item.get(key) {
+                stack.push(*next);
+            }
+        }
+
+        if let Ok(item) = doc.get_dictionary_mut(id) {
+            if dest_invalid {
+                item.remove(b"Dest");
+            }
+            if action_invalid {
+                item.remove(b"A");
+            }
+        }
+    }
+}
+
+/// 从名称树叶节点中移除失效的目标
+fn prune_name_tree(doc: &mut Document, node: &mut Object, depth: usize) {
+    if depth >= MAX_DEPTH {
+        return;
+    }
+    match node {
+        Object::Reference(id) => {
+            // 取出后处理，避免同时借用文档
+            let Some(mut object) = doc.objects.remove(id) else {
+                return;
+            };
+            prune_name_tree(doc, &mut object, depth + 1);
+            doc.objects.insert(*id, object);
+        }
+        Object::Dictionary(dict) => {
+            if let Ok(Object::Array(kids)) = dict.get_mut(b"Kids") {
+                for kid in kids {
+                    prune_name_tree(doc, kid, depth + 1);
+                }
+            }
+            if let Ok(Object::Array(names)) = dict.get_mut(b"Names") {
+                *names = names
+                    .chunks(2)
+                    .filter(|pair| pair.len() == 2 && dest_valid(doc, &pair[1]))
+                    .flatten()
+                    .cloned()
+                    .collect();
+            }
+        }
+        _ => {}
+    }
+}
+
+/// 按保留的页面重新计算页面标签
+///
+/// 每个标签区间从其中第一个保留的页面开始，起始编号加上区间内被删除的页数，
+/// 保证保留页面的标签与原文档一致。
+fn page_labels(
+    source: &Document,
+    catalog: &Dictionary,
+    pages: &[Option<ObjectId>],
+) -> Option<Dictionary> {
+    let tree = catalog.get(b"PageLabels").ok()?;
+    let mut ranges = Vec::new();
+    collect_numbers(source, tree, &mut ranges, 0);
+    ranges.sort_by_key(|(start, _)| *start);
+
+    // 源页序 -> 新页序
+    let mut new_index = Vec::with_capacity(pages.len());
+    let mut kept = 0;
+    for page in pages {
+        new_index.push(page.map(|_| kept));
+        kept += usize::from(page.is_some());
+    }
+
+    let mut nums = Vec::new();
+    for (i, (start, label)) in ranges.iter().enumerate() {
+        let end = ranges
+            .get(i + 1)
+            .map_or(pages.len(), |(next, _)| *next)
+            .min(pages.len());
+        let Some((first, index)) = (*start..end).find_map(|p| Some((p, new_index[p]?))) else {
+            continue;
+        };
+        let mut label = label.clone();
+        if first > *start {
+            let st = label.get(b"St").and_then(Object::as_i64).unwrap_or(1);
+            label.set("St", st + (first - start) as i64);
+        }
+        nums.push(Object::Integer(index as i64));
+        nums.push(Object::Dictionary(label));
+    }
+
+    (!nums.is_empty()).then(|| Dictionary::from_iter([("Nums", Object::Array(nums))]))
+}
+
+/// 展开数字树，收集 (页序, 标签字典)
+fn collect_numbers(
+    doc: &Document,
+    node: &Object,
+    out: &mut Vec<(usize, Dictionary)>,
+    depth: usize,
+) {
+    let Object::Dictionary(node) = resolve(doc, node) else {
+        return;
+    };
+    if depth >= MAX_DEPTH {
+        return;
+    }
+    if let Ok(Object::Array(kids)) = node.get(b"Kids") {
+        for kid in kids {
+            collect_numbers(doc, kid, out, depth + 1);
+        }
+    }
+    if let Ok(Object::Array(nums)) = node.get(b"Nums").map(|n| resolve(doc, n)) {
+        for pair in nums.chunks(2) {
+            if let [Object::Integer(start), label] = pair {
+                if let (Ok(start), Object::Dictionary(label)) =
+                    (usize::try_from(*start), resolve(doc, label))
+                {
+                    out.push((start, label.clone()));
+                }
+            }
+        }
+    }
+}
+
+/// 复制保留页面上的链接注释，目标指向已删除页面的链接被丢弃
+fn copy_links(
+    copier: &mut Copier,
+    target: &mut Document,
+    source_pages: &[ObjectId],
+    pages: &[Option<ObjectId>],
+) {
+    for (&source_page, target_page) in source_pages.iter().zip(pages) {
+        let Some(target_page) = *target_page else {
+            continue;
+        };
+        let annots = match copier
+            .source
+            .get_dictionary(source_page)
+            .and_then(|page| page.get(b"Annots"))
+            .map(|a| resolve(copier.source, a))
+        {
+            Ok(Object::Array(annots)) => annots.clone(),
+            _ => continue,
+        };
+
+        let mut links = Vec::new();
+        for annot in &annots {
+            let is_link = matches!(
+                resolve(copier.source, annot),
+                Object::Dictionary(dict) if matches!(dict.get(b"Subtype"), Ok(Object::Name(s)) if s == b"Link")
+            );
+            if !is_link {
+                continue;
+            }
+            let link = copier.copy(target, annot);
+            let valid = match resolve(target, &link) {
+                Object::Dictionary(dict) => {
+                    dict.get(b"Dest").map_or(true, |d| dest_valid(target, d))
+                        && dict.get(b"A").map_or(true, |a| action_valid(target, a))
+                }
+                _ => false,
+            };
+            if valid {
+                links.push(link);
+            }
+        }
+        if links.is_empty() {
+            continue;
+        }
+
+        let Ok(page) = target.get_dictionary_mut(target_page) else {
+            continue;
+        };
+        match page.get_mut(b"Annots") {
+            Ok(Object::Array(existing)) => existing.extend(links),
+            _ => page.set("Annots", links),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lopdf::dictionary;
+
+    /// 创建 `count` 个空白页面的文档
+    fn blank_doc(count: usize) -> (Document, Vec<ObjectId>) {
+        let mut doc = Document::with_version("1.5");
+        let pages_id = doc.new_object_id();
+        let page_ids: Vec<ObjectId> = (0..count)
+            .map(|_| {
+                doc.add_object(dictionary! {
+                    "Type" => "Page",
+                    "Parent" => pages_id,
+                    "MediaBox" => vec![0.into(), 0.into(), 612.into(), 792.into()],
+                })
+            })
+            .collect();
+        doc.objects.insert(
+            pages_id,
+            Object::Dictionary(dictionary! {
+                "Type" => "Pages",
+                "Kids" => page_ids.iter().map(|&id| id.into()).collect::<Vec<Object>>(),
+                "Count" => count as i64,
+            }),
+        );
+        let catalog = doc.add_object(dictionary! { "Type" => "Catalog", "Pages" => pages_id });
+        doc.trailer.set("Root", catalog);
+        (doc, page_ids)
+    }
+
+    fn fit(page: ObjectId) -> Object {
+        Object::Array(vec![page.into(), "Fit".into()])
+    }
+
+    #[test]
+    fn test_remaps_structure_and_drops_deleted_targets() {
+        let (mut source, src) = blank_doc(3);
+        let outlines = source.new_object_id();
+        let first = source.add_object(dictionary! {
+            "Title" => Object::string_literal("第一章"),
+            "Parent" => outlines,
+            "Dest" => fit(src[0]),
+        });
+        let second = source.add_object(dictionary! {
+            "Title" => Object::string_literal("第二章"),
+            "Parent" => outlines,
+            "Prev" => first,
+            "A" => dictionary! { "S" => "GoTo", "D" => fit(src[1]) },
+        });
+        source
+            .get_dictionary_mut(first)
+            .unwrap()
+            .set("Next", second);
+        source.objects.insert(
+            outlines,
+            Object::Dictionary(dictionary! {
+                "Type" => "Outlines",
+                "First" => first,
+                "Last" => second,
+                "Count" => 2,
+            }),
+        );
+        let link = source.add_object(dictionary! {
+            "Type" => "Annot",
+            "Subtype" => "Link",
+            "Rect" => vec![0.into(), 0.into(), 10.into(), 10.into()],
+            "Dest" => fit(src[2]),
+        });
+        let dead_link = source.add_object(dictionary! {
+            "Type" => "Annot",
+            "Subtype" => "Link",
+            "Rect" => vec![0.into(), 20.into(), 10.into(), 30.into()],
+            "Dest" => fit(src[1]),
+        });
+        source.get_dictionary_mut(src[0]).unwrap().set(
+            "Annots",
+            vec![Object::Reference(link), Object::Reference(dead_link)],
+        );
+        let catalog = source.catalog_mut().unwrap();
+        catalog.set("Outlines", outlines);
+        catalog.set("PageMode", "UseOutlines");
+        catalog.set(
+            "Names",
+            dictionary! {
+                "Dests" => dictionary! {
+                    "Names" => vec![
+                        Object::string_literal("intro"), fit(src[0]),
+                        Object::string_literal("middle"), fit(src[1]),
+                    ],
+                },
+            },
+        );
+        catalog.set(
+            "PageLabels",
+            dictionary! {
+                "Nums" => vec![
+                    0.into(), dictionary! { "S" => "r" }.into(),
+                    1.into(), dictionary! { "S" => "D" }.into(),
+                ],
+            },
+        );
+
+        // 删除源文档第 2 页
+        let (mut target, dst) = blank_doc(2);
+        copy_document_structure(&source, &mut target, &[Some(dst[0]), None, Some(dst[1])]).unwrap();
+
+        let catalog = target.catalog().unwrap().clone();
+        assert_eq!(
+            catalog.get(b"PageMode").unwrap().as_name().unwrap(),
+            b"UseOutlines"
+        );
+
+        // 书签保留，指向已删除页面的目标被移除
+        let root = catalog.get(b"Outlines").unwrap().as_reference().unwrap();
+        let root = target.get_dictionary(root).unwrap();
+        let first = root.get(b"First").unwrap().as_reference().unwrap();
+        let first = target.get_dictionary(first).unwrap();
+        let dest = first.get(b"Dest").unwrap().as_array().unwrap();
+        assert_eq!(dest[0].as_reference().unwrap(), dst[0]);
+        let second = first.get(b"Next").unwrap().as_reference().unwrap();
+        let second = target.get_dictionary(second).unwrap();
+        assert!(second.get(b"Title").is_ok());
+        assert!(second.get(b"A").is_err());
+
+        // 链接改指向新页面，指向已删除页面的链接被丢弃
+        let annots = target
+            .get_dictionary(dst[0])
+            .unwrap()
+            .get(b"Annots")
+            .unwrap()
+            .as_array()
+            .unwrap();
+        assert_eq!(annots.len(), 1);
+        let link = target
+            .get_dictionary(annots[0].as_reference().unwrap())
+            .unwrap();
+        let dest = link.get(b"Dest").unwrap().as_array().unwrap();
+        assert_eq!(dest[0].as_reference().unwrap(), dst[1]);
+
+        // 命名目标只保留仍然有效的
+        let names = catalog.get(b"Names").unwrap().as_dict().unwrap();
+        let leaf = names.get(b"Dests").unwrap().as_dict().unwrap();
+        assert_eq!(leaf.get(b"Names").unwrap().as_array().unwrap().len(), 2);
+
+        // 第 3 页（阿拉伯数字区间内被删除一页）仍标为 2
+        let labels = catalog.get(b"PageLabels").unwrap().as_dict().unwrap();
+        let nums = labels.get(b"Nums").unwrap().as_array().unwrap();
+        assert_eq!(nums.len(), 4);
+        assert_eq!(nums[2].as_i64().unwrap(), 1);
+        let decimal = nums[3].as_dict().unwrap();
+        assert_eq!(decimal.get(b"St").unwrap().as_i64().unwrap(), 2);
+    }
+}
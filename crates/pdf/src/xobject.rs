@@ -0,0 +1,554 @@
+//! Form XObject 中的文本删除
+//!
+//! 页面通过 `Do` 绘制的 Form XObject 继承调用处的图形状态与文本状态，
+//! 表单坐标经 /Matrix 与调用处的 CTM 变换到页面空间。按同样的状态递归处理
+//! 表单内容流；内容有改动时写入副本，并让页面自己的资源字典指向副本，
+//! 其他页面共享的原始 XObject 保持不变。
+//!
+//! 注释的外观流（/AP）也是表单，绘制在页面内容之上，按 /Rect 与 /BBox 映射到页面空间后
+//! 以同样方式处理，改动写入副本并由该注释引用。
+//!
+//! 与遮罩相交的表单无法解压或解析时返回错误，由调用方让该页失败：
+//! 跳过它会让页眉、页脚、印章等表单中的文字原样保留。
+
+use crate::content::{
+    matrix_operand, multiply, transform, FormCall, MaskRect, Matrix, TextRemover, IDENTITY,
+};
+use crate::fonts::{fonts_in, number, resolve};
+use anyhow::{anyhow, Result};
+use lopdf::content::{Content, Operation};
+use lopdf::{Dictionary, Document, Object, ObjectId, Stream};
+use std::collections::HashMap;
+
+/// 表单嵌套的最大深度（防止循环引用）
+const MAX_DEPTH: usize = 8;
+
+/// 删除页面内容及其绘制的 Form XObject 中落在遮罩内的字形
+///
+/// 返回改写后的页面操作与删除的字形数。表单有改动时页面的 /Resources
+/// 会替换为本页独立的副本。
+pub fn remove_page_text(
+    doc: &mut Document,
+    page_id: ObjectId,
+    operations: Vec<Operation>,
+    masks: &[MaskRect],
+) -> Result<(Vec<Operation>, usize)> {
+    let mut resources = page_resources(doc, page_id);
+    let fonts = fonts_in(doc, &resources);
+    let mut remover = TextRemover::new(&fonts, masks);
+    let operations = remover.process(operations);
+    let calls = remover.take_form_calls();
+
+    let mut forms = FormRewriter {
+        masks,
+        clones: HashMap::new(),
+        removed: remover.removed_glyphs(),
+    };
+    if forms.rewrite_calls(doc, &mut resources, &calls, 0)? {
+        if let Ok(Object::Dictionary(page)) = doc.get_object_mut(page_id) {
+            page.set("Resources", Object::Dictionary(resources));
+        }
+    }
+
+    Ok((operations, forms.removed))
+}
+
+/// 删除页面注释外观流中落在遮罩内的字形，返回删除的字形数
+///
+/// 外观流绘制在页面内容之上，页面上的黑框遮不住其中的文字，
+/// 因此无论是否删除页面文字都需要处理。
+pub fn remove_annotation_text(
+    doc: &mut Document,
+    page_id: ObjectId,
+    masks: &[MaskRect],
+) -> Result<usize> {
+    let resources = page_resources(doc, page_id);
+    let annot_ids: Vec<ObjectId> = doc
+        .get_dictionary(page_id)
+        .and_then(|page| page.get(b"Annots"))
+        .map(|annots| match resolve(doc, annots) {
+            Object::Array(items) => items
+                .iter()
+                .filter_map(|item| item.as_reference().ok())
+                .collect(),
+            _ => Vec::new(),
+        })
+        .unwrap_or_default();
+
+    let mut forms = FormRewriter {
+        masks,
+        clones: HashMap::new(),
+        removed: 0,
+    };
+    for annot_id in annot_ids {
+        let Ok(annot) = doc.get_dictionary(annot_id) else {
+            continue;
+        };
+        let Some(rect) = annot.get(b"Rect").ok().and_then(|r| rect_of(doc, r)) else {
+            continue;
+        };
+        let mut appearances = match annot.get(b"AP").map(|ap| resolve(doc, ap)) {
+            Ok(Object::Dictionary(ap)) => ap.clone(),
+            _ => continue,
+        };
+
+        let mut changed = false;
+        // 正常、悬停、按下三种外观，复选框等按状态再分一层
+        for key in [b"N".as_slice(), b"R", b"D"] {
+            let Ok(entry) = appearances.get(key).cloned() else {
+                continue;
+            };
+            let rewritten = match resolve(doc, &entry) {
+                Object::Dictionary(states) => {
+                    let mut states = states.clone();
+                    let mut any = false;
+                    for (_, state) in states.iter_mut() {
+                        if let Ok(id) = state.as_reference() {
+                            if let Some(clone_id) =
+                                forms.rewrite_appearance(doc, id, rect, &resources)?
+                            {
+                                *state = Object::Reference(clone_id);
+                                any = true;
+                            }
+                        }
+                    }
+                    any.then_some(Object::Dictionary(states))
+                }
+                _ => match entry.as_reference() {
+                    Ok(id) => forms
+                        .rewrite_appearance(doc, id, rect, &resources)?
+                        .map(Object::Reference),
+                    Err(_) => None,
+                },
+            };
+            if let Some(rewritten) = rewritten {
+                appearances.set(key.to_vec(), rewritten);
+                changed = true;
+            }
+        }
+
+        if changed {
+            if let Ok(Object::Dictionary(annot)) = doc.get_object_mut(annot_id) {
+                annot.set("AP", Object::Dictionary(appearances));
+            }
+        }
+    }
+
+    Ok(forms.removed)
+}
+
+struct FormRewriter<'a> {
+    masks: &'a [MaskRect],
+    /// 原始 XObject -> 本页副本
+    clones: HashMap<ObjectId, ObjectId>,
+    removed: usize,
+}
+
+impl FormRewriter<'_> {
+    /// 处理一组 `Do` 调用，改写 `resources` 中指向副本的 /XObject 条目，返回是否有改写
+    fn rewrite_calls(
+        &mut self,
+        doc: &mut Document,
+        resources: &mut Dictionary,
+        calls: &[FormCall],
+        depth: usize,
+    ) -> Result<bool> {
+        if calls.is_empty() || depth >= MAX_DEPTH {
+            return Ok(false);
+        }
+        let mut xobjects = match resources.get(b"XObject").map(|x| resolve(doc, x)) {
+            Ok(Object::Dictionary(dict)) => dict.clone(),
+            _ => return Ok(false),
+        };
+
+        let mut changed = false;
+        for call in calls {
+            let Ok(Object::Reference(id)) = xobjects.get(call.name()) else {
+                continue;
+            };
+            let id = *id;
+            if let Some(clone_id) = self.rewrite_form(doc, id, resources, call, depth)? {
+                xobjects.set(call.name().to_vec(), Object::Reference(clone_id));
+                changed = true;
+            }
+        }
+
+        if changed {
+            resources.set("XObject", Object::Dictionary(xobjects));
+        }
+        Ok(changed)
+    }
+
+    /// 处理单次表单调用，表单（或其嵌套表单）有改动时返回本页副本
+    ///
+    /// 表单 /BBox 不与任何遮罩相交时直接跳过；相交的表单无法解析时返回错误。
+    fn rewrite_form(
+        &mut self,
+        doc: &mut Document,
+        id: ObjectId,
+        parent_resources: &Dictionary,
+        call: &FormCall,
+        depth: usize,
+    ) -> Result<Option<ObjectId>> {
+        // 同一表单在本页多次绘制时，在已有副本上继续删除
+        let existing = self.clones.get(&id).copied();
+        let Ok(stream) = doc
+            .get_object(existing.unwrap_or(id))
+            .and_then(Object::as_stream)
+        else {
+            return Ok(existing);
+        };
+        // 外观流常省略 /Subtype，只排除明确不是表单的 XObject（图片等）
+        if matches!(stream.dict.get(b"Subtype"), Ok(Object::Name(name)) if name != b"Form") {
+            return Ok(existing);
+        }
+
+        let matrix = match stream.dict.get(b"Matrix").map(|m| resolve(doc, m)) {
+            Ok(Object::Array(values)) => matrix_operand(values).unwrap_or(IDENTITY),
+            _ => IDENTITY,
+        };
+        if !self.covers(doc, &stream.dict, &multiply(&matrix, &call.ctm())) {
+            return Ok(existing);
+        }
+        // 没有 /Resources 的表单使用调用方的资源
+        let mut resources = match stream.dict.get(b"Resources").map(|r| resolve(doc, r)) {
+            Ok(Object::Dictionary(dict)) => dict.clone(),
+            _ => parent_resources.clone(),
+        };
+        let data = match stream.decompressed_content() {
+            Ok(data) => data,
+            Err(_) if stream.dict.get(b"Filter").is_err() => stream.content.clone(),
+            Err(e) => return Err(anyhow!("无法解压表单 {} {} R: {}", id.0, id.1, e)),
+        };
+        let mut dict = stream.dict.clone();
+        let content = Content::decode(&data)
+            .map_err(|e| anyhow!("无法解析表单 {} {} R: {}", id.0, id.1, e))?;
+
+        let fonts = fonts_in(doc, &resources);
+        let mut remover = TextRemover::in_form(&fonts, self.masks, call, matrix);
+        let operations = remover.process(content.operations);
+        let removed = remover.removed_glyphs();
+        let calls = remover.take_form_calls();
+        let nested = self.rewrite_calls(doc, &mut resources, &calls, depth + 1)?;
+
+        if removed == 0 && !nested {
+            return Ok(existing);
+        }
+        self.removed += removed;
+
+        let encoded = Content { operations }
+            .encode()
+            .map_err(|e| anyhow!("无法编码表单 {} {} R: {}", id.0, id.1, e))?;
+        dict.remove(b"Filter");
+        dict.remove(b"DecodeParms");
+        if nested {
+            dict.set("Resources", Object::Dictionary(resources));
+        }
+        let mut stream = Stream::new(dict, encoded);
+        // 压缩失败时保留未压缩内容
+        let _ = stream.compress();
+
+        match existing {
+            Some(clone_id) => {
+                doc.objects.insert(clone_id, Object::Stream(stream));
+                Ok(Some(clone_id))
+            }
+            None => {
+                let clone_id = doc.add_object(stream);
+                self.clones.insert(id, clone_id);
+                Ok(Some(clone_id))
+            }
+        }
+    }
+
+    /// 表单 /BBox 经 `placement` 变换到页面空间后是否与遮罩相交（缺少 /BBox 时视为相交）
+    fn covers(&self, doc: &Document, dict: &Dictionary, placement: &Matrix) -> bool {
+        let Some(bbox) = dict.get(b"BBox").ok().and_then(|b| rect_of(doc, b)) else {
+            return true;
+        };
+        let corners = [
+            transform(placement, bbox[0], bbox[1]),
+            transform(placement, bbox[2], bbox[1]),
+            transform(placement, bbox[0], bbox[3]),
+            transform(placement, bbox[2], bbox[3]),
+        ];
+        let (left, right) = bounds(corners.iter().map(|c| c.0));
+        let (bottom, top) = bounds(corners.iter().map(|c| c.1));
+        self.masks
+            .iter()
+            .any(|m| m.left <= right && m.right >= left && m.bottom <= top && m.top >= bottom)
+    }
+}
+
+impl FormRewriter<'_> {
+    /// 处理注释外观流：/BBox 经 /Matrix 变换后的外接矩形映射到注释的 /Rect
+    fn rewrite_appearance(
+        &mut self,
+        doc: &mut Document,
+        id: ObjectId,
+        rect: [f32; 4],
+        page_resources: &Dictionary,
+    ) -> Result<Option<ObjectId>> {
+        let Ok(stream) = doc.get_object(id).and_then(Object::as_stream) else {
+            return Ok(None);
+        };
+        let dict = &stream.dict;
+        let Some(bbox) = dict.get(b"BBox").ok().and_then(|b| rect_of(doc, b)) else {
+            return Ok(None);
+        };
+        let matrix = match dict.get(b"Matrix").map(|m| resolve(doc, m)) {
+            Ok(Object::Array(values)) => matrix_operand(values).unwrap_or(IDENTITY),
+            _ => IDENTITY,
+        };
+
+        let corners = [
+            transform(&matrix, bbox[0], bbox[1]),
+            transform(&matrix, bbox[2], bbox[1]),
+            transform(&matrix, bbox[0], bbox[3]),
+            transform(&matrix, bbox[2], bbox[3]),
+        ];
+        let (min_x, max_x) = bounds(corners.iter().map(|c| c.0));
+        let (min_y, max_y) = bounds(corners.iter().map(|c| c.1));
+        if max_x - min_x <= 0.0 || max_y - min_y <= 0.0 {
+            return Ok(None);
+        }
+        let sx = (rect[2] - rect[0]) / (max_x - min_x);
+        let sy = (rect[3] - rect[1]) / (max_y - min_y);
+        let placement = [sx, 0.0, 0.0, sy, rect[0] - min_x * sx, rect[1] - min_y * sy];
+
+        self.rewrite_form(doc, id, page_resources, &FormCall::detached(placement), 0)
+    }
+}
+
+/// 读取矩形数组并规范化为 [左, 下, 右, 上]
+fn rect_of(doc: &Document, obj: &Object) -> Option<[f32; 4]> {
+    let Object::Array(values) = resolve(doc, obj) else {
+        return None;
+    };
+    if values.len() < 4 {
+        return None;
+    }
+    let mut v = [0.0; 4];
+    for (slot, value) in v.iter_mut().zip(values) {
+        *slot = number(resolve(doc, value))?;
+    }
+    Some([
+        v[0].min(v[2]),
+        v[1].min(v[3]),
+        v[0].max(v[2]),
+        v[1].max(v[3]),
+    ])
+}
+
+fn bounds(values: impl Iterator<Item = f32>) -> (f32, f32) {
+    values.fold((f32::MAX, f32::MIN), |(lo, hi), v| (lo.min(v), hi.max(v)))
+}
+
+/// 页面资源字典（页面未定义时沿 /Parent 继承）
+pub fn page_resources(doc: &Document, page_id: ObjectId) -> Dictionary {
+    let mut node = doc.get_dictionary(page_id).ok();
+    // 页面树深度有限，限制次数防止 /Parent 循环
+    for _ in 0..32 {
+        let Some(dict) = node else {
+            break;
+        };
+        if let Ok(Object::Dictionary(resources)) = dict.get(b"Resources").map(|r| resolve(doc, r)) {
+            return resources.clone();
+        }
+        node = dict
+            .get(b"Parent")
+            .and_then(Object::as_reference)
+            .and_then(|parent| doc.get_dictionary(parent))
+            .ok();
+    }
+    Dictionary::new()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lopdf::dictionary;
+
+    /// 两个页面共享同一个包含文字的页眉表单
+    fn shared_header_doc() -> (Document, Vec<ObjectId>, ObjectId) {
+        let mut doc = Document::with_version("1.5");
+        let font = doc.add_object(dictionary! {
+            "Type" => "Font",
+            "Subtype" => "Type1",
+            "FirstChar" => 32,
+            "Widths" => vec![Object::Integer(500); 96],
+        });
+        let header = doc.add_object(Stream::new(
+            dictionary! {
+                "Type" => "XObject",
+                "Subtype" => "Form",
+                "BBox" => vec![0.into(), 0.into(), 200.into(), 20.into()],
+                "Matrix" => vec![1.into(), 0.into(), 0.into(), 1.into(), 0.into(), 5.into()],
+                "Resources" => dictionary! { "Font" => dictionary! { "F1" => font } },
+            },
+            b"BT /F1 10 Tf 0 0 Td (TOP SECRET) Tj ET".to_vec(),
+        ));
+        let resources = doc.add_object(dictionary! {
+            "XObject" => dictionary! { "Hdr" => header },
+        });
+
+        let pages_id = doc.new_object_id();
+        let mut page_ids = Vec::new();
+        for _ in 0..2 {
+            let contents = doc.add_object(Stream::new(dictionary! {}, Vec::new()));
+            page_ids.push(doc.add_object(dictionary! {
+                "Type" => "Page",
+                "Parent" => pages_id,
+                "Contents" => contents,
+                "Resources" => resources,
+            }));
+        }
+        doc.objects.insert(
+            pages_id,
+            Object::Dictionary(dictionary! {
+                "Type" => "Pages",
+                "Kids" => page_ids.iter().map(|&id| id.into()).collect::<Vec<Object>>(),
+                "Count" => 2,
+            }),
+        );
+        (doc, page_ids, header)
+    }
+
+    fn form_text(doc: &Document, id: ObjectId) -> Vec<u8> {
+        let stream = doc.get_object(id).and_then(Object::as_stream).unwrap();
+        let data = stream
+            .decompressed_content()
+            .unwrap_or(stream.content.clone());
+        let mut text = Vec::new();
+        for op in Content::decode(&data).unwrap().operations {
+            for operand in op.operands {
+                match operand {
+                    Object::String(s, _) => text.extend(s),
+                    Object::Array(items) => {
+                        for item in items {
+                            if let Object::String(s, _) = item {
+                                text.extend(s);
+                            }
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+        text
+    }
+
+    #[test]
+    fn test_redacts_form_on_one_page_only() {
+        let (mut doc, pages, header) = shared_header_doc();
+        // 页面把表单绘制在 (100, 700)，/Matrix 再上移 5pt；"SECRET" 位于 x=[120, 150]
+        let operations = Content::decode(b"q 1 0 0 1 100 700 cm /Hdr Do Q")
+            .unwrap()
+            .operations;
+        let masks = [MaskRect {
+            left: 119.0,
+            bottom: 704.0,
+            right: 151.0,
+            top: 716.0,
+        }];
+
+        let (_, removed) = remove_page_text(&mut doc, pages[0], operations, &masks).unwrap();
+        assert_eq!(removed, 6);
+
+        let resources = page_resources(&doc, pages[0]);
+        let xobjects = resources.get(b"XObject").unwrap().as_dict().unwrap();
+        let clone = xobjects.get(b"Hdr").unwrap().as_reference().unwrap();
+        assert_ne!(clone, header);
+        assert_eq!(form_text(&doc, clone), b"TOP ");
+
+        // 另一页仍使用原始表单
+        assert_eq!(form_text(&doc, header), b"TOP SECRET");
+        let other = page_resources(&doc, pages[1]);
+        let other = resolve(&doc, other.get(b"XObject").unwrap())
+            .as_dict()
+            .unwrap();
+        assert_eq!(other.get(b"Hdr").unwrap().as_reference().unwrap(), header);
+    }
+
+    #[test]
+    fn test_redacts_annotation_appearance() {
+        let (mut doc, pages, _) = shared_header_doc();
+        let font = doc.add_object(dictionary! {
+            "Type" => "Font",
+            "Subtype" => "Type1",
+            "FirstChar" => 32,
+            "Widths" => vec![Object::Integer(500); 96],
+        });
+        // 外观流 BBox 宽 50，注释 Rect 宽 100：横向放大 2 倍，"B" 位于 x=[110, 120]
+        let appearance = doc.add_object(Stream::new(
+            dictionary! {
+                "BBox" => vec![0.into(), 0.into(), 50.into(), 10.into()],
+                "Resources" => dictionary! { "Font" => dictionary! { "F1" => font } },
+            },
+            b"BT /F1 10 Tf 0 0 Td (AB) Tj ET".to_vec(),
+        ));
+        let annot = doc.add_object(dictionary! {
+            "Type" => "Annot",
+            "Subtype" => "FreeText",
+            "Rect" => vec![100.into(), 700.into(), 200.into(), 720.into()],
+            "AP" => dictionary! { "N" => appearance },
+        });
+        if let Ok(Object::Dictionary(page)) = doc.get_object_mut(pages[0]) {
+            page.set("Annots", vec![Object::Reference(annot)]);
+        }
+        let masks = [MaskRect {
+            left: 111.0,
+            bottom: 700.0,
+            right: 119.0,
+            top: 720.0,
+        }];
+
+        assert_eq!(
+            remove_annotation_text(&mut doc, pages[0], &masks).unwrap(),
+            1
+        );
+        let ap = doc.get_dictionary(annot).unwrap().get(b"AP").unwrap();
+        let clone = ap
+            .as_dict()
+            .unwrap()
+            .get(b"N")
+            .unwrap()
+            .as_reference()
+            .unwrap();
+        assert_ne!(clone, appearance);
+        assert_eq!(form_text(&doc, clone), b"A");
+        assert_eq!(form_text(&doc, appearance), b"AB");
+    }
+
+    #[test]
+    fn test_fails_on_undecodable_form_under_mask() {
+        let (mut doc, pages, header) = shared_header_doc();
+        if let Ok(Object::Stream(stream)) = doc.get_object_mut(header) {
+            stream.dict.set("Filter", "ASCIIHexDecode");
+        }
+        let operations = || {
+            Content::decode(b"q 1 0 0 1 100 700 cm /Hdr Do Q")
+                .unwrap()
+                .operations
+        };
+
+        // 遮罩与表单相交：无法解码时该页失败
+        let covering = [MaskRect {
+            left: 119.0,
+            bottom: 704.0,
+            right: 151.0,
+            top: 716.0,
+        }];
+        assert!(remove_page_text(&mut doc, pages[0], operations(), &covering).is_err());
+
+        // 遮罩不在表单范围内：跳过该表单
+        let elsewhere = [MaskRect {
+            left: 10.0,
+            bottom: 10.0,
+            right: 50.0,
+            top: 30.0,
+        }];
+        let (_, removed) = remove_page_text(&mut doc, pages[1], operations(), &elsewhere).unwrap();
+        assert_eq!(removed, 0);
+    }
+}
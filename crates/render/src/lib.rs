@@ -1,18 +1,389 @@
 //! PDF rendering for preview and OCR.
+//!
+//! 统一管理 pdfium 库的加载与页面渲染，供桌面端（预览、SafeRender、OCR）
+//! 和输出校验共用：
+//! - `bind_pdfium` 按应用目录、打包资源目录、开发目录和系统库的顺序查找 pdfium
+//! - `render_pdf` 按 DPI、页码子集和输出格式逐页渲染，每渲染一页立即交给回调，
+//!   不会把整份文档的图片同时保存在内存中
+//! - `page_chars` 逐字符提取页面文本及字符框
 
+use anyhow::{anyhow, bail, Result};
+use image::codecs::jpeg::JpegEncoder;
+use image::DynamicImage;
+use pdfium_render::prelude::*;
 use serde::{Deserialize, Serialize};
+use std::io::Cursor;
+use std::path::PathBuf;
+
+pub use pdfium_render::prelude::{PdfDocument, PdfPage, Pdfium};
+
+/// 输出图片格式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OutputFormat {
+    #[default]
+    Png,
+    Jpeg,
+    /// 未压缩的 RGBA 像素（每像素 4 字节，按行排列）
+    Rgba,
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RenderOptions {
     pub dpi: u32,
+    /// 要渲染的页码（从 1 开始），为空时渲染全部页面
+    #[serde(default)]
+    pub pages: Option<Vec<u32>>,
+    #[serde(default)]
+    pub format: OutputFormat,
+    /// JPEG 质量（1-100）
+    #[serde(default = "default_jpeg_quality")]
+    pub jpeg_quality: u8,
+}
+
+fn default_jpeg_quality() -> u8 {
+    85
+}
+
+impl Default for RenderOptions {
+    fn default() -> Self {
+        Self {
+            dpi: 150,
+            pages: None,
+            format: OutputFormat::default(),
+            jpeg_quality: default_jpeg_quality(),
+        }
+    }
+}
+
+impl RenderOptions {
+    /// 是否需要渲染指定页（页码从 1 开始）
+    pub fn includes(&self, page: u32) -> bool {
+        match &self.pages {
+            Some(pages) => pages.contains(&page),
+            None => true,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PageImage {
+    /// 页码，从 1 开始
     pub page: u32,
+    /// 像素宽度
+    pub width: u32,
+    /// 像素高度
+    pub height: u32,
+    pub format: OutputFormat,
     pub bytes: Vec<u8>,
 }
 
-pub fn render_pdf(_input_path: &str, _options: &RenderOptions) -> Vec<PageImage> {
-    Vec::new()
+/// 获取 pdfium 库的搜索路径
+fn pdfium_search_paths() -> Vec<PathBuf> {
+    let mut paths = Vec::new();
+
+    if let Ok(exe_path) = std::env::current_exe() {
+        if let Some(exe_dir) = exe_path.parent() {
+            // 1. Linux/Windows: 可执行文件同级的 libs 目录
+            paths.push(exe_dir.join("libs"));
+
+            // 2. Linux/Windows: 可执行文件同级目录
+            paths.push(exe_dir.to_path_buf());
+
+            // 3. macOS: app bundle 内的 Resources 目录
+            #[cfg(target_os = "macos")]
+            {
+                // .app/Contents/MacOS/app -> .app/Contents/Resources/libs
+                if let Some(contents_dir) = exe_dir.parent() {
+                    paths.push(contents_dir.join("Resources").join("libs"));
+                    paths.push(contents_dir.join("Resources"));
+                }
+            }
+
+            // 4. Linux AppImage: 检查 APPDIR 环境变量
+            #[cfg(target_os = "linux")]
+            {
+                if let Ok(appdir) = std::env::var("APPDIR") {
+                    let appdir_path = PathBuf::from(appdir);
+                    paths.push(appdir_path.join("usr").join("lib").join("libs"));
+                    paths.push(appdir_path.join("usr").join("lib"));
+                }
+            }
+        }
+    }
+
+    // 5. 本地开发: src-tauri/libs 目录
+    paths.push(PathBuf::from("libs"));
+    paths.push(PathBuf::from("src-tauri/libs"));
+
+    // 6. 当前目录
+    paths.push(PathBuf::from("./"));
+
+    paths
+}
+
+/// 绑定 pdfium 库
+///
+/// 依次尝试各搜索路径，最后回退到系统库。
+pub fn bind_pdfium() -> Result<Pdfium> {
+    for path in pdfium_search_paths() {
+        let lib_path = Pdfium::pdfium_platform_library_name_at_path(&path);
+        log::debug!("[Render] 尝试加载 pdfium: {:?}", lib_path);
+
+        if let Ok(bindings) = Pdfium::bind_to_library(&lib_path) {
+            log::info!("[Render] 成功从 {:?} 加载 pdfium", path);
+            return Ok(Pdfium::new(bindings));
+        }
+    }
+
+    log::debug!("[Render] 尝试加载系统 pdfium 库");
+    Pdfium::bind_to_system_library()
+        .map(Pdfium::new)
+        .map_err(|e| {
+            anyhow!(
+                "Pdfium 库不可用: {}。\n请运行 ./scripts/setup-pdfium.sh 下载 pdfium 库。",
+                e
+            )
+        })
+}
+
+/// 按指定 DPI 渲染单个页面
+pub fn render_page(page: &PdfPage, dpi: u32) -> Result<DynamicImage> {
+    render_with(page, page_config(page, dpi))
+}
+
+/// 按指定 DPI 只渲染页面内容，不绘制注释和表单域
+///
+/// 用于把页面内容替换为图片：注释仍保留在页面上由阅读器绘制，避免重复。
+pub fn render_page_content(page: &PdfPage, dpi: u32) -> Result<DynamicImage> {
+    let config = page_config(page, dpi)
+        .render_annotations(false)
+        .render_form_data(false);
+    render_with(page, config)
+}
+
+fn page_config(page: &PdfPage, dpi: u32) -> PdfRenderConfig {
+    // PDF 默认 72 DPI
+    let scale = dpi as f32 / 72.0;
+    PdfRenderConfig::new()
+        .set_target_width((page.width().value * scale) as i32)
+        .set_target_height((page.height().value * scale) as i32)
+}
+
+fn render_with(page: &PdfPage, config: PdfRenderConfig) -> Result<DynamicImage> {
+    let bitmap = page
+        .render_with_config(&config)
+        .map_err(|e| anyhow!("渲染页面失败: {}", e))?;
+    Ok(bitmap.as_image())
+}
+
+/// 渲染 PDF 中的单页（页码从 1 开始）
+pub fn render_page_image(input_path: &str, page: u32, dpi: u32) -> Result<DynamicImage> {
+    let pdfium = bind_pdfium()?;
+    let document = pdfium
+        .load_pdf_from_file(input_path, None)
+        .map_err(|e| anyhow!("加载 PDF 失败: {}", e))?;
+    let page = get_page(&document, page)?;
+    render_page(&page, dpi)
+}
+
+/// 逐页渲染 PDF
+///
+/// 按 `options` 指定的 DPI、页码子集和格式渲染，每渲染完一页立即交给 `on_page`，
+/// 回调返回错误时停止渲染。返回实际渲染的页数。
+///
+/// # 错误
+/// - pdfium 库不可用或文件无法打开
+/// - 页码子集中包含超出范围的页码
+pub fn render_pdf<F>(input_path: &str, options: &RenderOptions, on_page: F) -> Result<usize>
+where
+    F: FnMut(PageImage) -> Result<()>,
+{
+    let pdfium = bind_pdfium()?;
+    let document = pdfium
+        .load_pdf_from_file(input_path, None)
+        .map_err(|e| anyhow!("加载 PDF 失败: {}", e))?;
+    render_document(&document, options, on_page)
+}
+
+/// 逐页渲染已打开的文档（调用方已持有 `PdfDocument` 时使用）
+pub fn render_document<F>(
+    document: &PdfDocument,
+    options: &RenderOptions,
+    mut on_page: F,
+) -> Result<usize>
+where
+    F: FnMut(PageImage) -> Result<()>,
+{
+    let page_count = document.pages().len() as u32;
+    if let Some(pages) = &options.pages {
+        if let Some(page) = pages.iter().find(|&&p| p == 0 || p > page_count) {
+            bail!("页码 {} 超出范围（共 {} 页）", page, page_count);
+        }
+    }
+
+    let mut rendered = 0;
+    for number in (1..=page_count).filter(|&n| options.includes(n)) {
+        let page = get_page(document, number)?;
+        let image = render_page(&page, options.dpi)?;
+        let bytes = encode(&image, options.format, options.jpeg_quality)?;
+
+        on_page(PageImage {
+            page: number,
+            width: image.width(),
+            height: image.height(),
+            format: options.format,
+            bytes,
+        })?;
+        rendered += 1;
+    }
+
+    Ok(rendered)
+}
+
+/// 按输出格式编码图片
+pub fn encode(image: &DynamicImage, format: OutputFormat, jpeg_quality: u8) -> Result<Vec<u8>> {
+    let mut bytes = Vec::new();
+    match format {
+        OutputFormat::Png => image
+            .write_to(&mut Cursor::new(&mut bytes), image::ImageFormat::Png)
+            .map_err(|e| anyhow!("PNG 编码失败: {}", e))?,
+        OutputFormat::Jpeg => {
+            // JPEG 不支持透明通道
+            JpegEncoder::new_with_quality(&mut bytes, jpeg_quality.clamp(1, 100))
+                .encode_image(&image.to_rgb8())
+                .map_err(|e| anyhow!("JPEG 编码失败: {}", e))?
+        }
+        OutputFormat::Rgba => bytes = image.to_rgba8().into_raw(),
+    }
+    Ok(bytes)
+}
+
+/// 页面上的单个字符
+#[derive(Debug, Clone, PartialEq)]
+pub struct PageChar {
+    /// 字符文本
+    pub text: String,
+    /// 字符框，pdfium 生成的空格、换行等没有有效字符框时为 None
+    pub bounds: Option<CharBounds>,
+}
+
+/// 字符框（PDF 坐标，单位为点，左下角原点）
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CharBounds {
+    pub left: f32,
+    pub bottom: f32,
+    pub right: f32,
+    pub top: f32,
+}
+
+impl CharBounds {
+    pub fn width(&self) -> f32 {
+        self.right - self.left
+    }
+
+    pub fn height(&self) -> f32 {
+        self.top - self.bottom
+    }
+
+    /// 换算为左上角原点的相对坐标 `(x, y, w, h)`（0-1）
+    pub fn relative(&self, page_width: f32, page_height: f32) -> (f32, f32, f32, f32) {
+        (
+            self.left / page_width,
+            1.0 - self.top / page_height,
+            self.width() / page_width,
+            self.height() / page_height,
+        )
+    }
+}
+
+/// 使用 pdfium 逐字符提取页面文本
+///
+/// 按 pdfium 的字符顺序返回每个字符及其字符框。脱敏、输出校验和 SafeRender
+/// 共用此函数，保证各处的文本和命中位置一致。
+pub fn page_chars(page: &PdfPage) -> Result<Vec<PageChar>> {
+    let text = page.text().map_err(|e| anyhow!("提取文本失败: {}", e))?;
+
+    let mut chars = Vec::new();
+    for ch in text.chars().iter() {
+        let Some(s) = ch.unicode_string() else {
+            continue;
+        };
+        let bounds = ch.loose_bounds().ok().and_then(|r| {
+            let bounds = CharBounds {
+                left: r.left().value,
+                bottom: r.bottom().value,
+                right: r.right().value,
+                top: r.top().value,
+            };
+            (bounds.width() > 0.0 && bounds.height() > 0.0).then_some(bounds)
+        });
+        chars.push(PageChar { text: s, bounds });
+    }
+    Ok(chars)
+}
+
+/// 获取页面（页码从 1 开始）
+fn get_page<'a>(document: &'a PdfDocument, page: u32) -> Result<PdfPage<'a>> {
+    let index = page
+        .checked_sub(1)
+        .and_then(|i| u16::try_from(i).ok())
+        .ok_or_else(|| anyhow!("无效页码: {}", page))?;
+    document
+        .pages()
+        .get(index)
+        .map_err(|e| anyhow!("获取第 {} 页失败: {}", page, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{Rgba, RgbaImage};
+
+    fn sample_image() -> DynamicImage {
+        DynamicImage::ImageRgba8(RgbaImage::from_pixel(4, 3, Rgba([10, 20, 30, 255])))
+    }
+
+    #[test]
+    fn test_encodes_each_format() {
+        let image = sample_image();
+
+        let png = encode(&image, OutputFormat::Png, 85).unwrap();
+        assert!(png.starts_with(b"\x89PNG"));
+
+        let jpeg = encode(&image, OutputFormat::Jpeg, 85).unwrap();
+        assert!(jpeg.starts_with(&[0xFF, 0xD8]));
+
+        let rgba = encode(&image, OutputFormat::Rgba, 85).unwrap();
+        assert_eq!(rgba.len(), 4 * 3 * 4);
+        assert_eq!(&rgba[..4], &[10, 20, 30, 255]);
+    }
+
+    #[test]
+    fn test_char_bounds_to_relative() {
+        let bounds = CharBounds {
+            left: 10.0,
+            bottom: 180.0,
+            right: 30.0,
+            top: 190.0,
+        };
+        let (x, y, w, h) = bounds.relative(100.0, 200.0);
+        assert_eq!((x, w), (0.1, 0.2));
+        assert!((y - 0.05).abs() < 1e-6);
+        assert!((h - 0.05).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_options_default_and_page_subset() {
+        let options: RenderOptions = serde_json::from_str(r#"{"dpi":72}"#).unwrap();
+        assert_eq!(options.format, OutputFormat::Png);
+        assert_eq!(options.jpeg_quality, 85);
+        assert!(options.includes(7));
+
+        let options: RenderOptions =
+            serde_json::from_str(r#"{"dpi":72,"pages":[2,3],"format":"rgba"}"#).unwrap();
+        assert_eq!(options.format, OutputFormat::Rgba);
+        assert!(!options.includes(1));
+        assert!(options.includes(3));
+    }
 }
@@ -1,50 +1,110 @@
 //! Rules schema and matching.
+//!
+//! 统一的规则定义与按位置匹配：
+//! - 文本类规则（关键词、正则、词典、启发式）转换为 `linch_core::rules::Rule`，
+//!   在拼接后的页面文本上执行 `CompiledRuleSet::match_text`，再把命中的字节范围映射回文本项的边界框
+//! - 区域规则与页面规则不依赖文本，按 `Rule::scope` 直接对页面产生命中
+//! - 多页匹配时用 `CompiledRules` 把文本类规则编译一次，在各页间复用
+//! - 规则包（`RulePack`）用于分发审定的规则集，带版本、内容摘要和可选签名
+//!
+//! 页码从 1 开始。坐标系由调用方决定，这里只做并集运算；
+//! 桌面端使用左上角为原点的相对坐标（0-1）。
 
+mod pack;
+
+use linch_core::rules::{
+    CompiledRuleSet, Rule as CoreRule, RuleMatch, RuleSet, RuleType as CoreRuleType,
+};
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct RulePack {
-    pub name: String,
-    pub version: String,
-    pub hash: String,
-    pub rules: Vec<Rule>,
-}
+pub use linch_core::rules::{
+    ContextDirection, ContextScope, ContextTerm, HeuristicType, MatchReason, RuleCompileError,
+    RuleContext,
+};
+pub use pack::{
+    parse_verifying_key, PackError, PackFormat, RulePack, RulePackInfo, SigningKey, VerifyingKey,
+};
 
+/// 检测规则
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Rule {
     pub id: String,
+    /// 用户可读的规则名称
+    #[serde(default)]
+    pub name: String,
     pub rule_type: RuleType,
+    /// 生效的页面范围
+    #[serde(default)]
     pub scope: PageScope,
+    #[serde(default)]
     pub action: RuleAction,
+    #[serde(default = "default_enabled")]
     pub enabled: bool,
+    /// 文本规则的上下文条件（区域、页面规则忽略）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub context: Option<RuleContext>,
 }
 
+fn default_enabled() -> bool {
+    true
+}
+
+/// 规则类型
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
 pub enum RuleType {
+    /// 关键词（精确匹配）
     Keyword { value: String },
+    /// 正则表达式
     Regex { pattern: String },
+    /// 命名词典
     Dictionary { name: String, entries: Vec<String> },
-    Region { name: String, page: u32, bbox: BBox },
-    PageRule { pages: PageScope },
+    /// 启发式识别（人名、地址、证件号等）
+    Heuristic { kind: HeuristicType },
+    /// 固定区域（签名栏、印章位置等），页面由 `Rule::scope` 决定
+    Region { name: String, bbox: BBox },
+    /// 整页命中，通常配合 `RemovePage`/`KeepOnlyPages` 使用
+    PageRule,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum RuleAction {
+    #[default]
     RedactText,
     RedactRegion,
     RemovePage,
     KeepOnlyPages,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// 页面范围（页码从 1 开始，`Range` 含两端）
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum PageScope {
+    #[default]
     All,
     Page(u32),
-    Range { start: u32, end: u32 },
+    Range {
+        start: u32,
+        end: u32,
+    },
     List(Vec<u32>),
 }
 
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+impl PageScope {
+    /// 页码是否在范围内
+    pub fn contains(&self, page: u32) -> bool {
+        match self {
+            PageScope::All => true,
+            PageScope::Page(p) => *p == page,
+            PageScope::Range { start, end } => (*start..=*end).contains(&page),
+            PageScope::List(pages) => pages.contains(&page),
+        }
+    }
+}
+
+/// 边界框，(x, y) 为坐标值较小的一角
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub struct BBox {
     pub x: f32,
     pub y: f32,
@@ -52,15 +112,531 @@ pub struct BBox {
     pub h: f32,
 }
 
+impl BBox {
+    /// 包含两个框的最小框
+    pub fn union(&self, other: &BBox) -> BBox {
+        let x = self.x.min(other.x);
+        let y = self.y.min(other.y);
+        BBox {
+            x,
+            y,
+            w: (self.x + self.w).max(other.x + other.w) - x,
+            h: (self.y + self.h).max(other.y + other.h) - y,
+        }
+    }
+
+    /// 两个框是否位于同一文本行（垂直方向重叠超过较矮者的一半）
+    fn same_line(&self, other: &BBox) -> bool {
+        let overlap = (self.y + self.h).min(other.y + other.h) - self.y.max(other.y);
+        overlap > self.h.min(other.h) * 0.5
+    }
+}
+
+/// 带位置的文本片段（字符、单词或 OCR 结果）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TextItem {
+    pub text: String,
+    /// 没有位置的片段（补充的空格、换行等）只参与文本拼接
+    pub bbox: Option<BBox>,
+    /// 识别置信度（OCR 结果），文本层为 None
+    pub confidence: Option<f32>,
+}
+
+/// 一页带位置的文本
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PositionedPage {
+    /// 页码（从 1 开始）
+    pub page: u32,
+    pub width: f32,
+    pub height: f32,
+    /// 按阅读顺序排列，直接拼接即为页面文本
+    pub items: Vec<TextItem>,
+}
+
+impl PositionedPage {
+    /// 拼接后的页面文本
+    pub fn text(&self) -> String {
+        self.items.iter().map(|i| i.text.as_str()).collect()
+    }
+
+    /// 拼接文本，并记录每个文本项的字节范围
+    fn layout(&self) -> (String, Vec<(usize, usize)>) {
+        let mut text = String::new();
+        let mut ranges = Vec::with_capacity(self.items.len());
+        for item in &self.items {
+            let start = text.len();
+            text.push_str(&item.text);
+            ranges.push((start, text.len()));
+        }
+        (text, ranges)
+    }
+
+    /// 将字节范围映射为按行拆分的 (边界框, 置信度)
+    ///
+    /// 覆盖的文本项都没有位置时返回单个 `None` 边界框。
+    fn locate(
+        &self,
+        ranges: &[(usize, usize)],
+        start: usize,
+        end: usize,
+    ) -> Vec<(Option<BBox>, Option<f32>)> {
+        let mut lines: Vec<(BBox, Option<f32>)> = Vec::new();
+        let mut unplaced: Option<f32> = None;
+
+        for (item, _) in self
+            .items
+            .iter()
+            .zip(ranges)
+            .filter(|(_, (s, e))| *s < end && *e > start)
+        {
+            let Some(bbox) = item.bbox else {
+                match lines.last_mut() {
+                    Some(line) => line.1 = min_confidence(line.1, item.confidence),
+                    None => unplaced = min_confidence(unplaced, item.confidence),
+                }
+                continue;
+            };
+            match lines.last_mut() {
+                Some(line) if line.0.same_line(&bbox) => {
+                    line.0 = line.0.union(&bbox);
+                    line.1 = min_confidence(line.1, item.confidence);
+                }
+                _ => lines.push((bbox, min_confidence(unplaced.take(), item.confidence))),
+            }
+        }
+
+        if lines.is_empty() {
+            return vec![(None, unplaced)];
+        }
+        lines
+            .into_iter()
+            .map(|(bbox, confidence)| (Some(bbox), confidence))
+            .collect()
+    }
+}
+
+fn min_confidence(a: Option<f32>, b: Option<f32>) -> Option<f32> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(a.min(b)),
+        (a, b) => a.or(b),
+    }
+}
+
+/// 规则命中
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MatchHit {
+    /// 页码（从 1 开始）
     pub page: u32,
-    pub bbox: BBox,
+    /// 命中区域；文本没有位置信息时为 None
+    pub bbox: Option<BBox>,
     pub rule_id: String,
+    pub rule_name: String,
+    pub action: RuleAction,
+    /// 命中的原文（区域、页面规则为空）
     pub snippet: String,
+    /// 置信度：规则匹配的置信度乘以覆盖文本项中的最低 OCR 置信度
+    /// （区域、页面规则为 None）
     pub confidence: Option<f32>,
+    /// 命中依据（区域、页面规则为空）
+    #[serde(default)]
+    pub reasons: Vec<MatchReason>,
+}
+
+impl Rule {
+    /// 转换为 `linch_core` 文本规则
+    ///
+    /// 区域、页面规则以及空关键词、空词典返回 None。
+    pub fn to_core(&self) -> Option<CoreRule> {
+        let rule_type = match &self.rule_type {
+            RuleType::Keyword { value } if !value.is_empty() => {
+                CoreRuleType::Dictionary(vec![value.clone()])
+            }
+            RuleType::Regex { pattern } if !pattern.is_empty() => {
+                CoreRuleType::Regex(pattern.clone())
+            }
+            RuleType::Dictionary { entries, .. } => {
+                let entries: Vec<String> =
+                    entries.iter().filter(|e| !e.is_empty()).cloned().collect();
+                if entries.is_empty() {
+                    return None;
+                }
+                CoreRuleType::Dictionary(entries)
+            }
+            RuleType::Heuristic { kind } => CoreRuleType::Heuristic(kind.clone()),
+            _ => return None,
+        };
+
+        Some(CoreRule {
+            id: self.id.clone(),
+            name: self.name.clone(),
+            enabled: self.enabled,
+            is_system: false,
+            rule_type,
+            context: self.context.clone(),
+        })
+    }
+
+    fn hit(&self, page: u32, bbox: Option<BBox>) -> MatchHit {
+        MatchHit {
+            page,
+            bbox,
+            rule_id: self.id.clone(),
+            rule_name: self.name.clone(),
+            action: self.action,
+            snippet: String::new(),
+            confidence: None,
+            reasons: Vec::new(),
+        }
+    }
+}
+
+/// 将规则转换为文本规则集（忽略区域、页面规则）
+pub fn to_ruleset(rules: &[Rule]) -> RuleSet {
+    RuleSet {
+        rules: rules.iter().filter_map(Rule::to_core).collect(),
+        ..RuleSet::default()
+    }
+}
+
+/// 预编译的规则
+///
+/// 文本类规则编译为一个 `CompiledRuleSet`，页面范围在匹配时按页筛选。
+#[derive(Debug, Clone)]
+pub struct CompiledRules {
+    /// 启用的规则
+    rules: Vec<Rule>,
+    text: CompiledRuleSet,
 }
 
-pub fn match_text(_text: &str, _rules: &[Rule]) -> Vec<MatchHit> {
-    Vec::new()
+impl CompiledRules {
+    /// 编译规则，任一启用的文本规则无法编译时返回所有出错的规则
+    pub fn new(rules: &[Rule]) -> Result<Self, Vec<RuleCompileError>> {
+        let rules: Vec<Rule> = rules.iter().filter(|r| r.enabled).cloned().collect();
+        let text = to_ruleset(&rules).compile()?;
+        Ok(Self { rules, text })
+    }
+
+    /// 在带位置的页面上执行规则匹配
+    ///
+    /// 跨行的文本命中会按行拆分为多个 `MatchHit`。
+    /// 前后紧邻数字的文本命中会被丢弃，避免把更长数字串的一部分当作命中。
+    pub fn match_text(&self, pages: &[PositionedPage]) -> Vec<MatchHit> {
+        let mut hits = Vec::new();
+
+        for page in pages {
+            let in_scope: Vec<&Rule> = self
+                .rules
+                .iter()
+                .filter(|r| r.scope.contains(page.page))
+                .collect();
+            hits.extend(self.match_page(page, &in_scope));
+        }
+
+        hits
+    }
+
+    fn match_page(&self, page: &PositionedPage, in_scope: &[&Rule]) -> Vec<MatchHit> {
+        let mut hits = Vec::new();
+
+        for rule in in_scope {
+            let bbox = match &rule.rule_type {
+                RuleType::Region { bbox, .. } => *bbox,
+                RuleType::PageRule => BBox {
+                    x: 0.0,
+                    y: 0.0,
+                    w: page.width,
+                    h: page.height,
+                },
+                _ => continue,
+            };
+            hits.push(rule.hit(page.page, Some(bbox)));
+        }
+
+        let (text, ranges) = page.layout();
+        let matches = self
+            .text
+            .match_text_where(&text, |id| in_scope.iter().any(|r| r.id == id));
+        for mut hit in locate_matches(page, &text, &ranges, matches, true) {
+            if let Some(rule) = in_scope.iter().find(|r| r.id == hit.rule_id) {
+                hit.action = rule.action;
+            }
+            hits.push(hit);
+        }
+
+        hits
+    }
+}
+
+/// 在单页带位置的文本上执行文本规则集
+///
+/// 不区分页面范围，也不做数字边界过滤，适合输出校验等宁可多报的场景。
+pub fn match_ruleset(page: &PositionedPage, ruleset: &CompiledRuleSet) -> Vec<MatchHit> {
+    let (text, ranges) = page.layout();
+    let matches = ruleset.match_text(&text);
+    locate_matches(page, &text, &ranges, matches, false)
+}
+
+/// 把文本命中映射回页面位置
+fn locate_matches(
+    page: &PositionedPage,
+    text: &str,
+    ranges: &[(usize, usize)],
+    matches: Vec<RuleMatch>,
+    skip_digit_bounded: bool,
+) -> Vec<MatchHit> {
+    let mut hits = Vec::new();
+
+    for m in matches {
+        if m.start == m.end || (skip_digit_bounded && touches_digit(text, m.start, m.end)) {
+            continue;
+        }
+        for (bbox, ocr_confidence) in page.locate(ranges, m.start, m.end) {
+            hits.push(MatchHit {
+                page: page.page,
+                bbox,
+                rule_id: m.rule_id.clone(),
+                rule_name: m.rule_name.clone(),
+                action: RuleAction::RedactText,
+                snippet: m.matched_text.clone(),
+                confidence: Some(m.confidence * ocr_confidence.unwrap_or(1.0)),
+                reasons: m.reasons.clone(),
+            });
+        }
+    }
+
+    hits
+}
+
+/// 命中范围前后是否紧邻 ASCII 数字
+fn touches_digit(text: &str, start: usize, end: usize) -> bool {
+    let before = text[..start].chars().next_back();
+    let after = text[end..].chars().next();
+    before.is_some_and(|c| c.is_ascii_digit()) || after.is_some_and(|c| c.is_ascii_digit())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(id: &str, rule_type: RuleType) -> Rule {
+        Rule {
+            id: id.to_string(),
+            name: id.to_string(),
+            rule_type,
+            scope: PageScope::All,
+            action: RuleAction::RedactText,
+            enabled: true,
+            context: None,
+        }
+    }
+
+    /// 每个字符一个文本项，宽 1、高 2，`line` 决定纵向位置
+    fn chars(text: &str, line: f32) -> Vec<TextItem> {
+        text.chars()
+            .enumerate()
+            .map(|(i, c)| TextItem {
+                text: c.to_string(),
+                bbox: Some(BBox {
+                    x: i as f32,
+                    y: line * 3.0,
+                    w: 1.0,
+                    h: 2.0,
+                }),
+                confidence: None,
+            })
+            .collect()
+    }
+
+    fn page(page: u32, items: Vec<TextItem>) -> PositionedPage {
+        PositionedPage {
+            page,
+            width: 100.0,
+            height: 200.0,
+            items,
+        }
+    }
+
+    fn match_text(pages: &[PositionedPage], rules: &[Rule]) -> Vec<MatchHit> {
+        CompiledRules::new(rules).unwrap().match_text(pages)
+    }
+
+    #[test]
+    fn test_keyword_hit_uses_union_of_char_boxes() {
+        let pages = vec![page(1, chars("ab secret 1secret", 0.0))];
+        let rules = vec![rule(
+            "kw",
+            RuleType::Keyword {
+                value: "secret".to_string(),
+            },
+        )];
+
+        let hits = match_text(&pages, &rules);
+        // 第二处紧邻数字，被边界检查过滤
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].page, 1);
+        assert_eq!(hits[0].snippet, "secret");
+        assert_eq!(
+            hits[0].bbox,
+            Some(BBox {
+                x: 3.0,
+                y: 0.0,
+                w: 6.0,
+                h: 2.0
+            })
+        );
+    }
+
+    #[test]
+    fn test_hit_spanning_lines_is_split_and_keeps_lowest_confidence() {
+        let mut items = chars("foo", 0.0);
+        items.push(TextItem {
+            text: "\n".to_string(),
+            bbox: None,
+            confidence: None,
+        });
+        items.extend(chars("bar", 1.0));
+        items[1].confidence = Some(0.9);
+        items[5].confidence = Some(0.6);
+
+        let rules = vec![rule(
+            "re",
+            RuleType::Regex {
+                pattern: r"oo\nba".to_string(),
+            },
+        )];
+        let hits = match_text(&[page(1, items)], &rules);
+
+        assert_eq!(hits.len(), 2);
+        assert_eq!(hits[0].bbox.map(|b| (b.x, b.w)), Some((1.0, 2.0)));
+        assert_eq!(hits[0].confidence, Some(0.9));
+        assert_eq!(hits[1].bbox.map(|b| (b.y, b.w)), Some((3.0, 2.0)));
+        assert_eq!(hits[1].confidence, Some(0.6));
+    }
+
+    #[test]
+    fn test_heuristic_hit_carries_confidence_and_reasons() {
+        let mut items = chars("mail a@example.com", 0.0);
+        items[7].confidence = Some(0.5);
+        let rules = vec![rule(
+            "email",
+            RuleType::Heuristic {
+                kind: HeuristicType::Email,
+            },
+        )];
+        let hits = match_text(&[page(1, items)], &rules);
+
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].confidence, Some(0.9 * 0.5));
+        assert!(matches!(
+            hits[0].reasons.as_slice(),
+            [MatchReason::Pattern { .. }]
+        ));
+    }
+
+    #[test]
+    fn test_compiled_rules_report_errors_and_follow_scope() {
+        let bad = rule(
+            "bad",
+            RuleType::Regex {
+                pattern: "(".to_string(),
+            },
+        );
+        let errors = CompiledRules::new(&[bad]).unwrap_err();
+        assert_eq!(errors[0].rule_id, "bad");
+
+        let mut kw = rule(
+            "kw",
+            RuleType::Keyword {
+                value: "secret".to_string(),
+            },
+        );
+        kw.scope = PageScope::List(vec![2]);
+        let compiled = CompiledRules::new(&[kw]).unwrap();
+        let pages = vec![page(1, chars("secret", 0.0)), page(2, chars("secret", 0.0))];
+
+        let hits = compiled.match_text(&pages);
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].page, 2);
+    }
+
+    #[test]
+    fn test_context_is_evaluated_on_positioned_text() {
+        let json = r#"{
+            "id": "acct",
+            "rule_type": {"type": "regex", "pattern": "\\d{4}"},
+            "context": {"required": ["No."], "forbidden": ["sample"], "distance": 5}
+        }"#;
+        let acct: Rule = serde_json::from_str(json).unwrap();
+        let mut items = chars("No. 1234", 0.0);
+        items.push(TextItem {
+            text: "\n".to_string(),
+            bbox: None,
+            confidence: None,
+        });
+        items.extend(chars("sample No. 5678", 1.0));
+        items.push(TextItem {
+            text: "\n".to_string(),
+            bbox: None,
+            confidence: None,
+        });
+        items.extend(chars("ref 9999", 2.0));
+
+        let hits = match_text(&[page(1, items)], &[acct]);
+        let snippets: Vec<&str> = hits.iter().map(|h| h.snippet.as_str()).collect();
+        // 第二行的「sample」超出 5 个字符的距离；第三行缺少「No.」
+        assert_eq!(snippets, vec!["1234", "5678"]);
+    }
+
+    #[test]
+    fn test_region_and_page_rules_follow_scope() {
+        let region = BBox {
+            x: 10.0,
+            y: 10.0,
+            w: 5.0,
+            h: 5.0,
+        };
+        let mut stamp = rule(
+            "stamp",
+            RuleType::Region {
+                name: "印章".to_string(),
+                bbox: region,
+            },
+        );
+        stamp.scope = PageScope::Range { start: 2, end: 3 };
+        let mut cover = rule("cover", RuleType::PageRule);
+        cover.scope = PageScope::List(vec![1]);
+        cover.action = RuleAction::RemovePage;
+
+        let pages: Vec<PositionedPage> = (1..=3).map(|p| page(p, Vec::new())).collect();
+        let hits = match_text(&pages, &[stamp, cover]);
+
+        let summary: Vec<(u32, &str)> = hits.iter().map(|h| (h.page, h.rule_id.as_str())).collect();
+        assert_eq!(summary, vec![(1, "cover"), (2, "stamp"), (3, "stamp")]);
+        assert_eq!(hits[0].action, RuleAction::RemovePage);
+        assert_eq!(hits[0].bbox.map(|b| (b.w, b.h)), Some((100.0, 200.0)));
+        assert_eq!(hits[1].bbox, Some(region));
+    }
+
+    #[test]
+    fn test_rule_json_uses_tagged_snake_case() {
+        let json = r#"[
+            {"id": "a", "rule_type": {"type": "keyword", "value": "张三"}},
+            {"id": "b", "name": "邮箱", "rule_type": {"type": "heuristic", "kind": "Email"},
+             "scope": {"range": {"start": 1, "end": 2}}, "enabled": false},
+            {"id": "c", "rule_type": {"type": "page_rule"}, "action": "remove_page"}
+        ]"#;
+        let rules: Vec<Rule> = serde_json::from_str(json).unwrap();
+
+        assert!(rules[0].enabled);
+        assert_eq!(rules[0].scope, PageScope::All);
+        assert!(matches!(
+            rules[1].rule_type,
+            RuleType::Heuristic {
+                kind: HeuristicType::Email
+            }
+        ));
+        assert!(!rules[1].scope.contains(3));
+        assert_eq!(rules[2].action, RuleAction::RemovePage);
+        assert!(rules[2].to_core().is_none());
+    }
 }
@@ -0,0 +1,323 @@
+//! 规则包
+//!
+//! 可导入导出的规则集文件（JSON 或 TOML），带名称、版本、内容摘要和可选的 ed25519 签名。
+//!
+//! 内容摘要是 `{name, version, rules}` 规范 JSON 的 SHA-256，与文件格式无关，
+//! 同一规则包导出为 JSON 或 TOML 得到相同的摘要。签名对象为摘要的 32 字节原始值。
+
+use crate::Rule;
+use ed25519_dalek::{Signature, Signer, Verifier};
+use linch_core::audit::sha256_bytes;
+use linch_core::RulePackRef;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+pub use ed25519_dalek::{SigningKey, VerifyingKey};
+
+#[derive(Debug, thiserror::Error)]
+pub enum PackError {
+    #[error("invalid rule pack: {0}")]
+    Parse(String),
+    #[error("rule pack hash mismatch: expected {expected}, computed {actual}")]
+    HashMismatch { expected: String, actual: String },
+    #[error("invalid signature encoding")]
+    InvalidSignature,
+    #[error("invalid public key: {0}")]
+    InvalidKey(String),
+    #[error("rule pack signature does not match any trusted key")]
+    UntrustedSignature,
+    #[error("rule pack is not signed")]
+    Unsigned,
+}
+
+/// 规则包文件格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PackFormat {
+    Json,
+    Toml,
+}
+
+impl PackFormat {
+    /// 按扩展名判断格式，`.toml` 以外均视为 JSON
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|e| e.to_str()) {
+            Some(ext) if ext.eq_ignore_ascii_case("toml") => PackFormat::Toml,
+            _ => PackFormat::Json,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RulePack {
+    pub name: String,
+    pub version: String,
+    /// 内容摘要（SHA-256，十六进制小写）；导入时为空则不校验
+    #[serde(default)]
+    pub hash: String,
+    /// 对内容摘要的 ed25519 签名（十六进制）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signature: Option<String>,
+    pub rules: Vec<Rule>,
+}
+
+/// 规则包校验结果，写入审计记录用于证明使用了哪个规则包
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RulePackInfo {
+    pub name: String,
+    pub version: String,
+    pub hash: String,
+    /// 签名所用的受信公钥（十六进制），未签名时为空
+    pub signer: Option<String>,
+}
+
+impl RulePackInfo {
+    /// 写入审计记录的规则包引用，包含验证通过的签名公钥
+    pub fn reference(&self) -> RulePackRef {
+        RulePackRef {
+            name: self.name.clone(),
+            version: self.version.clone(),
+            hash: self.hash.clone(),
+            signer: self.signer.clone(),
+        }
+    }
+}
+
+/// 参与摘要计算的内容
+#[derive(Serialize)]
+struct PackContent<'a> {
+    name: &'a str,
+    version: &'a str,
+    rules: &'a [Rule],
+}
+
+impl RulePack {
+    /// 创建规则包并计算内容摘要
+    pub fn new(name: impl Into<String>, version: impl Into<String>, rules: Vec<Rule>) -> Self {
+        let mut pack = Self {
+            name: name.into(),
+            version: version.into(),
+            hash: String::new(),
+            signature: None,
+            rules,
+        };
+        pack.hash = pack.content_hash();
+        pack
+    }
+
+    /// 按格式解析规则包
+    pub fn parse(raw: &str, format: PackFormat) -> Result<Self, PackError> {
+        match format {
+            PackFormat::Json => {
+                serde_json::from_str(raw).map_err(|e| PackError::Parse(e.to_string()))
+            }
+            PackFormat::Toml => toml::from_str(raw).map_err(|e| PackError::Parse(e.to_string())),
+        }
+    }
+
+    /// 按格式序列化规则包
+    pub fn serialize(&self, format: PackFormat) -> Result<String, PackError> {
+        match format {
+            PackFormat::Json => {
+                serde_json::to_string_pretty(self).map_err(|e| PackError::Parse(e.to_string()))
+            }
+            PackFormat::Toml => {
+                toml::to_string_pretty(self).map_err(|e| PackError::Parse(e.to_string()))
+            }
+        }
+    }
+
+    /// 计算内容摘要
+    pub fn content_hash(&self) -> String {
+        let content = PackContent {
+            name: &self.name,
+            version: &self.version,
+            rules: &self.rules,
+        };
+        // 规则只包含可序列化的基础类型，序列化不会失败
+        sha256_bytes(&serde_json::to_vec(&content).unwrap_or_default())
+    }
+
+    /// 使用私钥签名（同时刷新内容摘要）
+    pub fn sign(&mut self, key: &SigningKey) {
+        self.hash = self.content_hash();
+        let digest = hex::decode(&self.hash).unwrap_or_default();
+        self.signature = Some(hex::encode(key.sign(&digest).to_bytes()));
+    }
+
+    /// 校验规则包
+    ///
+    /// - 文件中带有摘要时必须与内容一致
+    /// - 带有签名时必须能被 `trusted_keys` 中的某个公钥验证
+    /// - 没有签名时，`require_signature` 为 true 则拒绝
+    pub fn verify(
+        &self,
+        trusted_keys: &[VerifyingKey],
+        require_signature: bool,
+    ) -> Result<RulePackInfo, PackError> {
+        let actual = self.content_hash();
+        if !self.hash.is_empty() && !self.hash.eq_ignore_ascii_case(&actual) {
+            return Err(PackError::HashMismatch {
+                expected: self.hash.clone(),
+                actual,
+            });
+        }
+
+        let signer = match &self.signature {
+            Some(signature) => {
+                let bytes = hex::decode(signature).map_err(|_| PackError::InvalidSignature)?;
+                let signature =
+                    Signature::from_slice(&bytes).map_err(|_| PackError::InvalidSignature)?;
+                let digest = hex::decode(&actual).unwrap_or_default();
+                let key = trusted_keys
+                    .iter()
+                    .find(|key| key.verify(&digest, &signature).is_ok())
+                    .ok_or(PackError::UntrustedSignature)?;
+                Some(hex::encode(key.to_bytes()))
+            }
+            None if require_signature => return Err(PackError::Unsigned),
+            None => None,
+        };
+
+        Ok(RulePackInfo {
+            name: self.name.clone(),
+            version: self.version.clone(),
+            hash: actual,
+            signer,
+        })
+    }
+
+    /// 当前规则是否仍来自该规则包
+    ///
+    /// 只比较启用的规则（按 id 排序），停用的规则不影响脱敏结果。
+    pub fn has_rules(&self, rules: &[Rule]) -> bool {
+        fn enabled(rules: &[Rule]) -> Option<Vec<serde_json::Value>> {
+            let mut rules: Vec<&Rule> = rules.iter().filter(|r| r.enabled).collect();
+            rules.sort_by(|a, b| a.id.cmp(&b.id));
+            rules.iter().map(|r| serde_json::to_value(r).ok()).collect()
+        }
+        enabled(&self.rules) == enabled(rules)
+    }
+
+    /// 任务配置中使用的规则包引用（未经签名验证，不含签名公钥）
+    pub fn reference(&self) -> RulePackRef {
+        RulePackRef {
+            name: self.name.clone(),
+            version: self.version.clone(),
+            hash: self.content_hash(),
+            signer: None,
+        }
+    }
+}
+
+/// 解析十六进制编码的 ed25519 公钥
+pub fn parse_verifying_key(raw: &str) -> Result<VerifyingKey, PackError> {
+    let bytes = hex::decode(raw.trim()).map_err(|e| PackError::InvalidKey(e.to_string()))?;
+    let bytes: [u8; 32] = bytes
+        .try_into()
+        .map_err(|_| PackError::InvalidKey("expected 32 bytes".to_string()))?;
+    VerifyingKey::from_bytes(&bytes).map_err(|e| PackError::InvalidKey(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BBox, PageScope, RuleAction, RuleType};
+
+    fn sample_pack() -> RulePack {
+        RulePack::new(
+            "finance",
+            "1.2.0",
+            vec![
+                Rule {
+                    id: "phone".to_string(),
+                    name: "手机号".to_string(),
+                    rule_type: RuleType::Regex {
+                        pattern: r"1[3-9]\d{9}".to_string(),
+                    },
+                    scope: PageScope::All,
+                    action: RuleAction::RedactText,
+                    enabled: true,
+                    context: None,
+                },
+                Rule {
+                    id: "signature".to_string(),
+                    name: "签名栏".to_string(),
+                    rule_type: RuleType::Region {
+                        name: "签名栏".to_string(),
+                        bbox: BBox {
+                            x: 0.5,
+                            y: 0.875,
+                            w: 0.25,
+                            h: 0.0625,
+                        },
+                    },
+                    scope: PageScope::Range { start: 1, end: 3 },
+                    action: RuleAction::RedactText,
+                    enabled: true,
+                    context: None,
+                },
+            ],
+        )
+    }
+
+    #[test]
+    fn test_hash_is_format_independent() {
+        let pack = sample_pack();
+        for format in [PackFormat::Json, PackFormat::Toml] {
+            let raw = pack.serialize(format).unwrap();
+            let parsed = RulePack::parse(&raw, format).unwrap();
+            assert_eq!(parsed.content_hash(), pack.hash);
+            assert!(parsed.has_rules(&pack.rules));
+        }
+
+        // 停用的规则和顺序不影响是否来自该规则包
+        let mut rules = pack.rules.clone();
+        rules.reverse();
+        let mut extra = rules[0].clone();
+        extra.id = "disabled".to_string();
+        extra.enabled = false;
+        rules.push(extra);
+        assert!(pack.has_rules(&rules));
+        rules[0].enabled = false;
+        assert!(!pack.has_rules(&rules));
+    }
+
+    #[test]
+    fn test_tampered_pack_is_rejected() {
+        let mut pack = sample_pack();
+        pack.rules[0].enabled = false;
+        assert!(matches!(
+            pack.verify(&[], false),
+            Err(PackError::HashMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn test_signature_requires_trusted_key() {
+        let key = SigningKey::from_bytes(&[7u8; 32]);
+        let other = SigningKey::from_bytes(&[9u8; 32]);
+        let mut pack = sample_pack();
+        pack.sign(&key);
+
+        let info = pack.verify(&[other.verifying_key(), key.verifying_key()], true);
+        assert_eq!(
+            info.unwrap().signer,
+            Some(hex::encode(key.verifying_key().to_bytes()))
+        );
+        assert!(matches!(
+            pack.verify(&[other.verifying_key()], false),
+            Err(PackError::UntrustedSignature)
+        ));
+
+        let unsigned = sample_pack();
+        assert!(unsigned.verify(&[], false).unwrap().signer.is_none());
+        assert!(matches!(
+            unsigned.verify(&[], true),
+            Err(PackError::Unsigned)
+        ));
+
+        let parsed = parse_verifying_key(&hex::encode(key.verifying_key().to_bytes())).unwrap();
+        assert_eq!(parsed, key.verifying_key());
+    }
+}
@@ -3,8 +3,8 @@
 //! 实现 `Document` trait，支持 .txt 和 .md 文件的加载、文本提取和脱敏。
 
 use anyhow::{anyhow, Result};
-use linch_core::document::{Document, Page};
-use linch_core::rules::RuleSet;
+use linch_core::document::{Document, Page, RedactOutcome};
+use linch_core::rules::CompiledRuleSet;
 use std::fs;
 use std::path::{Path, PathBuf};
 
@@ -41,10 +41,9 @@ impl Document for TextDocument {
         }])
     }
 
-    fn redact(&self, ruleset: &RuleSet) -> Result<Vec<u8>> {
-        // 使用 RuleSet 的 redact_text 方法进行脱敏
+    fn redact(&self, ruleset: &CompiledRuleSet) -> Result<RedactOutcome> {
         let result = ruleset.redact_text(&self.content, None);
-        Ok(result.into_bytes())
+        Ok(result.into_bytes().into())
     }
 
     fn get_supported_features(&self) -> Vec<String> {
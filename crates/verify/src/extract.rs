@@ -0,0 +1,64 @@
+//! 输出文件的文本提取
+
+use anyhow::{anyhow, Result};
+use linch_rules::{BBox, PositionedPage, TextItem};
+use pdfium_render::prelude::*;
+
+/// 使用 pdfium 逐字符提取各页文本与位置
+///
+/// 坐标为左上角原点的相对坐标（0-1），页码从 1 开始，没有文本的页面也会返回。
+pub(crate) fn pdfium_pages(document: &PdfDocument) -> Vec<PositionedPage> {
+    let mut pages = Vec::new();
+
+    for (index, page) in document.pages().iter().enumerate() {
+        let page_width = page.width().value;
+        let page_height = page.height().value;
+        let items = linch_render::page_chars(&page)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|ch| TextItem {
+                text: ch.text,
+                bbox: ch.bounds.map(|b| {
+                    let (x, y, w, h) = b.relative(page_width, page_height);
+                    BBox { x, y, w, h }
+                }),
+                confidence: None,
+            })
+            .collect();
+
+        pages.push(PositionedPage {
+            page: (index + 1) as u32,
+            width: 1.0,
+            height: 1.0,
+            items,
+        });
+    }
+
+    pages
+}
+
+/// 使用 lopdf 提取各页文本（没有位置信息）
+///
+/// 单页提取失败时记录到 `errors` 并继续处理其余页面。
+pub(crate) fn lopdf_pages(path: &str, errors: &mut Vec<String>) -> Result<Vec<PositionedPage>> {
+    let doc = lopdf::Document::load(path).map_err(|e| anyhow!("lopdf 无法打开输出文件: {}", e))?;
+
+    let mut pages = Vec::new();
+    for page_number in doc.get_pages().into_keys() {
+        match doc.extract_text(&[page_number]) {
+            Ok(text) => pages.push(PositionedPage {
+                page: page_number,
+                width: 1.0,
+                height: 1.0,
+                items: vec![TextItem {
+                    text,
+                    bbox: None,
+                    confidence: None,
+                }],
+            }),
+            Err(e) => errors.push(format!("lopdf 提取第 {} 页文本失败: {}", page_number, e)),
+        }
+    }
+
+    Ok(pages)
+}
@@ -1,22 +1,402 @@
 //! Post-processing verification checks.
+//!
+//! 重新打开脱敏后的 PDF，确认敏感信息确实已被移除：
+//! 1. 分别使用 pdfium 与 lopdf 重新提取文本，重跑原规则集，并逐字搜索每个已脱敏的原文片段
+//! 2. 启用 `ocr_sample` 时渲染抽样页面并 OCR，覆盖文本层反映不出的内容（图片、矢量字形）
+//!
+//! 任何残留都以带页码和位置的结构化警告返回。
 
+mod extract;
+
+use image::DynamicImage;
+use linch_core::pipeline::VerifySummary;
+use linch_core::rules::{CompiledRuleSet, Rule, RuleCompileError, RuleSet, RuleType};
+use linch_core::{mask_snippet, VerifyOptions};
+use linch_rules::{BBox, PositionedPage, TextItem};
 use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct VerifyOptions {
-    pub text_search: bool,
-    pub ocr_sample: bool,
+/// 逐字搜索已脱敏片段时使用的规则 ID
+pub const REDACTED_SNIPPET_RULE: &str = "redacted_snippet";
+
+/// 发现残留的检查方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VerifySource {
+    Pdfium,
+    Lopdf,
+    Ocr,
 }
 
+impl VerifySource {
+    fn label(&self) -> &'static str {
+        match self {
+            VerifySource::Pdfium => "pdfium 文本",
+            VerifySource::Lopdf => "lopdf 文本",
+            VerifySource::Ocr => "OCR",
+        }
+    }
+}
+
+/// 残留警告
 #[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VerifyWarning {
+    /// 页码（从 1 开始）
+    pub page: u32,
+    /// 残留位置（左上角原点的相对坐标 0-1），lopdf 提取的文本没有位置
+    pub bbox: Option<BBox>,
+    pub source: VerifySource,
+    /// 命中的规则；逐字搜索命中时为 `REDACTED_SNIPPET_RULE`
+    pub rule_id: String,
+    pub rule_name: String,
+    /// 脱敏显示的残留片段
+    pub snippet: String,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct VerifyResult {
     pub ok: bool,
-    pub warnings: Vec<String>,
+    pub warnings: Vec<VerifyWarning>,
+    /// 跳过的检查和提取错误等说明（不代表发现残留）
+    pub notes: Vec<String>,
+    /// 实际执行 OCR 的页码
+    pub ocr_pages: Vec<u32>,
+}
+
+impl VerifyResult {
+    /// 转换为审计记录中的校验结果，警告只保留说明文字
+    pub fn summary(&self, options: &VerifyOptions) -> VerifySummary {
+        VerifySummary {
+            performed: options.text_search || options.ocr_sample,
+            ok: self.ok,
+            warnings: self.warnings.iter().map(|w| w.message.clone()).collect(),
+            notes: self.notes.clone(),
+            ocr_pages: self.ocr_pages.clone(),
+        }
+    }
 }
 
-pub fn verify_output(_output_path: &str, _options: &VerifyOptions) -> VerifyResult {
-    VerifyResult {
-        ok: true,
-        warnings: Vec::new(),
+/// OCR 抽样使用的识别器，由调用方接入具体引擎
+pub trait PageRecognizer {
+    /// 识别渲染后的页面图片
+    ///
+    /// 返回按阅读顺序排列的单词或行，坐标为左上角原点的相对坐标（0-1）。
+    fn recognize(&self, image: &DynamicImage) -> Result<Vec<TextItem>, String>;
+}
+
+/// 校验脱敏输出
+///
+/// # 参数
+/// - `output_path`: 脱敏后的 PDF
+/// - `rules`: 脱敏时使用的已编译规则集
+/// - `snippets`: 已脱敏的原文片段，逐字检查是否残留（只在内存中使用，不会写入结果）
+/// - `recognizer`: OCR 抽样使用的识别器，为 None 时跳过 OCR 抽样
+///
+/// 输出文件无法被任何方式打开，或已脱敏片段无法编译为检查规则时 `ok` 为 false。
+pub fn verify_output(
+    output_path: &str,
+    rules: &CompiledRuleSet,
+    snippets: &[String],
+    options: &VerifyOptions,
+    recognizer: Option<&dyn PageRecognizer>,
+) -> VerifyResult {
+    let mut result = VerifyResult::default();
+    let snippet_checks = match snippet_checks(snippets) {
+        Ok(checks) => checks,
+        Err(errors) => {
+            for error in errors {
+                result.notes.push(format!("无法检查已脱敏片段: {}", error));
+            }
+            result.ok = false;
+            return result;
+        }
+    };
+    let checks: Vec<&CompiledRuleSet> = std::iter::once(rules).chain(&snippet_checks).collect();
+    let mut seen = HashSet::new();
+    let mut opened = false;
+
+    match linch_render::bind_pdfium() {
+        Ok(pdfium) => match pdfium.load_pdf_from_file(output_path, None) {
+            Ok(document) => {
+                opened = true;
+                let pages = extract::pdfium_pages(&document);
+                if options.text_search {
+                    for page in &pages {
+                        scan(page, &checks, VerifySource::Pdfium, &mut result, &mut seen);
+                    }
+                }
+                if options.ocr_sample {
+                    match recognizer {
+                        Some(recognizer) => ocr_sample(
+                            &document,
+                            &pages,
+                            &checks,
+                            options,
+                            recognizer,
+                            &mut result,
+                            &mut seen,
+                        ),
+                        None => result
+                            .notes
+                            .push("未提供 OCR 引擎，已跳过 OCR 抽样校验".to_string()),
+                    }
+                }
+            }
+            Err(e) => result.notes.push(format!("pdfium 无法打开输出文件: {}", e)),
+        },
+        Err(e) => result.notes.push(e.to_string()),
+    }
+
+    if options.text_search {
+        match extract::lopdf_pages(output_path, &mut result.notes) {
+            Ok(pages) => {
+                opened = true;
+                for page in &pages {
+                    scan(page, &checks, VerifySource::Lopdf, &mut result, &mut seen);
+                }
+            }
+            Err(e) => result.notes.push(e.to_string()),
+        }
+    }
+
+    let performed = options.text_search || options.ocr_sample;
+    result.ok = result.warnings.is_empty() && (opened || !performed);
+    result
+}
+
+/// 逐字搜索已脱敏片段的词典规则，没有可搜索的片段时为 None
+fn snippet_checks(snippets: &[String]) -> Result<Option<CompiledRuleSet>, Vec<RuleCompileError>> {
+    let mut words: Vec<String> = Vec::new();
+    for snippet in snippets {
+        let snippet = snippet.trim();
+        // 单个字符误报太多，不做逐字搜索
+        if snippet.chars().count() >= 2 && !words.iter().any(|w| w == snippet) {
+            words.push(snippet.to_string());
+        }
+    }
+    if words.is_empty() {
+        return Ok(None);
+    }
+
+    let mut checks = RuleSet::new();
+    checks.add(Rule {
+        id: REDACTED_SNIPPET_RULE.to_string(),
+        name: "已脱敏片段".to_string(),
+        enabled: true,
+        is_system: true,
+        rule_type: RuleType::Dictionary(words),
+        context: None,
+    });
+    checks.compile().map(Some)
+}
+
+/// 在一页文本中查找残留，同一来源、同一位置的重复命中只报告一次
+fn scan(
+    page: &PositionedPage,
+    checks: &[&CompiledRuleSet],
+    source: VerifySource,
+    result: &mut VerifyResult,
+    seen: &mut HashSet<String>,
+) {
+    let hits = checks
+        .iter()
+        .flat_map(|checks| linch_rules::match_ruleset(page, checks));
+    for hit in hits {
+        let bbox_key = hit
+            .bbox
+            .map(|b| format!("{:.3},{:.3},{:.3},{:.3}", b.x, b.y, b.w, b.h))
+            .unwrap_or_default();
+        let key = format!("{:?}|{}|{}|{}", source, hit.page, bbox_key, hit.snippet);
+        if !seen.insert(key) {
+            continue;
+        }
+
+        let snippet = mask_snippet(&hit.snippet);
+        result.warnings.push(VerifyWarning {
+            page: hit.page,
+            bbox: hit.bbox,
+            source,
+            message: format!(
+                "第 {} 页仍能通过{}检测到「{}」: {}",
+                hit.page,
+                source.label(),
+                hit.rule_name,
+                snippet
+            ),
+            rule_id: hit.rule_id,
+            rule_name: hit.rule_name,
+            snippet,
+        });
+    }
+}
+
+/// 渲染抽样页面并 OCR
+fn ocr_sample(
+    document: &pdfium_render::prelude::PdfDocument,
+    pages: &[PositionedPage],
+    checks: &[&CompiledRuleSet],
+    options: &VerifyOptions,
+    recognizer: &dyn PageRecognizer,
+    result: &mut VerifyResult,
+    seen: &mut HashSet<String>,
+) {
+    let textless: Vec<u32> = pages
+        .iter()
+        .filter(|p| p.text().trim().is_empty())
+        .map(|p| p.page)
+        .collect();
+
+    for page_number in sample_pages(pages.len() as u32, &textless, options.ocr_sample_pages) {
+        let image = document
+            .pages()
+            .get((page_number - 1) as u16)
+            .map_err(|e| anyhow::anyhow!("获取页面失败: {}", e))
+            .and_then(|page| linch_render::render_page(&page, options.ocr_dpi));
+        let image = match image {
+            Ok(image) => image,
+            Err(e) => {
+                result
+                    .notes
+                    .push(format!("第 {} 页渲染失败: {}", page_number, e));
+                continue;
+            }
+        };
+
+        match recognizer.recognize(&image) {
+            Ok(items) => {
+                let page = ocr_page(page_number, items);
+                scan(&page, checks, VerifySource::Ocr, result, seen);
+                result.ocr_pages.push(page_number);
+            }
+            Err(e) => result
+                .notes
+                .push(format!("第 {} 页 OCR 失败: {}", page_number, e)),
+        }
+    }
+}
+
+/// 将 OCR 结果组装为页面文本，相邻文本项之间补空格
+fn ocr_page(page: u32, items: Vec<TextItem>) -> PositionedPage {
+    let mut joined = Vec::with_capacity(items.len() * 2);
+    for item in items {
+        if !joined.is_empty() {
+            joined.push(TextItem {
+                text: " ".to_string(),
+                bbox: None,
+                confidence: None,
+            });
+        }
+        joined.push(item);
+    }
+
+    PositionedPage {
+        page,
+        width: 1.0,
+        height: 1.0,
+        items: joined,
+    }
+}
+
+/// 选择 OCR 抽样页面
+///
+/// 优先选择没有文本层的页面（文本检索覆盖不到），其余名额在剩余页面中等间距选取。
+fn sample_pages(page_count: u32, textless: &[u32], limit: usize) -> Vec<u32> {
+    let mut selected: Vec<u32> = textless.iter().copied().take(limit).collect();
+
+    let rest: Vec<u32> = (1..=page_count).filter(|p| !selected.contains(p)).collect();
+    let slots = limit.saturating_sub(selected.len()).min(rest.len());
+    for i in 0..slots {
+        selected.push(rest[i * rest.len() / slots]);
+    }
+
+    selected.sort_unstable();
+    selected
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lopdf::content::{Content, Operation};
+    use lopdf::{dictionary, Object, Stream};
+
+    /// 生成单页 PDF，页面上用 Helvetica 写一行文本
+    fn write_pdf(path: &std::path::Path, text: &str) {
+        let mut doc = lopdf::Document::with_version("1.5");
+        let pages_id = doc.new_object_id();
+        let font_id = doc.add_object(dictionary! {
+            "Type" => "Font",
+            "Subtype" => "Type1",
+            "BaseFont" => "Helvetica",
+            "Encoding" => "WinAnsiEncoding",
+        });
+        let content = Content {
+            operations: vec![
+                Operation::new("BT", vec![]),
+                Operation::new("Tf", vec!["F1".into(), 12.into()]),
+                Operation::new("Td", vec![72.into(), 720.into()]),
+                Operation::new("Tj", vec![Object::string_literal(text)]),
+                Operation::new("ET", vec![]),
+            ],
+        };
+        let content_id = doc.add_object(Stream::new(dictionary! {}, content.encode().unwrap()));
+        let page_id = doc.add_object(dictionary! {
+            "Type" => "Page",
+            "Parent" => pages_id,
+            "Contents" => content_id,
+            "Resources" => dictionary! { "Font" => dictionary! { "F1" => font_id } },
+            "MediaBox" => vec![0.into(), 0.into(), 612.into(), 792.into()],
+        });
+        doc.objects.insert(
+            pages_id,
+            Object::Dictionary(dictionary! {
+                "Type" => "Pages",
+                "Kids" => vec![page_id.into()],
+                "Count" => 1,
+            }),
+        );
+        let catalog_id = doc.add_object(dictionary! {
+            "Type" => "Catalog",
+            "Pages" => pages_id,
+        });
+        doc.trailer.set("Root", catalog_id);
+        doc.save(path).unwrap();
+    }
+
+    #[test]
+    fn test_residual_snippet_is_reported_from_lopdf_text() {
+        let dir = std::env::temp_dir().join(format!("linch-verify-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let leaked = dir.join("leaked.pdf");
+        let clean = dir.join("clean.pdf");
+        write_pdf(&leaked, "Contact alice@example.com today");
+        write_pdf(&clean, "Contact [redacted] today");
+
+        let snippets = vec!["alice@example.com".to_string()];
+        let rules = RuleSet::new().compile().unwrap();
+        let options = VerifyOptions::default();
+
+        let result = verify_output(leaked.to_str().unwrap(), &rules, &snippets, &options, None);
+        assert!(!result.ok);
+        let warning = result
+            .warnings
+            .iter()
+            .find(|w| w.source == VerifySource::Lopdf)
+            .expect("lopdf 应发现残留");
+        assert_eq!(warning.page, 1);
+        assert_eq!(warning.rule_id, REDACTED_SNIPPET_RULE);
+        assert!(!warning.snippet.contains("alice"));
+
+        let result = verify_output(clean.to_str().unwrap(), &rules, &snippets, &options, None);
+        assert!(result.ok, "{:?}", result.warnings);
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_sample_prefers_textless_pages_then_spreads_evenly() {
+        assert_eq!(sample_pages(10, &[4], 3), vec![1, 4, 6]);
+        assert_eq!(sample_pages(2, &[], 5), vec![1, 2]);
+        assert_eq!(sample_pages(8, &[2, 3, 7], 2), vec![2, 3]);
     }
 }
@@ -1,3 +1,5 @@
 fn main() {
+    // 命令行工具（未启用 gui）不需要 Tauri 的构建步骤
+    #[cfg(feature = "gui")]
     tauri_build::build()
 }
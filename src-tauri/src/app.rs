@@ -0,0 +1,195 @@
+//! 桌面端：文档加载、规则匹配与脱敏命令
+
+use crate::{config, pdf};
+use linch_core::document::{Document, Page};
+use linch_core::rules::RuleMatch;
+use linch_core::{run_task, TaskConfig, VerifyOptions};
+use linch_rules::Rule;
+use std::collections::BTreeSet;
+use std::path::Path;
+
+// ============================================================================
+// 新架构：多文档格式支持
+// ============================================================================
+
+/// 文档信息（返回给前端）
+#[derive(Clone, serde::Serialize)]
+pub struct DocumentInfo {
+    pub path: String,
+    pub name: String,
+    pub file_type: String,
+    pub pages: Vec<Page>,
+    pub total_pages: usize,
+    pub supported_features: Vec<String>,
+}
+
+/// 加载文档命令
+///
+/// 根据文件扩展名自动选择对应的处理器。
+#[tauri::command]
+pub async fn load_document(file_path: String) -> Result<DocumentInfo, String> {
+    let path = Path::new(&file_path);
+    let extension = path
+        .extension()
+        .and_then(std::ffi::OsStr::to_str)
+        .unwrap_or("")
+        .to_lowercase();
+
+    let result = match extension.as_str() {
+        "pdf" => load_with_handler::<linch_pdf::PdfDocument>(path, "pdf"),
+        "txt" => load_with_handler::<linch_text::TextDocument>(path, "txt"),
+        "md" => load_with_handler::<linch_text::TextDocument>(path, "md"),
+        "docx" => load_with_handler::<linch_docx::DocxDocument>(path, "docx"),
+        _ => Err(anyhow::anyhow!("不支持的文件类型: {}", extension)),
+    };
+
+    result.map_err(|e| e.to_string())
+}
+
+/// 使用指定处理器加载文档
+fn load_with_handler<D: Document>(path: &Path, file_type: &str) -> anyhow::Result<DocumentInfo> {
+    let doc = D::load(path)?;
+    let pages = doc.get_pages()?;
+    let total_pages = pages.len();
+    let supported_features = doc.get_supported_features();
+
+    Ok(DocumentInfo {
+        path: path.to_string_lossy().to_string(),
+        name: path
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .to_string(),
+        file_type: file_type.to_string(),
+        pages,
+        total_pages,
+        supported_features,
+    })
+}
+
+// ============================================================================
+// 新架构：规则匹配与脱敏
+// ============================================================================
+
+/// 脱敏结果
+#[derive(Clone, serde::Serialize)]
+pub struct RedactionResult {
+    pub success: bool,
+    pub output_path: Option<String>,
+    /// 审计记录路径
+    pub audit_path: Option<String>,
+    /// 报告路径
+    pub report_path: Option<String>,
+    pub matches_count: usize,
+    pub message: String,
+}
+
+/// 匹配预览结果
+#[derive(Clone, serde::Serialize)]
+pub struct MatchPreviewResult {
+    pub matches: Vec<RuleMatch>,
+    pub total_count: usize,
+}
+
+/// 预览匹配结果
+///
+/// 对文档内容进行规则匹配，返回所有匹配项（不修改文件）。
+#[tauri::command]
+pub async fn preview_matches(
+    file_path: String,
+    rules: Vec<Rule>,
+) -> Result<MatchPreviewResult, String> {
+    let path = Path::new(&file_path);
+    let extension = path
+        .extension()
+        .and_then(std::ffi::OsStr::to_str)
+        .unwrap_or("")
+        .to_lowercase();
+
+    // 加载文档获取文本
+    let doc_info = match extension.as_str() {
+        "pdf" => load_with_handler::<linch_pdf::PdfDocument>(path, "pdf"),
+        "txt" => load_with_handler::<linch_text::TextDocument>(path, "txt"),
+        "md" => load_with_handler::<linch_text::TextDocument>(path, "md"),
+        "docx" => load_with_handler::<linch_docx::DocxDocument>(path, "docx"),
+        _ => return Err(format!("不支持的文件类型: {}", extension)),
+    }
+    .map_err(|e| e.to_string())?;
+
+    // 合并所有页面文本
+    let full_text: String = doc_info
+        .pages
+        .iter()
+        .map(|p| p.content.as_str())
+        .collect::<Vec<_>>()
+        .join("\n\n");
+
+    // 转换规则并匹配
+    let ruleset = pdf::compile_text_rules(&rules)?;
+    let matches = ruleset.match_text(&full_text);
+    let total_count = matches.len();
+
+    Ok(MatchPreviewResult {
+        matches,
+        total_count,
+    })
+}
+
+/// 执行脱敏
+///
+/// 通过核心流水线对文档应用规则进行脱敏，保存到输出路径并校验输出，
+/// 在输出文件旁写出审计记录和报告。规则来自已导入的规则包时，审计记录中注明该规则包。
+#[tauri::command]
+pub async fn apply_redaction(
+    app: tauri::AppHandle,
+    file_path: String,
+    rules: Vec<Rule>,
+    output_path: String,
+    operator: Option<String>,
+    verify: Option<VerifyOptions>,
+) -> Result<RedactionResult, String> {
+    let path = Path::new(&file_path);
+    let extension = path
+        .extension()
+        .and_then(std::ffi::OsStr::to_str)
+        .unwrap_or("")
+        .to_lowercase();
+
+    // PDF 输出按规则和命中原文校验（启用时 OCR 抽样），其他格式重新加载后再次匹配
+    let verify_options = verify.unwrap_or_default();
+    let config = TaskConfig {
+        operator,
+        rule_pack: config::active_rule_pack(&app, &rules).map(|pack| pack.reference()),
+        ..pdf::task_config(&file_path, &output_path, &rules, &verify_options)?
+    };
+
+    // 根据文件类型选择处理器，由核心流水线完成脱敏、写出、校验和审计
+    let result = match extension.as_str() {
+        "txt" | "md" => run_task::<linch_text::TextDocument>(config),
+        // 替换命中文字，并移除批注、修订、自定义 XML 和文档属性
+        "docx" => run_task::<linch_docx::DocxDocument>(config),
+        // 删除命中字形并在原位置绘制黑框
+        "pdf" => run_task::<pdf::RulePdf>(config),
+        _ => return Err(format!("不支持的文件类型: {}", extension)),
+    }
+    .map_err(pdf::describe_task_error)?;
+    let result = pdf::finish_task(result, &BTreeSet::new())?;
+
+    let message = if result.verify.ok {
+        format!("脱敏完成，共处理 {} 处敏感信息", result.hits)
+    } else {
+        format!(
+            "脱敏完成，但校验发现 {} 处可能残留的敏感信息",
+            result.verify.warnings.len()
+        )
+    };
+
+    Ok(RedactionResult {
+        success: result.verify.ok,
+        output_path: Some(result.output_path),
+        audit_path: Some(result.audit_path),
+        report_path: Some(result.report_path),
+        matches_count: result.hits,
+        message,
+    })
+}
@@ -0,0 +1,195 @@
+//! 审计记录
+//!
+//! 审计记录由核心流水线在每个输出文件旁写出（`<文件名>.audit.json`，结构为
+//! `linch_core::audit::TaskAudit`）。这里把桌面端的检测命中、遮罩、规则、清理选项和
+//! OCR 引擎信息转换为通用字段。命中片段只保存脱敏后的形式，原文不会写入审计记录。
+
+use crate::ocr::OcrAuditInfo;
+use crate::pdf::{CleaningOptions, DetectionBbox, DetectionHit, Mask, RedactionMode};
+use linch_core::audit::{sha256_bytes, OcrEngineInfo};
+use linch_core::{CleanOptions, Region, TaskHit};
+use linch_rules::Rule;
+use std::collections::BTreeMap;
+
+/// 规则摘要（包括区域、页面规则）
+pub fn rules_hash(rules: &[Rule]) -> Result<String, String> {
+    let raw = serde_json::to_vec(rules).map_err(|e| format!("序列化规则失败: {}", e))?;
+    Ok(sha256_bytes(&raw))
+}
+
+/// 检测阶段使用 OCR 时记录当前引擎的审计信息
+pub fn ocr_engine(used: bool) -> Option<OcrEngineInfo> {
+    used.then(|| crate::ocr::get_current_audit_info().into())
+}
+
+/// 脱敏模式的名称（与前端、命令行使用的 snake_case 名称一致）
+pub fn mode_name(mode: &RedactionMode) -> Option<String> {
+    serde_json::to_value(mode)
+        .ok()
+        .and_then(|value| value.as_str().map(str::to_string))
+}
+
+/// 各页实际使用的脱敏模式（页码从 0 开始）转换为审计记录中的名称（页码从 1 开始）
+pub fn page_modes(page_modes: &BTreeMap<usize, RedactionMode>) -> BTreeMap<u32, String> {
+    page_modes
+        .iter()
+        .filter_map(|(page, mode)| Some(((page + 1) as u32, mode_name(mode)?)))
+        .collect()
+}
+
+/// 审计记录中的整体脱敏模式：各页实际使用同一模式时记录该模式，否则记录请求的模式
+pub fn redact_mode(
+    requested: &RedactionMode,
+    page_modes: &BTreeMap<usize, RedactionMode>,
+) -> Option<String> {
+    let mut modes = page_modes.values();
+    match modes.next() {
+        Some(first) if modes.all(|mode| mode == first) => mode_name(first),
+        _ => mode_name(requested),
+    }
+}
+
+/// 由 PDF 检测命中转换（检测结果中的片段已脱敏，页码从 0 开始）
+pub fn hit_from_detection(hit: &DetectionHit) -> TaskHit {
+    TaskHit {
+        page: (hit.page + 1) as u32,
+        rule_id: hit.rule_id.clone(),
+        rule_name: hit.rule_name.clone(),
+        snippet: hit.snippet.clone(),
+        range: None,
+        bbox: Some(hit.bbox.clone().into()),
+        confidence: hit.confidence,
+    }
+}
+
+/// 按页的遮罩（页码从 0 开始）转换为审计记录中的遮罩（页码从 1 开始）
+pub fn masks(masks_by_page: &BTreeMap<usize, Vec<Mask>>) -> BTreeMap<u32, Vec<Region>> {
+    masks_by_page
+        .iter()
+        .map(|(page, masks)| {
+            let regions = masks.iter().map(|m| Region::from(m.clone())).collect();
+            ((page + 1) as u32, regions)
+        })
+        .collect()
+}
+
+impl From<OcrAuditInfo> for OcrEngineInfo {
+    fn from(info: OcrAuditInfo) -> Self {
+        Self {
+            engine: info.engine_type.to_string(),
+            version: info.engine_version,
+            params: info.engine_params,
+            data_hash: info.tessdata_hash,
+        }
+    }
+}
+
+impl From<&CleaningOptions> for CleanOptions {
+    fn from(options: &CleaningOptions) -> Self {
+        Self {
+            metadata: options.document_info,
+            xmp: options.xmp_metadata,
+            hidden: options.hidden_data,
+            annots: options.annotations,
+            forms: options.forms,
+            attachments: options.attachments,
+            javascript: options.javascript,
+        }
+    }
+}
+
+impl From<DetectionBbox> for Region {
+    fn from(bbox: DetectionBbox) -> Self {
+        Self {
+            x: bbox.x,
+            y: bbox.y,
+            width: bbox.width,
+            height: bbox.height,
+        }
+    }
+}
+
+impl From<Mask> for Region {
+    fn from(mask: Mask) -> Self {
+        Self {
+            x: mask.x,
+            y: mask.y,
+            width: mask.width,
+            height: mask.height,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detection_hits_use_one_based_pages() {
+        let hit = hit_from_detection(&DetectionHit {
+            page: 0,
+            bbox: DetectionBbox {
+                x: 0.1,
+                y: 0.2,
+                width: 0.3,
+                height: 0.05,
+            },
+            rule_id: "phone".to_string(),
+            rule_name: "手机号".to_string(),
+            snippet: "1381****5678".to_string(),
+            confidence: Some(0.7),
+            reasons: Vec::new(),
+        });
+
+        assert_eq!(hit.page, 1);
+        assert_eq!(hit.snippet, "1381****5678");
+        assert_eq!(hit.bbox.unwrap().width, 0.3);
+        assert!(hit.range.is_none());
+    }
+
+    #[test]
+    fn test_masks_use_one_based_pages() {
+        let mask = Mask {
+            x: 0.1,
+            y: 0.2,
+            width: 0.3,
+            height: 0.4,
+        };
+        let masks = masks(&BTreeMap::from([(2, vec![mask])]));
+
+        assert_eq!(masks.keys().copied().collect::<Vec<_>>(), vec![3]);
+        assert_eq!(masks[&3][0].height, 0.4);
+    }
+
+    #[test]
+    fn test_page_modes_record_effective_modes() {
+        let uniform = BTreeMap::from([
+            (0, RedactionMode::SafeRender),
+            (2, RedactionMode::SafeRender),
+        ]);
+        assert_eq!(
+            redact_mode(&RedactionMode::Auto, &uniform).as_deref(),
+            Some("safe_render")
+        );
+
+        let mixed = BTreeMap::from([
+            (0, RedactionMode::TextReplace),
+            (2, RedactionMode::SafeRender),
+        ]);
+        assert_eq!(
+            redact_mode(&RedactionMode::Auto, &mixed).as_deref(),
+            Some("auto")
+        );
+        assert_eq!(
+            page_modes(&mixed),
+            BTreeMap::from([
+                (1, "text_replace".to_string()),
+                (3, "safe_render".to_string())
+            ])
+        );
+        assert_eq!(
+            redact_mode(&RedactionMode::Auto, &BTreeMap::new()).as_deref(),
+            Some("auto")
+        );
+    }
+}
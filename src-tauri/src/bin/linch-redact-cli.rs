@@ -0,0 +1,5 @@
+//! Linch Redact 命令行入口
+
+fn main() {
+    std::process::exit(app_lib::cli::run())
+}
@@ -0,0 +1,360 @@
+//! 命令行入口
+//!
+//! 提供无界面的批量脱敏能力，便于在 CI 或文件服务器上运行。
+//! 复用与桌面端相同的检测（含 OCR）与脱敏流程。
+
+use crate::config::{parse_detection_rules, read_rule_pack};
+use crate::pdf::{
+    self, CleaningOptions, ColorMode, ImageEncoding, ProcessOptions, RedactionMode, RenderConfig,
+};
+use clap::Parser;
+use linch_core::VerifyOptions;
+use linch_rules::{Rule, RulePackInfo};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// 退出码：全部成功
+const EXIT_OK: i32 = 0;
+/// 退出码：存在处理失败的文件
+const EXIT_FAILED: i32 = 1;
+/// 退出码：参数或配置错误
+const EXIT_USAGE: i32 = 2;
+
+/// Linch Redact 命令行批量脱敏工具
+#[derive(Debug, Parser)]
+#[command(name = "linch-redact-cli", version, about)]
+struct Args {
+    /// 输入的 PDF 文件或目录
+    #[arg(required = true)]
+    inputs: Vec<PathBuf>,
+
+    /// 规则文件：detection-rules.json 格式的规则列表，或规则包（.json / .toml）
+    #[arg(short, long)]
+    rules: PathBuf,
+
+    /// 受信的规则包签名公钥（ed25519，十六进制），可重复指定
+    #[arg(long = "trusted-key")]
+    trusted_keys: Vec<String>,
+
+    /// 只接受已签名且由受信公钥验证通过的规则包
+    #[arg(long)]
+    require_signed: bool,
+
+    /// 输出目录
+    #[arg(short, long)]
+    output: PathBuf,
+
+    /// 脱敏模式：auto / text_replace / black_overlay / image_mode / safe_render
+    #[arg(short, long, default_value = "auto", value_parser = parse_mode)]
+    mode: RedactionMode,
+
+    /// 栅格化页面的 DPI（SafeRender）
+    #[arg(long, default_value_t = 150)]
+    dpi: u32,
+
+    /// 栅格化页面的图片编码：jpeg / flate（无损）
+    #[arg(long, default_value = "jpeg", value_parser = parse_encoding)]
+    image_encoding: ImageEncoding,
+
+    /// JPEG 质量（1-100）
+    #[arg(long, default_value_t = 90)]
+    jpeg_quality: u8,
+
+    /// 栅格化页面的颜色模式：rgb / gray / bilevel（1 位黑白）
+    #[arg(long, default_value = "rgb", value_parser = parse_color)]
+    color: ColorMode,
+
+    /// 输出文件名前缀
+    #[arg(long, default_value = "redacted_")]
+    prefix: String,
+
+    /// 对无文本层的页面启用 OCR 检测
+    #[arg(long)]
+    ocr: bool,
+
+    /// 递归扫描子目录
+    #[arg(long)]
+    recursive: bool,
+
+    /// 操作者（写入审计记录）
+    #[arg(long)]
+    operator: Option<String>,
+
+    /// 跳过输出文本复查
+    #[arg(long)]
+    no_verify: bool,
+
+    /// 校验时对抽样页面执行 OCR
+    #[arg(long)]
+    verify_ocr: bool,
+
+    /// 清理文档信息（Info 字典）
+    #[arg(long)]
+    clean_info: bool,
+
+    /// 清理 XMP 元数据
+    #[arg(long)]
+    clean_xmp: bool,
+
+    /// 清理隐藏数据（PieceInfo、LastModified 等）
+    #[arg(long)]
+    clean_hidden: bool,
+
+    /// 清理注释
+    #[arg(long)]
+    clean_annotations: bool,
+
+    /// 清理表单字段
+    #[arg(long)]
+    clean_forms: bool,
+
+    /// 清理附件
+    #[arg(long)]
+    clean_attachments: bool,
+
+    /// 清理 JavaScript
+    #[arg(long)]
+    clean_javascript: bool,
+
+    /// 启用全部清理选项
+    #[arg(long)]
+    clean_all: bool,
+}
+
+impl Args {
+    fn verify(&self) -> VerifyOptions {
+        VerifyOptions {
+            text_search: !self.no_verify,
+            ocr_sample: self.verify_ocr,
+            ..VerifyOptions::default()
+        }
+    }
+
+    fn render(&self) -> RenderConfig {
+        RenderConfig {
+            dpi: self.dpi,
+            jpeg_quality: self.jpeg_quality,
+            encoding: self.image_encoding,
+            color: self.color,
+        }
+    }
+
+    fn cleaning(&self) -> CleaningOptions {
+        CleaningOptions {
+            document_info: self.clean_all || self.clean_info,
+            xmp_metadata: self.clean_all || self.clean_xmp,
+            hidden_data: self.clean_all || self.clean_hidden,
+            annotations: self.clean_all || self.clean_annotations,
+            forms: self.clean_all || self.clean_forms,
+            attachments: self.clean_all || self.clean_attachments,
+            javascript: self.clean_all || self.clean_javascript,
+        }
+    }
+}
+
+/// 解析脱敏模式（与前端传入的 snake_case 名称一致）
+fn parse_mode(value: &str) -> Result<RedactionMode, String> {
+    serde_json::from_value(serde_json::Value::String(value.to_string()))
+        .map_err(|_| format!("无效的脱敏模式: {}", value))
+}
+
+/// 解析图片编码
+fn parse_encoding(value: &str) -> Result<ImageEncoding, String> {
+    serde_json::from_value(serde_json::Value::String(value.to_string()))
+        .map_err(|_| format!("无效的图片编码: {}", value))
+}
+
+/// 解析颜色模式
+fn parse_color(value: &str) -> Result<ColorMode, String> {
+    serde_json::from_value(serde_json::Value::String(value.to_string()))
+        .map_err(|_| format!("无效的颜色模式: {}", value))
+}
+
+/// 读取规则文件
+///
+/// TOML 文件和顶层为对象的 JSON 文件按规则包读取并校验，返回规则包信息；
+/// 其余按规则列表读取。
+fn load_rules(
+    path: &Path,
+    trusted_keys: &[String],
+    require_signed: bool,
+) -> Result<(Vec<Rule>, Option<RulePackInfo>), String> {
+    let content = fs::read_to_string(path)
+        .map_err(|e| format!("读取规则文件失败 {}: {}", path.display(), e))?;
+
+    let is_pack = path
+        .extension()
+        .and_then(std::ffi::OsStr::to_str)
+        .map(|ext| ext.eq_ignore_ascii_case("toml"))
+        .unwrap_or(false)
+        || content.trim_start().starts_with('{');
+    if is_pack {
+        let (pack, info) = read_rule_pack(path, trusted_keys, require_signed)?;
+        return Ok((pack.rules, Some(info)));
+    }
+
+    if require_signed {
+        return Err("已指定 --require-signed，规则文件必须是已签名的规则包".to_string());
+    }
+    let rules = parse_detection_rules(&content).map_err(|e| format!("解析规则文件失败: {}", e))?;
+    Ok((rules, None))
+}
+
+/// 展开输入路径，目录中只收集 .pdf 文件
+fn collect_inputs(inputs: &[PathBuf], recursive: bool) -> Result<Vec<PathBuf>, String> {
+    let mut files = Vec::new();
+    for input in inputs {
+        if input.is_dir() {
+            collect_dir(input, recursive, &mut files)?;
+        } else if input.is_file() {
+            files.push(input.clone());
+        } else {
+            return Err(format!("输入路径不存在: {}", input.display()));
+        }
+    }
+    Ok(files)
+}
+
+fn collect_dir(dir: &Path, recursive: bool, files: &mut Vec<PathBuf>) -> Result<(), String> {
+    let mut entries: Vec<PathBuf> = fs::read_dir(dir)
+        .map_err(|e| format!("读取目录失败 {}: {}", dir.display(), e))?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .collect();
+    entries.sort();
+
+    for path in entries {
+        if path.is_dir() {
+            if recursive {
+                collect_dir(&path, recursive, files)?;
+            }
+        } else if is_pdf(&path) {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+fn is_pdf(path: &Path) -> bool {
+    path.extension()
+        .and_then(std::ffi::OsStr::to_str)
+        .map(|ext| ext.eq_ignore_ascii_case("pdf"))
+        .unwrap_or(false)
+}
+
+/// 运行命令行工具，返回进程退出码
+pub fn run() -> i32 {
+    let args = Args::parse();
+
+    let (rules, rule_pack) = match load_rules(&args.rules, &args.trusted_keys, args.require_signed)
+    {
+        Ok(loaded) => loaded,
+        Err(e) => {
+            eprintln!("{}", e);
+            return EXIT_USAGE;
+        }
+    };
+    if !rules.iter().any(|r| r.enabled) {
+        eprintln!("规则文件中没有启用的规则");
+        return EXIT_USAGE;
+    }
+    if let Some(pack) = &rule_pack {
+        println!(
+            "使用规则包 {} {}（{}，{}）",
+            pack.name,
+            pack.version,
+            pack.hash,
+            if pack.signer.is_some() {
+                "签名已验证"
+            } else {
+                "未签名"
+            }
+        );
+    }
+
+    let files = match collect_inputs(&args.inputs, args.recursive) {
+        Ok(files) => files,
+        Err(e) => {
+            eprintln!("{}", e);
+            return EXIT_USAGE;
+        }
+    };
+    if files.is_empty() {
+        eprintln!("未找到需要处理的 PDF 文件");
+        return EXIT_USAGE;
+    }
+
+    if let Err(e) = fs::create_dir_all(&args.output) {
+        eprintln!("无法创建输出目录: {}", e);
+        return EXIT_USAGE;
+    }
+    if let Err(e) = pdf::plan_outputs(&files, &args.output, &args.prefix) {
+        eprintln!("{}", e);
+        return EXIT_USAGE;
+    }
+    let options = ProcessOptions {
+        output_directory: args.output.to_string_lossy().to_string(),
+        prefix: args.prefix.clone(),
+        mode: args.mode.clone(),
+        cleaning: args.cleaning(),
+        operator: args.operator.clone(),
+        rules,
+        verify: args.verify(),
+        render: args.render(),
+    };
+
+    let mut failed = 0;
+    for file in &files {
+        let input = file.to_string_lossy().to_string();
+        let result =
+            pdf::detect_with_rules(&input, &options.rules, args.ocr).and_then(|detected| {
+                pdf::run_pdf_task(
+                    &detected.request,
+                    &options,
+                    &detected.matched_texts,
+                    rule_pack.as_ref(),
+                    &|_, _| Ok(()),
+                )
+            });
+        match result {
+            Ok(result) if result.verify.ok => {
+                println!(
+                    "[OK] {} -> {}（命中 {} 处，审计记录 {}，报告 {}）",
+                    input, result.output_path, result.hits, result.audit_path, result.report_path
+                );
+                for warning in &result.warnings {
+                    eprintln!("  警告：{}", warning);
+                }
+            }
+            Ok(result) => {
+                // 校验未通过：输出文件保留以便复核，但按失败计
+                failed += 1;
+                eprintln!(
+                    "[UNVERIFIED] {} -> {}（校验发现 {} 处可能的残留，报告 {}）",
+                    input,
+                    result.output_path,
+                    result.verify.warnings.len(),
+                    result.report_path
+                );
+                for warning in result.warnings.iter().chain(&result.verify.warnings) {
+                    eprintln!("  {}", warning);
+                }
+            }
+            Err(e) => {
+                failed += 1;
+                eprintln!("[FAILED] {}: {}", input, e);
+            }
+        }
+    }
+
+    println!(
+        "处理完成：成功 {} 个，失败 {} 个",
+        files.len() - failed,
+        failed
+    );
+
+    if failed > 0 {
+        EXIT_FAILED
+    } else {
+        EXIT_OK
+    }
+}
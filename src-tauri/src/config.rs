@@ -1,11 +1,20 @@
+#[cfg(feature = "gui")]
 use serde::{Deserialize, Serialize};
 use std::fs;
+use std::path::Path;
+#[cfg(feature = "gui")]
 use std::path::PathBuf;
+#[cfg(feature = "gui")]
 use tauri::Manager;
 
+#[cfg(feature = "gui")]
 use crate::ocr::{OcrEngineType, TesseractConfig};
-use crate::pdf::Rule;
+use linch_rules::{
+    parse_verifying_key, PackFormat, PageScope, Rule, RuleAction, RulePack, RulePackInfo, RuleType,
+    VerifyingKey,
+};
 
+#[cfg(feature = "gui")]
 #[derive(Debug, Serialize, Deserialize, Clone, Default)]
 #[serde(default, rename_all = "camelCase")]
 pub struct AppConfig {
@@ -26,8 +35,16 @@ pub struct AppConfig {
     pub ocr_engine: Option<OcrEngineType>,
     /// Tesseract 配置
     pub tesseract: Option<TesseractConfig>,
+
+    // ============ 规则包 ============
+    /// 受信的规则包签名公钥（ed25519，十六进制）
+    pub trusted_rule_pack_keys: Option<Vec<String>>,
+    /// 是否只允许导入已签名的规则包
+    pub require_signed_rule_packs: Option<bool>,
 }
 
+/// 应用数据目录相关的错误（仅桌面端）
+#[cfg(feature = "gui")]
 #[derive(Debug, thiserror::Error)]
 pub enum ConfigError {
     #[error("app data dir unavailable")]
@@ -40,6 +57,7 @@ pub enum ConfigError {
 
 pub type ConfigResult<T> = Result<T, String>;
 
+#[cfg(feature = "gui")]
 pub fn config_path(app: &tauri::AppHandle) -> Result<PathBuf, ConfigError> {
     let base = app
         .path()
@@ -48,6 +66,7 @@ pub fn config_path(app: &tauri::AppHandle) -> Result<PathBuf, ConfigError> {
     Ok(base.join("linch-redact").join("config.json"))
 }
 
+#[cfg(feature = "gui")]
 pub fn ocr_root(app: &tauri::AppHandle) -> Result<PathBuf, ConfigError> {
     let base = app
         .path()
@@ -56,10 +75,22 @@ pub fn ocr_root(app: &tauri::AppHandle) -> Result<PathBuf, ConfigError> {
     Ok(base.join("linch-redact").join("ocr"))
 }
 
+#[cfg(feature = "gui")]
 pub fn models_dir(app: &tauri::AppHandle) -> Result<PathBuf, ConfigError> {
     Ok(ocr_root(app)?.join("models"))
 }
 
+/// 任务队列持久化文件
+#[cfg(feature = "gui")]
+pub fn queue_path(app: &tauri::AppHandle) -> Result<PathBuf, ConfigError> {
+    let base = app
+        .path()
+        .app_data_dir()
+        .map_err(|_| ConfigError::NoAppDataDir)?;
+    Ok(base.join("linch-redact").join("queue.json"))
+}
+
+#[cfg(feature = "gui")]
 #[tauri::command]
 pub fn load_config(app: tauri::AppHandle) -> ConfigResult<AppConfig> {
     let path = config_path(&app).map_err(|err| err.to_string())?;
@@ -70,6 +101,7 @@ pub fn load_config(app: tauri::AppHandle) -> ConfigResult<AppConfig> {
     serde_json::from_str(&raw).map_err(|err| err.to_string())
 }
 
+#[cfg(feature = "gui")]
 #[tauri::command]
 pub fn save_config(app: tauri::AppHandle, config: AppConfig) -> ConfigResult<()> {
     let path = config_path(&app).map_err(|err| err.to_string())?;
@@ -83,6 +115,7 @@ pub fn save_config(app: tauri::AppHandle, config: AppConfig) -> ConfigResult<()>
 
 // ============ 检测规则存储 ============
 
+#[cfg(feature = "gui")]
 fn rules_path(app: &tauri::AppHandle) -> Result<PathBuf, ConfigError> {
     let base = app
         .path()
@@ -91,6 +124,7 @@ fn rules_path(app: &tauri::AppHandle) -> Result<PathBuf, ConfigError> {
     Ok(base.join("linch-redact").join("detection-rules.json"))
 }
 
+#[cfg(feature = "gui")]
 #[tauri::command]
 pub fn load_detection_rules(app: tauri::AppHandle) -> ConfigResult<Vec<Rule>> {
     let path = rules_path(&app).map_err(|err| err.to_string())?;
@@ -98,9 +132,92 @@ pub fn load_detection_rules(app: tauri::AppHandle) -> ConfigResult<Vec<Rule>> {
         return Ok(Vec::new());
     }
     let raw = fs::read_to_string(path).map_err(|err| err.to_string())?;
-    serde_json::from_str(&raw).map_err(|err| err.to_string())
+    parse_detection_rules(&raw)
+}
+
+/// 解析检测规则 JSON
+///
+/// 兼容旧版 `{ ruleType: "keyword" | "regex" | "heuristic", pattern }` 格式，读取时转换为新结构。
+pub fn parse_detection_rules(raw: &str) -> Result<Vec<Rule>, String> {
+    let values: Vec<serde_json::Value> =
+        serde_json::from_str(raw).map_err(|err| err.to_string())?;
+    values
+        .into_iter()
+        .map(|value| {
+            if value.get("ruleType").is_some() {
+                Ok(migrate_legacy_rule(&value))
+            } else {
+                serde_json::from_value(value).map_err(|err| err.to_string())
+            }
+        })
+        .collect()
+}
+
+/// 转换旧版规则
+///
+/// 旧版文件没有保存启发式类型，这类规则转换为空关键词（不产生命中），
+/// 内置规则的定义由前端按 id 合并恢复。
+fn migrate_legacy_rule(value: &serde_json::Value) -> Rule {
+    let field = |key: &str| {
+        value
+            .get(key)
+            .and_then(|v| v.as_str())
+            .unwrap_or_default()
+            .to_string()
+    };
+    let pattern = field("pattern");
+    let heuristic = value
+        .get("heuristicType")
+        .and_then(|v| serde_json::from_value(v.clone()).ok());
+
+    let rule_type = match (field("ruleType").as_str(), heuristic) {
+        ("regex", _) => RuleType::Regex { pattern },
+        ("heuristic", Some(kind)) => RuleType::Heuristic { kind },
+        ("heuristic", None) => RuleType::Keyword {
+            value: String::new(),
+        },
+        _ => RuleType::Keyword { value: pattern },
+    };
+
+    Rule {
+        id: field("id"),
+        name: field("name"),
+        rule_type,
+        scope: PageScope::All,
+        action: RuleAction::RedactText,
+        enabled: value
+            .get("enabled")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(true),
+        context: None,
+    }
+}
+
+/// 读取并校验规则包文件（按扩展名区分 JSON 与 TOML）
+pub fn read_rule_pack(
+    path: &Path,
+    trusted_keys: &[String],
+    require_signed: bool,
+) -> Result<(RulePack, RulePackInfo), String> {
+    let raw = fs::read_to_string(path)
+        .map_err(|e| format!("读取规则包失败 {}: {}", path.display(), e))?;
+    let pack = RulePack::parse(&raw, PackFormat::from_path(path))
+        .map_err(|e| format!("解析规则包失败: {}", e))?;
+    let keys = parse_trusted_keys(trusted_keys)?;
+    let info = pack
+        .verify(&keys, require_signed)
+        .map_err(|e| format!("规则包校验失败: {}", e))?;
+    Ok((pack, info))
 }
 
+/// 解析受信公钥，任一公钥无效即报错，避免配置笔误导致签名校验静默失效
+pub fn parse_trusted_keys(keys: &[String]) -> Result<Vec<VerifyingKey>, String> {
+    keys.iter()
+        .map(|key| parse_verifying_key(key).map_err(|e| format!("受信公钥无效 {}: {}", key, e)))
+        .collect()
+}
+
+#[cfg(feature = "gui")]
 #[tauri::command]
 pub fn save_detection_rules(app: tauri::AppHandle, rules: Vec<Rule>) -> ConfigResult<()> {
     let path = rules_path(&app).map_err(|err| err.to_string())?;
@@ -111,3 +228,140 @@ pub fn save_detection_rules(app: tauri::AppHandle, rules: Vec<Rule>) -> ConfigRe
     fs::write(path, raw).map_err(|err| err.to_string())?;
     Ok(())
 }
+
+// ============ 规则包 ============
+
+#[cfg(feature = "gui")]
+fn rule_pack_path(app: &tauri::AppHandle) -> Result<PathBuf, ConfigError> {
+    let base = app
+        .path()
+        .app_data_dir()
+        .map_err(|_| ConfigError::NoAppDataDir)?;
+    Ok(base.join("linch-redact").join("rule-pack.json"))
+}
+
+/// 导入规则包
+///
+/// 按配置中的受信公钥校验签名，通过后用规则包中的规则替换当前检测规则，
+/// 并保存规则包本身，供审计记录引用。
+#[cfg(feature = "gui")]
+#[tauri::command]
+pub fn import_rule_pack(app: tauri::AppHandle, path: String) -> ConfigResult<RulePackInfo> {
+    let config = load_config(app.clone())?;
+    let (pack, info) = read_rule_pack(
+        Path::new(&path),
+        config.trusted_rule_pack_keys.as_deref().unwrap_or_default(),
+        config.require_signed_rule_packs.unwrap_or(false),
+    )?;
+
+    save_detection_rules(app.clone(), pack.rules.clone())?;
+
+    let pack_path = rule_pack_path(&app).map_err(|err| err.to_string())?;
+    let raw = pack
+        .serialize(PackFormat::Json)
+        .map_err(|err| err.to_string())?;
+    fs::write(pack_path, raw).map_err(|err| err.to_string())?;
+
+    log::info!(
+        "[Config] 已导入规则包 {} {}（{}）",
+        info.name,
+        info.version,
+        info.hash
+    );
+    Ok(info)
+}
+
+/// 将规则导出为规则包（按扩展名写出 JSON 或 TOML），返回规则包信息
+///
+/// 导出的规则包不带签名，需要签名时由规则维护方使用私钥另行签署。
+#[cfg(feature = "gui")]
+#[tauri::command]
+pub fn export_rule_pack(
+    path: String,
+    name: String,
+    version: String,
+    rules: Vec<Rule>,
+) -> ConfigResult<RulePackInfo> {
+    let path = Path::new(&path);
+    let pack = RulePack::new(name, version, rules);
+    let raw = pack
+        .serialize(PackFormat::from_path(path))
+        .map_err(|err| err.to_string())?;
+    fs::write(path, raw).map_err(|err| err.to_string())?;
+    pack.verify(&[], false).map_err(|err| err.to_string())
+}
+
+/// 当前使用的规则包
+///
+/// 只有导入的规则包仍能通过校验、且与本次使用的规则一致时才返回，
+/// 规则被修改后不再视为来自该规则包。
+#[cfg(feature = "gui")]
+pub fn active_rule_pack(app: &tauri::AppHandle, rules: &[Rule]) -> Option<RulePackInfo> {
+    let path = rule_pack_path(app).ok()?;
+    if !path.exists() {
+        return None;
+    }
+    let config = load_config(app.clone()).ok()?;
+    let (pack, info) = match read_rule_pack(
+        &path,
+        config.trusted_rule_pack_keys.as_deref().unwrap_or_default(),
+        false,
+    ) {
+        Ok(result) => result,
+        Err(e) => {
+            log::warn!("[Config] 已导入的规则包无效: {}", e);
+            return None;
+        }
+    };
+    pack.has_rules(rules).then_some(info)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_legacy_rules_are_migrated() {
+        let raw = r#"[
+            {"id": "phone", "name": "手机号", "ruleType": "regex", "pattern": "1\\d{10}", "enabled": true},
+            {"id": "project", "name": "项目", "ruleType": "keyword", "pattern": "机密", "enabled": false},
+            {"id": "email", "name": "邮箱", "rule_type": {"type": "heuristic", "kind": "Email"}}
+        ]"#;
+        let rules = parse_detection_rules(raw).unwrap();
+
+        assert!(
+            matches!(&rules[0].rule_type, RuleType::Regex { pattern } if pattern == r"1\d{10}")
+        );
+        assert!(matches!(&rules[1].rule_type, RuleType::Keyword { value } if value == "机密"));
+        assert!(!rules[1].enabled);
+        assert!(matches!(rules[2].rule_type, RuleType::Heuristic { .. }));
+        assert!(rules[2].enabled);
+    }
+
+    #[test]
+    fn test_rule_pack_requires_trusted_signature() {
+        let dir = std::env::temp_dir().join(format!("linch-pack-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let rules = parse_detection_rules(
+            r#"[{"id": "phone", "name": "手机号", "rule_type": {"type": "regex", "pattern": "1\\d{10}"}}]"#,
+        )
+        .unwrap();
+
+        let key = linch_rules::SigningKey::from_bytes(&[3u8; 32]);
+        let public = hex::encode(key.verifying_key().to_bytes());
+        let mut pack = RulePack::new("finance", "1.0.0", rules);
+        pack.sign(&key);
+        let path = dir.join("finance.toml");
+        fs::write(&path, pack.serialize(PackFormat::Toml).unwrap()).unwrap();
+
+        let (read, info) = read_rule_pack(&path, &[public], true).unwrap();
+        assert_eq!(info.hash, pack.hash);
+        assert!(info.signer.is_some());
+        assert!(read.has_rules(&pack.rules));
+
+        assert!(read_rule_pack(&path, &[], false).is_err());
+        assert!(read_rule_pack(&path, &["zz".to_string()], false).is_err());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -10,14 +10,16 @@ mod tesseract;
 mod types;
 
 pub use engine::OcrEngine;
+#[cfg(feature = "gui")]
 pub use paddle::{
     get_paddle_status, init_paddle_engine, install_paddle_models, is_paddle_installed,
 };
-pub use tesseract::{
-    detect_tesseract_status, get_tesseract_langs, install_tesseract, Platform, TesseractEngine,
-};
+pub use tesseract::TesseractEngine;
+#[cfg(feature = "gui")]
+pub use tesseract::{detect_tesseract_status, get_tesseract_langs, install_tesseract, Platform};
 pub use types::*;
 
+#[cfg(feature = "gui")]
 use crate::config::{load_config, save_config, ConfigResult};
 use std::sync::Mutex;
 
@@ -31,6 +33,7 @@ static TESSERACT_ENGINE: Mutex<Option<TesseractEngine>> = Mutex::new(None);
 // ============ Tauri Commands ============
 
 /// 获取平台信息
+#[cfg(feature = "gui")]
 #[tauri::command]
 pub fn get_platform() -> PlatformInfo {
     PlatformInfo {
@@ -40,6 +43,7 @@ pub fn get_platform() -> PlatformInfo {
 }
 
 /// 获取所有 OCR 引擎状态
+#[cfg(feature = "gui")]
 #[tauri::command]
 pub fn get_ocr_engine_status(app: tauri::AppHandle) -> ConfigResult<OcrEngineStatus> {
     let config = load_config(app.clone())?;
@@ -59,6 +63,7 @@ pub fn get_ocr_engine_status(app: tauri::AppHandle) -> ConfigResult<OcrEngineSta
 }
 
 /// 设置当前 OCR 引擎
+#[cfg(feature = "gui")]
 #[tauri::command]
 pub fn set_ocr_engine(app: tauri::AppHandle, engine_type: OcrEngineType) -> ConfigResult<()> {
     // 更新内存中的引擎类型
@@ -77,6 +82,7 @@ pub fn set_ocr_engine(app: tauri::AppHandle, engine_type: OcrEngineType) -> Conf
 }
 
 /// 获取当前 OCR 引擎类型
+#[cfg(feature = "gui")]
 #[tauri::command]
 pub fn get_current_ocr_engine() -> ConfigResult<OcrEngineType> {
     let guard = CURRENT_ENGINE.lock().map_err(|e| e.to_string())?;
@@ -84,12 +90,14 @@ pub fn get_current_ocr_engine() -> ConfigResult<OcrEngineType> {
 }
 
 /// 初始化 Paddle OCR 引擎
+#[cfg(feature = "gui")]
 #[tauri::command]
 pub fn init_paddle_ocr(app: tauri::AppHandle) -> ConfigResult<()> {
     init_paddle_engine(&app)
 }
 
 /// 安装 Paddle OCR 模型
+#[cfg(feature = "gui")]
 #[tauri::command]
 pub async fn install_paddle_ocr(
     app: tauri::AppHandle,
@@ -99,12 +107,14 @@ pub async fn install_paddle_ocr(
 }
 
 /// 检查 Paddle OCR 是否已安装
+#[cfg(feature = "gui")]
 #[tauri::command]
 pub fn is_paddle_ocr_installed(app: tauri::AppHandle) -> ConfigResult<bool> {
     is_paddle_installed(&app)
 }
 
 /// 初始化 Tesseract 引擎
+#[cfg(feature = "gui")]
 #[tauri::command]
 pub fn init_tesseract_ocr(app: tauri::AppHandle) -> ConfigResult<()> {
     let config = load_config(app)?;
@@ -121,6 +131,7 @@ pub fn init_tesseract_ocr(app: tauri::AppHandle) -> ConfigResult<()> {
 }
 
 /// 检测 Tesseract 安装状态
+#[cfg(feature = "gui")]
 #[tauri::command]
 pub fn check_tesseract_status(app: tauri::AppHandle) -> ConfigResult<TesseractStatus> {
     let config = load_config(app)?;
@@ -129,6 +140,7 @@ pub fn check_tesseract_status(app: tauri::AppHandle) -> ConfigResult<TesseractSt
 }
 
 /// 保存 Tesseract 配置
+#[cfg(feature = "gui")]
 #[tauri::command]
 pub fn save_tesseract_config(
     app: tauri::AppHandle,
@@ -142,6 +154,7 @@ pub fn save_tesseract_config(
 }
 
 /// 获取 Tesseract 可用语言列表
+#[cfg(feature = "gui")]
 #[tauri::command]
 pub fn get_tesseract_languages(app: tauri::AppHandle) -> ConfigResult<Vec<String>> {
     let config = load_config(app)?;
@@ -158,12 +171,14 @@ pub fn get_tesseract_languages(app: tauri::AppHandle) -> ConfigResult<Vec<String
 }
 
 /// 获取当前平台信息
+#[cfg(feature = "gui")]
 #[tauri::command]
 pub fn get_current_platform() -> String {
     format!("{:?}", Platform::current()).to_lowercase()
 }
 
 /// 安装 Tesseract（自动根据平台选择安装方式）
+#[cfg(feature = "gui")]
 #[tauri::command]
 pub async fn install_tesseract_ocr(app: tauri::AppHandle) -> ConfigResult<()> {
     use tauri::Emitter;
@@ -180,12 +195,14 @@ pub async fn install_tesseract_ocr(app: tauri::AppHandle) -> ConfigResult<()> {
 }
 
 /// 使用当前引擎识别图片
+#[cfg(feature = "gui")]
 #[tauri::command]
 pub fn ocr_recognize(image_path: String) -> ConfigResult<Vec<OcrTextResult>> {
     recognize_with_current_engine(&image_path)
 }
 
 /// 获取 OCR 审计信息
+#[cfg(feature = "gui")]
 #[tauri::command]
 pub fn get_ocr_audit_info() -> ConfigResult<OcrAuditInfo> {
     Ok(get_current_audit_info())
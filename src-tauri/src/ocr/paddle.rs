@@ -1,15 +1,25 @@
 //! Paddle OCR 引擎实现
 
-use std::fs;
-use std::io::{Read, Write};
-use std::path::Path;
 use std::sync::Mutex;
-use tauri::Emitter;
 
-use crate::config::{models_dir, save_config, AppConfig, ConfigResult};
+use crate::config::ConfigResult;
+use crate::ocr::types::{BBox, OcrTextResult};
+
+// 模型安装与状态查询依赖应用数据目录，只在桌面端提供
+#[cfg(feature = "gui")]
+use crate::config::{models_dir, save_config, AppConfig};
+#[cfg(feature = "gui")]
 use crate::ocr::types::{
-    BBox, DownloadProgress, OcrTextResult, PaddleInstallRequest, PaddleInstallResult, PaddleStatus,
+    DownloadProgress, PaddleInstallRequest, PaddleInstallResult, PaddleStatus,
 };
+#[cfg(feature = "gui")]
+use std::{
+    fs,
+    io::{Read, Write},
+    path::Path,
+};
+#[cfg(feature = "gui")]
+use tauri::Emitter;
 
 /// 全局 Paddle OCR 引擎实例
 static PADDLE_ENGINE: Mutex<Option<linch_ocr::PaddleOcrEngine>> = Mutex::new(None);
@@ -18,6 +28,7 @@ static PADDLE_ENGINE: Mutex<Option<linch_ocr::PaddleOcrEngine>> = Mutex::new(Non
 static PADDLE_MODEL_PATHS: Mutex<Option<(String, String)>> = Mutex::new(None);
 
 /// 下载文件（带进度）
+#[cfg(feature = "gui")]
 fn download_file_with_progress(
     app: &tauri::AppHandle,
     url: &str,
@@ -87,6 +98,7 @@ fn download_file_with_progress(
 }
 
 /// 检查 Paddle OCR 模型是否已安装
+#[cfg(feature = "gui")]
 pub fn is_paddle_installed(app: &tauri::AppHandle) -> ConfigResult<bool> {
     let config = crate::config::load_config(app.clone())?;
 
@@ -106,6 +118,7 @@ pub fn is_paddle_installed(app: &tauri::AppHandle) -> ConfigResult<bool> {
 }
 
 /// 获取 Paddle 状态
+#[cfg(feature = "gui")]
 pub fn get_paddle_status(app: &tauri::AppHandle) -> PaddleStatus {
     let config = crate::config::load_config(app.clone()).ok();
 
@@ -132,6 +145,7 @@ pub fn get_paddle_status(app: &tauri::AppHandle) -> PaddleStatus {
 }
 
 /// 安装 Paddle OCR 模型
+#[cfg(feature = "gui")]
 pub async fn install_paddle_models(
     app: tauri::AppHandle,
     request: PaddleInstallRequest,
@@ -184,6 +198,7 @@ pub async fn install_paddle_models(
 }
 
 /// 初始化 Paddle OCR 引擎
+#[cfg(feature = "gui")]
 pub fn init_paddle_engine(app: &tauri::AppHandle) -> ConfigResult<()> {
     let config = crate::config::load_config(app.clone())?;
 
@@ -14,6 +14,7 @@ const CREATE_NO_WINDOW: u32 = 0x08000000;
 
 /// 创建一个隐藏窗口的 Command（Windows 下不弹出 cmd 窗口）
 fn silent_command(program: &str) -> Command {
+    #[cfg_attr(not(target_os = "windows"), allow(unused_mut))]
     let mut cmd = Command::new(program);
     #[cfg(target_os = "windows")]
     cmd.creation_flags(CREATE_NO_WINDOW);
@@ -21,9 +22,9 @@ fn silent_command(program: &str) -> Command {
 }
 
 use crate::ocr::engine::OcrEngine;
-use crate::ocr::types::{
-    BBox, OcrAuditInfo, OcrEngineType, OcrTextResult, TesseractConfig, TesseractStatus,
-};
+#[cfg(feature = "gui")]
+use crate::ocr::types::TesseractStatus;
+use crate::ocr::types::{BBox, OcrAuditInfo, OcrEngineType, OcrTextResult, TesseractConfig};
 
 /// Tesseract OCR 引擎
 pub struct TesseractEngine {
@@ -274,6 +275,7 @@ pub fn get_tesseract_version(binary_path: &str) -> Result<String, String> {
 }
 
 /// 获取 Tesseract 可用语言列表
+#[cfg(feature = "gui")]
 pub fn get_tesseract_langs(
     binary_path: &str,
     tessdata_path: Option<&str>,
@@ -309,6 +311,7 @@ pub fn get_tesseract_langs(
 }
 
 /// 检测 Tesseract 安装状态
+#[cfg(feature = "gui")]
 pub fn detect_tesseract_status(config: &TesseractConfig) -> TesseractStatus {
     // 使用统一的查找逻辑
     match find_tesseract_binary(config.binary_path.as_deref()) {
@@ -396,6 +399,7 @@ fn which_tesseract(binary: &str) -> Option<String> {
 }
 
 /// 查找 tessdata 路径
+#[cfg(feature = "gui")]
 fn find_tessdata_path(binary_path: &str) -> Option<String> {
     // 尝试从环境变量获取
     if let Ok(path) = std::env::var("TESSDATA_PREFIX") {
@@ -467,6 +471,7 @@ fn find_tessdata_path(binary_path: &str) -> Option<String> {
 }
 
 /// 获取当前操作系统类型
+#[cfg(feature = "gui")]
 #[derive(Debug, Clone, serde::Serialize)]
 #[serde(rename_all = "lowercase")]
 #[allow(dead_code)] // 各平台只使用对应的 variant
@@ -477,6 +482,7 @@ pub enum Platform {
     Unknown,
 }
 
+#[cfg(feature = "gui")]
 impl Platform {
     pub fn current() -> Self {
         #[cfg(target_os = "windows")]
@@ -491,6 +497,7 @@ impl Platform {
 }
 
 /// Tesseract 安装进度事件
+#[cfg(feature = "gui")]
 #[derive(Debug, Clone, serde::Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct TesseractInstallProgress {
@@ -502,6 +509,7 @@ pub struct TesseractInstallProgress {
 }
 
 /// 安装 Tesseract（根据平台自动选择方式）
+#[cfg(feature = "gui")]
 pub async fn install_tesseract<F>(progress_callback: F) -> Result<(), String>
 where
     F: Fn(TesseractInstallProgress) + Send + 'static,
@@ -517,6 +525,7 @@ where
 }
 
 /// 检测是否在 WSL 环境中运行
+#[cfg(feature = "gui")]
 fn is_wsl() -> bool {
     // 检查 /proc/version 是否包含 WSL 或 Microsoft
     if let Ok(version) = std::fs::read_to_string("/proc/version") {
@@ -527,6 +536,7 @@ fn is_wsl() -> bool {
 }
 
 /// Linux 安装 Tesseract
+#[cfg(feature = "gui")]
 async fn install_tesseract_linux<F>(progress_callback: F) -> Result<(), String>
 where
     F: Fn(TesseractInstallProgress) + Send + 'static,
@@ -685,6 +695,7 @@ where
 }
 
 /// macOS 安装 Tesseract（使用 Homebrew）
+#[cfg(feature = "gui")]
 async fn install_tesseract_macos<F>(progress_callback: F) -> Result<(), String>
 where
     F: Fn(TesseractInstallProgress) + Send + 'static,
@@ -749,6 +760,7 @@ where
 }
 
 /// Windows 安装 Tesseract（尝试 winget，否则打开下载页面）
+#[cfg(feature = "gui")]
 async fn install_tesseract_windows<F>(progress_callback: F) -> Result<(), String>
 where
     F: Fn(TesseractInstallProgress) + Send + 'static,
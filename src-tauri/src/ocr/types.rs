@@ -43,6 +43,7 @@ pub struct BBox {
 }
 
 /// 平台信息
+#[cfg(feature = "gui")]
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct PlatformInfo {
     pub os: String,
@@ -50,6 +51,7 @@ pub struct PlatformInfo {
 }
 
 /// Paddle 模型安装请求
+#[cfg(feature = "gui")]
 #[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct PaddleInstallRequest {
@@ -64,6 +66,7 @@ pub struct PaddleInstallRequest {
 }
 
 /// Paddle 模型安装结果
+#[cfg(feature = "gui")]
 #[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct PaddleInstallResult {
@@ -102,6 +105,7 @@ impl TesseractConfig {
 }
 
 /// Tesseract 安装状态
+#[cfg(feature = "gui")]
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct TesseractStatus {
@@ -120,6 +124,7 @@ pub struct TesseractStatus {
 }
 
 /// 下载进度事件
+#[cfg(feature = "gui")]
 #[derive(Clone, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct DownloadProgress {
@@ -132,6 +137,7 @@ pub struct DownloadProgress {
 }
 
 /// OCR 引擎状态（用于前端显示）
+#[cfg(feature = "gui")]
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct OcrEngineStatus {
@@ -141,6 +147,7 @@ pub struct OcrEngineStatus {
 }
 
 /// Paddle 引擎状态
+#[cfg(feature = "gui")]
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct PaddleStatus {
@@ -1,12 +1,18 @@
 //! 敏感信息检测模块
 
-use crate::pdf::safe_render;
+use crate::pdf::safe_render::SourcePdf;
 use crate::pdf::types::{
-    DetectionBbox, DetectionHit, PageContentType, PdfAnalysis, RedactionMode, Rule,
+    DetectionBbox, DetectionHit, DetectionProgress, PageContentType, PdfAnalysis, RedactionMode,
 };
 use crate::pdf::utils::{detect_page_content_type, get_page_content};
+use image::DynamicImage;
+use linch_core::mask_snippet;
+use linch_rules::{BBox, CompiledRules, PositionedPage, Rule, TextItem};
+use linch_verify::PageRecognizer;
 use lopdf::{Document, Object};
-use std::collections::HashMap;
+use std::cell::OnceCell;
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::time::Instant;
 
 /// 分析 PDF 文件
@@ -42,391 +48,250 @@ pub fn analyze_pdf_file(pdf_path: &str) -> Result<PdfAnalysis, String> {
 }
 
 /// 检测敏感内容（基于规则）
+///
+/// 文本层页面使用 pdfium 逐字符提取的位置，图片型页面（启用 OCR 时）使用 OCR 单词框，
+/// 统一交给 `linch_rules::CompiledRules` 匹配并计算命中区域。
+#[cfg(feature = "gui")]
 pub fn detect_sensitive_content_in_pdf(
     pdf_path: &str,
     rules: &[Rule],
     use_ocr: bool,
     page_indices: Option<&[usize]>, // 可选：指定要扫描的页面索引
 ) -> Result<Vec<DetectionHit>, String> {
-    let mut hits = Vec::new();
+    detect_sensitive_content_with_progress(pdf_path, rules, use_ocr, page_indices, |_| Ok(()))
+}
 
-    // 过滤启用的规则
-    let enabled_rules: Vec<&Rule> = rules.iter().filter(|r| r.enabled).collect();
+/// 检测敏感内容，逐页上报进度
+///
+/// 每处理完一页（提取文本、按需 OCR、匹配规则）立即把该页新增的命中交给 `on_page`，
+/// 回调返回错误时停止检测并返回该错误（用于取消）。
+pub fn detect_sensitive_content_with_progress<F>(
+    pdf_path: &str,
+    rules: &[Rule],
+    use_ocr: bool,
+    page_indices: Option<&[usize]>,
+    on_page: F,
+) -> Result<Vec<DetectionHit>, String>
+where
+    F: FnMut(DetectionProgress) -> Result<(), String>,
+{
+    detect_with_matched_texts(pdf_path, rules, use_ocr, page_indices, on_page).map(|(hits, _)| hits)
+}
 
-    if enabled_rules.is_empty() {
-        return Ok(hits);
+/// 检测敏感内容，同时返回命中的原文
+///
+/// 原文只在内存中用于输出校验（逐字搜索残留），不会写入审计记录或报告。
+pub(crate) fn detect_with_matched_texts<F>(
+    pdf_path: &str,
+    rules: &[Rule],
+    use_ocr: bool,
+    page_indices: Option<&[usize]>,
+    mut on_page: F,
+) -> Result<(Vec<DetectionHit>, Vec<String>), String>
+where
+    F: FnMut(DetectionProgress) -> Result<(), String>,
+{
+    if !rules.iter().any(|r| r.enabled) {
+        return Ok((Vec::new(), Vec::new()));
     }
-
-    // 编译正则表达式
-    let compiled_rules: Vec<(&Rule, Option<regex::Regex>)> = enabled_rules
-        .iter()
-        .map(|r| {
-            let regex = if r.rule_type == "regex" {
-                regex::Regex::new(&r.pattern).ok()
-            } else {
-                None
-            };
-            (*r, regex)
-        })
-        .collect();
+    // 规则只编译一次，各页复用
+    let compiled = CompiledRules::new(rules).map_err(|e| super::describe_compile_errors(&e))?;
 
     // 将页面索引转换为 HashSet 以便快速查找
-    let target_pages: Option<std::collections::HashSet<usize>> =
+    let target_pages: Option<HashSet<usize>> =
         page_indices.map(|indices| indices.iter().copied().collect());
 
-    // 优先使用 pdfium 提取文本（更准确的编码处理）
-    let mut page_texts = match safe_render::extract_text_from_pdf(pdf_path) {
-        Ok(texts) => {
-            log::info!("[Detection] 使用 pdfium 提取文本成功");
-            // 如果指定了页面，只保留目标页面
-            if let Some(ref targets) = target_pages {
-                texts
-                    .into_iter()
-                    .filter(|(idx, _)| targets.contains(idx))
-                    .collect()
-            } else {
-                texts
-            }
+    // 优先使用 pdfium 逐页提取文本（更准确的编码处理，并带有字符位置），各页共用同一份文档
+    let pdfium = OnceCell::new();
+    let source = SourcePdf::new(pdf_path, &pdfium);
+    let mut lopdf_pages = None;
+    let page_count = match source.page_count() {
+        Ok(count) => {
+            log::info!("[Detection] 使用 pdfium 提取文本");
+            count
         }
         Err(e) => {
             log::warn!("[Detection] pdfium 提取失败: {}，回退到 lopdf", e);
-            // 回退到 lopdf 的原始提取方式
-            let texts = extract_text_with_lopdf(pdf_path)?;
-            if let Some(ref targets) = target_pages {
-                texts
-                    .into_iter()
-                    .filter(|(idx, _)| targets.contains(idx))
-                    .collect()
-            } else {
-                texts
-            }
+            // 回退到 lopdf 的原始提取方式（没有位置信息）
+            let (count, pages) = extract_text_with_lopdf(pdf_path, target_pages.as_ref())?;
+            lopdf_pages = Some(pages);
+            count
         }
     };
+    let scan_pages: Vec<usize> = (0..page_count)
+        .filter(|idx| match &target_pages {
+            Some(targets) => targets.contains(idx),
+            None => true,
+        })
+        .collect();
 
-    let mut ocr_results_by_page: HashMap<usize, Vec<crate::ocr::OcrTextResult>> = HashMap::new();
-    // 按行分组的 OCR 结果（用于行级文本匹配）
-    let mut ocr_lines_by_page: HashMap<usize, Vec<OcrLineWithWords>> = HashMap::new();
-
-    // 如果启用 OCR，对没有提取到文本的页面进行 OCR
-    if use_ocr {
+    // 启用 OCR 时分析页面类型，图片型页面（或没有文本的页面）需要 OCR
+    let page_types = if use_ocr {
         log::info!("[Detection] OCR 已启用，检查是否需要 OCR 识别");
+        analyze_pdf_file(pdf_path)?.page_types
+    } else {
+        Vec::new()
+    };
 
-        // 分析 PDF 获取页面类型
-        let analysis = analyze_pdf_file(pdf_path)?;
-
-        // 找出图片型页面（或文本为空的页面）
-        let text_page_indices: std::collections::HashSet<usize> =
-            page_texts.iter().map(|(idx, _)| *idx).collect();
-
-        let mut ocr_needed_pages: Vec<usize> = Vec::new();
-        for (idx, page_type) in analysis.page_types.iter().enumerate() {
-            // 如果指定了页面，检查是否在目标页面中
-            if let Some(ref targets) = target_pages {
-                if !targets.contains(&idx) {
-                    continue;
-                }
-            }
-            if *page_type == PageContentType::ImageBased || !text_page_indices.contains(&idx) {
-                ocr_needed_pages.push(idx);
-            }
-        }
-
-        if !ocr_needed_pages.is_empty() {
-            log::info!("[Detection] 需要 OCR 的页面: {:?}", ocr_needed_pages);
-
-            // 对每个需要 OCR 的页面进行识别
-            for page_idx in ocr_needed_pages {
-                match ocr_page(pdf_path, page_idx) {
-                    Ok((text, results)) => {
-                        if !text.is_empty() {
-                            log::info!(
-                                "[Detection] OCR 页面 {} 识别到 {} 个字符",
-                                page_idx,
-                                text.len()
-                            );
-                            page_texts.push((page_idx, text));
-                        }
-                        if !results.is_empty() {
-                            // 按行合并 OCR 结果，保留原始单词信息用于 bbox 计算
-                            let lines = merge_ocr_to_lines_with_words(&results);
-                            ocr_lines_by_page.insert(page_idx, lines);
-                            ocr_results_by_page.insert(page_idx, results);
-                        }
-                    }
-                    Err(e) => {
-                        log::warn!("[Detection] OCR 页面 {} 失败: {}", page_idx, e);
+    let mut hits = Vec::new();
+    let mut matched_texts = Vec::new();
+    // 用于追踪已添加的位置，避免重复
+    let mut added_positions: HashSet<String> = HashSet::new();
+
+    for (done, &page_idx) in scan_pages.iter().enumerate() {
+        let mut page = match lopdf_pages.as_mut() {
+            Some(pages) => pages.remove(&page_idx),
+            None => source.positioned_text(page_idx)?,
+        };
+
+        // 图片型页面的文本层不可靠，以 OCR 结果为准
+        let mut ocr_used = false;
+        let is_image = page_types.get(page_idx) == Some(&PageContentType::ImageBased);
+        if use_ocr && (is_image || page.is_none()) {
+            match ocr_page(&source, page_idx) {
+                Ok(results) => {
+                    let ocr = ocr_results_to_page(page_idx, &results);
+                    if !ocr.items.is_empty() {
+                        log::info!(
+                            "[Detection] OCR 页面 {} 识别到 {} 个字符",
+                            page_idx,
+                            ocr.text().len()
+                        );
+                        page = Some(ocr);
+                        ocr_used = true;
                     }
                 }
-            }
-        }
-    }
-
-    // 遍历每页检测
-    for (page_idx, text) in &page_texts {
-        log::info!(
-            "[Detection] 页面 {} 提取到文本长度: {}",
-            page_idx,
-            text.len()
-        );
-        if !text.is_empty() {
-            if should_log_full_text() {
-                log::info!("[Detection] 文本全文: {:?}", text);
-            } else {
-                // 打印前 200 个字符用于调试
-                let preview: String = text.chars().take(200).collect();
-                log::info!("[Detection] 文本预览: {:?}", preview);
-            }
-        }
-
-        // 优先使用行级合并的 OCR 结果进行匹配（解决邮箱等跨词匹配问题）
-        if let Some(ocr_lines) = ocr_lines_by_page.get(page_idx) {
-            let mut added_positions: std::collections::HashSet<String> =
-                std::collections::HashSet::new();
-
-            for line in ocr_lines {
-                if line.text.is_empty() {
-                    continue;
-                }
-
-                for (rule, regex_opt) in &compiled_rules {
-                    // 在行文本中查找匹配
-                    let matches: Vec<(usize, usize)> = if let Some(regex) = regex_opt {
-                        regex
-                            .find_iter(&line.text)
-                            .map(|m| (m.start(), m.end()))
-                            .collect()
-                    } else {
-                        line.text
-                            .match_indices(&rule.pattern)
-                            .map(|(start, s)| (start, start + s.len()))
-                            .collect()
-                    };
-
-                    for (match_start, match_end) in matches {
-                        // 找到匹配覆盖的单词，使用原始 bbox
-                        let bbox = line.get_bbox_for_range(match_start, match_end);
-
-                        let pos_key =
-                            format!("{:.3},{:.3},{:.3},{:.3}", bbox.x, bbox.y, bbox.w, bbox.h);
-                        if added_positions.contains(&pos_key) {
-                            continue;
-                        }
-                        added_positions.insert(pos_key);
-
-                        let matched_text = &line.text[match_start..match_end];
-                        let snippet = mask_snippet(matched_text);
-                        hits.push(DetectionHit {
-                            page: *page_idx,
-                            bbox: DetectionBbox {
-                                x: bbox.x as f64,
-                                y: bbox.y as f64,
-                                width: bbox.w as f64,
-                                height: bbox.h as f64,
-                            },
-                            rule_id: rule.id.clone(),
-                            rule_name: rule.name.clone(),
-                            snippet,
-                        });
-                    }
+                Err(e) => {
+                    log::warn!("[Detection] OCR 页面 {} 失败: {}", page_idx, e);
                 }
             }
-
-            continue;
         }
 
-        // 回退：使用单词级 OCR 结果（旧逻辑）
-        if let Some(ocr_results) = ocr_results_by_page.get(page_idx) {
-            let mut added_positions: std::collections::HashSet<String> =
-                std::collections::HashSet::new();
-
-            for ocr_result in ocr_results {
-                let ocr_text = ocr_result.text.trim();
-                if ocr_text.is_empty() {
+        let mut page_hits = Vec::new();
+        if let Some(page) = &page {
+            log_page_text(page);
+
+            for hit in compiled.match_text(std::slice::from_ref(page)) {
+                let bbox = match hit.bbox {
+                    Some(b) if ocr_used => DetectionBbox {
+                        x: b.x as f64,
+                        y: b.y as f64,
+                        width: b.w as f64,
+                        height: b.h as f64,
+                    },
+                    Some(b) => pad_text_bbox(&b),
+                    None => estimate_bbox(&hit.snippet),
+                };
+
+                let pos_key = format!(
+                    "{},{:.3},{:.3},{:.3},{:.3}",
+                    page_idx, bbox.x, bbox.y, bbox.width, bbox.height
+                );
+                if !added_positions.insert(pos_key) {
                     continue;
                 }
 
-                for (rule, regex_opt) in &compiled_rules {
-                    let mut matched = text_matches_rule(ocr_text, rule, regex_opt.as_ref());
-                    if !matched && rule_uses_digits(rule) {
-                        let compact = compact_numeric_text(ocr_text);
-                        if !compact.is_empty() && compact != ocr_text {
-                            matched = text_matches_rule(&compact, rule, regex_opt.as_ref());
-                        }
-                    }
-
-                    if !matched {
-                        continue;
-                    }
-
-                    let bbox = &ocr_result.bbox;
-                    let pos_key =
-                        format!("{:.3},{:.3},{:.3},{:.3}", bbox.x, bbox.y, bbox.w, bbox.h);
-                    if added_positions.contains(&pos_key) {
-                        continue;
-                    }
-                    added_positions.insert(pos_key);
-
-                    let snippet = mask_snippet(ocr_text);
-                    hits.push(DetectionHit {
-                        page: *page_idx,
-                        bbox: DetectionBbox {
-                            x: bbox.x as f64,
-                            y: bbox.y as f64,
-                            width: bbox.w as f64,
-                            height: bbox.h as f64,
-                        },
-                        rule_id: rule.id.clone(),
-                        rule_name: rule.name.clone(),
-                        snippet,
-                    });
-                }
+                page_hits.push(DetectionHit {
+                    page: page_idx,
+                    bbox,
+                    rule_id: hit.rule_id,
+                    rule_name: hit.rule_name,
+                    snippet: mask_snippet(&hit.snippet),
+                    confidence: hit.confidence,
+                    reasons: hit.reasons,
+                });
+                matched_texts.push(hit.snippet);
             }
-
-            continue;
         }
 
-        // 收集当前页面所有规则的匹配结果
-        let mut rule_matches: Vec<(&Rule, &str)> = Vec::new();
-
-        // 对每个规则进行匹配
-        for (rule, regex_opt) in &compiled_rules {
-            // 收集匹配的文本（包含位置信息用于边界检查）
-            let matches_with_pos: Vec<(usize, &str)> = if let Some(regex) = regex_opt {
-                regex
-                    .find_iter(text)
-                    .map(|m| (m.start(), m.as_str()))
-                    .collect()
-            } else {
-                // 关键词匹配
-                text.match_indices(&rule.pattern).collect()
-            };
-
-            // 边界检查：过滤掉前后有数字的匹配（避免匹配到更长数字的一部分）
-            let filtered_matches: Vec<&str> = matches_with_pos
-                .into_iter()
-                .filter(|(start, matched)| {
-                    // start 是字节索引，需要检查前一个字节对应的字符
-                    // 检查前一个字符
-                    if *start > 0 {
-                        if let Some(prev_char) = text[..*start].chars().last() {
-                            if prev_char.is_ascii_digit() {
-                                return false;
-                            }
-                        }
-                    }
-                    // 检查后一个字符
-                    let end_pos = *start + matched.len();
-                    if let Some(next_char) = text[end_pos..].chars().next() {
-                        if next_char.is_ascii_digit() {
-                            return false;
-                        }
-                    }
-                    true
-                })
-                .map(|(_, s)| s)
-                .collect();
+        hits.extend(page_hits.iter().cloned());
+        on_page(DetectionProgress {
+            page: page_idx,
+            done: done + 1,
+            total: scan_pages.len(),
+            ocr_used,
+            hits: page_hits,
+        })?;
+    }
 
-            // 去重：相同的文本只搜索一次
-            let unique_matches: std::collections::HashSet<&str> =
-                filtered_matches.into_iter().collect();
+    Ok((hits, matched_texts))
+}
 
-            // 收集当前规则的所有匹配文本，稍后批量搜索
-            for matched_text in unique_matches {
-                rule_matches.push((rule, matched_text));
-            }
-        }
+fn log_page_text(page: &PositionedPage) {
+    let text = page.text();
+    log::info!(
+        "[Detection] 页面 {} 提取到文本长度: {}",
+        page.page - 1,
+        text.len()
+    );
+    if should_log_full_text() {
+        log::info!("[Detection] 文本全文: {:?}", text);
+    } else {
+        // 打印前 200 个字符用于调试
+        let preview: String = text.chars().take(200).collect();
+        log::info!("[Detection] 文本预览: {:?}", preview);
+    }
+}
 
-        // 批量搜索当前页面的所有匹配文本（性能优化：只打开 PDF 一次）
-        if !rule_matches.is_empty() {
-            let search_terms: Vec<&str> = rule_matches.iter().map(|(_, t)| *t).collect();
-
-            // 用于追踪已添加的位置，避免重复
-            let mut added_positions: std::collections::HashSet<String> =
-                std::collections::HashSet::new();
-
-            match safe_render::batch_search_text_in_page(pdf_path, *page_idx, &search_terms) {
-                Ok(batch_results) => {
-                    // 建立搜索词到规则的映射
-                    let term_to_rule: std::collections::HashMap<&str, &Rule> =
-                        rule_matches.iter().map(|(r, t)| (*t, *r)).collect();
-
-                    for (search_term, search_results) in batch_results {
-                        let rule = match term_to_rule.get(search_term.as_str()) {
-                            Some(r) => *r,
-                            None => continue,
-                        };
-
-                        for result in search_results {
-                            let pos_key = format!(
-                                "{:.3},{:.3},{:.3},{:.3}",
-                                result.x, result.y, result.width, result.height
-                            );
-
-                            if added_positions.contains(&pos_key) {
-                                continue;
-                            }
-                            added_positions.insert(pos_key);
-
-                            let snippet = mask_snippet(&search_term);
-                            hits.push(DetectionHit {
-                                page: *page_idx,
-                                bbox: DetectionBbox {
-                                    x: result.x,
-                                    y: result.y,
-                                    width: result.width,
-                                    height: result.height,
-                                },
-                                rule_id: rule.id.clone(),
-                                rule_name: rule.name.clone(),
-                                snippet,
-                            });
-                        }
-                    }
-                }
-                Err(e) => {
-                    log::warn!("[Detection] 批量搜索位置失败: {}，使用估算位置", e);
-                    // 回退到估算位置
-                    for (rule, matched_text) in &rule_matches {
-                        let snippet = mask_snippet(matched_text);
-                        let text_len = matched_text.chars().count() as f64;
-                        let width = (text_len * 0.015).min(0.4).max(0.08);
-
-                        hits.push(DetectionHit {
-                            page: *page_idx,
-                            bbox: DetectionBbox {
-                                x: 0.05,
-                                y: 0.5,
-                                width,
-                                height: 0.025,
-                            },
-                            rule_id: rule.id.clone(),
-                            rule_name: rule.name.clone(),
-                            snippet,
-                        });
-                    }
-                }
-            }
-        }
+/// 文本层字符框略微外扩，保证完整覆盖字形
+fn pad_text_bbox(bbox: &BBox) -> DetectionBbox {
+    let padding = 0.003;
+    DetectionBbox {
+        x: (bbox.x as f64 - padding).max(0.0),
+        y: (bbox.y as f64 - padding).max(0.0),
+        width: (bbox.w as f64 + padding * 2.0).min(1.0),
+        height: (bbox.h as f64 + padding * 2.0).min(1.0),
     }
+}
 
-    Ok(hits)
+/// 没有位置信息时估算命中区域
+fn estimate_bbox(matched_text: &str) -> DetectionBbox {
+    let text_len = matched_text.chars().count() as f64;
+    DetectionBbox {
+        x: 0.05,
+        y: 0.5,
+        width: (text_len * 0.015).clamp(0.08, 0.4),
+        height: 0.025,
+    }
 }
 
-/// 使用 lopdf 提取文本（回退方案）
-fn extract_text_with_lopdf(pdf_path: &str) -> Result<Vec<(usize, String)>, String> {
+/// 使用 lopdf 提取文本（回退方案，没有位置信息）
+///
+/// 返回总页数和包含文本的页面（按页面索引）。
+fn extract_text_with_lopdf(
+    pdf_path: &str,
+    pages: Option<&HashSet<usize>>,
+) -> Result<(usize, HashMap<usize, PositionedPage>), String> {
     let doc = Document::load(pdf_path).map_err(|e| format!("无法加载 PDF: {}", e))?;
     let page_ids: Vec<lopdf::ObjectId> = doc.page_iter().collect();
-    let mut results = Vec::new();
+    let mut results = HashMap::new();
 
     for (page_idx, page_id) in page_ids.iter().enumerate() {
+        if let Some(targets) = pages {
+            if !targets.contains(&page_idx) {
+                continue;
+            }
+        }
         let content_data = get_page_content(&doc, *page_id).unwrap_or_default();
         let text = extract_text_from_content(&content_data);
         if !text.is_empty() {
-            results.push((page_idx, text));
+            results.insert(
+                page_idx,
+                PositionedPage {
+                    page: page_idx as u32 + 1,
+                    width: 1.0,
+                    height: 1.0,
+                    items: vec![TextItem {
+                        text,
+                        bbox: None,
+                        confidence: None,
+                    }],
+                },
+            );
         }
     }
 
-    Ok(results)
+    Ok((page_ids.len(), results))
 }
 
 // ============ PDF 分析辅助函数 ============
@@ -682,66 +547,15 @@ fn decode_hex_string(hex: &str) -> String {
     result
 }
 
-/// 脱敏显示 snippet
-fn rule_uses_digits(rule: &Rule) -> bool {
-    rule.pattern.contains("\\d") || rule.pattern.contains("[0-9]")
-}
-
-fn compact_numeric_text(text: &str) -> String {
-    text.chars().filter(|c| c.is_ascii_alphanumeric()).collect()
-}
-
-fn text_matches_rule(text: &str, rule: &Rule, regex_opt: Option<&regex::Regex>) -> bool {
-    let matches_with_pos: Vec<(usize, &str)> = if let Some(regex) = regex_opt {
-        regex
-            .find_iter(text)
-            .map(|m| (m.start(), m.as_str()))
-            .collect()
-    } else {
-        text.match_indices(&rule.pattern).collect()
-    };
-
-    matches_with_pos.into_iter().any(|(start, matched)| {
-        if start > 0 {
-            if let Some(prev_char) = text[..start].chars().last() {
-                if prev_char.is_ascii_digit() {
-                    return false;
-                }
-            }
-        }
-        let end_pos = start + matched.len();
-        if let Some(next_char) = text[end_pos..].chars().next() {
-            if next_char.is_ascii_digit() {
-                return false;
-            }
-        }
-        true
-    })
-}
-
-fn mask_snippet(text: &str) -> String {
-    let chars: Vec<char> = text.chars().collect();
-    let len = chars.len();
-
-    if len <= 4 {
-        "*".repeat(len)
-    } else {
-        let visible = 4.min(len / 3);
-        let prefix: String = chars[..visible].iter().collect();
-        let suffix: String = chars[len - visible..].iter().collect();
-        format!("{}****{}", prefix, suffix)
-    }
-}
-
 /// 对 PDF 页面进行 OCR 识别
 ///
 /// 1. 将 PDF 页面渲染为图片
 /// 2. 调用当前配置的 OCR 引擎识别
-/// 3. 返回识别出的文本
+/// 3. 返回识别出的单词及位置
 fn ocr_page(
-    pdf_path: &str,
+    source: &SourcePdf,
     page_index: usize,
-) -> Result<(String, Vec<crate::ocr::OcrTextResult>), String> {
+) -> Result<Vec<crate::ocr::OcrTextResult>, String> {
     // 创建临时文件路径
     let temp_dir = std::env::temp_dir();
     let temp_image_path = temp_dir.join(format!(
@@ -759,7 +573,11 @@ fn ocr_page(
         .unwrap_or(150);
 
     // 渲染 PDF 页面到图片（可通过 LINCH_OCR_DPI 覆盖）
-    safe_render::render_page_to_image(pdf_path, page_index, &temp_image_str, dpi)?;
+    source
+        .render(page_index, dpi)?
+        .to_rgb8()
+        .save(&temp_image_path)
+        .map_err(|e| format!("保存图片失败: {}", e))?;
     log::info!(
         "[Detection] 页面 {} 渲染耗时: {} ms",
         page_index,
@@ -768,20 +586,43 @@ fn ocr_page(
 
     // 使用当前配置的 OCR 引擎识别
     let results = crate::ocr::recognize_with_current_engine(&temp_image_str)?;
-    let text = results
-        .iter()
-        .map(|r| r.text.as_str())
-        .collect::<Vec<_>>()
-        .join(" ");
 
     // 删除临时文件
     if let Err(e) = std::fs::remove_file(&temp_image_path) {
         log::warn!("[OCR] 删除临时文件失败: {}", e);
     }
 
-    Ok((text, results))
+    Ok(results)
 }
 
+/// 使用当前 OCR 引擎识别渲染好的页面（供输出校验抽样使用）
+pub(crate) struct CurrentEngineRecognizer;
+
+impl PageRecognizer for CurrentEngineRecognizer {
+    fn recognize(&self, image: &DynamicImage) -> Result<Vec<TextItem>, String> {
+        let temp_image_path = std::env::temp_dir().join(format!(
+            "verify_page_{}_{}.png",
+            std::process::id(),
+            VERIFY_IMAGE_COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        image
+            .to_rgb8()
+            .save(&temp_image_path)
+            .map_err(|e| format!("保存图片失败: {}", e))?;
+
+        let results = crate::ocr::recognize_with_current_engine(&temp_image_path.to_string_lossy());
+
+        if let Err(e) = std::fs::remove_file(&temp_image_path) {
+            log::warn!("[OCR] 删除临时文件失败: {}", e);
+        }
+
+        Ok(ocr_results_to_page(0, &results?).items)
+    }
+}
+
+/// 校验抽样临时图片的序号，避免同一进程内的文件名冲突
+static VERIFY_IMAGE_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
 fn should_log_full_text() -> bool {
     match std::env::var("LINCH_LOG_FULL_TEXT") {
         Ok(val) => {
@@ -792,76 +633,80 @@ fn should_log_full_text() -> bool {
     }
 }
 
-/// OCR 行级结果（保留原始单词信息用于精确 bbox）
-#[derive(Debug, Clone)]
-struct OcrLineWithWords {
-    /// 合并后的行文本（清理后）
-    text: String,
-    /// 每个单词在 text 中的字节范围和对应的 bbox
-    word_ranges: Vec<(std::ops::Range<usize>, crate::ocr::BBox)>,
-}
+/// 将 OCR 结果转换为带位置的页面文本
+///
+/// 单词按行分组、行内按 x 坐标排序；单词间距较大时补空格，行尾补换行。
+/// 点号、@ 前后以及数字之间不补空格（OCR 常把邮箱、证件号拆成多个单词）。
+fn ocr_results_to_page(page_index: usize, results: &[crate::ocr::OcrTextResult]) -> PositionedPage {
+    let mut items: Vec<TextItem> = Vec::new();
+
+    for mut words in group_ocr_lines(results) {
+        // 按 x 坐标排序
+        words.sort_by(|a, b| {
+            a.bbox
+                .x
+                .partial_cmp(&b.bbox.x)
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+
+        let line_start = items.len();
+        // 上一个单词的右边界和最后一个字符
+        let mut prev: Option<(f32, char)> = None;
 
-impl OcrLineWithWords {
-    /// 根据文本范围获取对应的 bbox（合并覆盖的单词 bbox）
-    fn get_bbox_for_range(&self, start: usize, end: usize) -> crate::ocr::BBox {
-        let mut min_x = f32::INFINITY;
-        let mut max_x = f32::NEG_INFINITY;
-        let mut min_y = f32::INFINITY;
-        let mut max_y = f32::NEG_INFINITY;
-        let mut found = false;
-
-        for (range, bbox) in &self.word_ranges {
-            // 检查范围是否有重叠
-            if range.start < end && range.end > start {
-                found = true;
-                min_x = min_x.min(bbox.x);
-                max_x = max_x.max(bbox.x + bbox.w);
-                min_y = min_y.min(bbox.y);
-                max_y = max_y.max(bbox.y + bbox.h);
+        for word in words {
+            let text = clean_ocr_text(word.text.trim());
+            let (Some(first), Some(last)) = (text.chars().next(), text.chars().last()) else {
+                continue;
+            };
+
+            if let Some((prev_x, prev_char)) = prev {
+                if word.bbox.x - prev_x > 0.005 && needs_space(prev_char, first) {
+                    items.push(TextItem {
+                        text: " ".to_string(),
+                        bbox: None,
+                        confidence: None,
+                    });
+                }
             }
+            prev = Some((word.bbox.x + word.bbox.w, last));
+
+            items.push(TextItem {
+                text,
+                bbox: Some(BBox {
+                    x: word.bbox.x,
+                    y: word.bbox.y,
+                    w: word.bbox.w,
+                    h: word.bbox.h,
+                }),
+                confidence: Some(word.confidence),
+            });
         }
 
-        if found {
-            crate::ocr::BBox {
-                x: min_x,
-                y: min_y,
-                w: max_x - min_x,
-                h: max_y - min_y,
-            }
-        } else {
-            // 回退：使用整行的 bbox
-            let all_min_x = self
-                .word_ranges
-                .iter()
-                .map(|(_, b)| b.x)
-                .fold(f32::INFINITY, f32::min);
-            let all_max_x = self
-                .word_ranges
-                .iter()
-                .map(|(_, b)| b.x + b.w)
-                .fold(f32::NEG_INFINITY, f32::max);
-            let all_min_y = self
-                .word_ranges
-                .iter()
-                .map(|(_, b)| b.y)
-                .fold(f32::INFINITY, f32::min);
-            let all_max_y = self
-                .word_ranges
-                .iter()
-                .map(|(_, b)| b.y + b.h)
-                .fold(f32::NEG_INFINITY, f32::max);
-            crate::ocr::BBox {
-                x: all_min_x,
-                y: all_min_y,
-                w: all_max_x - all_min_x,
-                h: all_max_y - all_min_y,
-            }
+        if items.len() > line_start {
+            items.push(TextItem {
+                text: "\n".to_string(),
+                bbox: None,
+                confidence: None,
+            });
         }
     }
+
+    PositionedPage {
+        page: page_index as u32 + 1,
+        width: 1.0,
+        height: 1.0,
+        items,
+    }
 }
 
-/// 将 OCR 单词按行合并，保留原始单词信息
-fn merge_ocr_to_lines_with_words(results: &[crate::ocr::OcrTextResult]) -> Vec<OcrLineWithWords> {
+fn needs_space(prev: char, next: char) -> bool {
+    !(matches!(prev, '.' | '@')
+        || matches!(next, '.' | '@')
+        || (prev.is_ascii_digit() && next.is_ascii_digit()))
+}
+
+/// 将 OCR 单词按行分组
+fn group_ocr_lines(results: &[crate::ocr::OcrTextResult]) -> Vec<Vec<&crate::ocr::OcrTextResult>> {
     if results.is_empty() {
         return Vec::new();
     }
@@ -869,119 +714,46 @@ fn merge_ocr_to_lines_with_words(results: &[crate::ocr::OcrTextResult]) -> Vec<O
     // 按行号分组（优先使用 Tesseract 原生行号）
     let has_line_nums = results.iter().any(|r| r.line_num.is_some());
 
-    let grouped: Vec<Vec<&crate::ocr::OcrTextResult>> = if has_line_nums {
+    if has_line_nums {
         let mut lines_map: std::collections::BTreeMap<u32, Vec<&crate::ocr::OcrTextResult>> =
             std::collections::BTreeMap::new();
         for word in results {
             let line_num = word.line_num.unwrap_or(0);
             lines_map.entry(line_num).or_default().push(word);
         }
-        lines_map.into_values().collect()
-    } else {
-        // 按 y 坐标分组
-        let mut sorted: Vec<&crate::ocr::OcrTextResult> = results.iter().collect();
-        sorted.sort_by(|a, b| {
-            a.bbox
-                .y
-                .partial_cmp(&b.bbox.y)
-                .unwrap_or(std::cmp::Ordering::Equal)
-        });
-
-        let mut groups: Vec<Vec<&crate::ocr::OcrTextResult>> = Vec::new();
-        let mut current_group: Vec<&crate::ocr::OcrTextResult> = Vec::new();
-        let y_tolerance = 0.015;
-
-        for word in sorted {
-            if current_group.is_empty() {
-                current_group.push(word);
-            } else {
-                let last_y = current_group.last().unwrap().bbox.y;
-                if (word.bbox.y - last_y).abs() <= y_tolerance {
-                    current_group.push(word);
-                } else {
-                    groups.push(std::mem::take(&mut current_group));
-                    current_group.push(word);
-                }
-            }
-        }
-        if !current_group.is_empty() {
-            groups.push(current_group);
-        }
-        groups
-    };
-
-    // 转换为 OcrLineWithWords
-    grouped
-        .into_iter()
-        .filter_map(|words| merge_words_to_line_with_ranges(&words))
-        .collect()
-}
-
-/// 将同一行的单词合并，保留每个单词的范围和 bbox
-fn merge_words_to_line_with_ranges(
-    words: &[&crate::ocr::OcrTextResult],
-) -> Option<OcrLineWithWords> {
-    if words.is_empty() {
-        return None;
+        return lines_map.into_values().collect();
     }
 
-    // 按 x 坐标排序
-    let mut sorted_words: Vec<&&crate::ocr::OcrTextResult> = words.iter().collect();
-    sorted_words.sort_by(|a, b| {
+    // 按 y 坐标分组
+    let mut sorted: Vec<&crate::ocr::OcrTextResult> = results.iter().collect();
+    sorted.sort_by(|a, b| {
         a.bbox
-            .x
-            .partial_cmp(&b.bbox.x)
+            .y
+            .partial_cmp(&b.bbox.y)
             .unwrap_or(std::cmp::Ordering::Equal)
     });
 
-    let mut text = String::new();
-    let mut word_ranges: Vec<(std::ops::Range<usize>, crate::ocr::BBox)> = Vec::new();
-    let mut prev_end_x: Option<f32> = None;
-
-    for word in &sorted_words {
-        let word_text = word.text.trim();
-        if word_text.is_empty() {
-            continue;
-        }
+    let mut groups: Vec<Vec<&crate::ocr::OcrTextResult>> = Vec::new();
+    let mut current_group: Vec<&crate::ocr::OcrTextResult> = Vec::new();
+    let y_tolerance = 0.015;
 
-        // 检查是否需要添加空格
-        if let Some(prev_x) = prev_end_x {
-            let gap = word.bbox.x - prev_x;
-            if gap > 0.005 {
-                text.push(' ');
+    for word in sorted {
+        if current_group.is_empty() {
+            current_group.push(word);
+        } else {
+            let last_y = current_group.last().unwrap().bbox.y;
+            if (word.bbox.y - last_y).abs() <= y_tolerance {
+                current_group.push(word);
+            } else {
+                groups.push(std::mem::take(&mut current_group));
+                current_group.push(word);
             }
         }
-
-        let start = text.len();
-        text.push_str(word_text);
-        let end = text.len();
-
-        word_ranges.push((start..end, word.bbox));
-        prev_end_x = Some(word.bbox.x + word.bbox.w);
     }
-
-    // 清理文本（但保持 word_ranges 对应关系不变）
-    // 注意：清理后 word_ranges 的范围可能不完全准确，但对于 bbox 查找仍然有效
-    let cleaned_text = clean_ocr_text(&text);
-
-    if cleaned_text.is_empty() {
-        return None;
+    if !current_group.is_empty() {
+        groups.push(current_group);
     }
-
-    // 如果清理没有改变文本，直接返回
-    if cleaned_text == text {
-        return Some(OcrLineWithWords {
-            text: cleaned_text,
-            word_ranges,
-        });
-    }
-
-    // 清理改变了文本，需要重新映射范围
-    // 简化处理：直接使用清理后的文本，但保留原始 bbox
-    Some(OcrLineWithWords {
-        text: cleaned_text,
-        word_ranges, // 范围可能不完全准确，但 get_bbox_for_range 会找到重叠的单词
-    })
+    groups
 }
 
 /// 清理 OCR 文本，移除不该有空格的地方
@@ -1010,3 +782,105 @@ fn clean_ocr_text(text: &str) -> String {
 
     result
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lopdf::{dictionary, Stream};
+
+    /// 生成每页都有一个手机号的 PDF
+    fn write_phone_pdf(name: &str, pages: usize) -> std::path::PathBuf {
+        let mut doc = Document::with_version("1.5");
+        let pages_id = doc.new_object_id();
+        let font_id = doc.add_object(dictionary! {
+            "Type" => "Font",
+            "Subtype" => "Type1",
+            "BaseFont" => "Helvetica",
+        });
+        let kids: Vec<Object> = (0..pages)
+            .map(|_| {
+                let contents = doc.add_object(Stream::new(
+                    dictionary! {},
+                    b"BT /F1 12 Tf 100 700 Td (13812345678) Tj ET".to_vec(),
+                ));
+                doc.add_object(dictionary! {
+                    "Type" => "Page",
+                    "Parent" => pages_id,
+                    "MediaBox" => vec![0.into(), 0.into(), 612.into(), 792.into()],
+                    "Contents" => contents,
+                    "Resources" => dictionary! { "Font" => dictionary! { "F1" => font_id } },
+                })
+                .into()
+            })
+            .collect();
+        doc.objects.insert(
+            pages_id,
+            Object::Dictionary(dictionary! {
+                "Type" => "Pages",
+                "Kids" => kids,
+                "Count" => pages as i64,
+            }),
+        );
+        let catalog_id = doc.add_object(dictionary! { "Type" => "Catalog", "Pages" => pages_id });
+        doc.trailer.set("Root", catalog_id);
+
+        let path =
+            std::env::temp_dir().join(format!("linch-detect-{}-{}.pdf", name, std::process::id()));
+        doc.save(&path).unwrap();
+        path
+    }
+
+    fn phone_rules() -> Vec<Rule> {
+        serde_json::from_str(
+            r#"[{ "id": "phone", "name": "手机号", "rule_type": { "type": "regex", "pattern": "1[3-9]\\d{9}" } }]"#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_reports_hits_page_by_page() {
+        let path = write_phone_pdf("progress", 3);
+        let mut progress = Vec::new();
+        let hits = detect_sensitive_content_with_progress(
+            path.to_str().unwrap(),
+            &phone_rules(),
+            false,
+            None,
+            |p| {
+                progress.push(p);
+                Ok(())
+            },
+        )
+        .unwrap();
+        let _ = std::fs::remove_file(&path);
+
+        assert_eq!(hits.len(), 3);
+        assert_eq!(progress.len(), 3);
+        for (idx, p) in progress.iter().enumerate() {
+            assert_eq!((p.page, p.done, p.total), (idx, idx + 1, 3));
+            assert_eq!(p.hits.len(), 1);
+            assert_eq!(p.hits[0].page, idx);
+        }
+    }
+
+    #[test]
+    fn test_callback_error_stops_detection() {
+        let path = write_phone_pdf("cancel", 3);
+        let mut pages_seen = 0;
+        let result = detect_sensitive_content_with_progress(
+            path.to_str().unwrap(),
+            &phone_rules(),
+            false,
+            Some(&[1, 2]),
+            |p| {
+                pages_seen += 1;
+                assert_eq!(p.page, 1);
+                Err("检测已取消".to_string())
+            },
+        );
+        let _ = std::fs::remove_file(&path);
+
+        assert_eq!(result.unwrap_err(), "检测已取消");
+        assert_eq!(pages_seen, 1);
+    }
+}
@@ -1,177 +1,485 @@
-use super::types::Mask;
-use image::{DynamicImage, ImageFormat, Rgba, RgbaImage};
-use lopdf::{Document, Object, Stream};
-use std::io::Cursor;
-
-/// 在图片上绘制黑色矩形
-fn draw_black_rectangles_on_image(img: &mut RgbaImage, masks: &[Mask]) {
-    let (img_width, img_height) = img.dimensions();
-    let black = Rgba([0u8, 0u8, 0u8, 255u8]);
-
-    for mask in masks {
-        let x_start = (mask.x * img_width as f64) as u32;
-        let y_start = (mask.y * img_height as f64) as u32;
-        let rect_width = (mask.width * img_width as f64) as u32;
-        let rect_height = (mask.height * img_height as f64) as u32;
-
-        let x_end = (x_start + rect_width).min(img_width);
-        let y_end = (y_start + rect_height).min(img_height);
-
-        for y in y_start..y_end {
-            for x in x_start..x_end {
-                img.put_pixel(x, y, black);
-            }
-        }
-    }
+use super::image_codec::ImageData;
+use super::types::MaskRect;
+use linch_pdf::{
+    encode_pieces, image_placements, mask_pixels, page_resources, split_inline_images,
+    ContentPiece, ImageSource, PixelRect,
+};
+use lopdf::content::Operation;
+use lopdf::{Dictionary, Document, Object, ObjectId, Stream};
+use std::collections::HashMap;
+
+/// 表单嵌套的最大深度
+const MAX_DEPTH: usize = 8;
+/// 内嵌图片转换为 XObject 后的资源名称前缀
+const INLINE_PREFIX: &str = "LinchInline";
+
+/// 页面图片脱敏结果
+pub struct ImageRedaction {
+    /// 新的页面内容（内嵌图片被替换为 `Do` 调用）
+    pub content: Vec<u8>,
+    /// 已脱敏的图片数量
+    pub redacted: usize,
 }
 
 /// 处理页面中的图片（用于扫描件 PDF）
+///
+/// 按内容流中的 CTM 计算每个图片的绘制位置，把页面坐标中的 mask 逐图映射到像素坐标后涂黑。
+/// 图片 XObject、表单内的图片和内嵌图片（BI/ID/EI）都会处理；带 /SMask 的图片同时把
+/// 软遮罩对应区域设为不透明，保证黑块可见。改动写入本页独立的副本，
+/// 其他页面共享的原始图片保持不变。与 mask 相交的图片无法解码时返回错误。
 pub fn redact_page_images(
     doc: &mut Document,
-    page_id: lopdf::ObjectId,
-    masks: &[Mask],
-) -> Result<bool, String> {
-    let page = doc.get_object(page_id).map_err(|e| e.to_string())?;
-    let resources_ref = if let Object::Dictionary(dict) = page {
-        dict.get(b"Resources").ok().cloned()
-    } else {
-        return Ok(false);
+    page_id: ObjectId,
+    content_data: &[u8],
+    masks: &[MaskRect],
+    jpeg_quality: u8,
+) -> Result<ImageRedaction, String> {
+    let masks: Vec<linch_pdf::MaskRect> = masks
+        .iter()
+        .map(|m| linch_pdf::MaskRect {
+            left: m.x,
+            bottom: m.y,
+            right: m.x + m.width,
+            top: m.y + m.height,
+        })
+        .collect();
+    let mut redactor = ImageRedactor {
+        masks: &masks,
+        jpeg_quality,
+        redacted: 0,
     };
 
-    let resources = match resources_ref {
-        Some(Object::Reference(ref_id)) => doc.get_object(ref_id).ok().cloned(),
-        Some(obj) => Some(obj),
-        None => None,
+    let resources = page_resources(doc, page_id);
+    let rewritten = redactor.rewrite(doc, content_data, &resources, IDENTITY, 0)?;
+    let content = match rewritten {
+        Some((content, resources)) => {
+            if let Ok(Object::Dictionary(page)) = doc.get_object_mut(page_id) {
+                page.set("Resources", Object::Dictionary(resources));
+            }
+            content
+        }
+        None => content_data.to_vec(),
     };
 
-    let xobject_dict = match resources {
-        Some(Object::Dictionary(dict)) => match dict.get(b"XObject") {
-            Ok(Object::Reference(ref_id)) => doc.get_object(*ref_id).ok().cloned(),
-            Ok(obj) => Some(obj.clone()),
-            Err(_) => None,
-        },
-        _ => None,
-    };
+    Ok(ImageRedaction {
+        content,
+        redacted: redactor.redacted,
+    })
+}
 
-    let xobject_dict = match xobject_dict {
-        Some(Object::Dictionary(dict)) => dict,
-        _ => return Ok(false),
-    };
+const IDENTITY: [f32; 6] = [1.0, 0.0, 0.0, 1.0, 0.0, 0.0];
 
-    let mut processed_any = false;
+struct ImageRedactor<'a> {
+    masks: &'a [linch_pdf::MaskRect],
+    jpeg_quality: u8,
+    redacted: usize,
+}
 
-    for (name, obj) in xobject_dict.iter() {
-        let image_ref = match obj {
-            Object::Reference(ref_id) => *ref_id,
-            _ => continue,
-        };
+impl ImageRedactor<'_> {
+    /// 处理一段内容流，有改动时返回新的内容与资源字典
+    fn rewrite(
+        &mut self,
+        doc: &mut Document,
+        data: &[u8],
+        resources: &Dictionary,
+        base: [f32; 6],
+        depth: usize,
+    ) -> Result<Option<(Vec<u8>, Dictionary)>, String> {
+        let mut pieces = split_inline_images(data).map_err(|e| e.to_string())?;
+        let placements = image_placements(&pieces, base);
+        if placements.is_empty() {
+            return Ok(None);
+        }
 
-        let image_obj = match doc.get_object(image_ref) {
-            Ok(Object::Stream(stream)) => stream.clone(),
-            _ => continue,
+        let mut xobjects = match resources.get(b"XObject").map(|o| resolve(doc, o)) {
+            Ok(Object::Dictionary(dict)) => dict.clone(),
+            _ => Dictionary::new(),
         };
+        let mut changed = false;
+        let mut content_changed = false;
+        // 同一图片可能被多次绘制，先汇总所有位置上的像素区域
+        let mut image_rects: HashMap<Vec<u8>, Vec<PixelRect>> = HashMap::new();
 
-        let subtype = image_obj.dict.get(b"Subtype");
-        if !matches!(subtype, Ok(Object::Name(n)) if n == b"Image") {
-            continue;
+        for placement in placements {
+            match placement.source {
+                ImageSource::XObject(name) => {
+                    let Some(id) = xobjects.get(&name).ok().and_then(|o| o.as_reference().ok())
+                    else {
+                        continue;
+                    };
+                    let Ok(stream) = doc.get_object(id).and_then(Object::as_stream) else {
+                        continue;
+                    };
+                    if is_subtype(&stream.dict, b"Image") {
+                        let rects = self.pixel_rects(&stream.dict, &placement.ctm);
+                        if !rects.is_empty() {
+                            image_rects.entry(name).or_default().extend(rects);
+                        }
+                    } else if is_subtype(&stream.dict, b"Form") && depth < MAX_DEPTH {
+                        let stream = stream.clone();
+                        if let Some(form_id) =
+                            self.rewrite_form(doc, &stream, resources, &placement.ctm, depth)?
+                        {
+                            xobjects.set(name, Object::Reference(form_id));
+                            changed = true;
+                        }
+                    }
+                }
+                ImageSource::Inline(index) => {
+                    let ContentPiece::InlineImage(inline) = &pieces[index] else {
+                        continue;
+                    };
+                    let stream = Stream::new(inline.to_xobject_dict(), inline.data.clone());
+                    let rects = self.pixel_rects(&stream.dict, &placement.ctm);
+                    if rects.is_empty() {
+                        continue;
+                    }
+                    let image_id = self.redact_image(doc, "（内嵌）", &stream, &rects)?;
+                    let name = unused_name(&xobjects);
+                    xobjects.set(name.clone(), Object::Reference(image_id));
+                    // 内嵌图片与 Do 调用的图片都绘制在当前 CTM 的单位正方形中
+                    pieces[index] = ContentPiece::Operations(vec![Operation::new(
+                        "Do",
+                        vec![Object::Name(name)],
+                    )]);
+                    changed = true;
+                    content_changed = true;
+                }
+            }
         }
 
-        log::info!("找到图片 XObject: {:?}", String::from_utf8_lossy(name));
+        for (name, rects) in image_rects {
+            let Some(id) = xobjects.get(&name).ok().and_then(|o| o.as_reference().ok()) else {
+                continue;
+            };
+            let Ok(stream) = doc.get_object(id).and_then(Object::as_stream) else {
+                continue;
+            };
+            let stream = stream.clone();
+            let label = String::from_utf8_lossy(&name).into_owned();
+            let image_id = self.redact_image(doc, &label, &stream, &rects)?;
+            log::info!("图片 {} 脱敏完成", label);
+            xobjects.set(name, Object::Reference(image_id));
+            changed = true;
+        }
 
-        let width = match image_obj.dict.get(b"Width") {
-            Ok(Object::Integer(w)) => *w as u32,
-            _ => continue,
-        };
-        let height = match image_obj.dict.get(b"Height") {
-            Ok(Object::Integer(h)) => *h as u32,
-            _ => continue,
+        if !changed {
+            return Ok(None);
+        }
+        let content = if content_changed {
+            encode_pieces(&pieces).map_err(|e| e.to_string())?
+        } else {
+            data.to_vec()
         };
+        let mut resources = resources.clone();
+        resources.set("XObject", Object::Dictionary(xobjects));
+        Ok(Some((content, resources)))
+    }
 
-        let image_data = match image_obj.decompressed_content() {
-            Ok(data) => data,
-            Err(_) => image_obj.content.clone(),
+    /// 处理表单内的图片，有改动时返回表单副本的对象 ID
+    fn rewrite_form(
+        &mut self,
+        doc: &mut Document,
+        form: &Stream,
+        inherited: &Dictionary,
+        ctm: &[f32; 6],
+        depth: usize,
+    ) -> Result<Option<ObjectId>, String> {
+        let matrix = match form.dict.get(b"Matrix") {
+            Ok(Object::Array(values)) if values.len() == 6 => {
+                let mut matrix = IDENTITY;
+                for (slot, value) in matrix.iter_mut().zip(values) {
+                    *slot = value.as_float().unwrap_or(0.0);
+                }
+                matrix
+            }
+            _ => IDENTITY,
+        };
+        let resources = match form.dict.get(b"Resources").map(|o| resolve(doc, o)) {
+            Ok(Object::Dictionary(dict)) => dict.clone(),
+            // 未定义资源的表单使用调用处的资源
+            _ => inherited.clone(),
         };
+        let data = form
+            .decompressed_content()
+            .unwrap_or_else(|_| form.content.clone());
 
-        let bits_per_component = match image_obj.dict.get(b"BitsPerComponent") {
-            Ok(Object::Integer(b)) => *b as u8,
-            _ => 8,
+        let Some((content, resources)) =
+            self.rewrite(doc, &data, &resources, concat(&matrix, ctm), depth + 1)?
+        else {
+            return Ok(None);
         };
+        let mut dict = form.dict.clone();
+        dict.remove(b"Filter");
+        dict.remove(b"DecodeParms");
+        dict.set("Resources", Object::Dictionary(resources));
+        let mut stream = Stream::new(dict, content);
+        let _ = stream.compress();
+        Ok(Some(doc.add_object(stream)))
+    }
 
-        let color_space = image_obj.dict.get(b"ColorSpace");
-        let is_rgb = matches!(color_space, Ok(Object::Name(n)) if n == b"DeviceRGB");
-        let is_gray = matches!(color_space, Ok(Object::Name(n)) if n == b"DeviceGray");
-
-        let mut rgba_img = if is_rgb
-            && bits_per_component == 8
-            && image_data.len() == (width * height * 3) as usize
-        {
-            let mut img = RgbaImage::new(width, height);
-            for (i, pixel) in image_data.chunks(3).enumerate() {
-                if pixel.len() == 3 {
-                    let x = (i as u32) % width;
-                    let y = (i as u32) / width;
-                    img.put_pixel(x, y, Rgba([pixel[0], pixel[1], pixel[2], 255]));
-                }
-            }
-            img
-        } else if is_gray
-            && bits_per_component == 8
-            && image_data.len() == (width * height) as usize
-        {
-            let mut img = RgbaImage::new(width, height);
-            for (i, &gray) in image_data.iter().enumerate() {
-                let x = (i as u32) % width;
-                let y = (i as u32) / width;
-                img.put_pixel(x, y, Rgba([gray, gray, gray, 255]));
-            }
-            img
-        } else {
-            match image::load_from_memory(&image_data) {
-                Ok(img) => img.to_rgba8(),
-                Err(_) => {
-                    log::warn!("无法解码图片，跳过");
-                    continue;
-                }
-            }
+    /// mask 在图片像素坐标中覆盖的区域
+    fn pixel_rects(&self, dict: &Dictionary, ctm: &[f32; 6]) -> Vec<PixelRect> {
+        let (Some(width), Some(height)) = (integer(dict, b"Width"), integer(dict, b"Height"))
+        else {
+            return Vec::new();
         };
+        self.masks
+            .iter()
+            .filter_map(|mask| mask_pixels(ctm, width, height, mask))
+            .collect()
+    }
 
-        draw_black_rectangles_on_image(&mut rgba_img, masks);
+    /// 涂黑图片中的区域，返回写入的副本 ID
+    ///
+    /// 无法解码的图片返回错误而不是跳过，提示改用 SafeRender 模式。
+    fn redact_image(
+        &mut self,
+        doc: &mut Document,
+        name: &str,
+        image: &Stream,
+        rects: &[PixelRect],
+    ) -> Result<ObjectId, String> {
+        let stream = self.redacted_copy(doc, image, rects).map_err(|e| {
+            format!(
+                "图片 {} 无法在图片模式下脱敏（{}），请改用 SafeRender 模式",
+                name, e
+            )
+        })?;
+        self.redacted += 1;
+        Ok(doc.add_object(stream))
+    }
 
-        let mut output_data = Vec::new();
-        let mut cursor = Cursor::new(&mut output_data);
+    fn redacted_copy(
+        &self,
+        doc: &mut Document,
+        image: &Stream,
+        rects: &[PixelRect],
+    ) -> Result<Stream, String> {
+        let mut data = ImageData::decode(doc, image)?;
+        let black = data.black()?;
+        for rect in rects {
+            data.fill(rect, &black);
+        }
 
-        let filter = image_obj.dict.get(b"Filter");
-        let use_jpeg = matches!(filter, Ok(Object::Name(n)) if n == b"DCTDecode");
+        let mut dict = image.dict.clone();
+        // 颜色键遮罩可能让黑色变为透明
+        if matches!(dict.get(b"Mask"), Ok(Object::Array(_))) {
+            dict.remove(b"Mask");
+        }
+        for key in [b"SMask".as_slice(), b"Mask"] {
+            let Ok(mask_id) = dict.get(key).and_then(Object::as_reference) else {
+                continue;
+            };
+            match self.opaque_mask(doc, mask_id, &data, rects) {
+                Ok(id) => dict.set(key, Object::Reference(id)),
+                Err(e) => log::warn!("无法处理图片遮罩，黑块可能不可见: {}", e),
+            }
+        }
+        data.encode(dict, self.jpeg_quality)
+    }
 
-        if use_jpeg {
-            let rgb_img = DynamicImage::ImageRgba8(rgba_img).to_rgb8();
-            rgb_img
-                .write_to(&mut cursor, ImageFormat::Jpeg)
-                .map_err(|e| e.to_string())?;
-        } else {
-            let rgb_img = DynamicImage::ImageRgba8(rgba_img).to_rgb8();
-            output_data = rgb_img.into_raw();
+    /// 把软遮罩或模板遮罩中对应区域设为不透明，返回遮罩副本 ID
+    fn opaque_mask(
+        &self,
+        doc: &mut Document,
+        mask_id: ObjectId,
+        image: &ImageData,
+        rects: &[PixelRect],
+    ) -> Result<ObjectId, String> {
+        let stream = doc
+            .get_object(mask_id)
+            .and_then(Object::as_stream)
+            .map_err(|e| e.to_string())?
+            .clone();
+        let mut mask = ImageData::decode(doc, &stream)?;
+        let opaque = mask.opaque();
+        for rect in rects {
+            let scaled = scale_rect(rect, image, &mask);
+            mask.fill(&scaled, &opaque);
         }
+        let stream = mask.encode(stream.dict.clone(), self.jpeg_quality)?;
+        Ok(doc.add_object(stream))
+    }
+}
+
+/// 把图片像素区域按比例换算到尺寸不同的遮罩上
+fn scale_rect(rect: &PixelRect, image: &ImageData, mask: &ImageData) -> PixelRect {
+    let sx = mask.width as f32 / image.width as f32;
+    let sy = mask.height as f32 / image.height as f32;
+    PixelRect {
+        left: (rect.left as f32 * sx).floor() as u32,
+        top: (rect.top as f32 * sy).floor() as u32,
+        right: (rect.right as f32 * sx).ceil() as u32,
+        bottom: (rect.bottom as f32 * sy).ceil() as u32,
+    }
+}
+
+fn integer(dict: &Dictionary, key: &[u8]) -> Option<u32> {
+    dict.get(key)
+        .ok()
+        .and_then(|o| o.as_i64().ok())
+        .and_then(|v| u32::try_from(v).ok())
+}
+
+fn is_subtype(dict: &Dictionary, subtype: &[u8]) -> bool {
+    matches!(dict.get(b"Subtype"), Ok(Object::Name(n)) if n == subtype)
+}
+
+fn resolve<'a>(doc: &'a Document, obj: &'a Object) -> &'a Object {
+    match obj {
+        Object::Reference(id) => doc.get_object(*id).unwrap_or(obj),
+        _ => obj,
+    }
+}
 
-        let mut new_dict = image_obj.dict.clone();
-        if !use_jpeg {
-            new_dict.remove(b"Filter");
-            new_dict.remove(b"DecodeParms");
+/// 资源字典中未使用的内嵌图片名称
+fn unused_name(xobjects: &Dictionary) -> Vec<u8> {
+    (1..)
+        .map(|n| format!("{}{}", INLINE_PREFIX, n).into_bytes())
+        .find(|name| !xobjects.has(name))
+        .unwrap_or_default()
+}
+
+/// 矩阵乘法 a × b（先应用 a 再应用 b）
+fn concat(a: &[f32; 6], b: &[f32; 6]) -> [f32; 6] {
+    [
+        a[0] * b[0] + a[1] * b[2],
+        a[0] * b[1] + a[1] * b[3],
+        a[2] * b[0] + a[3] * b[2],
+        a[2] * b[1] + a[3] * b[3],
+        a[4] * b[0] + a[5] * b[2] + b[4],
+        a[4] * b[1] + a[5] * b[3] + b[5],
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lopdf::dictionary;
+
+    /// 同一张 4x4 灰度图片在两个页面上使用，第一页绘制在 (100, 100) 起 40x40 的区域
+    fn shared_image_doc() -> (Document, Vec<ObjectId>, ObjectId) {
+        let mut doc = Document::with_version("1.5");
+        let image = doc.add_object(Stream::new(
+            dictionary! {
+                "Type" => "XObject",
+                "Subtype" => "Image",
+                "Width" => 4,
+                "Height" => 4,
+                "ColorSpace" => "DeviceGray",
+                "BitsPerComponent" => 8,
+            },
+            vec![200u8; 16],
+        ));
+        let pages_id = doc.new_object_id();
+        let resources = doc.add_object(dictionary! {
+            "XObject" => dictionary! { "Im1" => image },
+        });
+        let mut page_ids = Vec::new();
+        for _ in 0..2 {
+            page_ids.push(doc.add_object(dictionary! {
+                "Type" => "Page",
+                "Parent" => pages_id,
+                "MediaBox" => vec![0.into(), 0.into(), 300.into(), 300.into()],
+                "Resources" => resources,
+            }));
         }
-        new_dict.set(b"Length", Object::Integer(output_data.len() as i64));
+        doc.objects.insert(
+            pages_id,
+            Object::Dictionary(dictionary! {
+                "Type" => "Pages",
+                "Kids" => page_ids.iter().map(|&id| id.into()).collect::<Vec<Object>>(),
+                "Count" => 2,
+            }),
+        );
+        (doc, page_ids, image)
+    }
 
-        let new_stream = if use_jpeg {
-            Stream::new(new_dict, output_data)
+    fn page_image(doc: &Document, page_id: ObjectId, name: &[u8]) -> Vec<u8> {
+        let resources = page_resources(doc, page_id);
+        let xobjects = resources.get(b"XObject").unwrap().as_dict().unwrap();
+        let id = xobjects.get(name).unwrap().as_reference().unwrap();
+        let stream = doc.get_object(id).unwrap().as_stream().unwrap();
+        if stream.dict.has(b"Filter") {
+            // lopdf 拒绝直接解压图片流
+            let mut stream = stream.clone();
+            stream.dict.remove(b"Subtype");
+            stream.decompressed_content().unwrap()
         } else {
-            let mut s = Stream::new(new_dict, output_data);
-            s.compress().ok();
-            s
-        };
+            stream.content.clone()
+        }
+    }
+
+    #[test]
+    fn test_redacts_image_pixels_under_mask() {
+        let (mut doc, page_ids, _) = shared_image_doc();
+        // 覆盖图片左上四分之一（像素列 0-1、行 0-1）
+        let masks = [MaskRect {
+            x: 100.0,
+            y: 120.0,
+            width: 20.0,
+            height: 20.0,
+        }];
+        let content = b"q 40 0 0 40 100 100 cm /Im1 Do Q".to_vec();
+        let result = redact_page_images(&mut doc, page_ids[0], &content, &masks, 90).unwrap();
+        assert_eq!(result.redacted, 1);
+
+        let pixels = page_image(&doc, page_ids[0], b"Im1");
+        let expected: Vec<u8> = (0..16)
+            .map(|i| if i % 4 < 2 && i / 4 < 2 { 0 } else { 200 })
+            .collect();
+        assert_eq!(pixels, expected);
+        // 第二页仍使用原图
+        assert_eq!(page_image(&doc, page_ids[1], b"Im1"), vec![200u8; 16]);
+
+        // mask 不在图片上时不做改动
+        let far = [MaskRect {
+            x: 200.0,
+            y: 200.0,
+            width: 20.0,
+            height: 20.0,
+        }];
+        let result = redact_page_images(&mut doc, page_ids[1], &content, &far, 90).unwrap();
+        assert_eq!(result.redacted, 0);
+    }
+
+    #[test]
+    fn test_converts_redacted_inline_image() {
+        let (mut doc, page_ids, _) = shared_image_doc();
+        let masks = [MaskRect {
+            x: 0.0,
+            y: 0.0,
+            width: 300.0,
+            height: 300.0,
+        }];
+        let mut content = b"q 10 0 0 10 5 5 cm BI /W 2 /H 1 /CS /G /BPC 8 ID ".to_vec();
+        content.extend_from_slice(b"\x80\x80\nEI Q");
 
-        doc.objects.insert(image_ref, Object::Stream(new_stream));
-        processed_any = true;
-        log::info!("图片 {:?} 脱敏完成", String::from_utf8_lossy(name));
+        let result = redact_page_images(&mut doc, page_ids[0], &content, &masks, 90).unwrap();
+        assert_eq!(result.redacted, 1);
+        let text = String::from_utf8_lossy(&result.content);
+        assert!(text.contains("/LinchInline1 Do"));
+        assert!(!text.contains("BI"));
+        assert_eq!(page_image(&doc, page_ids[0], b"LinchInline1"), vec![0, 0]);
     }
 
-    Ok(processed_any)
+    #[test]
+    fn test_unsupported_image_is_an_error() {
+        let (mut doc, page_ids, image) = shared_image_doc();
+        if let Ok(Object::Stream(stream)) = doc.get_object_mut(image) {
+            stream.dict.set("Filter", "JPXDecode");
+        }
+        let masks = [MaskRect {
+            x: 100.0,
+            y: 100.0,
+            width: 10.0,
+            height: 10.0,
+        }];
+        let content = b"q 40 0 0 40 100 100 cm /Im1 Do Q".to_vec();
+        let err = redact_page_images(&mut doc, page_ids[0], &content, &masks, 90)
+            .err()
+            .unwrap();
+        assert!(err.contains("Im1") && err.contains("JPXDecode") && err.contains("SafeRender"));
+    }
 }
@@ -0,0 +1,683 @@
+//! 图片 XObject 的解码与写回
+//!
+//! 把图片数据解码为按行打包的取样值，保留原有的颜色空间、位深度和 /Decode，
+//! 修改后按原图的压缩方式写回等价的流：DCTDecode 重新编码为 JPEG，CCITTFaxDecode
+//! 重新编码为 Group 4，其余压缩方式统一写为 FlateDecode。
+//! JBIG2 和 JPEG 2000 没有可用的解码器，解码时返回错误。
+
+use fax::decoder::{decode_g3, decode_g4, pels};
+use fax::encoder::Encoder;
+use fax::{Color, VecWriter};
+use image::codecs::jpeg::JpegEncoder;
+use image::ExtendedColorType;
+use linch_pdf::PixelRect;
+use lopdf::{dictionary, Dictionary, Document, Object, Stream};
+
+/// 解码后的图片取样
+pub struct ImageData {
+    pub width: u32,
+    pub height: u32,
+    /// 每像素分量数
+    components: usize,
+    /// 每分量位数（1、2、4、8 或 16）
+    bits: u8,
+    /// 按行打包的取样值，每行按字节对齐
+    data: Vec<u8>,
+    model: ColorModel,
+    /// 每个分量的 /Decode 范围
+    decode: Vec<(f32, f32)>,
+    encoding: Encoding,
+}
+
+/// 写回时使用的压缩方式
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Encoding {
+    Flate,
+    Jpeg,
+    Fax { black_is_1: bool },
+}
+
+/// 用于确定黑色取样值的颜色模型
+#[derive(Debug, Clone)]
+enum ColorModel {
+    /// 模板图片或模板遮罩（ImageMask）
+    Stencil,
+    Gray,
+    Rgb,
+    Cmyk,
+    Indexed {
+        base: Box<ColorModel>,
+        hival: usize,
+        lookup: Vec<u8>,
+    },
+    /// 可以解码但无法确定黑色的颜色空间（Lab、Separation、DeviceN 等）
+    Other(String),
+}
+
+impl ColorModel {
+    /// 按颜色空间对象解析颜色模型与分量数
+    fn parse(doc: &Document, cs: &Object) -> Result<(Self, usize), String> {
+        match resolve(doc, cs) {
+            Object::Name(name) => match name.as_slice() {
+                b"DeviceGray" | b"G" | b"CalGray" => Ok((ColorModel::Gray, 1)),
+                b"DeviceRGB" | b"RGB" | b"CalRGB" => Ok((ColorModel::Rgb, 3)),
+                b"DeviceCMYK" | b"CMYK" => Ok((ColorModel::Cmyk, 4)),
+                other => Err(format!("颜色空间 {}", String::from_utf8_lossy(other))),
+            },
+            Object::Array(items) => {
+                let family = items.first().and_then(|o| o.as_name().ok()).unwrap_or(b"");
+                match family {
+                    b"CalGray" => Ok((ColorModel::Gray, 1)),
+                    b"CalRGB" => Ok((ColorModel::Rgb, 3)),
+                    b"Lab" => Ok((ColorModel::Other("Lab".to_string()), 3)),
+                    b"ICCBased" => {
+                        let profile = items
+                            .get(1)
+                            .map(|o| resolve(doc, o))
+                            .and_then(|o| o.as_stream().ok())
+                            .ok_or("ICCBased 颜色空间缺少配置文件")?;
+                        match profile.dict.get(b"N").and_then(Object::as_i64) {
+                            Ok(1) => Ok((ColorModel::Gray, 1)),
+                            Ok(3) => Ok((ColorModel::Rgb, 3)),
+                            Ok(4) => Ok((ColorModel::Cmyk, 4)),
+                            _ => match profile.dict.get(b"Alternate") {
+                                Ok(alternate) => ColorModel::parse(doc, alternate),
+                                Err(_) => Err("ICCBased 颜色空间分量数无效".to_string()),
+                            },
+                        }
+                    }
+                    b"Indexed" | b"I" => {
+                        let [_, base, hival, lookup] = items.as_slice() else {
+                            return Err("Indexed 颜色空间格式无效".to_string());
+                        };
+                        let (base, _) = ColorModel::parse(doc, base)?;
+                        let hival = resolve(doc, hival).as_i64().unwrap_or(0).clamp(0, 255);
+                        let lookup = match resolve(doc, lookup) {
+                            Object::String(bytes, _) => bytes.clone(),
+                            Object::Stream(stream) => transport_decode(stream)?,
+                            _ => return Err("Indexed 颜色空间缺少调色板".to_string()),
+                        };
+                        Ok((
+                            ColorModel::Indexed {
+                                base: Box::new(base),
+                                hival: hival as usize,
+                                lookup,
+                            },
+                            1,
+                        ))
+                    }
+                    b"Separation" => Ok((ColorModel::Other("Separation".to_string()), 1)),
+                    b"DeviceN" => {
+                        let count = items
+                            .get(1)
+                            .map(|o| resolve(doc, o))
+                            .and_then(|o| o.as_array().ok())
+                            .map_or(0, Vec::len);
+                        Ok((ColorModel::Other("DeviceN".to_string()), count.max(1)))
+                    }
+                    // 数组形式的设备颜色空间，如 [/DeviceRGB]
+                    _ if items.len() == 1 => ColorModel::parse(doc, &items[0]),
+                    other => Err(format!("颜色空间 {}", String::from_utf8_lossy(other))),
+                }
+            }
+            _ => Err("颜色空间无效".to_string()),
+        }
+    }
+
+    fn components(&self) -> usize {
+        match self {
+            ColorModel::Stencil | ColorModel::Gray | ColorModel::Indexed { .. } => 1,
+            ColorModel::Rgb => 3,
+            ColorModel::Cmyk => 4,
+            ColorModel::Other(_) => 0,
+        }
+    }
+
+    /// 颜色分量值（0-1）的亮度
+    fn luminance(&self, values: &[f32]) -> f32 {
+        match (self, values) {
+            (ColorModel::Rgb, [r, g, b, ..]) => 0.299 * r + 0.587 * g + 0.114 * b,
+            (ColorModel::Cmyk, [c, m, y, k, ..]) => {
+                1.0 - (0.3 * c + 0.59 * m + 0.11 * y + k).min(1.0)
+            }
+            (_, [v, ..]) => *v,
+            _ => 1.0,
+        }
+    }
+
+    /// 显示为黑色的颜色分量值；索引颜色取调色板中最暗的一项
+    fn black(&self) -> Result<Vec<f32>, String> {
+        match self {
+            // 模板取样值 0 表示按填充色绘制
+            ColorModel::Stencil | ColorModel::Gray => Ok(vec![0.0]),
+            ColorModel::Rgb => Ok(vec![0.0; 3]),
+            ColorModel::Cmyk => Ok(vec![0.0, 0.0, 0.0, 1.0]),
+            ColorModel::Indexed {
+                base,
+                hival,
+                lookup,
+            } => {
+                let width = base.components();
+                if width == 0 {
+                    return Err("Indexed 颜色空间的基础颜色空间无法确定黑色".to_string());
+                }
+                let darkest = (0..=*hival)
+                    .filter_map(|index| {
+                        let entry = lookup.get(index * width..(index + 1) * width)?;
+                        let values: Vec<f32> = entry.iter().map(|&b| b as f32 / 255.0).collect();
+                        Some((index, base.luminance(&values)))
+                    })
+                    .min_by(|a, b| a.1.total_cmp(&b.1))
+                    .map(|(index, _)| index)
+                    .ok_or("Indexed 调色板为空")?;
+                Ok(vec![darkest as f32])
+            }
+            ColorModel::Other(name) => Err(format!("无法确定 {} 颜色空间中的黑色", name)),
+        }
+    }
+}
+
+impl ImageData {
+    /// 解码图片流
+    pub fn decode(doc: &Document, stream: &Stream) -> Result<Self, String> {
+        let dict = &stream.dict;
+        let width = integer(dict, b"Width").ok_or("缺少 Width")?;
+        let height = integer(dict, b"Height").ok_or("缺少 Height")?;
+        let stencil = matches!(dict.get(b"ImageMask"), Ok(Object::Boolean(true)));
+
+        let filters = filter_chain(dict)?;
+        if let Some((name, _)) = filters
+            .iter()
+            .find(|(name, _)| matches!(name.as_slice(), b"JBIG2Decode" | b"JPXDecode"))
+        {
+            return Err(format!("不支持 {} 压缩", String::from_utf8_lossy(name)));
+        }
+
+        let (model, components) = if stencil {
+            (ColorModel::Stencil, 1)
+        } else {
+            let cs = dict.get(b"ColorSpace").map_err(|_| "缺少 ColorSpace")?;
+            ColorModel::parse(doc, cs)?
+        };
+        let bits = if stencil {
+            1
+        } else {
+            integer(dict, b"BitsPerComponent").ok_or("缺少 BitsPerComponent")? as u8
+        };
+        if !matches!(bits, 1 | 2 | 4 | 8 | 16) {
+            return Err(format!("不支持的位深度: {}", bits));
+        }
+
+        let mut data = stream.content.clone();
+        let mut encoding = Encoding::Flate;
+        for (index, (name, params)) in filters.iter().enumerate() {
+            let last = index + 1 == filters.len();
+            data = match name.as_slice() {
+                b"FlateDecode" | b"LZWDecode" | b"ASCII85Decode" => {
+                    lopdf_decode(name, params.as_ref(), data)?
+                }
+                b"ASCIIHexDecode" => ascii_hex(&data),
+                b"RunLengthDecode" => run_length(&data),
+                b"DCTDecode" if last => {
+                    if bits != 8 || !matches!(components, 1 | 3) {
+                        return Err(format!("不支持 {} 分量的 JPEG 图片", components));
+                    }
+                    encoding = Encoding::Jpeg;
+                    let decoded = image::load_from_memory(&data).map_err(|e| e.to_string())?;
+                    if components == 1 {
+                        decoded.to_luma8().into_raw()
+                    } else {
+                        decoded.to_rgb8().into_raw()
+                    }
+                }
+                b"CCITTFaxDecode" if last => {
+                    if bits != 1 || components != 1 {
+                        return Err("CCITT 图片必须为 1 位单分量".to_string());
+                    }
+                    let black_is_1 = matches!(
+                        params.as_ref().and_then(|p| p.get(b"BlackIs1").ok()),
+                        Some(Object::Boolean(true))
+                    );
+                    encoding = Encoding::Fax { black_is_1 };
+                    ccitt_decode(&data, params.as_ref(), width, height)?
+                }
+                other => return Err(format!("不支持 {} 压缩", String::from_utf8_lossy(other))),
+            };
+        }
+
+        let row = row_bytes(width, components, bits);
+        if data.len() < row * height as usize {
+            return Err("图片数据长度不足".to_string());
+        }
+        data.truncate(row * height as usize);
+
+        let max = ((1u32 << bits) - 1) as f32;
+        let default_decode = match model {
+            ColorModel::Indexed { .. } => (0.0, max),
+            _ => (0.0, 1.0),
+        };
+        let decode = match dict.get(b"Decode") {
+            Ok(Object::Array(values)) if values.len() >= components * 2 => values
+                .chunks(2)
+                .take(components)
+                .map(|pair| {
+                    (
+                        pair[0].as_float().unwrap_or(default_decode.0),
+                        pair[1].as_float().unwrap_or(default_decode.1),
+                    )
+                })
+                .collect(),
+            _ => vec![default_decode; components],
+        };
+
+        Ok(Self {
+            width,
+            height,
+            components,
+            bits,
+            data,
+            model,
+            decode,
+            encoding,
+        })
+    }
+
+    /// 显示为黑色（模板为"绘制"）的取样值
+    pub fn black(&self) -> Result<Vec<u16>, String> {
+        Ok(self.samples(&self.model.black()?))
+    }
+
+    /// 遮罩中表示不透明的取样值：软遮罩为 1，模板遮罩为绘制
+    pub fn opaque(&self) -> Vec<u16> {
+        match self.model {
+            ColorModel::Stencil => self.samples(&[0.0]),
+            _ => self.samples(&[1.0]),
+        }
+    }
+
+    /// 颜色分量值按 /Decode 换算为取样值
+    fn samples(&self, values: &[f32]) -> Vec<u16> {
+        let max = ((1u32 << self.bits) - 1) as f32;
+        values
+            .iter()
+            .zip(&self.decode)
+            .map(|(&value, &(low, high))| {
+                let t = if (high - low).abs() < f32::EPSILON {
+                    0.0
+                } else {
+                    (value - low) / (high - low)
+                };
+                (t * max).round().clamp(0.0, max) as u16
+            })
+            .collect()
+    }
+
+    /// 把区域内的像素设为给定取样值
+    pub fn fill(&mut self, rect: &PixelRect, samples: &[u16]) {
+        let row = row_bytes(self.width, self.components, self.bits);
+        for y in rect.top.min(self.height)..rect.bottom.min(self.height) {
+            let line = &mut self.data[y as usize * row..(y as usize + 1) * row];
+            for x in rect.left.min(self.width)..rect.right.min(self.width) {
+                for (c, &value) in samples.iter().enumerate().take(self.components) {
+                    let index = x as usize * self.components + c;
+                    set_sample(line, index, self.bits, value);
+                }
+            }
+        }
+    }
+
+    /// 按原图的压缩方式写回，`dict` 为原图字典
+    pub fn encode(self, mut dict: Dictionary, jpeg_quality: u8) -> Result<Stream, String> {
+        dict.remove(b"Filter");
+        dict.remove(b"DecodeParms");
+        dict.remove(b"Length");
+
+        match self.encoding {
+            Encoding::Jpeg => {
+                let color = if self.components == 1 {
+                    ExtendedColorType::L8
+                } else {
+                    ExtendedColorType::Rgb8
+                };
+                let mut jpeg = Vec::new();
+                JpegEncoder::new_with_quality(&mut jpeg, jpeg_quality.clamp(1, 100))
+                    .encode(&self.data, self.width, self.height, color)
+                    .map_err(|e| format!("JPEG 编码失败: {}", e))?;
+                dict.set("Filter", "DCTDecode");
+                Ok(Stream::new(dict, jpeg).with_compression(false))
+            }
+            Encoding::Fax { black_is_1 } => {
+                let data = ccitt_encode(&self.data, self.width, self.height, black_is_1)?;
+                dict.set("Filter", "CCITTFaxDecode");
+                dict.set(
+                    "DecodeParms",
+                    dictionary! {
+                        "K" => -1,
+                        "Columns" => self.width as i64,
+                        "Rows" => self.height as i64,
+                        "BlackIs1" => black_is_1,
+                    },
+                );
+                Ok(Stream::new(dict, data).with_compression(false))
+            }
+            Encoding::Flate => {
+                let mut stream = Stream::new(dict, self.data);
+                let _ = stream.compress();
+                Ok(stream)
+            }
+        }
+    }
+}
+
+fn row_bytes(width: u32, components: usize, bits: u8) -> usize {
+    (width as usize * components * bits as usize).div_ceil(8)
+}
+
+fn set_sample(line: &mut [u8], index: usize, bits: u8, value: u16) {
+    match bits {
+        16 => line[index * 2..index * 2 + 2].copy_from_slice(&value.to_be_bytes()),
+        8 => line[index] = value as u8,
+        _ => {
+            let bit = index * bits as usize;
+            let shift = 8 - bits as usize - bit % 8;
+            let mask = (((1u16 << bits) - 1) as u8) << shift;
+            let byte = &mut line[bit / 8];
+            *byte = (*byte & !mask) | (((value as u8) << shift) & mask);
+        }
+    }
+}
+
+/// 压缩方式列表及各自的参数（按解码顺序）
+type FilterChain = Vec<(Vec<u8>, Option<Dictionary>)>;
+
+fn filter_chain(dict: &Dictionary) -> Result<FilterChain, String> {
+    let params = |index: usize| match dict.get(b"DecodeParms") {
+        Ok(Object::Dictionary(params)) if index == 0 => Some(params.clone()),
+        Ok(Object::Array(items)) => items.get(index).and_then(|p| p.as_dict().ok()).cloned(),
+        _ => None,
+    };
+    match dict.get(b"Filter") {
+        Err(_) => Ok(Vec::new()),
+        Ok(Object::Name(name)) => Ok(vec![(name.clone(), params(0))]),
+        Ok(Object::Array(names)) => names
+            .iter()
+            .enumerate()
+            .map(|(index, name)| match name.as_name() {
+                Ok(name) => Ok((name.to_vec(), params(index))),
+                Err(_) => Err("压缩方式无效".to_string()),
+            })
+            .collect(),
+        Ok(_) => Err("压缩方式无效".to_string()),
+    }
+}
+
+/// 解码调色板等非图片流
+fn transport_decode(stream: &Stream) -> Result<Vec<u8>, String> {
+    let mut data = stream.content.clone();
+    for (name, params) in filter_chain(&stream.dict)? {
+        data = match name.as_slice() {
+            b"ASCIIHexDecode" => ascii_hex(&data),
+            b"RunLengthDecode" => run_length(&data),
+            _ => lopdf_decode(&name, params.as_ref(), data)?,
+        };
+    }
+    Ok(data)
+}
+
+/// 使用 lopdf 解码单个压缩方式（FlateDecode、LZWDecode、ASCII85Decode）
+fn lopdf_decode(
+    name: &[u8],
+    params: Option<&Dictionary>,
+    data: Vec<u8>,
+) -> Result<Vec<u8>, String> {
+    let mut dict = dictionary! { "Filter" => Object::Name(name.to_vec()) };
+    if let Some(params) = params {
+        dict.set("DecodeParms", params.clone());
+    }
+    Stream::new(dict, data)
+        .decompressed_content()
+        .map_err(|e| format!("{} 解码失败: {}", String::from_utf8_lossy(name), e))
+}
+
+fn ascii_hex(data: &[u8]) -> Vec<u8> {
+    let digits: Vec<u8> = data
+        .iter()
+        .take_while(|&&b| b != b'>')
+        .filter_map(|&b| (b as char).to_digit(16).map(|d| d as u8))
+        .collect();
+    // 奇数个数字时最后一位补 0
+    digits
+        .chunks(2)
+        .map(|pair| pair[0] << 4 | pair.get(1).copied().unwrap_or(0))
+        .collect()
+}
+
+fn run_length(data: &[u8]) -> Vec<u8> {
+    let mut output = Vec::new();
+    let mut pos = 0;
+    while let Some(&length) = data.get(pos) {
+        match length {
+            128 => break,
+            0..=127 => {
+                let end = (pos + 2 + length as usize).min(data.len());
+                output.extend_from_slice(&data[pos + 1..end]);
+                pos = end;
+            }
+            _ => {
+                if let Some(&byte) = data.get(pos + 1) {
+                    output.resize(output.len() + 257 - length as usize, byte);
+                }
+                pos += 2;
+            }
+        }
+    }
+    output
+}
+
+/// 解码 CCITT 传真数据为 1 位取样（BlackIs1 为 false 时 0 表示黑色）
+fn ccitt_decode(
+    data: &[u8],
+    params: Option<&Dictionary>,
+    width: u32,
+    height: u32,
+) -> Result<Vec<u8>, String> {
+    let param = |key: &[u8]| params.and_then(|p| p.get(key).ok());
+    let k = param(b"K").and_then(|o| o.as_i64().ok()).unwrap_or(0);
+    let columns = param(b"Columns")
+        .and_then(|o| o.as_i64().ok())
+        .unwrap_or(1728);
+    let black_is_1 = matches!(param(b"BlackIs1"), Some(Object::Boolean(true)));
+    if matches!(param(b"EncodedByteAlign"), Some(Object::Boolean(true))) {
+        return Err("不支持按字节对齐的 CCITT 数据".to_string());
+    }
+    if columns != width as i64 || width > u16::MAX as u32 || height > u16::MAX as u32 {
+        return Err("CCITT 图片尺寸无效".to_string());
+    }
+
+    let row = row_bytes(width, 1, 1);
+    let mut output = Vec::with_capacity(row * height as usize);
+    let mut push_line = |transitions: &[u16]| {
+        if output.len() >= row * height as usize {
+            return;
+        }
+        let mut line = vec![0u8; row];
+        for (x, color) in pels(transitions, width as u16).enumerate() {
+            if (color == Color::Black) == black_is_1 {
+                line[x / 8] |= 0x80 >> (x % 8);
+            }
+        }
+        output.extend(line);
+    };
+    let decoded = match k {
+        k if k < 0 => decode_g4(
+            data.iter().copied(),
+            width as u16,
+            Some(height as u16),
+            &mut push_line,
+        ),
+        0 => decode_g3(data.iter().copied(), &mut push_line),
+        _ => return Err("不支持 CCITT 混合编码（K > 0）".to_string()),
+    };
+    if decoded.is_none() && output.is_empty() {
+        return Err("CCITT 数据解码失败".to_string());
+    }
+    Ok(output)
+}
+
+/// 把 1 位取样编码为 CCITT Group 4
+fn ccitt_encode(data: &[u8], width: u32, height: u32, black_is_1: bool) -> Result<Vec<u8>, String> {
+    let row = row_bytes(width, 1, 1);
+    let mut encoder = Encoder::new(VecWriter::new());
+    for y in 0..height as usize {
+        let line = &data[y * row..(y + 1) * row];
+        let colors = (0..width as usize).map(|x| {
+            let bit = line[x / 8] & (0x80 >> (x % 8)) != 0;
+            if bit == black_is_1 {
+                Color::Black
+            } else {
+                Color::White
+            }
+        });
+        encoder
+            .encode_line(colors, width as u16)
+            .map_err(|_| "CCITT 编码失败".to_string())?;
+    }
+    let writer = encoder.finish().map_err(|_| "CCITT 编码失败".to_string())?;
+    Ok(writer.finish())
+}
+
+fn integer(dict: &Dictionary, key: &[u8]) -> Option<u32> {
+    dict.get(key)
+        .ok()
+        .and_then(|o| o.as_i64().ok())
+        .and_then(|v| u32::try_from(v).ok())
+}
+
+fn resolve<'a>(doc: &'a Document, obj: &'a Object) -> &'a Object {
+    match obj {
+        Object::Reference(id) => doc.get_object(*id).unwrap_or(obj),
+        _ => obj,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rect(left: u32, top: u32, right: u32, bottom: u32) -> PixelRect {
+        PixelRect {
+            left,
+            top,
+            right,
+            bottom,
+        }
+    }
+
+    #[test]
+    fn test_indexed_image_uses_darkest_palette_entry() {
+        let mut doc = Document::with_version("1.5");
+        // 2 位索引图片，调色板：白、红、黑、绿
+        let palette = vec![255, 255, 255, 255, 0, 0, 0, 0, 0, 0, 255, 0];
+        let mut stream = Stream::new(
+            dictionary! {
+                "Subtype" => "Image",
+                "Width" => 4,
+                "Height" => 2,
+                "BitsPerComponent" => 2,
+                "ColorSpace" => vec![
+                    "Indexed".into(),
+                    "DeviceRGB".into(),
+                    3.into(),
+                    Object::string_literal(palette),
+                ],
+            },
+            vec![0b0001_1011, 0b0000_0000],
+        );
+        stream.compress().unwrap();
+        let id = doc.add_object(stream);
+        let stream = doc.get_object(id).unwrap().as_stream().unwrap();
+
+        let mut image = ImageData::decode(&doc, stream).unwrap();
+        assert_eq!(image.black().unwrap(), vec![2]);
+        image.fill(&rect(1, 0, 3, 2), &image.black().unwrap());
+        assert_eq!(image.data, vec![0b0010_1011, 0b0010_1000]);
+    }
+
+    #[test]
+    fn test_decode_array_and_cmyk() {
+        let doc = Document::with_version("1.5");
+        // /Decode [1 0] 的灰度图片，黑色对应取样值 255
+        let gray = Stream::new(
+            dictionary! {
+                "Width" => 2,
+                "Height" => 1,
+                "BitsPerComponent" => 8,
+                "ColorSpace" => "DeviceGray",
+                "Decode" => vec![1.into(), 0.into()],
+                "Filter" => "ASCIIHexDecode",
+            },
+            b"1020>".to_vec(),
+        );
+        let image = ImageData::decode(&doc, &gray).unwrap();
+        assert_eq!(image.data, vec![0x10, 0x20]);
+        assert_eq!(image.black().unwrap(), vec![255]);
+
+        let cmyk = Stream::new(
+            dictionary! {
+                "Width" => 1,
+                "Height" => 1,
+                "BitsPerComponent" => 8,
+                "ColorSpace" => "DeviceCMYK",
+                "Filter" => "RunLengthDecode",
+            },
+            vec![3, 1, 2, 3, 4, 128],
+        );
+        let image = ImageData::decode(&doc, &cmyk).unwrap();
+        assert_eq!(image.black().unwrap(), vec![0, 0, 0, 255]);
+    }
+
+    #[test]
+    fn test_ccitt_round_trip() {
+        let doc = Document::with_version("1.5");
+        let (width, height) = (16u32, 4u32);
+        // 左半边黑、右半边白（BlackIs1 为 false 时 0 表示黑色）
+        let pixels = [0x00, 0xff].repeat(height as usize);
+        let encoded = ccitt_encode(&pixels, width, height, false).unwrap();
+        let stream = Stream::new(
+            dictionary! {
+                "Width" => width as i64,
+                "Height" => height as i64,
+                "ImageMask" => true,
+                "Filter" => "CCITTFaxDecode",
+                "DecodeParms" => dictionary! { "K" => -1, "Columns" => width as i64 },
+            },
+            encoded,
+        );
+
+        let mut image = ImageData::decode(&doc, &stream).unwrap();
+        assert_eq!(image.data, pixels);
+        // 模板图片中"绘制"为取样值 0
+        image.fill(&rect(8, 0, 16, 1), &image.black().unwrap());
+        let written = image.encode(stream.dict.clone(), 90).unwrap();
+        let reread = ImageData::decode(&doc, &written).unwrap();
+        assert_eq!(&reread.data[..2], &[0x00, 0x00]);
+        assert_eq!(&reread.data[2..], &pixels[2..]);
+    }
+
+    #[test]
+    fn test_rejects_jbig2() {
+        let doc = Document::with_version("1.5");
+        let stream = Stream::new(
+            dictionary! {
+                "Width" => 1,
+                "Height" => 1,
+                "BitsPerComponent" => 1,
+                "ColorSpace" => "DeviceGray",
+                "Filter" => "JBIG2Decode",
+            },
+            vec![0],
+        );
+        let err = ImageData::decode(&doc, &stream).err().unwrap();
+        assert!(err.contains("JBIG2Decode"));
+    }
+}
@@ -4,84 +4,138 @@ mod detection;
 #[allow(dead_code)]
 mod forms;
 mod image;
+mod image_codec;
 mod metadata;
+#[cfg(feature = "gui")]
+mod queue;
 mod safe_render;
+mod task;
 mod text;
 #[allow(dead_code)]
 mod types;
 mod utils;
 
+#[cfg(feature = "gui")]
+pub use queue::{
+    cancel_job, clear_finished_jobs, enqueue_pdfs, get_queue, init_queue, pause_queue,
+    resume_queue, retry_job, QueueEvent,
+};
 pub use types::{
-    DetectionHit, FileProcessRequest, Mask, PageContentType, PdfAnalysis, ProcessRequest,
-    ProcessResult, RedactionMode, Rule,
+    CleaningOptions, ColorMode, DetectionBbox, DetectionHit, DetectionProgress, FileProcessRequest,
+    ImageEncoding, Mask, PageContentType, ProcessOptions, RedactionMode, RenderConfig,
 };
+#[cfg(feature = "gui")]
+pub use types::{PdfAnalysis, ProcessRequest, ProcessResult};
 
+use linch_core::rules::CompiledRuleSet;
+use linch_core::VerifyOptions;
+use linch_rules::{Rule, RuleCompileError};
+use linch_verify::{PageRecognizer, VerifyResult};
 use lopdf::{Document, Object, Stream};
+use std::cell::OnceCell;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+// 桌面端：批量处理、检测进度事件与取消
+#[cfg(feature = "gui")]
+use serde::Serialize;
+#[cfg(feature = "gui")]
 use std::fs;
-use std::path::Path;
-
+#[cfg(feature = "gui")]
+use std::sync::atomic::{AtomicBool, Ordering};
+#[cfg(feature = "gui")]
+use std::sync::{Arc, Mutex, MutexGuard};
+#[cfg(feature = "gui")]
+use tauri::Emitter;
+
+pub use detection::detect_sensitive_content_with_progress;
+use detection::detect_with_matched_texts;
+#[cfg(feature = "gui")]
 use detection::{analyze_pdf_file, detect_sensitive_content_in_pdf};
 use image::redact_page_images;
-use text::{add_black_overlay, process_content_stream};
+use safe_render::SourcePdf;
+pub(crate) use task::run_pdf_task;
+#[cfg(feature = "gui")]
+pub(crate) use task::{describe_task_error, finish_task, task_config, FileResult, RulePdf};
+use text::{add_black_overlay, process_annotation_appearances, process_content_stream};
 use utils::{
     convert_masks_to_pdf_coords_with_rotation, detect_page_content_type,
     get_media_box_with_rotation, get_page_content,
 };
 
 /// 应用清理选项
-fn apply_cleaning(doc: &mut Document, cleaning: &types::CleaningOptions) -> Result<(), String> {
+///
+/// 单项清理失败只记录警告，返回实际执行的清理动作（`CleanResult.details`）。
+fn apply_cleaning(
+    doc: &mut Document,
+    cleaning: &types::CleaningOptions,
+) -> Result<Vec<String>, String> {
+    let mut actions = metadata::CleanResult::new();
+
     if cleaning.document_info {
-        if let Err(e) = metadata::clean_info_dict(doc) {
-            log::warn!("清理文档信息失败: {}", e);
+        match metadata::clean_info_dict(doc) {
+            Ok(result) => actions.merge(result),
+            Err(e) => log::warn!("清理文档信息失败: {}", e),
         }
     }
 
     if cleaning.xmp_metadata {
-        if let Err(e) = metadata::clean_xmp_metadata(doc) {
-            log::warn!("清理 XMP 元数据失败: {}", e);
+        match metadata::clean_xmp_metadata(doc) {
+            Ok(result) => actions.merge(result),
+            Err(e) => log::warn!("清理 XMP 元数据失败: {}", e),
         }
     }
 
     if cleaning.hidden_data {
-        if let Err(e) = metadata::remove_hidden_data(doc) {
-            log::warn!("清理隐藏数据失败: {}", e);
+        match metadata::remove_hidden_data(doc) {
+            Ok(result) => actions.merge(result),
+            Err(e) => log::warn!("清理隐藏数据失败: {}", e),
         }
     }
 
     if cleaning.javascript {
-        if let Err(e) = metadata::remove_javascript(doc) {
-            log::warn!("清理 JavaScript 失败: {}", e);
+        match metadata::remove_javascript(doc) {
+            Ok(result) => actions.merge(result),
+            Err(e) => log::warn!("清理 JavaScript 失败: {}", e),
         }
     }
 
     if cleaning.attachments {
-        if let Err(e) = metadata::remove_attachments(doc) {
-            log::warn!("清理附件失败: {}", e);
+        match metadata::remove_attachments(doc) {
+            Ok(result) => actions.merge(result),
+            Err(e) => log::warn!("清理附件失败: {}", e),
         }
     }
 
     if cleaning.forms {
-        if let Err(e) = forms::remove_all_forms(doc) {
-            log::warn!("清理表单失败: {}", e);
+        match forms::remove_all_forms(doc) {
+            Ok(result) => actions.merge(result),
+            Err(e) => log::warn!("清理表单失败: {}", e),
         }
     }
 
     if cleaning.annotations {
-        if let Err(e) = annotations::remove_all_annotations_from_document(doc) {
-            log::warn!("清理注释失败: {}", e);
+        match annotations::remove_all_annotations_from_document(doc) {
+            Ok(result) => actions.merge(result),
+            Err(e) => log::warn!("清理注释失败: {}", e),
         }
     }
 
-    Ok(())
+    Ok(actions.details)
 }
 
-/// 对页面进行脱敏处理
+/// 对页面进行脱敏处理，返回该页实际使用的脱敏模式
+///
+/// `source` 与 `page_index` 指向原文件中的同一页，SafeRender 模式用 pdfium 渲染该页。
 fn redact_page(
     doc: &mut Document,
     page_id: lopdf::ObjectId,
+    source: &SourcePdf,
+    page_index: usize,
     masks: &[Mask],
     mode: &RedactionMode,
-) -> Result<(), String> {
+    render: &RenderConfig,
+) -> Result<RedactionMode, String> {
     let (llx, lly, urx, ury, rotation) = get_media_box_with_rotation(doc, page_id);
     let media_box = (llx, lly, urx, ury);
     log::info!("MediaBox: {:?}, 旋转: {}°", media_box, rotation);
@@ -94,7 +148,7 @@ fn redact_page(
     let page_type = detect_page_content_type(&content_data);
     log::info!("页面类型检测: {:?}", page_type);
 
-    let effective_mode = match mode {
+    let mut effective_mode = match mode {
         RedactionMode::Auto => {
             match page_type {
                 PageContentType::Text => RedactionMode::TextReplace,
@@ -110,11 +164,61 @@ fn redact_page(
 
     log::info!("使用脱敏模式: {:?}", effective_mode);
 
+    if effective_mode == RedactionMode::ImageMode {
+        // mask 下没有图片时不能退回黑框覆盖：黑框下的文字层仍可提取
+        let redacted = redact_page_images(
+            doc,
+            page_id,
+            &content_data,
+            &mask_rects,
+            render.jpeg_quality,
+        )
+        .and_then(|result| match result.redacted {
+            0 => Err(
+                "遮罩区域内没有可脱敏的图片，图片模式无法移除该区域的内容，请改用 SafeRender 模式"
+                    .to_string(),
+            ),
+            _ => Ok(result),
+        });
+        match redacted {
+            Ok(result) => {
+                // 图片上方可能叠有文字层（如 OCR 生成的不可见文字），一并删除
+                let processed_data =
+                    process_content_stream(doc, page_id, &result.content, &mask_rects)?;
+                let stream = Stream::new(lopdf::Dictionary::new(), processed_data);
+                let stream_id = doc.add_object(stream);
+                if let Ok(Object::Dictionary(ref mut dict)) = doc.get_object_mut(page_id) {
+                    dict.set(b"Contents", Object::Reference(stream_id));
+                }
+                process_annotation_appearances(doc, page_id, &mask_rects)?;
+                return Ok(RedactionMode::ImageMode);
+            }
+            // 自动模式下改为栅格化整页；明确指定图片模式时报错，由用户决定
+            Err(e) if *mode == RedactionMode::Auto => {
+                log::warn!("{}，自动改用 SafeRender", e);
+                effective_mode = RedactionMode::SafeRender;
+            }
+            Err(e) => return Err(e),
+        }
+    }
+
+    if effective_mode == RedactionMode::SafeRender {
+        // 栅格化失败时该页失败：只删除文字再画黑框会留下路径、注释等可提取的内容
+        safe_render::rasterize_page(doc, page_id, source, page_index, masks, render)
+            .map_err(|e| format!("SafeRender 栅格化失败: {}", e))?;
+        process_annotation_appearances(doc, page_id, &mask_rects)?;
+        return Ok(RedactionMode::SafeRender);
+    }
+
     match effective_mode {
-        RedactionMode::TextReplace | RedactionMode::Auto => {
-            // 先进行文字替换
-            let processed_data = process_content_stream(&content_data, &mask_rects)?;
-            // 然后添加黑框覆盖作为保险（处理编码字体等问题）
+        // Auto 已映射为具体模式，ImageMode 与 SafeRender 已在上面处理
+        RedactionMode::TextReplace
+        | RedactionMode::Auto
+        | RedactionMode::SafeRender
+        | RedactionMode::ImageMode => {
+            // 先按字体编码与字形宽度删除 mask 内的字形
+            let processed_data = process_content_stream(doc, page_id, &content_data, &mask_rects)?;
+            // 文字层已删除，再绘制黑框标示脱敏区域
             let final_data = add_black_overlay(&processed_data, &mask_rects)?;
             let stream = Stream::new(lopdf::Dictionary::new(), final_data);
             let stream_id = doc.add_object(stream);
@@ -130,78 +234,118 @@ fn redact_page(
                 dict.set(b"Contents", Object::Reference(stream_id));
             }
         }
-        RedactionMode::ImageMode => {
-            let processed = redact_page_images(doc, page_id, masks)?;
-            if !processed {
-                log::warn!("未找到可处理的图片，回退到黑框覆盖模式");
-                let processed_data = add_black_overlay(&content_data, &mask_rects)?;
-                let stream = Stream::new(lopdf::Dictionary::new(), processed_data);
-                let stream_id = doc.add_object(stream);
-                if let Ok(Object::Dictionary(ref mut dict)) = doc.get_object_mut(page_id) {
-                    dict.set(b"Contents", Object::Reference(stream_id));
-                }
-            }
-        }
-        RedactionMode::SafeRender => {
-            // SafeRender 模式需要 pdfium-render，暂时回退到黑框覆盖
-            log::warn!("SafeRender 模式尚未完全实现，回退到黑框覆盖模式");
-            let processed_data = add_black_overlay(&content_data, &mask_rects)?;
-            let stream = Stream::new(lopdf::Dictionary::new(), processed_data);
-            let stream_id = doc.add_object(stream);
-            if let Ok(Object::Dictionary(ref mut dict)) = doc.get_object_mut(page_id) {
-                dict.set(b"Contents", Object::Reference(stream_id));
-            }
-        }
     }
 
-    Ok(())
+    // 注释外观流绘制在页面内容和黑框之上，需单独删除其中的文字
+    process_annotation_appearances(doc, page_id, &mask_rects)?;
+
+    Ok(effective_mode)
 }
 
-fn process_pdf_file(
-    file_req: &FileProcessRequest,
-    output_dir: &str,
-    prefix: &str,
-    mode: &RedactionMode,
-    cleaning: &types::CleaningOptions,
-) -> Result<String, String> {
-    let input_path = Path::new(&file_req.path);
-    let stem = input_path
+/// 页面进度回调：参数为已处理和需处理的页数，返回错误时中止处理（用于取消任务）
+pub(crate) type PageProgress<'a> = &'a (dyn Fn(usize, usize) -> Result<(), String> + Sync);
+
+/// 脱敏输出路径：`<output_dir>/<prefix><文件名>.pdf`
+pub(crate) fn output_path_for(input: &Path, output_dir: &Path, prefix: &str) -> PathBuf {
+    let stem = input
         .file_stem()
         .and_then(|s| s.to_str())
         .unwrap_or("output");
-    let output_filename = format!("{}{}.pdf", prefix, stem);
-    let output_path = Path::new(output_dir).join(&output_filename);
-
-    // 检测是否需要使用 SafeRender 模式
-    let use_safe_render = should_use_safe_render(&file_req.path, &file_req.masks_by_page, mode)?;
-
-    // 用于回退的模式
-    let mut fallback_mode = mode.clone();
-
-    if use_safe_render {
-        // 尝试使用 SafeRender 模式
-        log::info!("尝试使用 SafeRender 模式处理文件: {}", file_req.path);
-        let config = safe_render::RenderConfig::default();
-        match safe_render::safe_redact_pdf(
-            &file_req.path,
-            output_path.to_str().ok_or("无效输出路径")?,
-            &file_req.masks_by_page,
-            &config,
-        ) {
-            Ok(()) => {
-                return Ok(output_path.to_string_lossy().to_string());
-            }
-            Err(e) => {
-                // SafeRender 失败，回退到 BlackOverlay 模式
-                log::warn!("SafeRender 失败: {}，回退到 BlackOverlay 模式", e);
-                fallback_mode = RedactionMode::BlackOverlay;
-            }
+    output_dir.join(format!("{}{}.pdf", prefix, stem))
+}
+
+/// 规范化路径；文件尚不存在时规范化其所在目录
+fn canonical(path: &Path) -> Result<PathBuf, String> {
+    if let Ok(path) = path.canonicalize() {
+        return Ok(path);
+    }
+    let parent = match path.parent() {
+        Some(p) if !p.as_os_str().is_empty() => p,
+        _ => Path::new("."),
+    };
+    let name = path
+        .file_name()
+        .ok_or_else(|| format!("无效路径: {}", path.display()))?;
+    parent
+        .canonicalize()
+        .map(|parent| parent.join(name))
+        .map_err(|e| format!("无法解析路径 {}: {}", path.display(), e))
+}
+
+/// 计算每个输入的输出路径，并在处理前拒绝会覆盖输入或相互覆盖的输出
+pub(crate) fn plan_outputs<P: AsRef<Path>>(
+    files: &[P],
+    output_dir: &Path,
+    prefix: &str,
+) -> Result<Vec<PathBuf>, String> {
+    let inputs = files
+        .iter()
+        .map(|file| canonical(file.as_ref()))
+        .collect::<Result<HashSet<_>, _>>()?;
+
+    let mut seen: HashMap<PathBuf, &Path> = HashMap::new();
+    let mut outputs = Vec::with_capacity(files.len());
+    for file in files {
+        let file = file.as_ref();
+        let output = output_path_for(file, output_dir, prefix);
+        let key = canonical(&output)?;
+        if inputs.contains(&key) {
+            return Err(format!(
+                "输出文件 {} 会覆盖输入文件，请更换输出目录或前缀",
+                output.display()
+            ));
+        }
+        if let Some(previous) = seen.insert(key, file) {
+            return Err(format!(
+                "{} 与 {} 的输出文件同为 {}，请分批处理或更换前缀",
+                previous.display(),
+                file.display(),
+                output.display()
+            ));
         }
+        outputs.push(output);
     }
+    Ok(outputs)
+}
+
+/// 已脱敏、尚未清理的文档
+struct RedactedPdf {
+    data: Vec<u8>,
+    /// 请求的脱敏模式
+    mode: RedactionMode,
+    /// 各页实际使用的脱敏模式（页码从 0 开始）
+    page_modes: BTreeMap<usize, RedactionMode>,
+    /// 未能完成、但不影响脱敏结果的步骤
+    warnings: Vec<String>,
+}
 
-    // 使用传统 lopdf 模式
-    let effective_mode = &fallback_mode;
-    let mut doc = Document::load(&file_req.path).map_err(|e| format!("无法加载 PDF: {}", e))?;
+/// 按遮罩脱敏并删除页面
+fn redact_pdf_pages(
+    file_req: &FileProcessRequest,
+    mode: &RedactionMode,
+    render: &RenderConfig,
+    progress: PageProgress,
+) -> Result<RedactedPdf, String> {
+    let mut doc = match Document::load(&file_req.path) {
+        Ok(doc) => doc,
+        Err(e) => {
+            // lopdf 无法解析时由 pdfium 整体重建：脱敏页栅格化，其余页面原样复制
+            log::warn!("lopdf 无法加载 PDF: {}，改用 pdfium 重建文档", e);
+            let deleted_pages = file_req
+                .pages
+                .iter()
+                .filter(|p| p.action == "delete")
+                .map(|p| p.index)
+                .collect();
+            return safe_render::safe_redact_pdf(
+                &file_req.path,
+                &file_req.masks_by_page,
+                &deleted_pages,
+                render,
+            )
+            .map_err(|render_err| format!("无法加载 PDF: {}（{}）", e, render_err));
+        }
+    };
 
     let page_ids: Vec<lopdf::ObjectId> = doc.page_iter().collect();
     let total_pages = page_ids.len();
@@ -220,6 +364,17 @@ fn process_pdf_file(
         file_req.masks_by_page.len(),
         mode
     );
+    let pdfium = OnceCell::new();
+    let source = SourcePdf::new(&file_req.path, &pdfium);
+    let total = file_req
+        .masks_by_page
+        .iter()
+        .filter(|(page_idx, masks)| **page_idx < total_pages && !masks.is_empty())
+        .count();
+    let mut done = 0;
+    let mut page_modes = BTreeMap::new();
+    progress(done, total)?;
+
     for (page_idx, masks) in &file_req.masks_by_page {
         log::info!("页面 {}: {} 个 masks", page_idx, masks.len());
         for (i, m) in masks.iter().enumerate() {
@@ -235,11 +390,13 @@ fn process_pdf_file(
         if *page_idx < page_ids.len() && !masks.is_empty() {
             let page_id = page_ids[*page_idx];
             log::info!("正在处理页面 {} (page_id: {:?})", page_idx, page_id);
-            if let Err(e) = redact_page(&mut doc, page_id, masks, effective_mode) {
-                log::warn!("脱敏处理失败 (页 {}): {}", page_idx + 1, e);
-            } else {
-                log::info!("页面 {} 处理成功", page_idx);
-            }
+            // 页面未能脱敏时整个文件失败，不输出残留敏感信息的文件
+            let page_mode = redact_page(&mut doc, page_id, &source, *page_idx, masks, mode, render)
+                .map_err(|e| format!("第 {} 页脱敏失败: {}", page_idx + 1, e))?;
+            log::info!("页面 {} 处理成功", page_idx);
+            page_modes.insert(*page_idx, page_mode);
+            done += 1;
+            progress(done, total)?;
         }
     }
 
@@ -248,113 +405,201 @@ fn process_pdf_file(
         doc.delete_pages(&[page_num]);
     }
 
+    Ok(RedactedPdf {
+        data: save_pdf(&mut doc)?,
+        mode: mode.clone(),
+        page_modes,
+        warnings: Vec::new(),
+    })
+}
+
+/// 清理脱敏后的文档并写入脱敏工具元信息，返回清理后的数据和实际执行的清理动作
+fn clean_pdf_data(
+    data: &[u8],
+    cleaning: &types::CleaningOptions,
+) -> Result<(Vec<u8>, Vec<String>), String> {
+    let mut doc = Document::load_mem(data).map_err(|e| format!("解析脱敏结果失败: {}", e))?;
+
     // 执行清理操作
-    apply_cleaning(&mut doc, cleaning)?;
+    let clean_actions = apply_cleaning(&mut doc, cleaning)?;
 
     // 设置脱敏工具元信息
     metadata::set_redaction_metadata(&mut doc)?;
 
     doc.compress();
+    Ok((save_pdf(&mut doc)?, clean_actions))
+}
 
-    log::info!("正在保存文件到: {}", output_path.display());
-    let mut file = fs::File::create(&output_path).map_err(|e| {
-        let err_msg = e.to_string();
-        log::error!("创建文件失败: {} - {}", output_path.display(), err_msg);
-        // 检测文件被占用的各种错误信息
-        // Windows: "being used by another process" / "os error 32"
-        // Linux/macOS: "resource busy" / "Permission denied"
-        let is_file_locked = err_msg.contains("denied")
-            || err_msg.contains("being used")
-            || err_msg.contains("拒绝")
-            || err_msg.contains("os error 32")
-            || err_msg.contains("resource busy");
-
-        if is_file_locked {
-            format!("文件被占用，请关闭正在使用该文件的程序（如 PDF 阅读器）后重试",)
-        } else {
-            format!("创建文件失败: {}", err_msg)
-        }
-    })?;
-
-    doc.save_to(&mut file).map_err(|e| {
+fn save_pdf(doc: &mut Document) -> Result<Vec<u8>, String> {
+    let mut data = Vec::new();
+    doc.save_to(&mut data).map_err(|e| {
         log::error!("保存 PDF 失败: {}", e);
         format!("保存 PDF 失败: {}", e)
     })?;
+    Ok(data)
+}
 
-    log::info!("文件保存成功: {}", output_path.display());
-    Ok(output_path.to_string_lossy().to_string())
+/// 按规则检测单个 PDF 的结果
+pub(crate) struct RuleDetection {
+    /// 由命中区域生成遮罩的处理请求
+    pub request: FileProcessRequest,
+    /// 命中原文，只在内存中用于校验
+    pub matched_texts: Vec<String>,
 }
 
-/// 判断是否应该使用 SafeRender 模式
-fn should_use_safe_render(
+/// 按规则检测单个 PDF，将命中区域转换为遮罩（供命令行等无界面入口使用）
+///
+/// 返回的请求交给 `task::run_pdf_task` 脱敏，命中原文用于校验输出。
+pub(crate) fn detect_with_rules(
     pdf_path: &str,
-    masks_by_page: &std::collections::BTreeMap<usize, Vec<Mask>>,
-    mode: &RedactionMode,
-) -> Result<bool, String> {
-    // 如果用户明确选择 SafeRender，直接使用
-    if *mode == RedactionMode::SafeRender {
-        return Ok(true);
-    }
-
-    // 如果用户明确选择其他模式（非 Auto），不使用 SafeRender
-    if *mode != RedactionMode::Auto {
-        return Ok(false);
+    rules: &[Rule],
+    use_ocr: bool,
+) -> Result<RuleDetection, String> {
+    let (hits, matched_texts) =
+        detect_with_matched_texts(pdf_path, rules, use_ocr, None, |_| Ok(()))?;
+
+    let mut masks_by_page: BTreeMap<usize, Vec<Mask>> = BTreeMap::new();
+    for hit in &hits {
+        masks_by_page.entry(hit.page).or_default().push(Mask {
+            x: hit.bbox.x,
+            y: hit.bbox.y,
+            width: hit.bbox.width,
+            height: hit.bbox.height,
+        });
     }
 
-    // Auto 模式：检测页面类型
-    let doc = Document::load(pdf_path).map_err(|e| format!("无法加载 PDF: {}", e))?;
-    let page_ids: Vec<lopdf::ObjectId> = doc.page_iter().collect();
+    Ok(RuleDetection {
+        request: FileProcessRequest {
+            path: pdf_path.to_string(),
+            pages: Vec::new(),
+            masks_by_page,
+            hits,
+            ocr_used: use_ocr,
+        },
+        matched_texts,
+    })
+}
 
-    for (page_idx, masks) in masks_by_page {
-        if masks.is_empty() {
-            continue;
-        }
-        if *page_idx >= page_ids.len() {
-            continue;
-        }
+/// 编译规则中的文本类规则，用于校验输出
+#[cfg(feature = "gui")]
+pub(crate) fn compile_text_rules(rules: &[Rule]) -> Result<CompiledRuleSet, String> {
+    linch_rules::to_ruleset(rules)
+        .compile()
+        .map_err(|e| describe_compile_errors(&e))
+}
 
-        let page_id = page_ids[*page_idx];
-        let content_data = get_page_content(&doc, page_id).unwrap_or_default();
-        let page_type = detect_page_content_type(&content_data);
+/// 规则编译错误的提示信息（逐条列出规则 ID）
+pub(crate) fn describe_compile_errors(errors: &[RuleCompileError]) -> String {
+    let details: Vec<String> = errors.iter().map(ToString::to_string).collect();
+    format!("规则无法编译: {}", details.join("; "))
+}
 
-        // Mixed 或 PathDrawn 类型使用 SafeRender
-        if page_type == PageContentType::Mixed || page_type == PageContentType::PathDrawn {
-            log::info!(
-                "页面 {} 类型为 {:?}，使用 SafeRender 模式",
-                page_idx,
-                page_type
-            );
-            return Ok(true);
-        }
+/// 校验脱敏输出是否仍残留敏感信息
+///
+/// 启用 OCR 抽样时使用当前 OCR 引擎识别抽样页面。
+pub(crate) fn verify_pdf_output(
+    output_path: &str,
+    ruleset: &CompiledRuleSet,
+    matched_texts: &[String],
+    options: &VerifyOptions,
+) -> VerifyResult {
+    let recognizer = detection::CurrentEngineRecognizer;
+    let recognizer: Option<&dyn PageRecognizer> = options.ocr_sample.then_some(&recognizer);
+    let result =
+        linch_verify::verify_output(output_path, ruleset, matched_texts, options, recognizer);
+
+    if !result.ok {
+        log::warn!(
+            "[Verify] {} 校验未通过，发现 {} 处可能的残留",
+            output_path,
+            result.warnings.len()
+        );
     }
+    result
+}
+
+/// 检查一批文件的输出路径，拒绝会覆盖输入或相互覆盖的输出
+#[cfg(feature = "gui")]
+pub(crate) fn plan_request_outputs(request: &ProcessRequest) -> Result<Vec<PathBuf>, String> {
+    let files: Vec<&Path> = request
+        .files
+        .iter()
+        .map(|file| Path::new(&file.path))
+        .collect();
+    plan_outputs(
+        &files,
+        Path::new(&request.options.output_directory),
+        &request.options.prefix,
+    )
+}
 
-    Ok(false)
+/// 按批次选项处理单个文件：脱敏、校验输出，并在输出文件旁写出审计记录和报告
+#[cfg(feature = "gui")]
+pub(crate) fn redact_file(
+    file_req: &FileProcessRequest,
+    options: &ProcessOptions,
+    progress: PageProgress,
+) -> Result<FileResult, String> {
+    fs::create_dir_all(&options.output_directory)
+        .map_err(|e| format!("无法创建输出目录: {}", e))?;
+    // 前端只持有脱敏后的片段，校验仅按规则重新匹配
+    run_pdf_task(file_req, options, &[], None, progress)
 }
 
+#[cfg(feature = "gui")]
 #[tauri::command]
 pub async fn process_pdfs(request: ProcessRequest) -> Result<ProcessResult, String> {
+    // 脱敏和并行处理都是阻塞操作，放到阻塞线程池，避免占用异步运行时的工作线程
+    tauri::async_runtime::spawn_blocking(move || process_files(&request))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+/// 并行处理一批文件，汇总输出、审计记录、报告和错误
+#[cfg(feature = "gui")]
+fn process_files(request: &ProcessRequest) -> Result<ProcessResult, String> {
     let mut processed_files = Vec::new();
+    let mut audit_files = Vec::new();
+    let mut report_files = Vec::new();
     let mut errors = Vec::new();
+    let mut warnings = Vec::new();
 
-    fs::create_dir_all(&request.output_directory)
+    fs::create_dir_all(&request.options.output_directory)
         .map_err(|e| format!("无法创建输出目录: {}", e))?;
-
-    for file_req in &request.files {
-        match process_pdf_file(
-            file_req,
-            &request.output_directory,
-            &request.prefix,
-            &request.mode,
-            &request.cleaning,
-        ) {
-            Ok(output_path) => {
-                processed_files.push(output_path);
+    // 不同目录下的同名文件会写到同一个输出文件，处理前拒绝整批
+    plan_request_outputs(request)?;
+
+    // 多个文件并行处理，结果按输入顺序汇总
+    let results = queue::parallel_map(&request.files, |file_req| {
+        redact_file(file_req, &request.options, &|_, _| Ok(()))
+    });
+
+    for (file_req, result) in request.files.iter().zip(results) {
+        let filename = Path::new(&file_req.path)
+            .file_name()
+            .and_then(|s| s.to_str())
+            .unwrap_or(&file_req.path);
+
+        match result {
+            Ok(result) => {
+                if !result.verify.ok {
+                    errors.push(format!(
+                        "{}: 校验发现 {} 处可能残留的敏感信息，详见报告",
+                        filename,
+                        result.verify.warnings.len()
+                    ));
+                }
+                warnings.extend(
+                    result
+                        .warnings
+                        .iter()
+                        .map(|warning| format!("{}: {}", filename, warning)),
+                );
+                processed_files.push(result.output_path);
+                audit_files.push(result.audit_path);
+                report_files.push(result.report_path);
             }
             Err(e) => {
-                let filename = Path::new(&file_req.path)
-                    .file_name()
-                    .and_then(|s| s.to_str())
-                    .unwrap_or(&file_req.path);
                 errors.push(format!("{}: {}", filename, e));
             }
         }
@@ -363,28 +608,260 @@ pub async fn process_pdfs(request: ProcessRequest) -> Result<ProcessResult, Stri
     Ok(ProcessResult {
         success: errors.is_empty(),
         processed_files,
+        audit_files,
+        report_files,
         errors,
+        warnings,
     })
 }
 
 /// 分析 PDF 文件内容
+#[cfg(feature = "gui")]
 #[tauri::command]
 pub async fn analyze_pdf(pdf_path: String) -> Result<PdfAnalysis, String> {
     analyze_pdf_file(&pdf_path)
 }
 
 /// 检测敏感内容（基于规则）
+///
+/// 传入 `detection_id` 时每处理完一页发送 `detection-progress` 事件（包含该页新增的命中），
+/// 并可通过 `cancel_detection` 取消；取消后返回错误，已上报的命中仍然有效。
+#[cfg(feature = "gui")]
 #[tauri::command]
 pub async fn detect_sensitive_content(
+    app: tauri::AppHandle,
     pdf_path: String,
     rules: Vec<Rule>,
     use_ocr: Option<bool>,
     page_indices: Option<Vec<usize>>, // 可选：指定要扫描的页面索引
+    detection_id: Option<String>,
 ) -> Result<Vec<DetectionHit>, String> {
-    detect_sensitive_content_in_pdf(
-        &pdf_path,
-        &rules,
-        use_ocr.unwrap_or(false),
-        page_indices.as_deref(),
-    )
+    let use_ocr = use_ocr.unwrap_or(false);
+    // 检测（尤其是 OCR）是阻塞操作，放到阻塞线程池，避免占用异步运行时的工作线程
+    let Some(detection_id) = detection_id else {
+        return tauri::async_runtime::spawn_blocking(move || {
+            detect_sensitive_content_in_pdf(&pdf_path, &rules, use_ocr, page_indices.as_deref())
+        })
+        .await
+        .map_err(|e| e.to_string())?;
+    };
+
+    let cancel = Arc::new(AtomicBool::new(false));
+    running_detections().insert(detection_id.clone(), cancel.clone());
+    let result = tauri::async_runtime::spawn_blocking({
+        let detection_id = detection_id.clone();
+        move || {
+            detect_sensitive_content_with_progress(
+                &pdf_path,
+                &rules,
+                use_ocr,
+                page_indices.as_deref(),
+                |progress| {
+                    if cancel.load(Ordering::Relaxed) {
+                        return Err("检测已取消".to_string());
+                    }
+                    let event = DetectionProgressEvent {
+                        detection_id: &detection_id,
+                        progress,
+                    };
+                    if let Err(e) = app.emit("detection-progress", &event) {
+                        log::warn!("[Detection] 发送检测进度失败: {}", e);
+                    }
+                    Ok(())
+                },
+            )
+        }
+    })
+    .await;
+    running_detections().remove(&detection_id);
+    result.map_err(|e| e.to_string())?
+}
+
+/// 取消正在进行的检测（处理完当前页后停止）
+#[cfg(feature = "gui")]
+#[tauri::command]
+pub fn cancel_detection(detection_id: String) -> Result<(), String> {
+    let detections = running_detections();
+    let cancel = detections
+        .get(&detection_id)
+        .ok_or_else(|| format!("检测任务不存在: {}", detection_id))?;
+    cancel.store(true, Ordering::Relaxed);
+    Ok(())
+}
+
+/// `detection-progress` 事件内容
+#[cfg(feature = "gui")]
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct DetectionProgressEvent<'a> {
+    detection_id: &'a str,
+    #[serde(flatten)]
+    progress: DetectionProgress,
+}
+
+/// 进行中的检测及其取消标记
+#[cfg(feature = "gui")]
+static RUNNING_DETECTIONS: Mutex<BTreeMap<String, Arc<AtomicBool>>> = Mutex::new(BTreeMap::new());
+
+#[cfg(feature = "gui")]
+fn running_detections() -> MutexGuard<'static, BTreeMap<String, Arc<AtomicBool>>> {
+    RUNNING_DETECTIONS
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lopdf::content::Content;
+    use lopdf::dictionary;
+    use std::fs;
+
+    /// 单页文档：(100, 100) 起 40x40 的灰度图片，图片上方叠有两个 CID 字形
+    fn image_with_text_layer() -> (Document, lopdf::ObjectId) {
+        let mut doc = Document::with_version("1.5");
+        let image = doc.add_object(Stream::new(
+            dictionary! {
+                "Type" => "XObject",
+                "Subtype" => "Image",
+                "Width" => 4,
+                "Height" => 4,
+                "ColorSpace" => "DeviceGray",
+                "BitsPerComponent" => 8,
+            },
+            vec![200u8; 16],
+        ));
+        let descendant = doc.add_object(dictionary! {
+            "Type" => "Font",
+            "Subtype" => "CIDFontType2",
+            "DW" => 1000,
+        });
+        let font = doc.add_object(dictionary! {
+            "Type" => "Font",
+            "Subtype" => "Type0",
+            "BaseFont" => "SimSun",
+            "Encoding" => "Identity-H",
+            "DescendantFonts" => vec![descendant.into()],
+        });
+        let contents = doc.add_object(Stream::new(
+            dictionary! {},
+            b"q 40 0 0 40 100 100 cm /Im1 Do Q BT /F1 10 Tf 105 120 Td <4E2D 59D3> Tj ET".to_vec(),
+        ));
+        let pages_id = doc.new_object_id();
+        let page_id = doc.add_object(dictionary! {
+            "Type" => "Page",
+            "Parent" => pages_id,
+            "MediaBox" => vec![0.into(), 0.into(), 300.into(), 300.into()],
+            "Contents" => contents,
+            "Resources" => dictionary! {
+                "Font" => dictionary! { "F1" => font },
+                "XObject" => dictionary! { "Im1" => image },
+            },
+        });
+        doc.objects.insert(
+            pages_id,
+            Object::Dictionary(dictionary! {
+                "Type" => "Pages",
+                "Kids" => vec![page_id.into()],
+                "Count" => 1,
+            }),
+        );
+        (doc, page_id)
+    }
+
+    /// 左上角原点的相对坐标
+    fn mask(left: f64, bottom: f64, size: f64) -> Mask {
+        Mask {
+            x: left / 300.0,
+            y: (300.0 - bottom - size) / 300.0,
+            width: size / 300.0,
+            height: size / 300.0,
+        }
+    }
+
+    fn redact_image_mode(
+        doc: &mut Document,
+        page_id: lopdf::ObjectId,
+        masks: &[Mask],
+    ) -> Result<RedactionMode, String> {
+        let pdfium = OnceCell::new();
+        let source = SourcePdf::new("unused.pdf", &pdfium);
+        redact_page(
+            doc,
+            page_id,
+            &source,
+            0,
+            masks,
+            &RedactionMode::ImageMode,
+            &RenderConfig::default(),
+        )
+    }
+
+    #[test]
+    fn test_image_mode_removes_text_over_image() {
+        let (mut doc, page_id) = image_with_text_layer();
+        let mode = redact_image_mode(&mut doc, page_id, &[mask(100.0, 100.0, 40.0)]).unwrap();
+        assert_eq!(mode, RedactionMode::ImageMode);
+
+        let content = get_page_content(&doc, page_id).unwrap();
+        let ops = Content::decode(&content).unwrap().operations;
+        let shown: Vec<u8> = ops
+            .iter()
+            .filter(|op| op.operator == "Tj" || op.operator == "TJ")
+            .flat_map(|op| match &op.operands[0] {
+                Object::Array(items) => items.clone(),
+                other => vec![other.clone()],
+            })
+            .filter_map(|item| item.as_str().ok().map(<[u8]>::to_vec))
+            .flatten()
+            .collect();
+        assert!(shown.is_empty(), "图片上方的文字应被删除: {:?}", shown);
+    }
+
+    #[test]
+    fn test_image_mode_without_image_under_mask_is_an_error() {
+        let (mut doc, page_id) = image_with_text_layer();
+        let err = redact_image_mode(&mut doc, page_id, &[mask(200.0, 200.0, 40.0)]).unwrap_err();
+        assert!(err.contains("SafeRender"), "{}", err);
+    }
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("linch-pdf-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_plan_outputs_rejects_overwriting_input() {
+        let dir = temp_dir("overwrite");
+        let input = dir.join("a.pdf");
+        fs::write(&input, b"%PDF").unwrap();
+
+        // 经由 `..` 指向同一目录，规范化后仍应识别为输入文件
+        let output_dir = dir.join("sub").join("..");
+        fs::create_dir_all(dir.join("sub")).unwrap();
+        let err = plan_outputs(std::slice::from_ref(&input), &output_dir, "").unwrap_err();
+        assert!(err.contains("覆盖输入文件"), "{}", err);
+
+        assert!(plan_outputs(&[input], &output_dir, "redacted_").is_ok());
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_plan_outputs_rejects_duplicate_outputs() {
+        let dir = temp_dir("duplicate");
+        fs::create_dir_all(dir.join("x")).unwrap();
+        fs::create_dir_all(dir.join("y")).unwrap();
+        let first = dir.join("x").join("report.pdf");
+        let second = dir.join("y").join("report.pdf");
+        fs::write(&first, b"%PDF").unwrap();
+        fs::write(&second, b"%PDF").unwrap();
+
+        let out = dir.join("out");
+        fs::create_dir_all(&out).unwrap();
+        let err = plan_outputs(&[first, second], &out, "redacted_").unwrap_err();
+        assert!(err.contains("redacted_report.pdf"), "{}", err);
+        let _ = fs::remove_dir_all(&dir);
+    }
 }
@@ -0,0 +1,698 @@
+//! 批量脱敏任务队列
+//!
+//! 每个文件是一个任务，由后台工作线程并行处理。队列状态持久化到磁盘，
+//! 应用重启后未完成的任务重新排队；任务状态变化和逐页进度通过回调上报给前端。
+
+use serde::{Deserialize, Serialize};
+use std::any::Any;
+use std::fs;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard, OnceLock};
+use std::thread;
+
+use super::types::{FileProcessRequest, ProcessOptions, ProcessRequest};
+use super::{plan_request_outputs, redact_file, FileResult, PageProgress};
+
+/// 工作线程数上限（每个线程同时持有一份文档和渲染结果）
+const MAX_WORKERS: usize = 4;
+
+/// 取消任务时处理流程返回的错误
+const CANCELLED: &str = "任务已取消";
+
+/// 按 CPU 核数确定工作线程数
+pub(crate) fn worker_count() -> usize {
+    thread::available_parallelism()
+        .map(|n| n.get())
+        .unwrap_or(1)
+        .clamp(1, MAX_WORKERS)
+}
+
+/// 用多个线程处理列表中的每一项，结果与输入顺序一致
+pub(crate) fn parallel_map<T, R, F>(items: &[T], f: F) -> Vec<R>
+where
+    T: Sync,
+    R: Send,
+    F: Fn(&T) -> R + Sync,
+{
+    let workers = worker_count().min(items.len());
+    if workers <= 1 {
+        return items.iter().map(f).collect();
+    }
+
+    let next = AtomicUsize::new(0);
+    let results: Mutex<Vec<Option<R>>> = Mutex::new(items.iter().map(|_| None).collect());
+    thread::scope(|scope| {
+        for _ in 0..workers {
+            scope.spawn(|| loop {
+                let index = next.fetch_add(1, Ordering::Relaxed);
+                let Some(item) = items.get(index) else {
+                    break;
+                };
+                let result = f(item);
+                results.lock().unwrap()[index] = Some(result);
+            });
+        }
+    });
+
+    results
+        .into_inner()
+        .unwrap()
+        .into_iter()
+        .map(|result| result.expect("每一项都已处理"))
+        .collect()
+}
+
+/// 任务状态
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum JobStatus {
+    Queued,
+    Running,
+    Completed,
+    Failed,
+    Cancelled,
+}
+
+impl JobStatus {
+    fn is_finished(self) -> bool {
+        matches!(
+            self,
+            JobStatus::Completed | JobStatus::Failed | JobStatus::Cancelled
+        )
+    }
+}
+
+/// 任务概况（返回给前端）
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct JobInfo {
+    pub id: u64,
+    /// 输入文件路径
+    pub path: String,
+    pub status: JobStatus,
+    /// 已执行次数（含重试）
+    pub attempts: u32,
+    pub error: Option<String>,
+    pub output_path: Option<String>,
+    pub audit_path: Option<String>,
+    pub report_path: Option<String>,
+    /// 脱敏过程中的警告（如未能迁移书签），不影响任务完成
+    #[serde(default)]
+    pub warnings: Vec<String>,
+    /// 已完成 / 需要脱敏的页数
+    pub pages_done: usize,
+    pub pages_total: usize,
+}
+
+/// 持久化的任务：概况加上重新执行所需的完整请求
+#[derive(Debug, Serialize, Deserialize, Clone)]
+struct Job {
+    #[serde(flatten)]
+    info: JobInfo,
+    file: FileProcessRequest,
+    options: ProcessOptions,
+    #[serde(skip)]
+    cancel: Arc<AtomicBool>,
+}
+
+/// 上报给前端的队列事件
+#[derive(Debug, Serialize, Clone)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum QueueEvent {
+    /// 任务状态变化
+    Job(JobInfo),
+    /// 任务逐页进度
+    Page {
+        job_id: u64,
+        done: usize,
+        total: usize,
+    },
+    /// 队列暂停或恢复
+    Queue { paused: bool },
+}
+
+/// 队列快照
+#[derive(Debug, Serialize, Clone)]
+pub struct QueueSnapshot {
+    pub paused: bool,
+    pub jobs: Vec<JobInfo>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+struct QueueState {
+    next_id: u64,
+    paused: bool,
+    jobs: Vec<Job>,
+}
+
+type Processor = dyn Fn(&FileProcessRequest, &ProcessOptions, PageProgress) -> Result<FileResult, String>
+    + Send
+    + Sync;
+type Notify = dyn Fn(&QueueEvent) + Send + Sync;
+
+struct Shared {
+    state: Mutex<QueueState>,
+    wake: Condvar,
+    store: Option<PathBuf>,
+    process: Box<Processor>,
+    notify: Box<Notify>,
+}
+
+/// 持久化的任务队列
+#[derive(Clone)]
+pub struct JobQueue {
+    shared: Arc<Shared>,
+}
+
+impl JobQueue {
+    /// 从 `store` 恢复队列并启动工作线程
+    ///
+    /// 上次退出时仍在执行的任务重新排队。
+    pub fn start(
+        store: Option<PathBuf>,
+        workers: usize,
+        notify: impl Fn(&QueueEvent) + Send + Sync + 'static,
+    ) -> Self {
+        Self::with_processor(store, workers, redact_file, notify)
+    }
+
+    fn with_processor(
+        store: Option<PathBuf>,
+        workers: usize,
+        process: impl Fn(&FileProcessRequest, &ProcessOptions, PageProgress) -> Result<FileResult, String>
+            + Send
+            + Sync
+            + 'static,
+        notify: impl Fn(&QueueEvent) + Send + Sync + 'static,
+    ) -> Self {
+        let mut state = store.as_ref().map(load_state).unwrap_or_default();
+        for job in &mut state.jobs {
+            if job.info.status == JobStatus::Running {
+                job.info.status = JobStatus::Queued;
+            }
+        }
+
+        let queue = Self {
+            shared: Arc::new(Shared {
+                state: Mutex::new(state),
+                wake: Condvar::new(),
+                store,
+                process: Box::new(process),
+                notify: Box::new(notify),
+            }),
+        };
+        for _ in 0..workers {
+            let worker = queue.clone();
+            thread::spawn(move || worker.run_worker());
+        }
+        queue
+    }
+
+    /// 每个文件加入一个任务
+    pub fn add(&self, request: ProcessRequest) -> Vec<JobInfo> {
+        let mut state = self.lock();
+        let mut added = Vec::new();
+        for file in request.files {
+            state.next_id += 1;
+            let info = JobInfo {
+                id: state.next_id,
+                path: file.path.clone(),
+                status: JobStatus::Queued,
+                attempts: 0,
+                error: None,
+                output_path: None,
+                audit_path: None,
+                report_path: None,
+                warnings: Vec::new(),
+                pages_done: 0,
+                pages_total: 0,
+            };
+            added.push(info.clone());
+            state.jobs.push(Job {
+                info,
+                file,
+                options: request.options.clone(),
+                cancel: Arc::default(),
+            });
+        }
+        self.persist(&state);
+        drop(state);
+
+        for info in &added {
+            (self.shared.notify)(&QueueEvent::Job(info.clone()));
+        }
+        self.shared.wake.notify_all();
+        added
+    }
+
+    pub fn snapshot(&self) -> QueueSnapshot {
+        let state = self.lock();
+        QueueSnapshot {
+            paused: state.paused,
+            jobs: state.jobs.iter().map(|job| job.info.clone()).collect(),
+        }
+    }
+
+    /// 取消任务：排队中的任务直接取消，执行中的任务在处理完当前页后停止
+    pub fn cancel(&self, id: u64) -> Result<JobInfo, String> {
+        self.update(id, |job| match job.info.status {
+            JobStatus::Queued => {
+                job.info.status = JobStatus::Cancelled;
+                Ok(())
+            }
+            JobStatus::Running => {
+                job.cancel.store(true, Ordering::Relaxed);
+                Ok(())
+            }
+            _ => Err("任务已结束，无法取消".to_string()),
+        })
+    }
+
+    /// 重新执行失败或已取消的任务
+    pub fn retry(&self, id: u64) -> Result<JobInfo, String> {
+        let info = self.update(id, |job| match job.info.status {
+            JobStatus::Failed | JobStatus::Cancelled => {
+                job.info.status = JobStatus::Queued;
+                job.info.error = None;
+                job.info.pages_done = 0;
+                job.info.pages_total = 0;
+                job.cancel = Arc::default();
+                Ok(())
+            }
+            _ => Err("只能重试失败或已取消的任务".to_string()),
+        })?;
+        self.shared.wake.notify_all();
+        Ok(info)
+    }
+
+    /// 暂停或恢复队列（不影响正在执行的任务）
+    pub fn set_paused(&self, paused: bool) {
+        let mut state = self.lock();
+        state.paused = paused;
+        self.persist(&state);
+        drop(state);
+
+        (self.shared.notify)(&QueueEvent::Queue { paused });
+        self.shared.wake.notify_all();
+    }
+
+    /// 移除已结束的任务
+    pub fn clear_finished(&self) -> QueueSnapshot {
+        let mut state = self.lock();
+        state.jobs.retain(|job| !job.info.status.is_finished());
+        self.persist(&state);
+        drop(state);
+        self.snapshot()
+    }
+
+    fn lock(&self) -> MutexGuard<'_, QueueState> {
+        self.shared
+            .state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// 修改指定任务，保存并上报修改后的状态
+    fn update(
+        &self,
+        id: u64,
+        change: impl FnOnce(&mut Job) -> Result<(), String>,
+    ) -> Result<JobInfo, String> {
+        let mut state = self.lock();
+        let job = state
+            .jobs
+            .iter_mut()
+            .find(|job| job.info.id == id)
+            .ok_or_else(|| format!("任务不存在: {}", id))?;
+        change(job)?;
+        let info = job.info.clone();
+        self.persist(&state);
+        drop(state);
+
+        (self.shared.notify)(&QueueEvent::Job(info.clone()));
+        Ok(info)
+    }
+
+    fn persist(&self, state: &QueueState) {
+        let Some(store) = &self.shared.store else {
+            return;
+        };
+        if let Err(e) = save_state(store, state) {
+            log::warn!("[Queue] 保存任务队列失败: {}", e);
+        }
+    }
+
+    fn run_worker(&self) {
+        loop {
+            let (id, file, options, cancel) = {
+                let mut state = self.lock();
+                let job = loop {
+                    if !state.paused {
+                        if let Some(job) = state
+                            .jobs
+                            .iter_mut()
+                            .find(|job| job.info.status == JobStatus::Queued)
+                        {
+                            break job;
+                        }
+                    }
+                    state = self
+                        .shared
+                        .wake
+                        .wait(state)
+                        .unwrap_or_else(|poisoned| poisoned.into_inner());
+                };
+                job.info.status = JobStatus::Running;
+                job.info.attempts += 1;
+                let taken = (
+                    job.info.id,
+                    job.file.clone(),
+                    job.options.clone(),
+                    job.cancel.clone(),
+                );
+                let info = job.info.clone();
+                self.persist(&state);
+                drop(state);
+                (self.shared.notify)(&QueueEvent::Job(info));
+                taken
+            };
+
+            let progress = |done: usize, total: usize| {
+                if cancel.load(Ordering::Relaxed) {
+                    return Err(CANCELLED.to_string());
+                }
+                if let Some(job) = self.lock().jobs.iter_mut().find(|job| job.info.id == id) {
+                    job.info.pages_done = done;
+                    job.info.pages_total = total;
+                }
+                (self.shared.notify)(&QueueEvent::Page {
+                    job_id: id,
+                    done,
+                    total,
+                });
+                Ok(())
+            };
+            // 处理器 panic 时只让该任务失败，不能让工作线程退出
+            let result = panic::catch_unwind(AssertUnwindSafe(|| {
+                (self.shared.process)(&file, &options, &progress)
+            }))
+            .unwrap_or_else(|payload| Err(format!("处理时发生异常: {}", panic_message(&*payload))));
+
+            // 任务执行期间可能已被清除
+            let _ = self.update(id, |job| {
+                match result {
+                    Ok(result) => {
+                        job.info.status = JobStatus::Completed;
+                        job.info.output_path = Some(result.output_path);
+                        job.info.audit_path = Some(result.audit_path);
+                        job.info.report_path = Some(result.report_path);
+                        job.info.warnings = result.warnings;
+                        job.info.error = (!result.verify.ok).then(|| {
+                            format!(
+                                "校验发现 {} 处可能残留的敏感信息，详见报告",
+                                result.verify.warnings.len()
+                            )
+                        });
+                    }
+                    Err(_) if cancel.load(Ordering::Relaxed) => {
+                        job.info.status = JobStatus::Cancelled;
+                    }
+                    Err(e) => {
+                        job.info.status = JobStatus::Failed;
+                        job.info.error = Some(e);
+                    }
+                }
+                Ok(())
+            });
+        }
+    }
+}
+
+/// 取出 panic 信息（`panic!` 的参数为字符串时）
+fn panic_message(payload: &(dyn Any + Send)) -> &str {
+    payload
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+        .unwrap_or("未知错误")
+}
+
+fn load_state(store: &PathBuf) -> QueueState {
+    let raw = match fs::read_to_string(store) {
+        Ok(raw) => raw,
+        Err(_) => return QueueState::default(),
+    };
+    serde_json::from_str(&raw).unwrap_or_else(|e| {
+        log::warn!("[Queue] 任务队列文件无法解析，已忽略: {}", e);
+        QueueState::default()
+    })
+}
+
+/// 先写临时文件再替换，避免退出时留下不完整的队列文件
+fn save_state(store: &PathBuf, state: &QueueState) -> Result<(), String> {
+    if let Some(parent) = store.parent() {
+        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let json = serde_json::to_string_pretty(state).map_err(|e| e.to_string())?;
+    let tmp = store.with_extension("json.tmp");
+    fs::write(&tmp, json).map_err(|e| e.to_string())?;
+    fs::rename(&tmp, store).map_err(|e| e.to_string())
+}
+
+static QUEUE: OnceLock<JobQueue> = OnceLock::new();
+
+/// 启动全局任务队列（应用启动时调用一次）
+pub fn init_queue(store: PathBuf, notify: impl Fn(&QueueEvent) + Send + Sync + 'static) {
+    if QUEUE
+        .set(JobQueue::start(Some(store), worker_count(), notify))
+        .is_err()
+    {
+        log::warn!("[Queue] 任务队列已初始化");
+    }
+}
+
+fn queue() -> Result<&'static JobQueue, String> {
+    QUEUE.get().ok_or_else(|| "任务队列尚未初始化".to_string())
+}
+
+/// 把一批文件加入任务队列
+///
+/// 入队前检查输出路径，避免不同目录下的同名文件在执行时相互覆盖输出。
+#[cfg_attr(feature = "gui", tauri::command)]
+pub fn enqueue_pdfs(request: ProcessRequest) -> Result<Vec<JobInfo>, String> {
+    fs::create_dir_all(&request.options.output_directory)
+        .map_err(|e| format!("无法创建输出目录: {}", e))?;
+    plan_request_outputs(&request)?;
+    Ok(queue()?.add(request))
+}
+
+#[cfg_attr(feature = "gui", tauri::command)]
+pub fn get_queue() -> Result<QueueSnapshot, String> {
+    Ok(queue()?.snapshot())
+}
+
+#[cfg_attr(feature = "gui", tauri::command)]
+pub fn cancel_job(id: u64) -> Result<JobInfo, String> {
+    queue()?.cancel(id)
+}
+
+#[cfg_attr(feature = "gui", tauri::command)]
+pub fn retry_job(id: u64) -> Result<JobInfo, String> {
+    queue()?.retry(id)
+}
+
+#[cfg_attr(feature = "gui", tauri::command)]
+pub fn pause_queue() -> Result<(), String> {
+    queue()?.set_paused(true);
+    Ok(())
+}
+
+#[cfg_attr(feature = "gui", tauri::command)]
+pub fn resume_queue() -> Result<(), String> {
+    queue()?.set_paused(false);
+    Ok(())
+}
+
+#[cfg_attr(feature = "gui", tauri::command)]
+pub fn clear_finished_jobs() -> Result<QueueSnapshot, String> {
+    Ok(queue()?.clear_finished())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use linch_core::VerifySummary;
+    use std::sync::mpsc;
+    use std::time::Duration;
+
+    fn request(paths: &[&str]) -> ProcessRequest {
+        serde_json::from_value(serde_json::json!({
+            "files": paths
+                .iter()
+                .map(|path| serde_json::json!({ "path": path, "pages": [], "masks_by_page": {} }))
+                .collect::<Vec<_>>(),
+            "output_directory": "out",
+        }))
+        .unwrap()
+    }
+
+    fn done(path: &str) -> FileResult {
+        FileResult {
+            output_path: format!("out/{}", path),
+            audit_path: String::new(),
+            report_path: String::new(),
+            hits: 0,
+            warnings: Vec::new(),
+            verify: VerifySummary {
+                ok: true,
+                ..VerifySummary::default()
+            },
+        }
+    }
+
+    /// 等待指定任务进入某个状态
+    fn wait_for(events: &mpsc::Receiver<QueueEvent>, id: u64, status: JobStatus) -> JobInfo {
+        loop {
+            match events.recv_timeout(Duration::from_secs(5)).unwrap() {
+                QueueEvent::Job(info) if info.id == id && info.status == status => return info,
+                _ => {}
+            }
+        }
+    }
+
+    fn store(name: &str) -> PathBuf {
+        std::env::temp_dir()
+            .join(format!("linch-queue-{}-{}", name, std::process::id()))
+            .join("queue.json")
+    }
+
+    #[test]
+    fn test_cancels_running_job_between_pages() {
+        let (tx, events) = mpsc::channel();
+        let queue = JobQueue::with_processor(
+            None,
+            1,
+            |file, _, progress| {
+                for page in 0.. {
+                    progress(page, 1000)?;
+                    thread::sleep(Duration::from_millis(5));
+                }
+                Ok(done(&file.path))
+            },
+            move |event| {
+                let _ = tx.send(event.clone());
+            },
+        );
+
+        let id = queue.add(request(&["a.pdf"]))[0].id;
+        loop {
+            match events.recv_timeout(Duration::from_secs(5)).unwrap() {
+                QueueEvent::Page { done, .. } if done >= 2 => break,
+                _ => {}
+            }
+        }
+        queue.cancel(id).unwrap();
+        let info = wait_for(&events, id, JobStatus::Cancelled);
+        assert!(info.error.is_none());
+        assert!(info.pages_done > 0);
+        assert!(queue.cancel(id).is_err());
+    }
+
+    #[test]
+    fn test_retries_failed_job() {
+        let (tx, events) = mpsc::channel();
+        let calls = AtomicUsize::new(0);
+        let queue = JobQueue::with_processor(
+            None,
+            2,
+            move |file, _, _| {
+                if calls.fetch_add(1, Ordering::Relaxed) == 0 {
+                    Err("无法打开 PDF".to_string())
+                } else {
+                    Ok(done(&file.path))
+                }
+            },
+            move |event| {
+                let _ = tx.send(event.clone());
+            },
+        );
+
+        let id = queue.add(request(&["a.pdf"]))[0].id;
+        let failed = wait_for(&events, id, JobStatus::Failed);
+        assert_eq!(failed.error.as_deref(), Some("无法打开 PDF"));
+        assert!(queue.retry(id).is_ok());
+
+        let completed = wait_for(&events, id, JobStatus::Completed);
+        assert_eq!(completed.attempts, 2);
+        assert_eq!(completed.output_path.as_deref(), Some("out/a.pdf"));
+        assert!(completed.error.is_none());
+        assert!(queue.retry(id).is_err());
+    }
+
+    #[test]
+    fn test_panicking_job_fails_without_stopping_worker() {
+        let (tx, events) = mpsc::channel();
+        let queue = JobQueue::with_processor(
+            None,
+            1,
+            |file, _, _| {
+                if file.path == "a.pdf" {
+                    panic!("字体表损坏");
+                }
+                Ok(done(&file.path))
+            },
+            move |event| {
+                let _ = tx.send(event.clone());
+            },
+        );
+
+        let ids: Vec<u64> = queue
+            .add(request(&["a.pdf", "b.pdf"]))
+            .iter()
+            .map(|info| info.id)
+            .collect();
+        let failed = wait_for(&events, ids[0], JobStatus::Failed);
+        assert!(
+            failed.error.as_deref().unwrap().contains("字体表损坏"),
+            "{:?}",
+            failed.error
+        );
+        wait_for(&events, ids[1], JobStatus::Completed);
+    }
+
+    #[test]
+    fn test_queue_survives_restart() {
+        let store = store("restart");
+        let _ = fs::remove_file(&store);
+
+        // 不启动工作线程，模拟退出前尚未处理完的队列
+        let first =
+            JobQueue::with_processor(Some(store.clone()), 0, |_, _, _| unreachable!(), |_| {});
+        let ids: Vec<u64> = first
+            .add(request(&["a.pdf", "b.pdf"]))
+            .iter()
+            .map(|info| info.id)
+            .collect();
+        first.cancel(ids[1]).unwrap();
+        {
+            let mut state = first.lock();
+            state.jobs[0].info.status = JobStatus::Running;
+            first.persist(&state);
+        }
+        first.set_paused(true);
+
+        let restored =
+            JobQueue::with_processor(Some(store.clone()), 0, |_, _, _| unreachable!(), |_| {});
+        let snapshot = restored.snapshot();
+        assert!(snapshot.paused);
+        assert_eq!(snapshot.jobs.len(), 2);
+        assert_eq!(snapshot.jobs[0].status, JobStatus::Queued);
+        assert_eq!(snapshot.jobs[1].status, JobStatus::Cancelled);
+        assert_eq!(restored.add(request(&["c.pdf"]))[0].id, ids[1] + 1);
+
+        let snapshot = restored.clear_finished();
+        assert_eq!(snapshot.jobs.len(), 2);
+        let _ = fs::remove_dir_all(store.parent().unwrap());
+    }
+}
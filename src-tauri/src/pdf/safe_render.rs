@@ -3,94 +3,112 @@
 //! 使用 pdfium-render 将页面渲染为图片，在图片上绘制黑框，
 //! 然后用图片替换原页面内容。这样底层文字完全被销毁，无法复制。
 
-use image::{DynamicImage, Rgba, RgbaImage};
+use image::codecs::jpeg::JpegEncoder;
+use image::{DynamicImage, ExtendedColorType, GrayImage, Rgba, RgbaImage};
 use imageproc::drawing::draw_filled_rect_mut;
 use imageproc::rect::Rect;
+use linch_rules::{BBox, PositionedPage, TextItem};
 use pdfium_render::prelude::*;
-use std::path::PathBuf;
-
-use super::types::Mask;
-
-/// 获取 pdfium 库的搜索路径
-fn get_pdfium_search_paths() -> Vec<PathBuf> {
-    let mut paths = Vec::new();
-
-    if let Ok(exe_path) = std::env::current_exe() {
-        if let Some(exe_dir) = exe_path.parent() {
-            // 1. Linux/Windows: 可执行文件同级的 libs 目录
-            paths.push(exe_dir.join("libs"));
-
-            // 2. Linux/Windows: 可执行文件同级目录
-            paths.push(exe_dir.to_path_buf());
-
-            // 3. macOS: app bundle 内的 Resources 目录
-            #[cfg(target_os = "macos")]
-            {
-                // .app/Contents/MacOS/app -> .app/Contents/Resources/libs
-                if let Some(contents_dir) = exe_dir.parent() {
-                    paths.push(contents_dir.join("Resources").join("libs"));
-                    paths.push(contents_dir.join("Resources"));
-                }
-            }
-
-            // 4. Linux AppImage: 检查 APPDIR 环境变量
-            #[cfg(target_os = "linux")]
-            {
-                if let Ok(appdir) = std::env::var("APPDIR") {
-                    let appdir_path = PathBuf::from(appdir);
-                    paths.push(appdir_path.join("usr").join("lib").join("libs"));
-                    paths.push(appdir_path.join("usr").join("lib"));
-                }
-            }
-        }
-    }
-
-    // 5. 本地开发: src-tauri/libs 目录
-    paths.push(PathBuf::from("libs"));
-    paths.push(PathBuf::from("src-tauri/libs"));
-
-    // 6. 当前目录
-    paths.push(PathBuf::from("./"));
-
-    paths
+use std::cell::OnceCell;
+use std::collections::{BTreeMap, HashSet};
+
+use super::types::{ColorMode, ImageEncoding, Mask, RedactionMode, RenderConfig};
+use super::utils::{get_media_box, get_media_box_with_rotation};
+use super::RedactedPdf;
+use lopdf::content::{Content, Operation};
+use lopdf::{dictionary, Document, Object, ObjectId, Stream};
+
+/// 复制未脱敏页面时的渲染 DPI（2 倍于 72 DPI）
+const COPY_DPI: u32 = 144;
+/// 1 位黑白图片的二值化阈值
+const BILEVEL_THRESHOLD: u8 = 128;
+/// 栅格化页面图片的资源名称
+const RASTER_NAME: &[u8] = b"LinchRaster";
+
+/// 绑定 pdfium 库（搜索路径见 `linch_render::bind_pdfium`）
+fn bind_pdfium() -> Result<Pdfium, String> {
+    linch_render::bind_pdfium().map_err(|e| e.to_string())
 }
 
-/// 尝试绑定 pdfium 库
-fn bind_pdfium() -> Result<Pdfium, String> {
-    let search_paths = get_pdfium_search_paths();
+/// 原文件在 pdfium 中打开的文档
+///
+/// 第一次需要渲染时才绑定 pdfium 并打开文件，同一文件的各页共用，避免逐页重新打开。
+pub struct SourcePdf<'a> {
+    path: &'a str,
+    pdfium: &'a OnceCell<Pdfium>,
+    document: OnceCell<PdfDocument<'a>>,
+}
 
-    // 尝试从各个路径加载
-    for path in &search_paths {
-        let lib_path = Pdfium::pdfium_platform_library_name_at_path(path);
-        log::debug!("[SafeRender] 尝试加载 pdfium: {:?}", lib_path);
+impl<'a> SourcePdf<'a> {
+    pub fn new(path: &'a str, pdfium: &'a OnceCell<Pdfium>) -> Self {
+        Self {
+            path,
+            pdfium,
+            document: OnceCell::new(),
+        }
+    }
 
-        if let Ok(bindings) = Pdfium::bind_to_library(&lib_path) {
-            log::info!("[SafeRender] 成功从 {:?} 加载 pdfium", path);
-            return Ok(Pdfium::new(bindings));
+    fn document(&self) -> Result<&PdfDocument<'a>, String> {
+        if let Some(document) = self.document.get() {
+            return Ok(document);
         }
+        if self.pdfium.get().is_none() {
+            let _ = self.pdfium.set(bind_pdfium()?);
+        }
+        let pdfium = self.pdfium.get().ok_or("pdfium 初始化失败")?;
+        let document = pdfium
+            .load_pdf_from_file(self.path, None)
+            .map_err(|e| format!("加载 PDF 失败: {}", e))?;
+        Ok(self.document.get_or_init(|| document))
     }
 
-    // 最后尝试系统库
-    log::debug!("[SafeRender] 尝试加载系统 pdfium 库");
-    Pdfium::bind_to_system_library()
-        .map(Pdfium::new)
-        .map_err(|e| {
-            format!(
-                "Pdfium 库不可用: {}。\n请运行 ./scripts/setup-pdfium.sh 下载 pdfium 库。",
-                e
-            )
-        })
-}
+    fn page(&self, page_index: usize) -> Result<PdfPage<'_>, String> {
+        self.document()?
+            .pages()
+            .get(page_index as u16)
+            .map_err(|e| format!("获取页面 {} 失败: {}", page_index, e))
+    }
 
-/// 渲染配置
-pub struct RenderConfig {
-    /// DPI（每英寸点数），默认 150
-    pub dpi: u32,
-}
+    pub fn page_count(&self) -> Result<usize, String> {
+        Ok(self.document()?.pages().len() as usize)
+    }
+
+    /// 渲染页面（用于 OCR）
+    pub fn render(&self, page_index: usize, dpi: u32) -> Result<DynamicImage, String> {
+        let page = self.page(page_index)?;
+        linch_render::render_page(&page, dpi).map_err(|e| e.to_string())
+    }
 
-impl Default for RenderConfig {
-    fn default() -> Self {
-        Self { dpi: 150 }
+    /// 使用 pdfium 逐字符提取带位置的文本
+    ///
+    /// 坐标为左上角原点的相对坐标（0-1），与 `Mask` 一致；页码从 1 开始。
+    /// 页面不包含文本时返回 None。
+    pub fn positioned_text(&self, page_index: usize) -> Result<Option<PositionedPage>, String> {
+        let page = self.page(page_index)?;
+        let page_width = page.width().value;
+        let page_height = page.height().value;
+        let items: Vec<TextItem> = linch_render::page_chars(&page)
+            .map_err(|e| e.to_string())?
+            .into_iter()
+            .map(|ch| TextItem {
+                text: ch.text,
+                bbox: ch.bounds.map(|b| {
+                    let (x, y, w, h) = b.relative(page_width, page_height);
+                    BBox { x, y, w, h }
+                }),
+                confidence: None,
+            })
+            .collect();
+
+        if !items.iter().any(|item| !item.text.trim().is_empty()) {
+            return Ok(None);
+        }
+        Ok(Some(PositionedPage {
+            page: page_index as u32 + 1,
+            width: 1.0,
+            height: 1.0,
+            items,
+        }))
     }
 }
 
@@ -100,56 +118,34 @@ impl Default for RenderConfig {
 /// 2. 在图片上绘制黑色矩形覆盖 mask 区域
 /// 3. 返回脱敏后的图片
 pub fn render_and_redact_page(
-    pdfium: &Pdfium,
-    pdf_path: &str,
+    page: &PdfPage,
     page_index: usize,
     masks: &[Mask],
     config: &RenderConfig,
 ) -> Result<DynamicImage, String> {
-    let document = pdfium
-        .load_pdf_from_file(pdf_path, None)
-        .map_err(|e| format!("加载 PDF 失败: {}", e))?;
-
-    let page = document
-        .pages()
-        .get(page_index as u16)
-        .map_err(|e| format!("获取页面 {} 失败: {}", page_index, e))?;
-
-    // 计算渲染尺寸
-    let page_width = page.width().value;
-    let page_height = page.height().value;
-
-    // PDF 默认 72 DPI，计算目标像素尺寸
-    let scale = config.dpi as f32 / 72.0;
-    let target_width = (page_width * scale) as i32;
-    let target_height = (page_height * scale) as i32;
+    // 渲染页面为图片
+    let mut image: RgbaImage = linch_render::render_page(page, config.dpi)
+        .map_err(|e| e.to_string())?
+        .to_rgba8();
+    let target_width = image.width();
+    let target_height = image.height();
 
     log::info!(
-        "[SafeRender] 页面 {}: {}x{} pt -> {}x{} px (DPI: {})",
+        "[SafeRender] 页面 {}: {}x{} px (DPI: {})",
         page_index,
-        page_width,
-        page_height,
         target_width,
         target_height,
         config.dpi
     );
 
-    // 渲染页面为图片
-    let render_config = PdfRenderConfig::new()
-        .set_target_width(target_width)
-        .set_target_height(target_height);
+    draw_masks(&mut image, masks);
 
-    let bitmap = page
-        .render_with_config(&render_config)
-        .map_err(|e| format!("渲染页面失败: {}", e))?;
-
-    let mut image: RgbaImage = bitmap
-        .as_image()
-        .as_rgba8()
-        .ok_or("转换图片格式失败")?
-        .clone();
+    Ok(DynamicImage::ImageRgba8(image))
+}
 
-    // 在图片上绘制黑色矩形
+/// 在图片上绘制黑色矩形（mask 为相对图片的 0-1 坐标）
+fn draw_masks(image: &mut RgbaImage, masks: &[Mask]) {
+    let (target_width, target_height) = image.dimensions();
     let black = Rgba([0u8, 0u8, 0u8, 255u8]);
 
     for mask in masks {
@@ -162,28 +158,177 @@ pub fn render_and_redact_page(
         // 确保坐标在有效范围内
         let x = x.max(0) as u32;
         let y = y.max(0) as u32;
-        let w = w.min(target_width as u32 - x);
-        let h = h.min(target_height as u32 - y);
+        let w = w.min(target_width.saturating_sub(x));
+        let h = h.min(target_height.saturating_sub(y));
 
         if w > 0 && h > 0 {
             let rect = Rect::at(x as i32, y as i32).of_size(w, h);
-            draw_filled_rect_mut(&mut image, rect, black);
+            draw_filled_rect_mut(image, rect, black);
             log::info!("[SafeRender] 绘制黑框: ({}, {}, {}, {})", x, y, w, h);
         }
     }
+}
 
-    Ok(DynamicImage::ImageRgba8(image))
+/// 把 lopdf 文档中的单个页面替换为脱敏后的渲染图片
+///
+/// 用 pdfium 渲染原文件中的同一页（不含注释），绘制黑框后替换该页内容：
+/// 内容流只绘制这张图片，资源字典只保留该图片，原有文字、路径和图片全部移除。
+/// 注释、书签、页面标签和其他页面保持不变。
+pub fn rasterize_page(
+    doc: &mut Document,
+    page_id: ObjectId,
+    source: &SourcePdf,
+    page_index: usize,
+    masks: &[Mask],
+    config: &RenderConfig,
+) -> Result<(), String> {
+    let page = source.page(page_index)?;
+
+    // pdfium 按显示方向渲染，mask 也是相对显示后的页面
+    let mut image = linch_render::render_page_content(&page, config.dpi)
+        .map_err(|e| e.to_string())?
+        .to_rgba8();
+    draw_masks(&mut image, masks);
+    log::info!(
+        "[SafeRender] 页面 {} 栅格化: {}x{} px (DPI: {})",
+        page_index,
+        image.width(),
+        image.height(),
+        config.dpi
+    );
+
+    replace_with_image(doc, page_id, DynamicImage::ImageRgba8(image), config)
+}
+
+/// 用图片替换页面内容，图片铺满页面可见区域（CropBox）
+///
+/// 页面保留 /Rotate，因此先把按显示方向渲染的图片转回未旋转的方向。
+fn replace_with_image(
+    doc: &mut Document,
+    page_id: ObjectId,
+    image: DynamicImage,
+    config: &RenderConfig,
+) -> Result<(), String> {
+    let rotation = get_media_box_with_rotation(doc, page_id).4;
+    let image = match rotation.rem_euclid(360) {
+        90 => image.rotate270(),
+        180 => image.rotate180(),
+        270 => image.rotate90(),
+        _ => image,
+    };
+    let raster = encode_raster(&image, config)?;
+    place_raster(doc, page_id, raster)
+}
+
+/// 按配置把图片编码为 PDF 图片对象
+///
+/// 编码在内存中完成，不写临时文件。1 位黑白始终使用 Flate 压缩。
+fn encode_raster(image: &DynamicImage, config: &RenderConfig) -> Result<Stream, String> {
+    let (width, height) = (image.width(), image.height());
+    let (color_space, bits, pixels) = match config.color {
+        ColorMode::Rgb => ("DeviceRGB", 8, image.to_rgb8().into_raw()),
+        ColorMode::Gray => ("DeviceGray", 8, image.to_luma8().into_raw()),
+        ColorMode::Bilevel => ("DeviceGray", 1, pack_bilevel(&image.to_luma8())),
+    };
+    let mut dict = dictionary! {
+        "Type" => "XObject",
+        "Subtype" => "Image",
+        "Width" => width as i64,
+        "Height" => height as i64,
+        "ColorSpace" => color_space,
+        "BitsPerComponent" => bits,
+    };
+
+    if config.encoding == ImageEncoding::Jpeg && config.color != ColorMode::Bilevel {
+        let color = if config.color == ColorMode::Gray {
+            ExtendedColorType::L8
+        } else {
+            ExtendedColorType::Rgb8
+        };
+        let mut jpeg = Vec::new();
+        JpegEncoder::new_with_quality(&mut jpeg, config.jpeg_quality.clamp(1, 100))
+            .encode(&pixels, width, height, color)
+            .map_err(|e| format!("JPEG 编码失败: {}", e))?;
+        dict.set("Filter", "DCTDecode");
+        return Ok(Stream::new(dict, jpeg).with_compression(false));
+    }
+
+    let mut stream = Stream::new(dict, pixels);
+    stream
+        .compress()
+        .map_err(|e| format!("Flate 压缩失败: {}", e))?;
+    Ok(stream)
 }
 
-/// 对整个 PDF 进行安全脱敏，生成新的 PDF 文件
+/// 把灰度图二值化并按行打包为 1 位像素（0 为黑，每行补齐到整字节）
+fn pack_bilevel(image: &GrayImage) -> Vec<u8> {
+    let row_bytes = (image.width() as usize).div_ceil(8);
+    let mut packed = vec![0u8; row_bytes * image.height() as usize];
+    for (x, y, pixel) in image.enumerate_pixels() {
+        if pixel.0[0] >= BILEVEL_THRESHOLD {
+            packed[y as usize * row_bytes + x as usize / 8] |= 0x80 >> (x % 8);
+        }
+    }
+    packed
+}
+
+/// 让页面只绘制给定图片，图片铺满页面可见区域
 ///
-/// 将每个需要脱敏的页面渲染为图片，绘制黑框后保存为新 PDF
+/// pdfium 只渲染 CropBox（没有时为 MediaBox）内的区域，图片按该区域放置，
+/// 并把页面的 MediaBox 与 CropBox 都设为该区域，页面上不再有图片以外的部分。
+fn place_raster(doc: &mut Document, page_id: ObjectId, raster: Stream) -> Result<(), String> {
+    let (llx, lly, urx, ury) = get_media_box(doc, page_id);
+    let image_id = doc.add_object(raster);
+
+    let operations = vec![
+        Operation::new("q", vec![]),
+        Operation::new(
+            "cm",
+            vec![
+                (urx - llx).into(),
+                0.into(),
+                0.into(),
+                (ury - lly).into(),
+                llx.into(),
+                lly.into(),
+            ],
+        ),
+        Operation::new("Do", vec![Object::Name(RASTER_NAME.to_vec())]),
+        Operation::new("Q", vec![]),
+    ];
+    let content = Content { operations }
+        .encode()
+        .map_err(|e| format!("无法编码内容流: {}", e))?;
+    let content_id = doc.add_object(Stream::new(dictionary! {}, content));
+
+    match doc.get_object_mut(page_id) {
+        Ok(Object::Dictionary(page)) => {
+            page.set("Contents", content_id);
+            let page_box: Vec<Object> = vec![llx.into(), lly.into(), urx.into(), ury.into()];
+            page.set("MediaBox", page_box.clone());
+            page.set("CropBox", page_box);
+            // 直接定义资源字典，不再继承父节点中的字体等资源
+            page.set(
+                "Resources",
+                dictionary! { "XObject" => dictionary! { RASTER_NAME => image_id } },
+            );
+            Ok(())
+        }
+        _ => Err("页面对象无效".to_string()),
+    }
+}
+
+/// 对整个 PDF 进行安全脱敏，返回新 PDF
+///
+/// 将每个需要脱敏的页面渲染为图片，绘制黑框后生成新 PDF。
+/// `deleted_pages` 中的页面（从 0 开始）不复制；书签、页面标签、命名目标、
+/// 链接注释和文档级设置迁移到新文档，指向已删除页面的目标被丢弃。
 pub fn safe_redact_pdf(
     input_path: &str,
-    output_path: &str,
     masks_by_page: &std::collections::BTreeMap<usize, Vec<Mask>>,
+    deleted_pages: &HashSet<usize>,
     config: &RenderConfig,
-) -> Result<(), String> {
+) -> Result<RedactedPdf, String> {
     log::info!(
         "[SafeRender] 开始处理，masks_by_page keys: {:?}, 总 mask 数: {}",
         masks_by_page.keys().collect::<Vec<_>>(),
@@ -205,7 +350,14 @@ pub fn safe_redact_pdf(
         .create_new_pdf()
         .map_err(|e| format!("创建新 PDF 失败: {}", e))?;
 
+    // 新文档先用 pdfium 创建空白页面，保存后再用 lopdf 写入每页的图片
+    let mut rasters = Vec::new();
+    // 重建的文档中每一页都是图片，包括未脱敏而按原样复制的页面
+    let mut page_modes = BTreeMap::new();
     for page_idx in 0..page_count {
+        if deleted_pages.contains(&(page_idx as usize)) {
+            continue;
+        }
         let page = document
             .pages()
             .get(page_idx)
@@ -215,335 +367,259 @@ pub fn safe_redact_pdf(
         let page_height = page.height();
 
         // 检查此页是否需要脱敏
-        let masks = masks_by_page.get(&(page_idx as usize));
-        log::info!(
-            "[SafeRender] 页面 {} 查找 masks: found={}, count={}",
-            page_idx,
-            masks.is_some(),
-            masks.map(|m| m.len()).unwrap_or(0)
-        );
-
-        if let Some(masks) = masks {
-            if !masks.is_empty() {
+        let masks = masks_by_page
+            .get(&(page_idx as usize))
+            .filter(|masks| !masks.is_empty());
+        let image = match masks {
+            Some(masks) => {
                 log::info!(
                     "[SafeRender] 页面 {} 需要脱敏，masks: {:?}",
                     page_idx,
                     masks
                 );
                 // 需要脱敏：渲染为图片并添加黑框
-                let redacted_image =
-                    render_and_redact_page(&pdfium, input_path, page_idx as usize, masks, config)?;
-
-                // 将图片添加到新 PDF
-                let mut new_page = new_doc
-                    .pages_mut()
-                    .create_page_at_end(PdfPagePaperSize::Custom(page_width, page_height))
-                    .map_err(|e| format!("创建页面失败: {}", e))?;
-
-                // 保存图片到临时文件（使用跨平台临时目录）
-                let temp_dir = std::env::temp_dir();
-                let temp_path = temp_dir.join(format!("redact_page_{}.jpg", page_idx));
-                let temp_path = temp_path.to_string_lossy().to_string();
-                redacted_image
-                    .to_rgb8()
-                    .save_with_format(&temp_path, image::ImageFormat::Jpeg)
-                    .map_err(|e| format!("保存临时图片失败: {}", e))?;
-
-                // 创建图片对象并添加到页面
-                let mut image_obj = PdfPageImageObject::new_from_jpeg_file(&new_doc, &temp_path)
-                    .map_err(|e| format!("创建图片对象失败: {}", e))?;
-
-                // 设置图片尺寸和位置（覆盖整个页面）
-                image_obj
-                    .scale(page_width.value, page_height.value)
-                    .map_err(|e| format!("缩放图片失败: {}", e))?;
-
-                new_page
-                    .objects_mut()
-                    .add_image_object(image_obj)
-                    .map_err(|e| format!("添加图片到页面失败: {}", e))?;
-
-                // 删除临时文件
-                let _ = std::fs::remove_file(&temp_path);
-
-                log::info!("[SafeRender] 页面 {} 脱敏完成", page_idx);
-            } else {
-                // 没有 mask，复制原页面
-                copy_page(&pdfium, &document, &mut new_doc, page_idx)?;
+                render_and_redact_page(&page, page_idx as usize, masks, config)?
             }
-        } else {
-            // 没有 mask，复制原页面
-            copy_page(&pdfium, &document, &mut new_doc, page_idx)?;
-        }
+            // 没有 mask：按较低分辨率复制原页面
+            None => linch_render::render_page(&page, COPY_DPI).map_err(|e| e.to_string())?,
+        };
+
+        new_doc
+            .pages_mut()
+            .create_page_at_end(PdfPagePaperSize::Custom(page_width, page_height))
+            .map_err(|e| format!("创建页面失败: {}", e))?;
+        rasters.push(encode_raster(&image, config)?);
+        page_modes.insert(page_idx as usize, RedactionMode::SafeRender);
+        log::info!("[SafeRender] 页面 {} 处理完成", page_idx);
+    }
+
+    let rebuilt = new_doc
+        .save_to_bytes()
+        .map_err(|e| format!("保存 PDF 失败: {}", e))?;
+    let mut target =
+        Document::load_mem(&rebuilt).map_err(|e| format!("解析重建文档失败: {}", e))?;
+    let target_pages: Vec<ObjectId> = target.page_iter().collect();
+    for (&page_id, raster) in target_pages.iter().zip(rasters) {
+        place_raster(&mut target, page_id, raster)?;
+    }
+    // 文档结构不含页面内容，迁移失败时仍输出脱敏结果，并在审计记录和报告中注明
+    let mut warnings = Vec::new();
+    if let Err(e) = carry_structure(&document, &mut target, &target_pages, deleted_pages) {
+        log::warn!("[SafeRender] 无法迁移书签等文档结构: {}", e);
+        warnings.push(format!("未能迁移书签、页面标签和链接等文档结构: {}", e));
     }
 
     // 保存新 PDF
-    new_doc
-        .save_to_file(output_path)
+    let mut output = Vec::new();
+    target
+        .save_to(&mut output)
         .map_err(|e| format!("保存 PDF 失败: {}", e))?;
 
-    log::info!("[SafeRender] PDF 保存到: {}", output_path);
-    Ok(())
+    log::info!("[SafeRender] 重建完成，共 {} 页", target_pages.len());
+    Ok(RedactedPdf {
+        data: output,
+        mode: RedactionMode::SafeRender,
+        page_modes,
+        warnings,
+    })
 }
 
-/// 复制页面到新文档（对于不需要脱敏的页面）
-fn copy_page(
-    _pdfium: &Pdfium,
-    source_doc: &PdfDocument,
-    dest_doc: &mut PdfDocument,
-    page_idx: u16,
+/// 把源文档的书签、页面标签、命名目标和链接迁移到重建的文档
+///
+/// 源文件可能无法被 lopdf 直接解析，先经 pdfium 重新保存再读取。
+fn carry_structure(
+    source: &PdfDocument,
+    target: &mut Document,
+    target_pages: &[ObjectId],
+    deleted_pages: &HashSet<usize>,
 ) -> Result<(), String> {
-    let source_page = source_doc
-        .pages()
-        .get(page_idx)
-        .map_err(|e| format!("获取源页面 {} 失败: {}", page_idx, e))?;
-
-    let page_width = source_page.width();
-    let page_height = source_page.height();
-
-    // 渲染源页面为图片（高清晰度）
-    let render_config = PdfRenderConfig::new()
-        .set_target_width((page_width.value * 2.0) as i32)
-        .set_target_height((page_height.value * 2.0) as i32);
-
-    let bitmap = source_page
-        .render_with_config(&render_config)
-        .map_err(|e| format!("渲染页面失败: {}", e))?;
-
-    let image = bitmap.as_image();
-
-    // 保存到临时文件（使用跨平台临时目录）
-    let temp_dir = std::env::temp_dir();
-    let temp_path = temp_dir.join(format!("copy_page_{}.jpg", page_idx));
-    let temp_path = temp_path.to_string_lossy().to_string();
-    image
-        .to_rgb8()
-        .save_with_format(&temp_path, image::ImageFormat::Jpeg)
-        .map_err(|e| format!("保存临时图片失败: {}", e))?;
-
-    // 创建新页面
-    let mut new_page = dest_doc
-        .pages_mut()
-        .create_page_at_end(PdfPagePaperSize::Custom(page_width, page_height))
-        .map_err(|e| format!("创建页面失败: {}", e))?;
-
-    // 添加图片
-    let mut image_obj = PdfPageImageObject::new_from_jpeg_file(dest_doc, &temp_path)
-        .map_err(|e| format!("创建图片对象失败: {}", e))?;
-
-    image_obj
-        .scale(page_width.value, page_height.value)
-        .map_err(|e| format!("缩放图片失败: {}", e))?;
-
-    new_page
-        .objects_mut()
-        .add_image_object(image_obj)
-        .map_err(|e| format!("添加图片到页面失败: {}", e))?;
-
-    // 删除临时文件
-    let _ = std::fs::remove_file(&temp_path);
-
-    Ok(())
+    let source = source
+        .save_to_bytes()
+        .map_err(|e| format!("保存源文档失败: {}", e))?;
+    let source = Document::load_mem(&source).map_err(|e| format!("解析源文档失败: {}", e))?;
+
+    let mut kept = target_pages.iter().copied();
+    let pages: Vec<Option<ObjectId>> = (0..source.get_pages().len())
+        .map(|index| {
+            if deleted_pages.contains(&index) {
+                None
+            } else {
+                kept.next()
+            }
+        })
+        .collect();
+    linch_pdf::copy_document_structure(&source, target, &pages).map_err(|e| e.to_string())
 }
 
-/// 使用 pdfium 提取 PDF 中的所有文本
-///
-/// 返回 Vec<(page_index, text)>
-pub fn extract_text_from_pdf(pdf_path: &str) -> Result<Vec<(usize, String)>, String> {
-    let pdfium = bind_pdfium()?;
-
-    let document = pdfium
-        .load_pdf_from_file(pdf_path, None)
-        .map_err(|e| format!("加载 PDF 失败: {}", e))?;
-
-    let page_count = document.pages().len();
-    let mut results = Vec::new();
-
-    for page_idx in 0..page_count {
-        let page = document
-            .pages()
-            .get(page_idx)
-            .map_err(|e| format!("获取页面 {} 失败: {}", page_idx, e))?;
-
-        // 提取页面文本
-        let text = page.text().map_err(|e| format!("提取文本失败: {}", e))?;
-        let page_text = text.all();
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-        if !page_text.is_empty() {
-            results.push((page_idx as usize, page_text));
-        }
+    #[test]
+    fn test_render_config_default() {
+        let config = RenderConfig::default();
+        assert_eq!(config.dpi, 150);
     }
 
-    Ok(results)
-}
-
-/// 文本搜索结果
-#[derive(Debug, Clone)]
-pub struct TextSearchResult {
-    pub x: f64,
-    pub y: f64,
-    pub width: f64,
-    pub height: f64,
-}
-
-/// 在 PDF 页面中搜索文本并返回精确位置
-#[allow(dead_code)]
-pub fn search_text_in_page(
-    pdf_path: &str,
-    page_index: usize,
-    search_term: &str,
-) -> Result<Vec<TextSearchResult>, String> {
-    let results = batch_search_text_in_page(pdf_path, page_index, &[search_term])?;
-    Ok(results.into_iter().flat_map(|(_, v)| v).collect())
-}
-
-/// 批量在 PDF 页面中搜索多个文本（性能优化：只打开 PDF 一次）
-pub fn batch_search_text_in_page(
-    pdf_path: &str,
-    page_index: usize,
-    search_terms: &[&str],
-) -> Result<Vec<(String, Vec<TextSearchResult>)>, String> {
-    let pdfium = bind_pdfium()?;
-
-    let document = pdfium
-        .load_pdf_from_file(pdf_path, None)
-        .map_err(|e| format!("加载 PDF 失败: {}", e))?;
-
-    let page = document
-        .pages()
-        .get(page_index as u16)
-        .map_err(|e| format!("获取页面 {} 失败: {}", page_index, e))?;
-
-    let page_width = page.width().value as f64;
-    let page_height = page.height().value as f64;
-
-    let text = page.text().map_err(|e| format!("提取文本失败: {}", e))?;
-    let search_options = PdfSearchOptions::new();
-
-    let mut all_results = Vec::new();
-
-    for search_term in search_terms {
-        let search = match text.search(search_term, &search_options) {
-            Ok(s) => s,
-            Err(_) => continue,
-        };
+    #[test]
+    fn test_replace_with_image_drops_page_content() {
+        let mut doc = Document::with_version("1.5");
+        let pages_id = doc.new_object_id();
+        let contents = doc.add_object(Stream::new(
+            dictionary! {},
+            b"BT /F1 12 Tf 100 700 Td (SECRET) Tj ET".to_vec(),
+        ));
+        let page_id = doc.add_object(dictionary! {
+            "Type" => "Page",
+            "Parent" => pages_id,
+            "MediaBox" => vec![0.into(), 0.into(), 200.into(), 100.into()],
+            "Rotate" => 90,
+            "Contents" => contents,
+            "Resources" => dictionary! { "Font" => dictionary! { "F1" => dictionary! {} } },
+        });
+        doc.objects.insert(
+            pages_id,
+            Object::Dictionary(dictionary! {
+                "Type" => "Pages",
+                "Kids" => vec![page_id.into()],
+                "Count" => 1,
+            }),
+        );
 
-        let mut results = Vec::new();
-
-        for segments in search.iter(PdfSearchDirection::SearchForward) {
-            // 合并所有 segments 为一个完整的边界框
-            let mut min_left = f64::MAX;
-            let mut min_bottom = f64::MAX;
-            let mut max_right = f64::MIN;
-            let mut max_top = f64::MIN;
-            let mut has_segments = false;
-
-            for segment in segments.iter() {
-                has_segments = true;
-                let bounds = segment.bounds();
-
-                let pdf_left = bounds.left().value as f64;
-                let pdf_bottom = bounds.bottom().value as f64;
-                let pdf_right = bounds.right().value as f64;
-                let pdf_top = bounds.top().value as f64;
-
-                min_left = min_left.min(pdf_left);
-                min_bottom = min_bottom.min(pdf_bottom);
-                max_right = max_right.max(pdf_right);
-                max_top = max_top.max(pdf_top);
-            }
+        // 旋转 90° 的页面按显示方向渲染为竖图
+        let image = DynamicImage::ImageRgba8(RgbaImage::new(10, 20));
+        replace_with_image(&mut doc, page_id, image, &RenderConfig::default()).unwrap();
+
+        let page = doc.get_dictionary(page_id).unwrap();
+        let resources = page.get(b"Resources").unwrap().as_dict().unwrap();
+        assert!(resources.get(b"Font").is_err());
+        let xobjects = resources.get(b"XObject").unwrap().as_dict().unwrap();
+        let image_id = xobjects.get(RASTER_NAME).unwrap().as_reference().unwrap();
+        let image = doc.get_object(image_id).unwrap().as_stream().unwrap();
+        assert_eq!(image.dict.get(b"Width").unwrap().as_i64().unwrap(), 20);
+        assert_eq!(image.dict.get(b"Height").unwrap().as_i64().unwrap(), 10);
+
+        let contents = page.get(b"Contents").unwrap().as_reference().unwrap();
+        let data = &doc
+            .get_object(contents)
+            .unwrap()
+            .as_stream()
+            .unwrap()
+            .content;
+        let ops = Content::decode(data).unwrap().operations;
+        let operators: Vec<&str> = ops.iter().map(|op| op.operator.as_str()).collect();
+        assert_eq!(operators, ["q", "cm", "Do", "Q"]);
+    }
 
-            if has_segments {
-                let x = min_left / page_width;
-                let y = 1.0 - (max_top / page_height);
-                let width = (max_right - min_left) / page_width;
-                let height = (max_top - min_bottom) / page_height;
-
-                let padding = 0.003;
-                results.push(TextSearchResult {
-                    x: (x - padding).max(0.0),
-                    y: (y - padding).max(0.0),
-                    width: (width + padding * 2.0).min(1.0),
-                    height: (height + padding * 2.0).min(1.0),
-                });
-            }
-        }
+    #[test]
+    fn test_place_raster_uses_inherited_crop_box() {
+        let mut doc = Document::with_version("1.5");
+        let pages_id = doc.new_object_id();
+        let page_id = doc.add_object(dictionary! {
+            "Type" => "Page",
+            "Parent" => pages_id,
+            "MediaBox" => vec![0.into(), 0.into(), 612.into(), 792.into()],
+        });
+        doc.objects.insert(
+            pages_id,
+            Object::Dictionary(dictionary! {
+                "Type" => "Pages",
+                "Kids" => vec![page_id.into()],
+                "Count" => 1,
+                "CropBox" => vec![50.into(), 100.into(), 250.into(), 400.into()],
+            }),
+        );
 
-        if !results.is_empty() {
-            all_results.push((search_term.to_string(), results));
+        let raster = Stream::new(dictionary! {}, Vec::new());
+        place_raster(&mut doc, page_id, raster).unwrap();
+
+        let page = doc.get_dictionary(page_id).unwrap();
+        let contents = page.get(b"Contents").unwrap().as_reference().unwrap();
+        let data = &doc
+            .get_object(contents)
+            .unwrap()
+            .as_stream()
+            .unwrap()
+            .content;
+        let ops = Content::decode(data).unwrap().operations;
+        let matrix: Vec<f32> = ops[1]
+            .operands
+            .iter()
+            .map(|o| o.as_float().unwrap())
+            .collect();
+        assert_eq!(matrix, [200.0, 0.0, 0.0, 300.0, 50.0, 100.0]);
+
+        let expected = vec![50.0, 100.0, 250.0, 400.0];
+        for key in [b"MediaBox".as_slice(), b"CropBox".as_slice()] {
+            let values: Vec<f32> = page
+                .get(key)
+                .unwrap()
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|o| o.as_float().unwrap())
+                .collect();
+            assert_eq!(values, expected);
         }
     }
 
-    Ok(all_results)
-}
-
-/// 渲染 PDF 页面到图片文件（用于 OCR）
-///
-/// 返回保存的图片文件路径
-pub fn render_page_to_image(
-    pdf_path: &str,
-    page_index: usize,
-    output_path: &str,
-    dpi: u32,
-) -> Result<(), String> {
-    let pdfium = bind_pdfium()?;
-
-    let document = pdfium
-        .load_pdf_from_file(pdf_path, None)
-        .map_err(|e| format!("加载 PDF 失败: {}", e))?;
-
-    let page = document
-        .pages()
-        .get(page_index as u16)
-        .map_err(|e| format!("获取页面 {} 失败: {}", page_index, e))?;
-
-    // 计算渲染尺寸
-    let page_width = page.width().value;
-    let page_height = page.height().value;
-
-    // PDF 默认 72 DPI，计算目标像素尺寸
-    let scale = dpi as f32 / 72.0;
-    let target_width = (page_width * scale) as i32;
-    let target_height = (page_height * scale) as i32;
-
-    log::info!(
-        "[RenderPage] 页面 {}: {}x{} pt -> {}x{} px (DPI: {})",
-        page_index,
-        page_width,
-        page_height,
-        target_width,
-        target_height,
-        dpi
-    );
-
-    // 渲染页面为图片
-    let render_config = PdfRenderConfig::new()
-        .set_target_width(target_width)
-        .set_target_height(target_height);
-
-    let bitmap = page
-        .render_with_config(&render_config)
-        .map_err(|e| format!("渲染页面失败: {}", e))?;
-
-    let image = bitmap.as_image();
-
-    // 保存到文件
-    image
-        .to_rgb8()
-        .save(output_path)
-        .map_err(|e| format!("保存图片失败: {}", e))?;
-
-    log::info!("[RenderPage] 页面 {} 已保存到: {}", page_index, output_path);
-    Ok(())
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
+    /// 解压 Flate 编码的图片数据（lopdf 不直接解压图片流）
+    fn inflate(stream: &Stream) -> Vec<u8> {
+        let mut stream = stream.clone();
+        stream.dict.remove(b"Subtype");
+        stream.decompressed_content().unwrap()
+    }
 
     #[test]
-    fn test_render_config_default() {
-        let config = RenderConfig::default();
-        assert_eq!(config.dpi, 150);
+    fn test_encode_raster_modes() {
+        let mut image = RgbaImage::from_pixel(60, 64, Rgba([255, 255, 255, 255]));
+        image.put_pixel(0, 0, Rgba([0, 0, 0, 255]));
+        let image = DynamicImage::ImageRgba8(image);
+
+        let jpeg = encode_raster(&image, &RenderConfig::default()).unwrap();
+        assert_eq!(
+            jpeg.dict.get(b"Filter").unwrap().as_name().unwrap(),
+            b"DCTDecode"
+        );
+
+        let config = RenderConfig {
+            encoding: ImageEncoding::Flate,
+            color: ColorMode::Gray,
+            ..RenderConfig::default()
+        };
+        let gray = encode_raster(&image, &config).unwrap();
+        assert_eq!(
+            gray.dict.get(b"Filter").unwrap().as_name().unwrap(),
+            b"FlateDecode"
+        );
+        let pixels = inflate(&gray);
+        assert_eq!(pixels.len(), 60 * 64);
+        assert_eq!((pixels[0], pixels[1]), (0, 255));
+
+        // 1 位黑白即使配置为 JPEG 也无损压缩，每行 60 像素补齐到 8 字节
+        let config = RenderConfig {
+            color: ColorMode::Bilevel,
+            ..RenderConfig::default()
+        };
+        let bilevel = encode_raster(&image, &config).unwrap();
+        assert_eq!(
+            bilevel
+                .dict
+                .get(b"BitsPerComponent")
+                .unwrap()
+                .as_i64()
+                .unwrap(),
+            1
+        );
+        let packed = inflate(&bilevel);
+        assert_eq!(packed.len(), 8 * 64);
+        assert_eq!(
+            packed[..8],
+            [0x7F, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xF0]
+        );
+        assert_eq!(
+            packed[8..16],
+            [0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xF0]
+        );
     }
 }
@@ -0,0 +1,359 @@
+//! PDF 脱敏任务
+//!
+//! 桌面端、批处理队列和命令行处理 PDF 时，遮罩已经确定（来自前端确认或规则检测）。
+//! `MaskedPdf` 把处理请求包装为 `linch_core::Document`，由 `linch_core::run_task_with`
+//! 统一完成脱敏、清理、写出、校验和审计记录，这里再补充 HTML 报告。
+
+use super::types::{
+    CleaningOptions, FileProcessRequest, ProcessOptions, RedactionMode, RenderConfig,
+};
+use super::{clean_pdf_data, output_path_for, redact_pdf_pages, verify_pdf_output, PageProgress};
+use crate::{audit, report};
+use anyhow::{anyhow, bail};
+use linch_core::audit::read_audit;
+use linch_core::document::{CleanOutcome, Document, Page, RedactOutcome};
+use linch_core::rules::CompiledRuleSet;
+use linch_core::{
+    CleanOptions, CoreError, OcrMode, TaskConfig, TaskHit, TaskResult, VerifyOptions, VerifySummary,
+};
+use linch_rules::{Rule, RulePackInfo};
+use std::collections::BTreeSet;
+use std::path::Path;
+
+/// 单个文件的完整处理结果
+pub(crate) struct FileResult {
+    pub output_path: String,
+    pub audit_path: String,
+    pub report_path: String,
+    /// 命中数量
+    pub hits: usize,
+    /// 脱敏过程中的警告
+    pub warnings: Vec<String>,
+    pub verify: VerifySummary,
+}
+
+/// 已确定遮罩的 PDF
+///
+/// 只能由调用方构造后交给 `run_task_with`，不支持按路径加载。
+struct MaskedPdf<'a> {
+    request: &'a FileProcessRequest,
+    mode: &'a RedactionMode,
+    render: &'a RenderConfig,
+    /// 命中原文，只在内存中用于校验
+    matched_texts: &'a [String],
+    progress: PageProgress<'a>,
+}
+
+impl Document for MaskedPdf<'_> {
+    fn load(_path: &Path) -> anyhow::Result<Self> {
+        bail!("MaskedPdf 需要处理请求，只能通过 run_task_with 使用")
+    }
+
+    fn get_pages(&self) -> anyhow::Result<Vec<Page>> {
+        linch_pdf::PdfDocument::load(Path::new(&self.request.path))?.get_pages()
+    }
+
+    /// 命中来自检测阶段，页码从 0 转换为从 1 开始
+    fn detect(&self, _ruleset: &CompiledRuleSet) -> anyhow::Result<Vec<TaskHit>> {
+        Ok(self
+            .request
+            .hits
+            .iter()
+            .map(audit::hit_from_detection)
+            .collect())
+    }
+
+    fn redact(&self, _ruleset: &CompiledRuleSet) -> anyhow::Result<RedactOutcome> {
+        let redacted = redact_pdf_pages(self.request, self.mode, self.render, self.progress)
+            .map_err(|e| anyhow!(e))?;
+        Ok(RedactOutcome {
+            data: redacted.data,
+            mode: audit::redact_mode(&redacted.mode, &redacted.page_modes),
+            page_modes: audit::page_modes(&redacted.page_modes),
+            masks: audit::masks(&self.request.masks_by_page),
+            warnings: redacted.warnings,
+        })
+    }
+
+    fn clean(&self, data: Vec<u8>, options: &CleanOptions) -> anyhow::Result<CleanOutcome> {
+        let (data, actions) = clean_pdf_data(&data, &options.into()).map_err(|e| anyhow!(e))?;
+        Ok(CleanOutcome { data, actions })
+    }
+
+    /// 按规则和命中原文重新检查输出，启用时执行 OCR 抽样
+    fn verify(
+        &self,
+        output: &Path,
+        ruleset: &CompiledRuleSet,
+        options: &VerifyOptions,
+    ) -> anyhow::Result<VerifySummary> {
+        let output = output.to_string_lossy();
+        let result = verify_pdf_output(&output, ruleset, self.matched_texts, options);
+        Ok(result.summary(options))
+    }
+
+    fn get_supported_features(&self) -> Vec<String> {
+        vec!["image_redact".to_string(), "metadata_clean".to_string()]
+    }
+}
+
+/// 按规则脱敏的 PDF
+///
+/// 脱敏和清理由 `linch_pdf` 完成；校验使用 `linch_verify`，按规则和原文件中的
+/// 命中原文检查输出，启用时执行 OCR 抽样。
+#[cfg(feature = "gui")]
+pub(crate) struct RulePdf {
+    doc: linch_pdf::PdfDocument,
+}
+
+#[cfg(feature = "gui")]
+impl Document for RulePdf {
+    fn load(path: &Path) -> anyhow::Result<Self> {
+        Ok(Self {
+            doc: linch_pdf::PdfDocument::load(path)?,
+        })
+    }
+
+    fn get_pages(&self) -> anyhow::Result<Vec<Page>> {
+        self.doc.get_pages()
+    }
+
+    fn redact(&self, ruleset: &CompiledRuleSet) -> anyhow::Result<RedactOutcome> {
+        self.doc.redact(ruleset)
+    }
+
+    fn clean(&self, data: Vec<u8>, options: &CleanOptions) -> anyhow::Result<CleanOutcome> {
+        self.doc.clean(data, options)
+    }
+
+    fn verify(
+        &self,
+        output: &Path,
+        ruleset: &CompiledRuleSet,
+        options: &VerifyOptions,
+    ) -> anyhow::Result<VerifySummary> {
+        let matched_texts: Vec<String> = self
+            .doc
+            .get_pages()?
+            .iter()
+            .flat_map(|page| ruleset.match_text(&page.content))
+            .map(|m| m.matched_text)
+            .collect();
+        let output = output.to_string_lossy();
+        let result = verify_pdf_output(&output, ruleset, &matched_texts, options);
+        Ok(result.summary(options))
+    }
+
+    fn get_supported_features(&self) -> Vec<String> {
+        self.doc.get_supported_features()
+    }
+}
+
+/// 各入口共用的任务配置：规则、校验选项和版本，其余字段由调用方按需填写
+pub(crate) fn task_config(
+    input: &str,
+    output: &str,
+    rules: &[Rule],
+    verify: &VerifyOptions,
+) -> Result<TaskConfig, String> {
+    Ok(TaskConfig {
+        input_path: input.to_string(),
+        output_path: output.to_string(),
+        ocr_mode: OcrMode::Clear,
+        clean: CleanOptions::default(),
+        verify: verify.clone(),
+        rule_pack: None,
+        rules: linch_rules::to_ruleset(rules),
+        // 区域、页面规则不在文本规则集中，按完整规则计算摘要
+        rules_hash: Some(audit::rules_hash(rules)?),
+        operator: None,
+        ocr_engine: None,
+        tool_version: Some(env!("CARGO_PKG_VERSION").to_string()),
+    })
+}
+
+/// 读取任务写出的审计记录，在输出文件旁补充 HTML 报告
+///
+/// `deleted_pages` 为从输出中删除的输入页码（从 1 开始）。
+pub(crate) fn finish_task(
+    result: TaskResult,
+    deleted_pages: &BTreeSet<u32>,
+) -> Result<FileResult, String> {
+    let audit_path = result.audit_path.ok_or("任务未生成审计记录")?;
+    let audit =
+        read_audit(Path::new(&audit_path)).map_err(|e| format!("读取审计记录失败: {}", e))?;
+    let report_path = report::write_report(&audit, deleted_pages)?;
+
+    Ok(FileResult {
+        output_path: result.output_path.ok_or("任务未写出脱敏文件")?,
+        audit_path,
+        report_path,
+        hits: result.hits_count,
+        warnings: audit.redact_warnings,
+        verify: audit.verify,
+    })
+}
+
+/// 通过核心流水线脱敏单个 PDF
+///
+/// 输出写到 `<output_directory>/<prefix><文件名>.pdf`，旁边写出审计记录、
+/// JSON 报告和 HTML 报告。`matched_texts` 为检测到的命中原文，用于校验输出；
+/// 规则来自规则包时传入 `rule_pack`，写入审计记录。
+pub(crate) fn run_pdf_task(
+    request: &FileProcessRequest,
+    options: &ProcessOptions,
+    matched_texts: &[String],
+    rule_pack: Option<&RulePackInfo>,
+    progress: PageProgress,
+) -> Result<FileResult, String> {
+    let output_path = output_path_for(
+        Path::new(&request.path),
+        Path::new(&options.output_directory),
+        &options.prefix,
+    );
+    let config = TaskConfig {
+        clean: (&options.cleaning).into(),
+        rule_pack: rule_pack.map(RulePackInfo::reference),
+        operator: options.operator.clone(),
+        ocr_engine: audit::ocr_engine(request.ocr_used),
+        ..task_config(
+            &request.path,
+            &output_path.to_string_lossy(),
+            &options.rules,
+            &options.verify,
+        )?
+    };
+    let doc = MaskedPdf {
+        request,
+        mode: &options.mode,
+        render: &options.render,
+        matched_texts,
+        progress,
+    };
+
+    let result = linch_core::run_task_with(&doc, config).map_err(describe_task_error)?;
+    log::info!("文件保存成功: {}", output_path.display());
+    let deleted_pages = request
+        .pages
+        .iter()
+        .filter(|p| p.action == "delete")
+        .map(|p| p.index as u32 + 1)
+        .collect();
+    finish_task(result, &deleted_pages)
+}
+
+/// 任务错误的提示信息
+pub(crate) fn describe_task_error(error: CoreError) -> String {
+    match error {
+        CoreError::RuleCompile(errors) => super::describe_compile_errors(&errors),
+        CoreError::Io(e) => {
+            let err_msg = e.to_string();
+            log::error!("读写文件失败: {}", err_msg);
+            // 检测文件被占用的各种错误信息
+            // Windows: "being used by another process" / "os error 32"
+            // Linux/macOS: "resource busy" / "Permission denied"
+            let is_file_locked = err_msg.contains("denied")
+                || err_msg.contains("being used")
+                || err_msg.contains("拒绝")
+                || err_msg.contains("os error 32")
+                || err_msg.contains("resource busy");
+
+            if is_file_locked {
+                "文件被占用，请关闭正在使用该文件的程序（如 PDF 阅读器）后重试".to_string()
+            } else {
+                format!("读写文件失败: {}", err_msg)
+            }
+        }
+        CoreError::InvalidConfig(_) => "输出文件不能覆盖源文件".to_string(),
+        CoreError::Document(e) => e.to_string(),
+        other => other.to_string(),
+    }
+}
+
+impl From<&CleanOptions> for CleaningOptions {
+    fn from(options: &CleanOptions) -> Self {
+        Self {
+            document_info: options.metadata,
+            xmp_metadata: options.xmp,
+            hidden_data: options.hidden,
+            annotations: options.annots,
+            forms: options.forms,
+            attachments: options.attachments,
+            javascript: options.javascript,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lopdf::{dictionary, Object, Stream};
+    use std::fs;
+
+    /// 单页空白文档，Info 字典中带作者
+    fn write_pdf(path: &Path) {
+        let mut doc = lopdf::Document::with_version("1.5");
+        let contents = doc.add_object(Stream::new(dictionary! {}, Vec::new()));
+        let pages_id = doc.new_object_id();
+        let page_id = doc.add_object(dictionary! {
+            "Type" => "Page",
+            "Parent" => pages_id,
+            "MediaBox" => vec![0.into(), 0.into(), 200.into(), 200.into()],
+            "Contents" => contents,
+        });
+        doc.objects.insert(
+            pages_id,
+            Object::Dictionary(dictionary! {
+                "Type" => "Pages",
+                "Kids" => vec![page_id.into()],
+                "Count" => 1,
+            }),
+        );
+        let catalog = doc.add_object(dictionary! { "Type" => "Catalog", "Pages" => pages_id });
+        let info = doc.add_object(dictionary! { "Author" => Object::string_literal("alice") });
+        doc.trailer.set("Root", catalog);
+        doc.trailer.set("Info", info);
+        doc.save(path).unwrap();
+    }
+
+    #[test]
+    fn test_run_pdf_task_writes_audit_and_report() {
+        let dir = std::env::temp_dir().join(format!("linch-task-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let input = dir.join("input.pdf");
+        write_pdf(&input);
+
+        let request: FileProcessRequest = serde_json::from_value(serde_json::json!({
+            "path": input.to_string_lossy(),
+            "pages": [],
+            "masks_by_page": {},
+        }))
+        .unwrap();
+        let options: ProcessOptions = serde_json::from_value(serde_json::json!({
+            "output_directory": dir.join("out").to_string_lossy(),
+            "cleaning": { "document_info": true },
+            "operator": "bob",
+            "verify": { "text_search": false, "ocr_sample": false },
+        }))
+        .unwrap();
+
+        let result = run_pdf_task(&request, &options, &[], None, &|_, _| Ok(())).unwrap();
+
+        assert_eq!(
+            Path::new(&result.output_path),
+            dir.join("out").join("redacted_input.pdf")
+        );
+        assert!(Path::new(&result.report_path).exists());
+        let audit = read_audit(Path::new(&result.audit_path)).unwrap();
+        assert_eq!(audit.operator.as_deref(), Some("bob"));
+        assert_eq!(audit.redact_mode.as_deref(), Some("auto"));
+        assert!(audit.clean.metadata);
+        assert!(!audit.clean_actions.is_empty());
+
+        let output = lopdf::Document::load(&result.output_path).unwrap();
+        let info = output.trailer.get(b"Info").unwrap().as_reference().unwrap();
+        assert!(output.get_dictionary(info).unwrap().get(b"Author").is_err());
+
+        fs::remove_dir_all(&dir).ok();
+    }
+}
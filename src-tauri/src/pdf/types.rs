@@ -39,6 +39,48 @@ pub struct CleaningOptions {
     pub javascript: bool,
 }
 
+/// 栅格化页面的图片编码
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ImageEncoding {
+    #[default]
+    Jpeg, // JPEG（DCTDecode），体积小，文字边缘有损
+    Flate, // Flate 无损压缩（与 PNG 相同的压缩算法）
+}
+
+/// 栅格化页面的颜色模式
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ColorMode {
+    #[default]
+    Rgb,
+    Gray,
+    Bilevel, // 1 位黑白，适用于黑白扫描件，始终无损压缩
+}
+
+/// 页面栅格化配置（SafeRender 与 pdfium 重建使用）
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct RenderConfig {
+    /// DPI（每英寸点数），默认 150
+    pub dpi: u32,
+    /// JPEG 质量（1-100），默认 90
+    pub jpeg_quality: u8,
+    pub encoding: ImageEncoding,
+    pub color: ColorMode,
+}
+
+impl Default for RenderConfig {
+    fn default() -> Self {
+        Self {
+            dpi: 150,
+            jpeg_quality: 90,
+            encoding: ImageEncoding::default(),
+            color: ColorMode::default(),
+        }
+    }
+}
+
 /// 页面内容类型
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
@@ -63,17 +105,6 @@ pub struct PdfAnalysis {
     pub recommended_mode: RedactionMode,
 }
 
-/// 检测规则
-#[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct Rule {
-    pub id: String,
-    pub name: String,
-    pub rule_type: String, // "keyword" | "regex"
-    pub pattern: String,
-    pub enabled: bool,
-}
-
 /// 检测命中结果
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -83,6 +114,27 @@ pub struct DetectionHit {
     pub rule_id: String,
     pub rule_name: String,
     pub snippet: String,
+    /// 置信度（0-1），区域、页面规则为空
+    #[serde(default)]
+    pub confidence: Option<f32>,
+    /// 命中依据
+    #[serde(default)]
+    pub reasons: Vec<linch_rules::MatchReason>,
+}
+
+/// 逐页检测进度
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DetectionProgress {
+    /// 刚处理完的页面（从 0 开始）
+    pub page: usize,
+    /// 已处理 / 待处理的页数
+    pub done: usize,
+    pub total: usize,
+    /// 该页是否使用了 OCR
+    pub ocr_used: bool,
+    /// 该页新增的命中
+    pub hits: Vec<DetectionHit>,
 }
 
 /// 检测边界框
@@ -111,30 +163,17 @@ pub struct MaskRect {
 }
 
 impl MaskRect {
-    /// 检查文字边界框是否与 mask 区域相交
-    pub fn intersects_text_bbox(
-        &self,
-        text_x: f32,
-        text_y: f32,
-        text_width: f32,
-        text_height: f32,
-    ) -> bool {
-        let text_left = text_x;
-        let text_right = text_x + text_width;
-        let text_bottom = text_y;
-        let text_top = text_y + text_height;
-
-        // margin 用于捕捉检测框边缘的字符，但不能太大以免误伤相邻内容
+    /// 转换为删除字形使用的遮罩
+    ///
+    /// 四周各扩展 1pt 用于捕捉检测框边缘的字符，但不能太大以免误伤相邻内容。
+    pub fn to_text_mask(self) -> linch_pdf::MaskRect {
         let margin: f32 = 1.0;
-        let mask_left = self.x - margin;
-        let mask_right = self.x + self.width + margin;
-        let mask_bottom = self.y - margin;
-        let mask_top = self.y + self.height + margin;
-
-        let x_overlap = text_left < mask_right && text_right > mask_left;
-        let y_overlap = text_bottom < mask_top && text_top > mask_bottom;
-
-        x_overlap && y_overlap
+        linch_pdf::MaskRect {
+            left: self.x - margin,
+            bottom: self.y - margin,
+            right: self.x + self.width + margin,
+            top: self.y + self.height + margin,
+        }
     }
 }
 
@@ -149,11 +188,24 @@ pub struct FileProcessRequest {
     pub path: String,
     pub pages: Vec<PageAction>,
     pub masks_by_page: BTreeMap<usize, Vec<Mask>>,
+    /// 生成遮罩的检测命中（仅用于审计记录）
+    #[serde(default)]
+    pub hits: Vec<DetectionHit>,
+    /// 检测阶段是否使用了 OCR（仅用于审计记录）
+    #[serde(default)]
+    pub ocr_used: bool,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct ProcessRequest {
     pub files: Vec<FileProcessRequest>,
+    #[serde(flatten)]
+    pub options: ProcessOptions,
+}
+
+/// 同一批文件共享的处理选项
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ProcessOptions {
     pub output_directory: String,
     #[serde(default = "default_prefix")]
     pub prefix: String,
@@ -161,6 +213,18 @@ pub struct ProcessRequest {
     pub mode: RedactionMode,
     #[serde(default)]
     pub cleaning: CleaningOptions,
+    /// 操作者（写入审计记录）
+    #[serde(default)]
+    pub operator: Option<String>,
+    /// 检测使用的规则（用于审计摘要和输出校验）
+    #[serde(default)]
+    pub rules: Vec<linch_rules::Rule>,
+    /// 输出校验选项
+    #[serde(default)]
+    pub verify: linch_core::VerifyOptions,
+    /// 页面栅格化配置
+    #[serde(default)]
+    pub render: RenderConfig,
 }
 
 fn default_prefix() -> String {
@@ -171,5 +235,14 @@ fn default_prefix() -> String {
 pub struct ProcessResult {
     pub success: bool,
     pub processed_files: Vec<String>,
+    /// 与输出文件一一对应的审计记录路径
+    #[serde(default)]
+    pub audit_files: Vec<String>,
+    /// 与输出文件一一对应的报告路径
+    #[serde(default)]
+    pub report_files: Vec<String>,
     pub errors: Vec<String>,
+    /// 不影响输出的警告（如未能迁移书签）
+    #[serde(default)]
+    pub warnings: Vec<String>,
 }
@@ -18,67 +18,55 @@ fn extract_box_values(arr: &[Object]) -> Option<(f32, f32, f32, f32)> {
     }
 }
 
+/// 查找页面属性，页面自身没有时沿 Parent 链查找（Rotate、MediaBox、CropBox 均可继承）
+fn inherited_attribute<'a>(
+    doc: &'a Document,
+    page_id: lopdf::ObjectId,
+    key: &[u8],
+) -> Option<&'a Object> {
+    let mut dict = doc.get_dictionary(page_id).ok()?;
+    // 限制层数，避免 Parent 循环引用
+    for _ in 0..32 {
+        if let Ok(value) = dict.get(key) {
+            return doc.dereference(value).ok().map(|(_, object)| object);
+        }
+        let parent = dict.get(b"Parent").and_then(Object::as_reference).ok()?;
+        dict = doc.get_dictionary(parent).ok()?;
+    }
+    None
+}
+
 /// 获取页面的旋转角度
 fn get_page_rotation(doc: &Document, page_id: lopdf::ObjectId) -> i32 {
-    if let Ok(Object::Dictionary(dict)) = doc.get_object(page_id) {
-        // 先检查页面自身的 Rotate 属性
-        if let Ok(Object::Integer(rotate)) = dict.get(b"Rotate") {
-            return *rotate as i32;
-        }
-        // 尝试从父页面继承
-        if let Ok(Object::Reference(parent_ref)) = dict.get(b"Parent") {
-            if let Ok(Object::Dictionary(parent_dict)) = doc.get_object(*parent_ref) {
-                if let Ok(Object::Integer(rotate)) = parent_dict.get(b"Rotate") {
-                    return *rotate as i32;
-                }
-            }
-        }
+    match inherited_attribute(doc, page_id, b"Rotate") {
+        Some(Object::Integer(rotate)) => *rotate as i32,
+        _ => 0, // 默认无旋转
+    }
+}
+
+/// 读取页面上的边界框属性（含继承）
+fn page_box(doc: &Document, page_id: lopdf::ObjectId, key: &[u8]) -> Option<(f32, f32, f32, f32)> {
+    match inherited_attribute(doc, page_id, key) {
+        Some(Object::Array(arr)) => extract_box_values(arr),
+        _ => None,
     }
-    0 // 默认无旋转
 }
 
 /// 获取页面的有效边界框（优先使用 CropBox，否则使用 MediaBox）
-/// 返回 (llx, lly, urx, ury, rotation)
+///
+/// 两者都沿页面树继承。pdfium 与 pdf.js 都只渲染 CropBox 内的区域，
+/// mask 坐标与栅格化图片都相对于这个区域。
+/// 返回 (llx, lly, urx, ury)
 pub fn get_media_box(doc: &Document, page_id: lopdf::ObjectId) -> (f32, f32, f32, f32) {
     let rotation = get_page_rotation(doc, page_id);
     log::info!("[MediaBox] 页面旋转角度: {} 度", rotation);
 
-    let raw_box = if let Ok(Object::Dictionary(dict)) = doc.get_object(page_id) {
-        // 优先尝试 CropBox（实际可见区域）
-        if let Ok(Object::Array(arr)) = dict.get(b"CropBox") {
-            if let Some(values) = extract_box_values(arr) {
-                log::info!("[MediaBox] 使用 CropBox: {:?}", values);
-                Some(values)
-            } else {
-                None
-            }
-        // 其次尝试 MediaBox
-        } else if let Ok(Object::Array(arr)) = dict.get(b"MediaBox") {
-            if let Some(values) = extract_box_values(arr) {
-                log::info!("[MediaBox] 使用 MediaBox: {:?}", values);
-                Some(values)
-            } else {
-                None
-            }
-        // 尝试从父页面继承
-        } else if let Ok(Object::Reference(parent_ref)) = dict.get(b"Parent") {
-            if let Ok(Object::Dictionary(parent_dict)) = doc.get_object(*parent_ref) {
-                if let Ok(Object::Array(arr)) = parent_dict.get(b"MediaBox") {
-                    if let Some(values) = extract_box_values(arr) {
-                        log::info!("[MediaBox] 从父页面继承 MediaBox: {:?}", values);
-                        Some(values)
-                    } else {
-                        None
-                    }
-                } else {
-                    None
-                }
-            } else {
-                None
-            }
-        } else {
-            None
-        }
+    let raw_box = if let Some(values) = page_box(doc, page_id, b"CropBox") {
+        log::info!("[MediaBox] 使用 CropBox: {:?}", values);
+        Some(values)
+    } else if let Some(values) = page_box(doc, page_id, b"MediaBox") {
+        log::info!("[MediaBox] 使用 MediaBox: {:?}", values);
+        Some(values)
     } else {
         None
     };
@@ -91,7 +79,8 @@ pub fn get_media_box(doc: &Document, page_id: lopdf::ObjectId) -> (f32, f32, f32
     // 注意：MediaBox 的坐标是未旋转的
     // 但前端 pdf.js 显示时会自动应用旋转
     // 我们在这里返回原始的 MediaBox，旋转在坐标转换时处理
-    (llx, lly, urx, ury)
+    // 坐标可能按相反顺序给出，统一为左下、右上
+    (llx.min(urx), lly.min(ury), llx.max(urx), lly.max(ury))
 }
 
 /// 获取页面的有效边界框和旋转角度
@@ -224,15 +213,6 @@ pub fn convert_masks_to_pdf_coords_with_rotation(
         .collect()
 }
 
-/// 从 Object 获取数值
-pub fn get_number(obj: &Object) -> Option<f32> {
-    match obj {
-        Object::Integer(i) => Some(*i as f32),
-        Object::Real(r) => Some(*r),
-        _ => None,
-    }
-}
-
 /// 获取流内容（支持压缩和未压缩的流）
 pub fn get_stream_content(stream: &Stream) -> Result<Vec<u8>, String> {
     match stream.decompressed_content() {
@@ -0,0 +1,488 @@
+//! 脱敏报告
+//!
+//! 根据审计记录在输出文件旁生成 `<文件名>.report.html`，供合规人员人工复核：
+//! 涉及的页面、规则名称、脱敏后的片段、清理动作、校验结果，以及 PDF 页面
+//! 的对比缩略图。报告为单个自包含的 HTML 文件，缩略图以 data URI 内嵌。
+//!
+//! 报告会与输出文件一起分发，缩略图只从脱敏后的输出渲染：对比图在输出页面上
+//! 标出遮罩位置，而不是内嵌原文页面。
+
+use base64::Engine;
+use image::{DynamicImage, Rgb, RgbImage, RgbaImage};
+use imageproc::drawing::draw_hollow_rect_mut;
+use imageproc::rect::Rect;
+use linch_core::audit::TaskAudit;
+use linch_core::pipeline::sidecar_path;
+use linch_core::Region;
+use linch_render::{OutputFormat, RenderOptions};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write as _;
+use std::fs;
+use std::path::Path;
+
+/// 缩略图渲染分辨率
+const THUMBNAIL_DPI: u32 = 48;
+
+/// 缩略图 JPEG 质量
+const THUMBNAIL_QUALITY: u8 = 80;
+
+/// 最多生成缩略图的页数，避免大文件报告体积失控
+const MAX_THUMBNAIL_PAGES: usize = 20;
+
+/// 在输出文件旁写出 HTML 报告，返回报告路径
+///
+/// 输入输出均为 PDF 时为涉及的页面生成对比缩略图，渲染失败只在报告中注明。
+/// `deleted_pages` 为从输出中删除的输入页码（从 1 开始），这些页面不生成缩略图，
+/// 其余页面按删除后的位置在输出中查找。
+pub fn write_report(audit: &TaskAudit, deleted_pages: &BTreeSet<u32>) -> Result<String, String> {
+    let output = audit.output.as_ref().ok_or("审计记录缺少输出文件")?;
+    let thumbnails = is_pdf(&audit.input.path) && is_pdf(&output.path);
+    let html = render_html(audit, &output.path, thumbnails, deleted_pages);
+
+    let path = sidecar_path(Path::new(&output.path), "report.html");
+    fs::write(&path, html).map_err(|e| format!("写入报告失败: {}", e))?;
+    Ok(path.to_string_lossy().to_string())
+}
+
+/// 生成报告 HTML
+fn render_html(
+    audit: &TaskAudit,
+    output_path: &str,
+    thumbnails: bool,
+    deleted_pages: &BTreeSet<u32>,
+) -> String {
+    let title = format!("脱敏报告 - {}", file_name(&audit.input.path));
+    let pages = pages_touched(audit);
+    let mut html = String::new();
+
+    let _ = write!(
+        html,
+        "<!DOCTYPE html>\n<html lang=\"zh-CN\">\n<head>\n<meta charset=\"utf-8\">\n\
+         <title>{}</title>\n<style>{}</style>\n</head>\n<body>\n<h1>{}</h1>\n",
+        escape(&title),
+        STYLE,
+        escape(&title)
+    );
+
+    // 概要
+    html.push_str("<h2>概要</h2>\n<table>\n");
+    let mut summary = vec![("输入文件", audit.input.path.clone())];
+    summary.push(("输入 SHA-256", audit.input.sha256.clone()));
+    if let Some(output) = &audit.output {
+        summary.push(("输出文件", output.path.clone()));
+        summary.push(("输出 SHA-256", output.sha256.clone()));
+    }
+    summary.push(("处理时间", format_time(audit.created_at)));
+    summary.push(("工具版本", audit.tool_version.clone()));
+    if let Some(operator) = &audit.operator {
+        summary.push(("操作者", operator.clone()));
+    }
+    if let Some(mode) = &audit.redact_mode {
+        summary.push(("脱敏模式", mode.clone()));
+    }
+    if !audit.page_modes.is_empty() {
+        let modes = audit
+            .page_modes
+            .iter()
+            .map(|(page, mode)| format!("第 {} 页: {}", page, mode))
+            .collect::<Vec<_>>()
+            .join(", ");
+        summary.push(("各页脱敏模式", modes));
+    }
+    summary.push((
+        "OCR",
+        match &audit.ocr_engine {
+            Some(engine) => format!(
+                "{} {}",
+                engine.engine,
+                engine.version.as_deref().unwrap_or("")
+            ),
+            None => "未使用".to_string(),
+        },
+    ));
+    if !audit.rules_hash.is_empty() {
+        summary.push(("规则摘要", audit.rules_hash.clone()));
+    }
+    if let Some(pack) = &audit.rule_pack {
+        summary.push((
+            "规则包",
+            format!("{} {}（{}）", pack.name, pack.version, pack.hash),
+        ));
+        summary.push((
+            "规则包签名",
+            match &pack.signer {
+                Some(signer) => format!("已验证，公钥 {}", signer),
+                None => "未签名".to_string(),
+            },
+        ));
+    }
+    summary.push(("命中数量", audit.hits.len().to_string()));
+    summary.push((
+        "涉及页面",
+        if pages.is_empty() {
+            "无".to_string()
+        } else {
+            join_pages(pages.iter())
+        },
+    ));
+    if !deleted_pages.is_empty() {
+        summary.push(("删除页面", join_pages(deleted_pages.iter())));
+    }
+    for (label, value) in &summary {
+        let _ = writeln!(
+            html,
+            "<tr><th>{}</th><td>{}</td></tr>",
+            label,
+            escape(value)
+        );
+    }
+    html.push_str("</table>\n");
+
+    // 校验结果
+    html.push_str("<h2>校验结果</h2>\n");
+    let verify = &audit.verify;
+    if !verify.performed {
+        html.push_str("<p>未执行校验</p>\n");
+    } else {
+        if verify.ok {
+            html.push_str("<p class=\"ok\">通过：未发现残留的敏感信息</p>\n");
+        } else {
+            let _ = writeln!(
+                html,
+                "<p class=\"fail\">未通过：发现 {} 处可能残留的敏感信息</p>",
+                verify.warnings.len()
+            );
+        }
+        push_list(&mut html, &verify.warnings);
+        if !verify.ocr_pages.is_empty() {
+            let _ = writeln!(
+                html,
+                "<p>OCR 抽样页面：{}</p>",
+                join_pages(verify.ocr_pages.iter())
+            );
+        }
+        push_list(&mut html, &verify.notes);
+    }
+
+    // 脱敏警告
+    if !audit.redact_warnings.is_empty() {
+        html.push_str("<h2>脱敏警告</h2>\n");
+        push_list(&mut html, &audit.redact_warnings);
+    }
+
+    // 清理动作
+    html.push_str("<h2>清理动作</h2>\n");
+    if audit.clean_actions.is_empty() {
+        html.push_str("<p>无</p>\n");
+    } else {
+        push_list(&mut html, &audit.clean_actions);
+    }
+
+    // 命中列表
+    html.push_str("<h2>命中列表</h2>\n");
+    if audit.hits.is_empty() {
+        html.push_str("<p>无</p>\n");
+    } else {
+        html.push_str("<table>\n<tr><th>页码</th><th>规则</th><th>片段</th></tr>\n");
+        for hit in &audit.hits {
+            let _ = writeln!(
+                html,
+                "<tr><td>{}</td><td>{}</td><td>{}</td></tr>",
+                hit.page,
+                escape(&hit.rule_name),
+                escape(&hit.snippet)
+            );
+        }
+        html.push_str("</table>\n");
+    }
+
+    // 页面对比：已删除的页面不在输出中，不生成缩略图
+    let kept: Vec<u32> = pages
+        .iter()
+        .copied()
+        .filter(|page| !deleted_pages.contains(page))
+        .collect();
+    if thumbnails && !kept.is_empty() {
+        html.push_str("<h2>页面对比</h2>\n");
+        let shown = &kept[..kept.len().min(MAX_THUMBNAIL_PAGES)];
+        let masks = |page: &u32| audit.masks.get(page).map(Vec::as_slice).unwrap_or(&[]);
+        let rendered = render_thumbnails(
+            output_path,
+            shown
+                .iter()
+                .map(|page| (output_page(*page, deleted_pages), masks(page)))
+                .collect(),
+        );
+        if let Err(e) = &rendered {
+            log::warn!("[Report] 渲染缩略图失败: {}", e);
+        }
+        for page in shown {
+            let output_page = output_page(*page, deleted_pages);
+            let heading = if output_page == *page {
+                format!("第 {} 页", page)
+            } else {
+                format!("第 {} 页（输出第 {} 页）", page, output_page)
+            };
+            let _ = writeln!(
+                html,
+                "<div class=\"page\"><h3>{}</h3>\n{}\n</div>",
+                heading,
+                thumbnails_for(&rendered, output_page)
+            );
+        }
+        if kept.len() > MAX_THUMBNAIL_PAGES {
+            let _ = writeln!(
+                html,
+                "<p>其余 {} 页未生成缩略图</p>",
+                kept.len() - MAX_THUMBNAIL_PAGES
+            );
+        }
+    }
+
+    html.push_str("</body>\n</html>\n");
+    html
+}
+
+/// 报告样式
+const STYLE: &str = "body{font-family:sans-serif;margin:2em;color:#222}\
+table{border-collapse:collapse;margin-bottom:1em}\
+th,td{border:1px solid #ccc;padding:4px 8px;text-align:left;vertical-align:top}\
+th{background:#f5f5f5}.ok{color:#1a7f37}.fail{color:#cf222e;font-weight:bold}\
+.page{display:inline-block;margin:0 1em 1em 0;vertical-align:top}\
+figure{display:inline-block;margin:0 .5em}img{border:1px solid #ccc;max-width:320px}";
+
+/// 涉及的页面（从 1 开始），包括命中所在页和实际应用遮罩的页
+fn pages_touched(audit: &TaskAudit) -> BTreeSet<u32> {
+    audit
+        .hits
+        .iter()
+        .map(|h| h.page)
+        .chain(
+            audit
+                .masks
+                .iter()
+                .filter(|(_, masks)| !masks.is_empty())
+                .map(|(page, _)| *page),
+        )
+        .collect()
+}
+
+/// 输入页码在删除页面后对应的输出页码（均从 1 开始）
+fn output_page(page: u32, deleted_pages: &BTreeSet<u32>) -> u32 {
+    page - deleted_pages.range(..page).count() as u32
+}
+
+fn join_pages<'a>(pages: impl Iterator<Item = &'a u32>) -> String {
+    pages.map(u32::to_string).collect::<Vec<_>>().join(", ")
+}
+
+/// 格式化审计记录中的 Unix 时间戳（本地时区）
+fn format_time(timestamp: u64) -> String {
+    chrono::DateTime::from_timestamp(timestamp as i64, 0)
+        .map(|time| time.with_timezone(&chrono::Local).to_rfc3339())
+        .unwrap_or_else(|| timestamp.to_string())
+}
+
+/// 输出页面的对比缩略图片段：标出遮罩的缩略图和原样缩略图，渲染失败时注明原因
+fn thumbnails_for(rendered: &Result<Thumbnails, String>, output_page: u32) -> String {
+    match rendered
+        .as_ref()
+        .map(|thumbnails| thumbnails.get(&output_page))
+    {
+        Ok(Some((outlined, redacted))) => format!(
+            "{}\n{}",
+            figure(outlined, "遮罩位置"),
+            figure(redacted, "脱敏后")
+        ),
+        Ok(None) => "<figure><figcaption>缩略图不可用</figcaption></figure>".to_string(),
+        Err(e) => format!(
+            "<figure><figcaption>缩略图不可用（{}）</figcaption></figure>",
+            escape(e)
+        ),
+    }
+}
+
+fn figure(data: &str, caption: &str) -> String {
+    format!(
+        "<figure><img src=\"data:image/jpeg;base64,{}\" alt=\"{}\"><figcaption>{}</figcaption></figure>",
+        data, caption, caption
+    )
+}
+
+/// 按输出页码（从 1 开始）保存的缩略图：(标出遮罩的缩略图, 原样缩略图)，均为 base64 编码的 JPEG
+type Thumbnails = BTreeMap<u32, (String, String)>;
+
+/// 打开输出文件一次，在内存中渲染各输出页面的缩略图
+///
+/// `pages` 为输出页码及该页的遮罩。
+fn render_thumbnails(
+    pdf_path: &str,
+    pages: BTreeMap<u32, &[Region]>,
+) -> Result<Thumbnails, String> {
+    let options = RenderOptions {
+        dpi: THUMBNAIL_DPI,
+        pages: Some(pages.keys().copied().collect()),
+        format: OutputFormat::Rgba,
+        ..RenderOptions::default()
+    };
+    let mut thumbnails = Thumbnails::new();
+    linch_render::render_pdf(pdf_path, &options, |rendered| {
+        let image = RgbaImage::from_raw(rendered.width, rendered.height, rendered.bytes)
+            .map(DynamicImage::ImageRgba8)
+            .ok_or_else(|| anyhow::anyhow!("第 {} 页像素数据不完整", rendered.page))?;
+        let redacted = encode_jpeg(&image)?;
+
+        let mut outlined = image.to_rgb8();
+        draw_outlines(
+            &mut outlined,
+            pages.get(&rendered.page).copied().unwrap_or(&[]),
+        );
+        let outlined = encode_jpeg(&DynamicImage::ImageRgb8(outlined))?;
+
+        thumbnails.insert(rendered.page, (outlined, redacted));
+        Ok(())
+    })
+    .map_err(|e| e.to_string())?;
+    Ok(thumbnails)
+}
+
+/// 用红框标出遮罩（Region 为相对页面的 0-1 坐标，原点在左上角）
+fn draw_outlines(image: &mut RgbImage, masks: &[Region]) {
+    let (width, height) = image.dimensions();
+    let red = Rgb([220u8, 38u8, 38u8]);
+
+    for mask in masks {
+        let x = (mask.x * width as f64) as i32;
+        let y = (mask.y * height as f64) as i32;
+        let w = ((mask.width * width as f64) as u32).max(1);
+        let h = ((mask.height * height as f64) as u32).max(1);
+        draw_hollow_rect_mut(image, Rect::at(x, y).of_size(w, h), red);
+        // 缩略图分辨率较低，加一圈外框保证可见
+        draw_hollow_rect_mut(image, Rect::at(x - 1, y - 1).of_size(w + 2, h + 2), red);
+    }
+}
+
+fn encode_jpeg(image: &DynamicImage) -> anyhow::Result<String> {
+    let data = linch_render::encode(image, OutputFormat::Jpeg, THUMBNAIL_QUALITY)
+        .map_err(|e| anyhow::anyhow!("编码缩略图失败: {}", e))?;
+    Ok(base64::engine::general_purpose::STANDARD.encode(data))
+}
+
+fn push_list(html: &mut String, items: &[String]) {
+    if items.is_empty() {
+        return;
+    }
+    html.push_str("<ul>\n");
+    for item in items {
+        let _ = writeln!(html, "<li>{}</li>", escape(item));
+    }
+    html.push_str("</ul>\n");
+}
+
+fn is_pdf(path: &str) -> bool {
+    Path::new(path)
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.eq_ignore_ascii_case("pdf"))
+        .unwrap_or(false)
+}
+
+fn file_name(path: &str) -> String {
+    Path::new(path)
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| path.to_string())
+}
+
+/// 转义 HTML 特殊字符
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for ch in text.chars() {
+        match ch {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(ch),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use linch_core::TaskHit;
+
+    #[test]
+    fn test_report_lists_hits_and_escapes_text() {
+        let dir = std::env::temp_dir().join(format!("linch-report-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let input = dir.join("input.txt");
+        let output = dir.join("redacted_input.txt");
+        fs::write(&input, b"original").unwrap();
+        fs::write(&output, b"redacted").unwrap();
+
+        let mut audit = TaskAudit::new(&input, Some(&output)).unwrap();
+        audit.clean_actions = vec!["删除 <Author>".to_string()];
+        audit.redact_warnings = vec!["未能迁移书签".to_string()];
+        audit.hits.push(TaskHit {
+            page: 3,
+            rule_id: "email".to_string(),
+            rule_name: "邮箱".to_string(),
+            snippet: "a@b****.com".to_string(),
+            range: Some(0..11),
+            bbox: None,
+            confidence: None,
+        });
+
+        let path = write_report(&audit, &BTreeSet::new()).unwrap();
+        assert_eq!(Path::new(&path), dir.join("redacted_input.report.html"));
+
+        let html = fs::read_to_string(&path).unwrap();
+        assert!(html.contains("a@b****.com"));
+        assert!(html.contains("删除 &lt;Author&gt;"));
+        assert!(html.contains("<td>3</td>"));
+        assert!(html.contains("未执行校验"));
+        assert!(html.contains("<li>未能迁移书签</li>"));
+        // 非 PDF 文档不生成缩略图
+        assert!(!html.contains("页面对比"));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_report_skips_deleted_pages_and_maps_output_pages() {
+        let dir = std::env::temp_dir().join(format!("linch-report-pdf-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let input = dir.join("input.pdf");
+        let output = dir.join("redacted_input.pdf");
+        fs::write(&input, b"original").unwrap();
+        fs::write(&output, b"redacted").unwrap();
+
+        let region = Region {
+            x: 0.1,
+            y: 0.1,
+            width: 0.2,
+            height: 0.05,
+        };
+        let mut audit = TaskAudit::new(&input, Some(&output)).unwrap();
+        audit.masks.insert(2, vec![region]);
+        audit.masks.insert(5, vec![region]);
+        let deleted = BTreeSet::from([2, 3]);
+
+        let path = write_report(&audit, &deleted).unwrap();
+        let html = fs::read_to_string(&path).unwrap();
+        assert!(html.contains("<tr><th>删除页面</th><td>2, 3</td></tr>"));
+        assert!(html.contains("<h3>第 5 页（输出第 3 页）</h3>"));
+        assert!(!html.contains("<h3>第 2 页"));
+        // 不从原文件渲染缩略图
+        assert!(!html.contains("脱敏前"));
+
+        assert_eq!(output_page(1, &deleted), 1);
+        assert_eq!(output_page(4, &deleted), 2);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,7 +1,9 @@
-import { useState } from "react"
+import { useRef, useState } from "react"
 import { useTranslation } from "react-i18next"
 import { nanoid } from "nanoid"
 import { invoke } from "@tauri-apps/api/core"
+import { listen, type UnlistenFn } from "@tauri-apps/api/event"
+import { toBackendRules } from "@/lib/tauri/rules"
 import {
   Search,
   CreditCard,
@@ -17,6 +19,7 @@ import {
   FileText,
   Files,
   Settings,
+  X,
 } from "lucide-react"
 import { toast } from "sonner"
 import { Button } from "@/components/ui/button"
@@ -43,17 +46,29 @@ async function analyzePdf(path: string): Promise<PdfAnalysis> {
   return await invoke<PdfAnalysis>("analyze_pdf", { pdfPath: path })
 }
 
+/// 后端每处理完一页发送的检测进度（含该页新增命中）
+interface DetectionProgressEvent {
+  detectionId: string
+  page: number
+  done: number
+  total: number
+  ocrUsed: boolean
+  hits: DetectionHit[]
+}
+
 async function detectSensitiveContent(
   path: string,
   rules: Rule[],
   useOcr: boolean = false,
-  pageIndices?: number[]
+  pageIndices?: number[],
+  detectionId?: string
 ): Promise<DetectionHit[]> {
   return await invoke<DetectionHit[]>("detect_sensitive_content", {
     pdfPath: path,
-    rules,
+    rules: toBackendRules(rules),
     useOcr,
     pageIndices,
+    detectionId,
   })
 }
 
@@ -83,6 +98,9 @@ export function DetectionPanel() {
   const markAllHitsAdded = useDetectionRulesStore((s) => s.markAllHitsAdded)
 
   const [scanning, setScanning] = useState(false)
+  const [progress, setProgress] = useState<{ done: number; total: number } | null>(null)
+  const detectionIdRef = useRef<string | null>(null)
+  const cancelledRef = useRef(false)
   const [expanded, setExpanded] = useState(true)
   const [needsOcr, setNeedsOcr] = useState(false)
   const [scanScope, setScanScope] = useState<"current" | "all">("all")
@@ -102,6 +120,11 @@ export function DetectionPanel() {
 
     setScanning(true)
     setNeedsOcr(false)
+    cancelledRef.current = false
+
+    const detectionId = nanoid()
+    const partialHits: DetectionHit[] = []
+    let unlisten: UnlistenFn | undefined
 
     try {
       const analysis = await analyzePdf(selectedFile.path)
@@ -118,11 +141,24 @@ export function DetectionPanel() {
 
       const useOcr = imagePageCount > 0 && ocrReady
       const pageIndices = scanScope === "current" ? [currentPage] : undefined
+
+      // 逐页显示进度和已发现的命中
+      unlisten = await listen<DetectionProgressEvent>("detection-progress", (event) => {
+        if (event.payload.detectionId !== detectionId) return
+        setProgress({ done: event.payload.done, total: event.payload.total })
+        if (event.payload.hits.length > 0) {
+          partialHits.push(...event.payload.hits)
+          setHits(fileId, [...partialHits])
+        }
+      })
+      detectionIdRef.current = detectionId
+
       const results = await detectSensitiveContent(
         selectedFile.path,
         enabledRules,
         useOcr,
-        pageIndices
+        pageIndices,
+        detectionId
       )
       setHits(fileId, results)
 
@@ -132,13 +168,31 @@ export function DetectionPanel() {
         toast.success(t("detection.foundResults", { count: results.length }))
       }
     } catch (e) {
-      console.error("Detection failed:", e)
-      toast.error(t("detection.scanFailed"))
+      if (cancelledRef.current) {
+        toast.info(t("detection.scanCancelled", { count: partialHits.length }))
+      } else {
+        console.error("Detection failed:", e)
+        toast.error(t("detection.scanFailed"))
+      }
     } finally {
+      unlisten?.()
+      detectionIdRef.current = null
+      setProgress(null)
       setScanning(false)
     }
   }
 
+  const cancelDetection = async () => {
+    const detectionId = detectionIdRef.current
+    if (!detectionId) return
+    cancelledRef.current = true
+    try {
+      await invoke("cancel_detection", { detectionId })
+    } catch (e) {
+      console.error("Cancel detection failed:", e)
+    }
+  }
+
   // 存储每个 hit 对应的 mask id，用于删除
   const [hitMaskIds, setHitMaskIds] = useState<Map<number, string>>(new Map())
 
@@ -282,8 +336,23 @@ export function DetectionPanel() {
               disabled={scanning}
             >
               <Search className={`h-3.5 w-3.5 mr-1.5 ${scanning ? "animate-pulse" : ""}`} />
-              {scanning ? t("common.scanning") : t("detection.scan")}
+              {scanning
+                ? progress
+                  ? t("detection.scanProgress", progress)
+                  : t("common.scanning")
+                : t("detection.scan")}
             </Button>
+            {scanning && (
+              <Button
+                variant="outline"
+                size="sm"
+                className="px-2"
+                onClick={cancelDetection}
+                title={t("common.cancel")}
+              >
+                <X className="h-3.5 w-3.5" />
+              </Button>
+            )}
             <DropdownMenu>
               <DropdownMenuTrigger asChild>
                 <Button variant="outline" size="sm" className="px-2">
@@ -376,6 +445,16 @@ export function DetectionPanel() {
                           </span>
                           <span className="font-medium">{hit.ruleName}</span>
                           <span className="text-muted-foreground ml-1.5">{hit.snippet}</span>
+                          {hit.confidence != null && (
+                            <span
+                              className="text-xs text-muted-foreground ml-1.5"
+                              title={hit.reasons?.map((r) => r.kind).join(", ")}
+                            >
+                              {t("detection.confidence", {
+                                value: Math.round(hit.confidence * 100),
+                              })}
+                            </span>
+                          )}
                         </div>
                         {isAdded ? (
                           <Button
@@ -20,6 +20,7 @@ export function PreviewPane() {
         // PDF 仍然使用原有的 PreviewCanvas（需要 PdfFile 类型）
         // 这里暂时显示文本预览，后续可以适配
         return <TextPreview document={document} />
+      case "docx":
       case "txt":
       case "md":
         return <TextPreview document={document} />
@@ -1,10 +1,13 @@
 import { useMemo, useState } from "react"
-import { Trash2, RotateCcw, Plus } from "lucide-react"
+import { Trash2, RotateCcw, Plus, Download, Upload } from "lucide-react"
+import { open, save } from "@tauri-apps/plugin-dialog"
+import { toast } from "sonner"
 import { Input } from "@/components/ui/input"
 import { Switch } from "@/components/ui/switch"
 import { Button } from "@/components/ui/button"
 import { useDetectionRulesStore } from "@/stores"
 import { builtinRuleIds } from "@/stores/useDetectionRulesStore"
+import { exportRulePack, toBackendRules, type RulePackInfo } from "@/lib/tauri/rules"
 
 type RuleDraft = {
   name: string
@@ -18,6 +21,9 @@ export function DetectionRulesSettings() {
   const updateRule = useDetectionRulesStore((s) => s.updateRule)
   const removeRule = useDetectionRulesStore((s) => s.removeRule)
   const resetRules = useDetectionRulesStore((s) => s.resetRules)
+  const importRulePack = useDetectionRulesStore((s) => s.importRulePack)
+  const [pack, setPack] = useState({ name: "", version: "1.0.0" })
+  const [packInfo, setPackInfo] = useState<RulePackInfo | null>(null)
   const [draft, setDraft] = useState<RuleDraft>({
     name: "",
     ruleType: "regex",
@@ -37,6 +43,48 @@ export function DetectionRulesSettings() {
     setDraft({ name: "", ruleType: "regex", pattern: "" })
   }
 
+  const handleImportPack = async () => {
+    const selected = await open({
+      multiple: false,
+      filters: [{ name: "规则包", extensions: ["json", "toml"] }],
+      title: "导入规则包",
+    })
+    if (!selected) return
+    try {
+      const info = await importRulePack(selected as string)
+      setPackInfo(info)
+      setPack({ name: info.name, version: info.version })
+      toast.success(`已导入规则包 ${info.name} ${info.version}`)
+    } catch (err) {
+      toast.error(`导入规则包失败: ${err}`)
+    }
+  }
+
+  const handleExportPack = async () => {
+    if (!pack.name.trim() || !pack.version.trim()) {
+      toast.error("请填写规则包名称和版本")
+      return
+    }
+    const selected = await save({
+      defaultPath: `${pack.name.trim()}.json`,
+      filters: [{ name: "规则包", extensions: ["json", "toml"] }],
+      title: "导出规则包",
+    })
+    if (!selected) return
+    try {
+      const info = await exportRulePack(
+        selected,
+        pack.name.trim(),
+        pack.version.trim(),
+        toBackendRules(rules)
+      )
+      setPackInfo(info)
+      toast.success(`已导出规则包 ${info.name} ${info.version}`)
+    } catch (err) {
+      toast.error(`导出规则包失败: ${err}`)
+    }
+  }
+
   return (
     <div className="space-y-4">
       <div className="flex items-center justify-between">
@@ -47,6 +95,38 @@ export function DetectionRulesSettings() {
         </Button>
       </div>
 
+      <div className="rounded-lg border p-3 space-y-2">
+        <div className="text-xs text-muted-foreground">规则包</div>
+        <div className="grid grid-cols-2 gap-2">
+          <Input
+            value={pack.name}
+            onChange={(e) => setPack((prev) => ({ ...prev, name: e.target.value }))}
+            placeholder="规则包名称"
+          />
+          <Input
+            value={pack.version}
+            onChange={(e) => setPack((prev) => ({ ...prev, version: e.target.value }))}
+            placeholder="版本"
+          />
+        </div>
+        <div className="flex items-center gap-2">
+          <Button variant="outline" size="sm" onClick={handleImportPack}>
+            <Upload className="h-4 w-4 mr-1" />
+            导入
+          </Button>
+          <Button variant="outline" size="sm" onClick={handleExportPack}>
+            <Download className="h-4 w-4 mr-1" />
+            导出
+          </Button>
+        </div>
+        {packInfo && (
+          <div className="text-xs text-muted-foreground break-all">
+            {packInfo.name} {packInfo.version} · {packInfo.hash.slice(0, 16)}
+            {packInfo.signer ? " · 签名已验证" : " · 未签名"}
+          </div>
+        )}
+      </div>
+
       <div className="rounded-lg border p-3 space-y-2">
         <div className="text-xs text-muted-foreground">新增自定义规则</div>
         <div className="grid grid-cols-2 gap-2">
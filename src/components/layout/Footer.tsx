@@ -56,7 +56,10 @@ export function Footer() {
         const outputPath = `${settings.output.directory}/redacted_${baseName}.${ext}`
 
         try {
-          const result = await applyRedaction(doc.path, enabledRules, outputPath)
+          const result = await applyRedaction(doc.path, enabledRules, outputPath, {
+            text_search: settings.verification.textRecheck,
+            ocr_sample: settings.verification.imageSampling,
+          })
           if (result.success) {
             successCount++
             console.log(`[OK] ${doc.name}: ${result.message}`)
@@ -38,8 +38,9 @@ export function MainPanel() {
     const selected = await open({
       multiple: true,
       filters: [
-        { name: "All Supported", extensions: ["pdf", "txt", "md"] },
+        { name: "All Supported", extensions: ["pdf", "docx", "txt", "md"] },
         { name: "PDF", extensions: ["pdf"] },
+        { name: "Word", extensions: ["docx"] },
         { name: "Text", extensions: ["txt", "md"] },
       ],
       title: t("sidebar.addFile"),
@@ -26,8 +26,9 @@ export function Sidebar() {
     const selected = await open({
       multiple: true,
       filters: [
-        { name: "All Supported", extensions: ["pdf", "txt", "md"] },
+        { name: "All Supported", extensions: ["pdf", "docx", "txt", "md"] },
         { name: "PDF", extensions: ["pdf"] },
+        { name: "Word", extensions: ["docx"] },
         { name: "Text", extensions: ["txt", "md"] },
       ],
       title: t("sidebar.addFile"),
@@ -46,6 +47,8 @@ export function Sidebar() {
         return "📝"
       case "md":
         return "📑"
+      case "docx":
+        return "📘"
       default:
         return "📁"
     }
@@ -15,6 +15,10 @@ export interface AppConfig {
     psm?: number
     oem?: number
   }
+  /// 受信的规则包签名公钥（ed25519，十六进制）
+  trustedRulePackKeys?: string[]
+  /// 是否只允许导入已签名的规则包
+  requireSignedRulePacks?: boolean
 }
 
 export async function loadConfig(): Promise<AppConfig> {
@@ -1,5 +1,6 @@
 import { invoke } from "@tauri-apps/api/core"
 import type { DocumentPage, FileType, Rule } from "@/types"
+import { toBackendRules } from "./rules"
 
 /// 后端返回的文档信息
 export interface DocumentInfo {
@@ -30,36 +31,14 @@ export interface MatchPreviewResult {
 export interface RedactionResult {
   success: boolean
   output_path: string | null
+  /// 审计记录路径（输出文件旁的 .audit.json）
+  audit_path: string | null
+  /// 报告路径（输出文件旁的 .report.html）
+  report_path: string | null
   matches_count: number
   message: string
 }
 
-/// 前端规则格式（传给后端）
-interface FrontendRule {
-  id: string
-  name: string
-  enabled: boolean
-  is_system: boolean
-  ruleType: string
-  pattern: string
-}
-
-/// 将前端规则转换为后端格式
-function convertRules(rules: Rule[]): FrontendRule[] {
-  return rules.map((rule) => ({
-    id: rule.id,
-    name: rule.name,
-    enabled: rule.enabled,
-    is_system:
-      rule.id.startsWith("id_") ||
-      rule.id.startsWith("phone_") ||
-      rule.id.startsWith("email") ||
-      rule.id.startsWith("bank_"),
-    ruleType: rule.ruleType,
-    pattern: rule.pattern,
-  }))
-}
-
 /// 加载文档
 export async function loadDocument(filePath: string): Promise<DocumentInfo> {
   return invoke<DocumentInfo>("load_document", { filePath })
@@ -69,20 +48,28 @@ export async function loadDocument(filePath: string): Promise<DocumentInfo> {
 export async function previewMatches(filePath: string, rules: Rule[]): Promise<MatchPreviewResult> {
   return invoke<MatchPreviewResult>("preview_matches", {
     filePath,
-    rules: convertRules(rules),
+    rules: toBackendRules(rules),
   })
 }
 
+/// 输出校验选项（与 linch_verify::VerifyOptions 对应）
+export interface VerifyOptions {
+  text_search: boolean
+  ocr_sample: boolean
+}
+
 /// 执行脱敏
 export async function applyRedaction(
   filePath: string,
   rules: Rule[],
-  outputPath: string
+  outputPath: string,
+  verify?: VerifyOptions
 ): Promise<RedactionResult> {
   return invoke<RedactionResult>("apply_redaction", {
     filePath,
-    rules: convertRules(rules),
+    rules: toBackendRules(rules),
     outputPath,
+    verify,
   })
 }
 
@@ -3,3 +3,5 @@ export * from "./ocr"
 export * from "./window"
 export * from "./process"
 export * from "./document"
+export * from "./rules"
+export * from "./queue"
@@ -1,4 +1,7 @@
 import { invoke } from "@tauri-apps/api/core"
+import type { DetectionHit } from "@/types"
+import type { VerifyOptions } from "./document"
+import type { BackendRule } from "./rules"
 
 interface Mask {
   x: number
@@ -16,6 +19,10 @@ interface FileProcessRequest {
   path: string
   pages: PageAction[]
   masks_by_page: Record<number, Mask[]>
+  /// 生成遮罩的检测命中（仅写入审计记录）
+  hits?: DetectionHit[]
+  /// 检测阶段是否使用了 OCR
+  ocr_used?: boolean
 }
 
 interface CleaningOptions {
@@ -27,18 +34,36 @@ interface CleaningOptions {
   javascript: boolean
 }
 
-interface ProcessRequest {
+/// 页面栅格化配置（SafeRender）
+interface RenderConfig {
+  dpi?: number
+  jpeg_quality?: number
+  encoding?: "jpeg" | "flate"
+  color?: "rgb" | "gray" | "bilevel"
+}
+
+export interface ProcessRequest {
   files: FileProcessRequest[]
   output_directory: string
   prefix: string
   mode: string
   cleaning: CleaningOptions
+  /// 操作者（写入审计记录）
+  operator?: string
+  /// 检测使用的规则（用于审计摘要和输出校验）
+  rules?: BackendRule[]
+  verify?: VerifyOptions
+  render?: RenderConfig
 }
 
 interface ProcessResult {
   success: boolean
   processed_files: string[]
+  audit_files: string[]
+  report_files: string[]
   errors: string[]
+  /// 不影响输出的警告（如未能迁移书签）
+  warnings: string[]
 }
 
 export async function processPdfs(request: ProcessRequest): Promise<ProcessResult> {
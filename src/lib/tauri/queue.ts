@@ -0,0 +1,65 @@
+import { invoke } from "@tauri-apps/api/core"
+import { listen, type UnlistenFn } from "@tauri-apps/api/event"
+import type { ProcessRequest } from "./process"
+
+export type JobStatus = "queued" | "running" | "completed" | "failed" | "cancelled"
+
+/// 队列中的单个文件任务
+export interface JobInfo {
+  id: number
+  path: string
+  status: JobStatus
+  /// 已执行次数（含重试）
+  attempts: number
+  error: string | null
+  output_path: string | null
+  audit_path: string | null
+  report_path: string | null
+  /// 脱敏过程中的警告（如未能迁移书签），不影响任务完成
+  warnings: string[]
+  pages_done: number
+  pages_total: number
+}
+
+export interface QueueSnapshot {
+  paused: boolean
+  jobs: JobInfo[]
+}
+
+/// 后端通过 "process-queue" 事件上报的队列变化
+export type QueueEvent =
+  | ({ type: "job" } & JobInfo)
+  | { type: "page"; job_id: number; done: number; total: number }
+  | { type: "queue"; paused: boolean }
+
+export async function enqueuePdfs(request: ProcessRequest): Promise<JobInfo[]> {
+  return invoke<JobInfo[]>("enqueue_pdfs", { request })
+}
+
+export async function getQueue(): Promise<QueueSnapshot> {
+  return invoke<QueueSnapshot>("get_queue")
+}
+
+export async function cancelJob(id: number): Promise<JobInfo> {
+  return invoke<JobInfo>("cancel_job", { id })
+}
+
+export async function retryJob(id: number): Promise<JobInfo> {
+  return invoke<JobInfo>("retry_job", { id })
+}
+
+export async function pauseQueue(): Promise<void> {
+  return invoke("pause_queue")
+}
+
+export async function resumeQueue(): Promise<void> {
+  return invoke("resume_queue")
+}
+
+export async function clearFinishedJobs(): Promise<QueueSnapshot> {
+  return invoke<QueueSnapshot>("clear_finished_jobs")
+}
+
+export async function onQueueEvent(handler: (event: QueueEvent) => void): Promise<UnlistenFn> {
+  return listen<QueueEvent>("process-queue", (event) => handler(event.payload))
+}
@@ -0,0 +1,100 @@
+import { invoke } from "@tauri-apps/api/core"
+import type { HeuristicType, Rule, RuleContext } from "@/types"
+
+/// 后端规则类型（与 linch_rules::RuleType 对应）
+export type BackendRuleType =
+  | { type: "keyword"; value: string }
+  | { type: "regex"; pattern: string }
+  | { type: "dictionary"; name: string; entries: string[] }
+  | { type: "heuristic"; kind: HeuristicType }
+  | { type: "region"; name: string; bbox: { x: number; y: number; w: number; h: number } }
+  | { type: "page_rule" }
+
+/// 后端规则（与 linch_rules::Rule 对应，页面范围与动作使用默认值）
+export interface BackendRule {
+  id: string
+  name: string
+  rule_type: BackendRuleType
+  enabled: boolean
+  context?: RuleContext
+}
+
+/// 将界面规则转换为后端格式
+///
+/// 逗号分隔的多个关键词转换为词典规则。
+export function toBackendRule(rule: Rule): BackendRule {
+  let ruleType: BackendRuleType
+  if (rule.ruleType === "heuristic" && rule.heuristicType) {
+    ruleType = { type: "heuristic", kind: rule.heuristicType }
+  } else if (rule.ruleType === "regex") {
+    ruleType = { type: "regex", pattern: rule.pattern }
+  } else {
+    const entries = rule.pattern
+      .split(",")
+      .map((s) => s.trim())
+      .filter(Boolean)
+    ruleType =
+      entries.length > 1
+        ? { type: "dictionary", name: rule.name, entries }
+        : { type: "keyword", value: entries[0] ?? "" }
+  }
+
+  return {
+    id: rule.id,
+    name: rule.name,
+    rule_type: ruleType,
+    enabled: rule.enabled,
+    ...(rule.context && { context: rule.context }),
+  }
+}
+
+/// 将后端规则转换为界面规则（界面暂不支持的区域、整页规则返回 null）
+export function fromBackendRule(rule: BackendRule): Rule | null {
+  const base = {
+    id: rule.id,
+    name: rule.name,
+    enabled: rule.enabled,
+    ...(rule.context && { context: rule.context }),
+  }
+  const ruleType = rule.rule_type
+  switch (ruleType.type) {
+    case "keyword":
+      return { ...base, ruleType: "keyword", pattern: ruleType.value }
+    case "dictionary":
+      return { ...base, ruleType: "keyword", pattern: ruleType.entries.join(", ") }
+    case "regex":
+      return { ...base, ruleType: "regex", pattern: ruleType.pattern }
+    case "heuristic":
+      return { ...base, ruleType: "heuristic", pattern: "", heuristicType: ruleType.kind }
+    default:
+      return null
+  }
+}
+
+export function toBackendRules(rules: Rule[]): BackendRule[] {
+  return rules.map(toBackendRule)
+}
+
+/// 规则包信息（与 linch_rules::RulePackInfo 对应）
+export interface RulePackInfo {
+  name: string
+  version: string
+  hash: string
+  /// 验证签名所用的受信公钥，未签名时为 null
+  signer: string | null
+}
+
+/// 导入规则包（校验通过后替换已保存的检测规则）
+export async function importRulePack(path: string): Promise<RulePackInfo> {
+  return invoke("import_rule_pack", { path })
+}
+
+/// 将规则导出为规则包（按扩展名写出 JSON 或 TOML）
+export async function exportRulePack(
+  path: string,
+  name: string,
+  version: string,
+  rules: BackendRule[]
+): Promise<RulePackInfo> {
+  return invoke("export_rule_pack", { path, name, version, rules })
+}
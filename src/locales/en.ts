@@ -60,6 +60,9 @@ export const en = {
     foundResults: "Found {{count}} sensitive items",
     selectAtLeastOneRule: "Please select at least one detection rule",
     scanFailed: "Detection failed",
+    scanProgress: "Scanning {{done}}/{{total}}",
+    scanCancelled: "Scan cancelled, kept {{count}} hits found so far",
+    confidence: "Confidence {{value}}%",
     addMask: "Add mask",
     removeMask: "Remove mask",
     maskAdded: "Mask added",
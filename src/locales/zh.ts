@@ -60,6 +60,9 @@ export const zh = {
     foundResults: "发现 {{count}} 处敏感信息",
     selectAtLeastOneRule: "请至少选择一个检测规则",
     scanFailed: "检测失败",
+    scanProgress: "扫描中 {{done}}/{{total}}",
+    scanCancelled: "已取消扫描，保留已发现的 {{count}} 处",
+    confidence: "置信度 {{value}}%",
     addMask: "添加遮罩",
     removeMask: "移除遮罩",
     maskAdded: "已添加遮罩",
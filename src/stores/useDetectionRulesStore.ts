@@ -3,6 +3,13 @@ import { subscribeWithSelector } from "zustand/middleware"
 import { invoke } from "@tauri-apps/api/core"
 import { nanoid } from "nanoid"
 import type { Rule, DetectionHit, DetectionHitsByFile } from "@/types"
+import {
+  fromBackendRule,
+  importRulePack as importRulePackFile,
+  toBackendRules,
+  type BackendRule,
+  type RulePackInfo,
+} from "@/lib/tauri/rules"
 
 // 按文件存储已添加为遮罩的命中索引
 interface AddedHitsByFile {
@@ -96,6 +103,7 @@ interface DetectionRulesStore {
 
   // 规则管理
   loadRules: () => Promise<void>
+  importRulePack: (path: string) => Promise<RulePackInfo>
   addRule: (rule: Omit<Rule, "id">) => void
   updateRule: (id: string, updates: Partial<Rule>) => void
   removeRule: (id: string) => void
@@ -121,7 +129,9 @@ export const useDetectionRulesStore = create<DetectionRulesStore>()(
 
     loadRules: async () => {
       try {
-        const savedRules = await invoke<Rule[]>("load_detection_rules")
+        const savedRules = (await invoke<BackendRule[]>("load_detection_rules"))
+          .map(fromBackendRule)
+          .filter((rule): rule is Rule => rule !== null)
         if (savedRules && savedRules.length > 0) {
           // 合并：保留用户的启用状态，但使用最新的内置规则定义
           const defaultsById = new Map(defaultRules.map((rule) => [rule.id, rule]))
@@ -153,6 +163,26 @@ export const useDetectionRulesStore = create<DetectionRulesStore>()(
       }
     },
 
+    importRulePack: async (path) => {
+      const info = await importRulePackFile(path)
+      const imported = (await invoke<BackendRule[]>("load_detection_rules"))
+        .map(fromBackendRule)
+        .filter((rule): rule is Rule => rule !== null)
+      const importedById = new Map(imported.map((rule) => [rule.id, rule]))
+      const defaultIds = new Set(defaultRules.map((rule) => rule.id))
+
+      // 规则包中没有的内置规则保持停用，使启用的规则与规则包一致
+      const rules: Rule[] = [
+        ...defaultRules.map((def) => ({
+          ...def,
+          enabled: importedById.get(def.id)?.enabled ?? false,
+        })),
+        ...imported.filter((rule) => !defaultIds.has(rule.id)),
+      ]
+      set({ rules })
+      return info
+    },
+
     addRule: (rule) => {
       const newRule: Rule = { ...rule, id: `custom_${nanoid(8)}` }
       set((state) => ({ rules: [...state.rules, newRule] }))
@@ -263,7 +293,7 @@ useDetectionRulesStore.subscribe(
     const { initialized } = useDetectionRulesStore.getState()
     // 只有在初始化完成后才保存（避免覆盖已保存的数据）
     if (initialized && rules !== prevRules) {
-      invoke("save_detection_rules", { rules }).catch((err) => {
+      invoke("save_detection_rules", { rules: toBackendRules(rules) }).catch((err) => {
         console.error("保存检测规则失败:", err)
       })
     }
@@ -160,6 +160,17 @@ export type HeuristicType =
   | "IdNumber" // 身份证/社会安全号
   | "CreditCard" // 信用卡
 
+// 规则上下文条件（与后端 RuleContext 对应）
+export type ContextTerm = string | { regex: string }
+
+export interface RuleContext {
+  required?: ContextTerm[] // 附近必须出现其中之一
+  forbidden?: ContextTerm[] // 附近出现其中之一时排除
+  distance?: number | null // 查找的字符数，为空时不限
+  direction?: "before" | "after" | "around"
+  scope?: "same_line" | "same_page"
+}
+
 // 规则类型
 export interface Rule {
   id: string
@@ -168,8 +179,19 @@ export interface Rule {
   pattern: string // keyword/regex 使用
   heuristicType?: HeuristicType // heuristic 使用
   enabled: boolean
+  context?: RuleContext // 界面暂不编辑，导入规则包时保留
 }
 
+// 命中依据
+export type MatchReason =
+  | { kind: "pattern"; pattern: string }
+  | { kind: "dictionary_word"; word: string }
+  | { kind: "surname"; surname: string }
+  | { kind: "keywords"; count: number; keywords: string[] }
+  | { kind: "organization_suffix"; suffix: string }
+  | { kind: "checksum"; validator: string }
+  | { kind: "context_words"; words: string[] }
+
 // 检测命中结果
 export interface DetectionHit {
   page: number
@@ -177,6 +199,8 @@ export interface DetectionHit {
   ruleId: string
   ruleName: string
   snippet: string
+  confidence?: number | null // 0-1，区域、页面规则为空
+  reasons?: MatchReason[]
 }
 
 // 设置类型